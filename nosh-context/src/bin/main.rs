@@ -1,15 +1,45 @@
 //! nosh-context CLI - debugging tool for context detection.
+//!
+//! Usage: `nosh-context [--export] [--verbose] [--timings] [DIR]`
+//!
+//! With `--export`, prints `export NOSH_CTX_<NAME>=0|1` lines for `eval`
+//! instead of the full JSON context. With `--verbose`, each detector's
+//! status (found / not applicable / error) is printed to stderr first.
+//! With `--timings`, the milliseconds each detector took are printed to
+//! stderr as JSON, to track down slow prompts.
 
 use std::env;
 use std::path::Path;
 
 fn main() {
-    let dir = env::args()
-        .nth(1)
-        .map(|s| std::path::PathBuf::from(s))
+    let args: Vec<String> = env::args().skip(1).collect();
+    let export = args.iter().any(|a| a == "--export");
+    let verbose = args.iter().any(|a| a == "--verbose" || a == "-v");
+    let timings = args.iter().any(|a| a == "--timings");
+
+    let dir = args
+        .iter()
+        .find(|a| !a.starts_with('-'))
+        .map(std::path::PathBuf::from)
         .unwrap_or_else(|| env::current_dir().expect("Failed to get current directory"));
 
-    let ctx = nosh_context::detect(Path::new(&dir));
+    let (ctx, report) = nosh_context::detect_with_report(Path::new(&dir));
+
+    if verbose {
+        for entry in &report {
+            eprintln!("{:<8} {}", entry.detector, entry.status);
+        }
+        eprintln!();
+    }
+
+    if export {
+        print!("{}", ctx.export_env());
+    } else {
+        println!("{}", serde_json::to_string_pretty(&ctx).unwrap());
+    }
 
-    println!("{}", serde_json::to_string_pretty(&ctx).unwrap());
+    if timings {
+        let timings = nosh_context::detector_timings(&report);
+        eprintln!("{}", serde_json::to_string_pretty(&timings).unwrap());
+    }
 }
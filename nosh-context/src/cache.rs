@@ -6,21 +6,45 @@ use std::path::{Path, PathBuf};
 use std::time::{Instant, SystemTime};
 
 use crate::context::ProjectContext;
-use crate::scanner::detect;
+use crate::scanner::{detect_nearest, detect_vcs_context};
+
+/// Directories kept by [`ContextCache::new`].
+pub const DEFAULT_CAPACITY: usize = 128;
 
 /// Cache for project context to avoid redundant detection.
+///
+/// A directory inside a project (`crates/app/src`) gets the context of the
+/// innermost project containing it, via [`detect_nearest`].
+///
+/// Git info and everything else (packages, tools) are refreshed
+/// independently: switching branches only re-runs git detection, and
+/// editing a manifest re-runs the rest. Once `capacity` directories are
+/// cached, the least recently used one is evicted, so a long session that
+/// visits thousands of directories keeps a fixed number of contexts. An
+/// entry older than `MAX_CACHE_AGE_SECS` is re-detected when next used.
 pub struct ContextCache {
-    cached: Option<CachedContext>,
+    entries: HashMap<PathBuf, CachedContext>,
+    capacity: usize,
+    /// Bumped on every lookup to order entries by use
+    tick: u64,
 }
 
 struct CachedContext {
-    dir: PathBuf,
+    last_used: u64,
     context: ProjectContext,
+    /// Project directory the manifests were read from
+    root: PathBuf,
+    /// Directory holding `.git` (the workspace root, or `root`)
+    git_root: PathBuf,
+    /// Mtimes of git metadata, checked to refresh `git`/`vcs`
+    git_mtimes: HashMap<String, SystemTime>,
+    git_detected_at: Instant,
+    /// Mtimes of manifest files, checked to refresh everything else
     file_mtimes: HashMap<String, SystemTime>,
     detected_at: Instant,
 }
 
-/// Indicator files to monitor for changes.
+/// Manifest files to monitor for package and tool changes.
 const INDICATOR_FILES: &[&str] = &[
     "Cargo.toml",
     "Cargo.lock",
@@ -41,99 +65,148 @@ const INDICATOR_FILES: &[&str] = &[
     "docker-compose.yaml",
     "compose.yml",
     "compose.yaml",
-    ".git/HEAD",
-    ".git/index",
+    "build.zig",
+    "mix.exs",
+    ".tool-versions",
 ];
 
+/// Git metadata files to monitor for branch and status changes.
+const GIT_FILES: &[&str] = &[".git/HEAD", ".git/index", ".git/config"];
+
 /// Maximum cache age in seconds before forcing refresh (for version info).
 const MAX_CACHE_AGE_SECS: u64 = 5;
 
 impl ContextCache {
-    /// Create a new empty cache.
+    /// Create a new empty cache holding up to [`DEFAULT_CAPACITY`] directories.
     pub fn new() -> Self {
-        Self { cached: None }
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// Create a new empty cache holding up to `capacity` directories (at least one).
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity: capacity.max(1),
+            tick: 0,
+        }
     }
 
     /// Get project context, using cache if valid.
     pub fn get(&mut self, dir: &Path) -> ProjectContext {
         // Canonicalize path for consistent comparison
         let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        self.tick += 1;
 
-        // Check if cache is valid
-        if let Some(cached) = &self.cached
-            && cached.dir == dir
-            && !self.cache_expired(&cached.detected_at)
-            && !self.files_changed(&dir, &cached.file_mtimes)
+        // Package and tool info still valid: refresh git info alone if needed
+        if let Some(cached) = self.entries.get_mut(&dir)
+            && !cache_expired(&cached.detected_at)
+            && !files_changed(&cached.root, INDICATOR_FILES, &cached.file_mtimes)
         {
+            if cache_expired(&cached.git_detected_at)
+                || files_changed(&cached.git_root, GIT_FILES, &cached.git_mtimes)
+            {
+                let (git, vcs) = detect_vcs_context(&dir);
+                cached.context.git = git;
+                cached.context.vcs = vcs;
+                cached.git_mtimes = collect_mtimes(&cached.git_root, GIT_FILES);
+                cached.git_detected_at = Instant::now();
+            }
+            cached.last_used = self.tick;
             return cached.context.clone();
         }
 
         // Cache miss - detect fresh
-        let context = detect(&dir);
-        let file_mtimes = self.collect_mtimes(&dir);
+        let context = detect_nearest(&dir);
+        let root = PathBuf::from(&context.dir);
+        let git_root = context
+            .workspace_root
+            .as_ref()
+            .map_or_else(|| root.clone(), PathBuf::from);
+        let now = Instant::now();
 
-        self.cached = Some(CachedContext {
-            dir: dir.clone(),
-            context: context.clone(),
-            file_mtimes,
-            detected_at: Instant::now(),
-        });
+        if !self.entries.contains_key(&dir) && self.entries.len() >= self.capacity {
+            self.evict_least_recent();
+        }
+        self.entries.insert(
+            dir.clone(),
+            CachedContext {
+                last_used: self.tick,
+                git_mtimes: collect_mtimes(&git_root, GIT_FILES),
+                git_detected_at: now,
+                file_mtimes: collect_mtimes(&root, INDICATOR_FILES),
+                detected_at: now,
+                context: context.clone(),
+                root,
+                git_root,
+            },
+        );
 
         context
     }
 
-    /// Invalidate the cache.
+    /// Drop every cached directory so the next `get` detects afresh.
     pub fn invalidate(&mut self) {
-        self.cached = None;
+        self.entries.clear();
     }
 
-    /// Check if cache has expired.
-    fn cache_expired(&self, detected_at: &Instant) -> bool {
-        detected_at.elapsed().as_secs() > MAX_CACHE_AGE_SECS
+    fn evict_least_recent(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, cached)| cached.last_used)
+            .map(|(dir, _)| dir.clone());
+        if let Some(dir) = oldest {
+            self.entries.remove(&dir);
+        }
     }
+}
 
-    /// Check if any indicator files have changed.
-    fn files_changed(&self, dir: &Path, old_mtimes: &HashMap<String, SystemTime>) -> bool {
-        for file in INDICATOR_FILES {
-            let path = dir.join(file);
-            let old_mtime = old_mtimes.get(*file);
-
-            match (path.exists(), old_mtime) {
-                // File exists now, didn't before
-                (true, None) => return true,
-                // File doesn't exist now, did before
-                (false, Some(_)) => return true,
-                // File exists - check mtime
-                (true, Some(old)) => {
-                    if let Ok(meta) = fs::metadata(&path)
-                        && let Ok(new_mtime) = meta.modified()
-                        && &new_mtime != old
-                    {
-                        return true;
-                    }
+/// Check if a cache part has expired.
+fn cache_expired(detected_at: &Instant) -> bool {
+    detected_at.elapsed().as_secs() > MAX_CACHE_AGE_SECS
+}
+
+/// Check if any of `files` appeared, disappeared or changed mtime.
+fn files_changed(dir: &Path, files: &[&str], old_mtimes: &HashMap<String, SystemTime>) -> bool {
+    for file in files {
+        let path = dir.join(file);
+        let old_mtime = old_mtimes.get(*file);
+
+        match (path.exists(), old_mtime) {
+            // File exists now, didn't before
+            (true, None) => return true,
+            // File doesn't exist now, did before
+            (false, Some(_)) => return true,
+            // File exists - check mtime
+            (true, Some(old)) => {
+                if let Ok(meta) = fs::metadata(&path)
+                    && let Ok(new_mtime) = meta.modified()
+                    && &new_mtime != old
+                {
+                    return true;
                 }
-                // File doesn't exist and didn't before - no change
-                (false, None) => {}
             }
+            // File doesn't exist and didn't before - no change
+            (false, None) => {}
         }
-        false
     }
+    false
+}
 
-    /// Collect modification times for indicator files.
-    fn collect_mtimes(&self, dir: &Path) -> HashMap<String, SystemTime> {
-        let mut mtimes = HashMap::new();
+/// Collect modification times for `files`.
+fn collect_mtimes(dir: &Path, files: &[&str]) -> HashMap<String, SystemTime> {
+    let mut mtimes = HashMap::new();
 
-        for file in INDICATOR_FILES {
-            let path = dir.join(file);
-            if let Ok(meta) = fs::metadata(&path)
-                && let Ok(mtime) = meta.modified()
-            {
-                mtimes.insert(file.to_string(), mtime);
-            }
+    for file in files {
+        let path = dir.join(file);
+        if let Ok(meta) = fs::metadata(&path)
+            && let Ok(mtime) = meta.modified()
+        {
+            mtimes.insert(file.to_string(), mtime);
         }
-
-        mtimes
     }
+
+    mtimes
 }
 
 impl Default for ContextCache {
@@ -141,3 +214,107 @@ impl Default for ContextCache {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Rewrite a file, then set its mtime explicitly.
+    fn write_with_mtime(path: &Path, content: &str, mtime: SystemTime) {
+        fs::write(path, content).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_branch_switch_keeps_tool_info() {
+        let dir = std::env::temp_dir().join(format!("nosh_context_cache_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        let mtime = SystemTime::now() - Duration::from_secs(60);
+        write_with_mtime(&dir.join(".git/HEAD"), "ref: refs/heads/main\n", mtime);
+        let manifest = dir.join("package.json");
+        write_with_mtime(&manifest, r#"{"name": "demo", "version": "1.0.0"}"#, mtime);
+
+        let mut cache = ContextCache::new();
+        let ctx = cache.get(&dir);
+        assert_eq!(ctx.git.unwrap().branch, "main");
+        assert_eq!(ctx.package.unwrap().version, "1.0.0");
+
+        // Same manifest mtime, so a changed version proves tool info was reused
+        write_with_mtime(&manifest, r#"{"name": "demo", "version": "2.0.0"}"#, mtime);
+        write_with_mtime(
+            &dir.join(".git/HEAD"),
+            "ref: refs/heads/feature\n",
+            mtime + Duration::from_secs(10),
+        );
+
+        let ctx = cache.get(&dir);
+        assert_eq!(ctx.git.unwrap().branch, "feature");
+        assert_eq!(ctx.package.unwrap().version, "1.0.0");
+
+        // Touching the manifest refreshes the rest
+        write_with_mtime(
+            &manifest,
+            r#"{"name": "demo", "version": "2.0.0"}"#,
+            mtime + Duration::from_secs(20),
+        );
+        let ctx = cache.get(&dir);
+        assert_eq!(ctx.package.unwrap().version, "2.0.0");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_invalidate_forces_redetect() {
+        let dir =
+            std::env::temp_dir().join(format!("nosh_context_invalidate_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mtime = SystemTime::now() - Duration::from_secs(60);
+        let manifest = dir.join("package.json");
+        write_with_mtime(&manifest, r#"{"name": "demo", "version": "1.0.0"}"#, mtime);
+
+        let mut cache = ContextCache::new();
+        assert_eq!(cache.get(&dir).package.unwrap().version, "1.0.0");
+
+        // Same mtime, so only a fresh detection sees the new version
+        write_with_mtime(&manifest, r#"{"name": "demo", "version": "2.0.0"}"#, mtime);
+        assert_eq!(cache.get(&dir).package.unwrap().version, "1.0.0");
+        cache.invalidate();
+        assert_eq!(cache.get(&dir).package.unwrap().version, "2.0.0");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let root = std::env::temp_dir().join(format!("nosh_context_lru_{}", std::process::id()));
+        let dirs: Vec<PathBuf> = ["a", "b", "c"]
+            .iter()
+            .map(|name| {
+                let dir = root.join(name);
+                fs::create_dir_all(&dir).unwrap();
+                dir.canonicalize().unwrap()
+            })
+            .collect();
+
+        let mut cache = ContextCache::with_capacity(2);
+        cache.get(&dirs[0]);
+        cache.get(&dirs[1]);
+        // Using `a` again leaves `b` as the least recently used
+        cache.get(&dirs[0]);
+        cache.get(&dirs[2]);
+
+        assert_eq!(cache.entries.len(), 2);
+        assert!(cache.entries.contains_key(&dirs[0]));
+        assert!(!cache.entries.contains_key(&dirs[1]));
+        assert!(cache.entries.contains_key(&dirs[2]));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -3,14 +3,23 @@
 //! Defines the core data structures for project context information.
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
 
 /// Complete project context information.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ProjectContext {
-    /// Current directory path.
+    /// Directory the context was detected for (the enclosing project root
+    /// when found with [`crate::detect_nearest`]).
     pub dir: String,
+    /// Outermost enclosing project root (git root or topmost manifest),
+    /// set by [`crate::detect_nearest`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_root: Option<String>,
     /// Git repository information.
     pub git: Option<GitInfo>,
+    /// Active version control system (git, hg or jj).
+    pub vcs: Option<VcsInfo>,
     /// Package/project information.
     pub package: Option<PackageInfo>,
     /// Rust toolchain information.
@@ -27,6 +36,99 @@ pub struct ProjectContext {
     pub cpp: Option<ToolInfo>,
     /// Docker toolchain information.
     pub docker: Option<ToolInfo>,
+    /// Docker Compose project in this directory.
+    pub compose: Option<ComposeInfo>,
+    /// Zig toolchain information.
+    pub zig: Option<ToolInfo>,
+    /// Elixir toolchain information.
+    pub elixir: Option<ToolInfo>,
+    /// Tools pinned in `.tool-versions` (asdf/mise).
+    pub pinned: Vec<PinnedTool>,
+}
+
+impl ProjectContext {
+    /// Whether the directory is inside a git repository.
+    pub fn in_git(&self) -> bool {
+        self.git.is_some()
+    }
+
+    /// Whether the directory is under any supported VCS (git, hg or jj).
+    pub fn in_vcs(&self) -> bool {
+        self.vcs.is_some()
+    }
+
+    /// Whether a project manifest was found.
+    pub fn has_package(&self) -> bool {
+        self.package.is_some()
+    }
+
+    pub fn is_rust(&self) -> bool {
+        self.rust.is_some()
+    }
+
+    pub fn is_node(&self) -> bool {
+        self.node.is_some()
+    }
+
+    pub fn is_bun(&self) -> bool {
+        self.bun.is_some()
+    }
+
+    pub fn is_go(&self) -> bool {
+        self.go.is_some()
+    }
+
+    pub fn is_python(&self) -> bool {
+        self.python.is_some()
+    }
+
+    pub fn is_cpp(&self) -> bool {
+        self.cpp.is_some()
+    }
+
+    pub fn is_docker(&self) -> bool {
+        self.docker.is_some()
+    }
+
+    pub fn is_compose(&self) -> bool {
+        self.compose.is_some()
+    }
+
+    pub fn is_zig(&self) -> bool {
+        self.zig.is_some()
+    }
+
+    pub fn is_elixir(&self) -> bool {
+        self.elixir.is_some()
+    }
+
+    /// All presence flags as `(name, present)` pairs, e.g. `("RUST", true)`.
+    pub fn presence_flags(&self) -> Vec<(&'static str, bool)> {
+        vec![
+            ("GIT", self.in_git()),
+            ("VCS", self.in_vcs()),
+            ("PACKAGE", self.has_package()),
+            ("RUST", self.is_rust()),
+            ("NODE", self.is_node()),
+            ("BUN", self.is_bun()),
+            ("GO", self.is_go()),
+            ("PYTHON", self.is_python()),
+            ("CPP", self.is_cpp()),
+            ("DOCKER", self.is_docker()),
+            ("COMPOSE", self.is_compose()),
+            ("ZIG", self.is_zig()),
+            ("ELIXIR", self.is_elixir()),
+        ]
+    }
+
+    /// Shell `export` lines for every presence flag (`NOSH_CTX_RUST=1`, or `0`
+    /// when absent, so re-evaluating in another directory clears stale flags).
+    pub fn export_env(&self) -> String {
+        self.presence_flags()
+            .into_iter()
+            .map(|(name, present)| format!("export NOSH_CTX_{}={}\n", name, u8::from(present)))
+            .collect()
+    }
 }
 
 /// Git repository status information.
@@ -40,9 +142,56 @@ pub struct GitInfo {
     pub staged: bool,
     /// Whether there are untracked files.
     pub untracked: bool,
+    /// Host of the `origin` remote (e.g., "github.com").
+    pub remote_host: Option<String>,
+    /// Whether this is a linked worktree rather than the main checkout.
+    pub is_worktree: bool,
+    /// Whether a submodule is uninitialized or out of sync with its recorded commit.
+    pub submodules_dirty: bool,
+    /// Upstream tracking branch (e.g., "origin/main").
+    pub upstream: Option<String>,
+    /// Nearest tag reachable from HEAD, when tag lookup is enabled.
+    pub tag: Option<String>,
+    /// When `FETCH_HEAD` was last written, when fetch tracking is enabled.
+    #[serde(default)]
+    pub last_fetch: Option<SystemTime>,
+    /// Per-state file counts.
+    pub counts: GitStatusCounts,
+}
+
+/// Docker Compose project information.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct ComposeInfo {
+    /// Project name (the compose file's `name:`, or the directory name).
+    pub project: String,
+    /// Number of running containers, when the `docker compose ps` check is enabled.
+    pub running: Option<usize>,
+}
+
+/// Number of files in each git status state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct GitStatusCounts {
+    /// Files with staged changes.
+    pub staged: usize,
+    /// Files with unstaged changes in the working tree.
+    pub modified: usize,
+    /// Untracked files.
+    pub untracked: usize,
+    /// Files with merge conflicts.
+    pub conflicted: usize,
 }
 
 impl GitInfo {
+    /// Time since the last `git fetch` (`None` if never fetched or not tracked).
+    pub fn fetch_age(&self) -> Option<Duration> {
+        SystemTime::now().duration_since(self.last_fetch?).ok()
+    }
+
+    /// Whether the last fetch is older than `threshold`.
+    pub fn fetch_stale(&self, threshold: Duration) -> bool {
+        self.fetch_age().is_some_and(|age| age > threshold)
+    }
+
     /// Format git status as a short indicator string (e.g., "[!?]").
     pub fn status_indicator(&self) -> String {
         let mut s = String::new();
@@ -63,6 +212,35 @@ impl GitInfo {
     }
 }
 
+/// Supported version control systems.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Vcs {
+    Git,
+    Mercurial,
+    Jujutsu,
+}
+
+impl Vcs {
+    /// Short command name of the VCS (e.g., "git", "hg", "jj").
+    pub fn name(&self) -> &'static str {
+        match self {
+            Vcs::Git => "git",
+            Vcs::Mercurial => "hg",
+            Vcs::Jujutsu => "jj",
+        }
+    }
+}
+
+/// Version control information for whichever VCS is active.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VcsInfo {
+    /// Detected version control system.
+    pub vcs: Vcs,
+    /// Branch name (git/hg) or working-copy change id (jj).
+    pub branch: String,
+}
+
 /// Package/project metadata.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PackageInfo {
@@ -78,3 +256,86 @@ pub struct ToolInfo {
     /// Version string.
     pub version: String,
 }
+
+/// A tool version pinned in `.tool-versions`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PinnedTool {
+    /// asdf plugin name (e.g., "nodejs").
+    pub tool: String,
+    /// Pinned version (first listed).
+    pub version: String,
+    /// Version of the active runtime, if detected.
+    pub active: Option<String>,
+    /// Whether the active runtime doesn't match any pinned version.
+    pub mismatch: bool,
+}
+
+/// Per-detector outcome, reported by `nosh-context --verbose`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "status", content = "detail")]
+pub enum DetectorStatus {
+    /// Detected, with a short summary (usually the version).
+    Found(String),
+    /// The directory has no indicators for this detector.
+    NotApplicable,
+    /// The detector applied but failed.
+    Error(String),
+}
+
+impl std::fmt::Display for DetectorStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DetectorStatus::Found(detail) if detail.is_empty() => write!(f, "found"),
+            DetectorStatus::Found(detail) => write!(f, "found ({})", detail),
+            DetectorStatus::NotApplicable => write!(f, "not applicable"),
+            DetectorStatus::Error(message) => write!(f, "error: {}", message),
+        }
+    }
+}
+
+/// Status of one detector for a directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectorReport {
+    /// Detector name (e.g., "node").
+    pub detector: String,
+    pub status: DetectorStatus,
+    /// How long the detector took, or `None` if it didn't run.
+    #[serde(skip)]
+    pub elapsed: Option<Duration>,
+}
+
+/// Milliseconds taken by each detector that ran, keyed by detector name.
+pub fn detector_timings(report: &[DetectorReport]) -> BTreeMap<String, f64> {
+    report
+        .iter()
+        .filter_map(|entry| {
+            let elapsed = entry.elapsed?;
+            Some((entry.detector.clone(), elapsed.as_secs_f64() * 1000.0))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_env() {
+        let ctx = ProjectContext {
+            git: Some(GitInfo::default()),
+            rust: Some(ToolInfo::default()),
+            ..Default::default()
+        };
+
+        assert!(ctx.in_git() && ctx.is_rust());
+        assert!(!ctx.is_node());
+
+        let exported = ctx.export_env();
+        let vars: Vec<&str> = exported.lines().collect();
+        assert_eq!(vars.len(), ctx.presence_flags().len());
+        assert!(vars.contains(&"export NOSH_CTX_GIT=1"));
+        assert!(vars.contains(&"export NOSH_CTX_RUST=1"));
+        assert!(vars.contains(&"export NOSH_CTX_NODE=0"));
+        assert!(vars.contains(&"export NOSH_CTX_VCS=0"));
+    }
+}
@@ -1,35 +1,31 @@
 //! Bun runtime detection.
 
 use std::path::Path;
-use std::process::Command;
 
 use crate::context::ToolInfo;
+use crate::detectors::{Detection, run_version_command, unexpected_output};
 
 /// Detect Bun runtime information.
-pub fn detect(dir: &Path) -> Option<ToolInfo> {
+pub fn detect(dir: &Path) -> Detection<ToolInfo> {
     // Verify bun project files exist
     let has_bun_lock = dir.join("bun.lockb").exists() || dir.join("bun.lock").exists();
     let has_bunfig = dir.join("bunfig.toml").exists();
 
     if !has_bun_lock && !has_bunfig {
-        return None;
+        return Detection::NotApplicable;
     }
 
     // Get bun version
-    let version = get_bun_version()?;
-
-    Some(ToolInfo { version })
+    get_bun_version().map(|version| ToolInfo { version }).into()
 }
 
 /// Get Bun version string.
-fn get_bun_version() -> Option<String> {
-    let output = Command::new("bun").args(["--version"]).output().ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
+fn get_bun_version() -> Result<String, String> {
+    let stdout = run_version_command("bun", &["--version"])?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
     // Parse "1.0.0" directly
-    Some(stdout.trim().to_string())
+    match stdout.trim() {
+        "" => Err(unexpected_output("bun", &stdout)),
+        version => Ok(version.to_string()),
+    }
 }
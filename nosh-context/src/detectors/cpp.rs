@@ -2,12 +2,12 @@
 
 use std::collections::HashSet;
 use std::path::Path;
-use std::process::Command;
 
 use crate::context::ToolInfo;
+use crate::detectors::{Detection, run_version_command, unexpected_output};
 
 /// Detect C++ toolchain information.
-pub fn detect(_dir: &Path, files: &HashSet<String>) -> Option<ToolInfo> {
+pub fn detect(_dir: &Path, files: &HashSet<String>) -> Detection<ToolInfo> {
     // Check for C++ project indicators
     let has_cmake = files.contains("CMakeLists.txt");
     let has_makefile = files.contains("Makefile") || files.contains("makefile");
@@ -22,63 +22,52 @@ pub fn detect(_dir: &Path, files: &HashSet<String>) -> Option<ToolInfo> {
     let has_conan = files.contains("conanfile.txt") || files.contains("conanfile.py");
 
     if !has_cmake && !has_cpp_files && !has_meson && !has_conan && !has_makefile {
-        return None;
+        return Detection::NotApplicable;
     }
 
     // For Makefile-only projects, verify there are actually C++ files
     if has_makefile && !has_cmake && !has_cpp_files && !has_meson && !has_conan {
-        return None;
+        return Detection::NotApplicable;
     }
 
     // Get compiler version
-    let version = get_cpp_version()?;
-
-    Some(ToolInfo { version })
+    get_cpp_version().map(|version| ToolInfo { version }).into()
 }
 
 /// Get C++ compiler version string.
-fn get_cpp_version() -> Option<String> {
+fn get_cpp_version() -> Result<String, String> {
     // Try clang++ first (common on macOS), then g++
-    if let Some(version) = get_clang_version() {
-        return Some(version);
-    }
-
-    get_gpp_version()
+    get_clang_version().or_else(|clang_err| {
+        get_gpp_version().map_err(|gpp_err| format!("{}; {}", clang_err, gpp_err))
+    })
 }
 
-fn get_clang_version() -> Option<String> {
-    let output = Command::new("clang++").args(["--version"]).output().ok()?;
+fn get_clang_version() -> Result<String, String> {
+    let stdout = run_version_command("clang++", &["--version"])?;
 
-    if !output.status.success() {
-        return None;
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
     // Parse "Apple clang version 15.0.0" or "clang version 17.0.0"
     for line in stdout.lines() {
         if line.contains("clang version") {
             let parts: Vec<&str> = line.split_whitespace().collect();
             for (i, part) in parts.iter().enumerate() {
                 if *part == "version" && i + 1 < parts.len() {
-                    return Some(format!("clang {}", parts[i + 1]));
+                    return Ok(format!("clang {}", parts[i + 1]));
                 }
             }
         }
     }
-    None
+    Err(unexpected_output("clang++", &stdout))
 }
 
-fn get_gpp_version() -> Option<String> {
-    let output = Command::new("g++").args(["--version"]).output().ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
+fn get_gpp_version() -> Result<String, String> {
+    let stdout = run_version_command("g++", &["--version"])?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
     // Parse "g++ (GCC) 13.2.0" or similar
-    let first_line = stdout.lines().next()?;
-    let parts: Vec<&str> = first_line.split_whitespace().collect();
-    // Last part is usually the version
-    parts.last().map(|v| format!("g++ {}", v))
+    // Last part of the first line is usually the version
+    stdout
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().last())
+        .map(|v| format!("g++ {}", v))
+        .ok_or_else(|| unexpected_output("g++", &stdout))
 }
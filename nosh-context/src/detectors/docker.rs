@@ -1,13 +1,36 @@
 //! Docker project detection.
 
 use std::collections::HashSet;
+use std::fs;
 use std::path::Path;
 use std::process::Command;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use crate::context::ToolInfo;
+use crate::context::{ComposeInfo, ToolInfo};
+use crate::detectors::{
+    Detection, command_timeout, run_version_command, run_with_timeout, unexpected_output,
+};
+
+/// Compose file names, in the order `docker compose` looks for them.
+const COMPOSE_FILES: &[&str] = &[
+    "compose.yaml",
+    "compose.yml",
+    "docker-compose.yaml",
+    "docker-compose.yml",
+];
+
+static COMPOSE_PS: AtomicBool = AtomicBool::new(false);
+
+/// Enable counting running services with `docker compose ps` (process-wide).
+///
+/// Off by default since it talks to the Docker daemon on every detection.
+pub fn set_compose_ps(enabled: bool) {
+    COMPOSE_PS.store(enabled, Ordering::Relaxed);
+}
 
 /// Detect Docker toolchain information.
-pub fn detect(_dir: &Path, files: &HashSet<String>) -> Option<ToolInfo> {
+pub fn detect(_dir: &Path, files: &HashSet<String>) -> Detection<ToolInfo> {
     // Check for Docker project indicators
     let has_dockerfile =
         files.contains("Dockerfile") || files.iter().any(|f| f.starts_with("Dockerfile."));
@@ -18,31 +41,167 @@ pub fn detect(_dir: &Path, files: &HashSet<String>) -> Option<ToolInfo> {
     let has_dockerignore = files.contains(".dockerignore");
 
     if !has_dockerfile && !has_compose && !has_dockerignore {
-        return None;
+        return Detection::NotApplicable;
     }
 
     // Get docker version
-    let version = get_docker_version()?;
-
-    Some(ToolInfo { version })
+    get_docker_version()
+        .map(|version| ToolInfo { version })
+        .into()
 }
 
 /// Get Docker version string.
-fn get_docker_version() -> Option<String> {
-    let output = Command::new("docker").args(["--version"]).output().ok()?;
+fn get_docker_version() -> Result<String, String> {
+    let stdout = run_version_command("docker", &["--version"])?;
+
+    // Parse "Docker version 24.0.7, build afdd53b"
+    stdout
+        .split(',')
+        .next()
+        .and_then(|s| s.trim().strip_prefix("Docker version "))
+        .map(|s| s.to_string())
+        .ok_or_else(|| unexpected_output("docker", &stdout))
+}
+
+/// Detect a Docker Compose project from the compose file in `dir`.
+pub fn detect_compose(dir: &Path, files: &HashSet<String>) -> Option<ComposeInfo> {
+    let file = COMPOSE_FILES.iter().find(|f| files.contains(**f))?;
+
+    let declared = fs::read_to_string(dir.join(file))
+        .ok()
+        .and_then(|content| compose_name(&content));
+    let project = declared.or_else(|| {
+        let base = dir.file_name()?.to_str()?;
+        let normalized = normalize_project_name(base);
+        (!normalized.is_empty()).then_some(normalized)
+    })?;
+
+    let running = if COMPOSE_PS.load(Ordering::Relaxed) {
+        count_running(dir)
+    } else {
+        None
+    };
+
+    Some(ComposeInfo { project, running })
+}
+
+/// Top-level `name:` from a compose file.
+fn compose_name(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let value = line.strip_prefix("name:")?;
+        let value = value.split(" #").next().unwrap_or(value).trim();
+        let value = value.trim_matches(|c| c == '"' || c == '\'');
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+/// Derive a project name from a directory name like Compose does:
+/// lowercase, keeping only letters, digits, `-` and `_`.
+fn normalize_project_name(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect()
+}
+
+/// Container runtime nosh itself is running under ("docker", "podman" or
+/// "container"), or `None` on a regular host. Checked once per process.
+pub fn in_container() -> Option<&'static str> {
+    static IN_CONTAINER: OnceLock<Option<&'static str>> = OnceLock::new();
+    *IN_CONTAINER.get_or_init(|| in_container_at(Path::new("/")))
+}
 
+/// [`in_container`] for a filesystem rooted at `root`.
+pub fn in_container_at(root: &Path) -> Option<&'static str> {
+    if root.join(".dockerenv").exists() {
+        return Some("docker");
+    }
+    if root.join("run/.containerenv").exists() {
+        return Some("podman");
+    }
+    // cgroup v1 paths name the runtime; v2 shows only "0::/" inside a container
+    let cgroup = fs::read_to_string(root.join("proc/1/cgroup")).ok()?;
+    cgroup
+        .lines()
+        .any(|line| {
+            ["docker", "kubepods", "containerd", "lxc"]
+                .iter()
+                .any(|runtime| line.contains(runtime))
+        })
+        .then_some("container")
+}
+
+/// Number of running containers in the project, via `docker compose ps -q`.
+fn count_running(dir: &Path) -> Option<usize> {
+    let output = run_with_timeout(
+        Command::new("docker")
+            .args(["compose", "ps", "-q"])
+            .current_dir(dir),
+        command_timeout(),
+    )
+    .ok()?;
     if !output.status.success() {
         return None;
     }
-
     let stdout = String::from_utf8_lossy(&output.stdout);
-    // Parse "Docker version 24.0.7, build afdd53b"
-    let version = stdout
-        .split(',')
-        .next()?
-        .trim()
-        .strip_prefix("Docker version ")?
-        .to_string();
+    Some(stdout.lines().filter(|l| !l.trim().is_empty()).count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_compose_project_name() {
+        let root =
+            std::env::temp_dir().join(format!("nosh_context_compose_{}", std::process::id()));
+        let dir = root.join("My Shop.v2");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("compose.yaml"),
+            "services:\n  web:\n    image: nginx\n",
+        )
+        .unwrap();
 
-    Some(version)
+        let files: HashSet<String> = ["compose.yaml".to_string()].into();
+        let info = detect_compose(&dir, &files).unwrap();
+        assert_eq!(info.project, "myshopv2");
+        assert_eq!(info.running, None);
+
+        fs::write(
+            dir.join("compose.yaml"),
+            "name: \"storefront\" # shared\nservices:\n  web:\n    name: ignored\n",
+        )
+        .unwrap();
+        assert_eq!(detect_compose(&dir, &files).unwrap().project, "storefront");
+
+        assert_eq!(detect_compose(&dir, &HashSet::new()), None);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_in_container_markers() {
+        let root =
+            std::env::temp_dir().join(format!("nosh_context_container_{}", std::process::id()));
+        fs::create_dir_all(root.join("proc/1")).unwrap();
+        fs::write(root.join("proc/1/cgroup"), "0::/init.scope\n").unwrap();
+        assert_eq!(in_container_at(&root), None);
+
+        fs::write(
+            root.join("proc/1/cgroup"),
+            "12:memory:/kubepods/burstable/pod1234\n",
+        )
+        .unwrap();
+        assert_eq!(in_container_at(&root), Some("container"));
+
+        fs::create_dir_all(root.join("run")).unwrap();
+        fs::write(root.join("run/.containerenv"), "").unwrap();
+        assert_eq!(in_container_at(&root), Some("podman"));
+
+        fs::write(root.join(".dockerenv"), "").unwrap();
+        assert_eq!(in_container_at(&root), Some("docker"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
 }
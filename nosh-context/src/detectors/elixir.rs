@@ -0,0 +1,104 @@
+//! Elixir project detection.
+
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::context::ToolInfo;
+use crate::detectors::{Detection, run_version_command, unexpected_output};
+
+/// Detect Elixir toolchain information.
+pub fn detect(dir: &Path) -> Detection<ToolInfo> {
+    // Verify mix.exs exists
+    if !dir.join("mix.exs").exists() {
+        return Detection::NotApplicable;
+    }
+
+    // Get elixir version
+    get_elixir_version()
+        .map(|version| ToolInfo { version })
+        .into()
+}
+
+/// Get Elixir version string.
+fn get_elixir_version() -> Result<String, String> {
+    let stdout = run_version_command("elixir", &["-v"])?;
+    parse_elixir_version(&stdout).ok_or_else(|| unexpected_output("elixir", &stdout))
+}
+
+/// Parse `elixir -v` output.
+///
+/// The output includes an Erlang/OTP banner line first, then
+/// "Elixir 1.16.0 (compiled with Erlang/OTP 26)" -> "1.16.0".
+fn parse_elixir_version(stdout: &str) -> Option<String> {
+    stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("Elixir "))
+        .and_then(|rest| rest.split_whitespace().next())
+        .map(|s| s.to_string())
+}
+
+/// Get project info from mix.exs.
+///
+/// mix.exs is Elixir code, so `app:` and `version:` are matched with a regex.
+pub fn get_mix_project(dir: &Path) -> Option<(String, String)> {
+    let content = fs::read_to_string(dir.join("mix.exs")).ok()?;
+    parse_mix_project(&content)
+}
+
+fn parse_mix_project(content: &str) -> Option<(String, String)> {
+    let app_re = Regex::new(r"\bapp:\s*:([A-Za-z0-9_]+)").ok()?;
+    let version_re = Regex::new(r#"\bversion:\s*"([^"]+)""#).ok()?;
+
+    let name = app_re.captures(content)?.get(1)?.as_str().to_string();
+    let version = version_re
+        .captures(content)
+        .and_then(|c| c.get(1))
+        .map(|m| m.as_str().to_string())
+        .unwrap_or_default();
+
+    Some((name, version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_mix_project() {
+        let dir = std::env::temp_dir().join(format!("nosh_context_elixir_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("mix.exs"),
+            r#"defmodule MyApp.MixProject do
+  use Mix.Project
+
+  def project do
+    [
+      app: :my_app,
+      version: "0.3.1",
+      elixir: "~> 1.16",
+      deps: deps()
+    ]
+  end
+end
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            get_mix_project(&dir),
+            Some(("my_app".to_string(), "0.3.1".to_string()))
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_elixir_version() {
+        let output = "Erlang/OTP 26 [erts-14.2] [source] [64-bit]\n\nElixir 1.16.0 (compiled with Erlang/OTP 26)\n";
+        assert_eq!(parse_elixir_version(output).as_deref(), Some("1.16.0"));
+        assert_eq!(parse_elixir_version("nothing here"), None);
+    }
+}
@@ -2,40 +2,32 @@
 
 use std::fs;
 use std::path::Path;
-use std::process::Command;
 
 use crate::context::ToolInfo;
+use crate::detectors::{Detection, run_version_command, unexpected_output};
 
 /// Detect Go toolchain information.
-pub fn detect(dir: &Path) -> Option<ToolInfo> {
+pub fn detect(dir: &Path) -> Detection<ToolInfo> {
     // Verify go.mod exists
     if !dir.join("go.mod").exists() {
-        return None;
+        return Detection::NotApplicable;
     }
 
     // Get go version
-    let version = get_go_version()?;
-
-    Some(ToolInfo { version })
+    get_go_version().map(|version| ToolInfo { version }).into()
 }
 
 /// Get Go version string.
-fn get_go_version() -> Option<String> {
-    let output = Command::new("go").args(["version"]).output().ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
+fn get_go_version() -> Result<String, String> {
+    let stdout = run_version_command("go", &["version"])?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
     // Parse "go version go1.21.5 darwin/arm64" -> "1.21.5"
-    let version = stdout
+    stdout
         .split_whitespace()
         .nth(2)
         .and_then(|s| s.strip_prefix("go"))
-        .map(|s| s.to_string())?;
-
-    Some(version)
+        .map(|s| s.to_string())
+        .ok_or_else(|| unexpected_output("go", &stdout))
 }
 
 /// Get module info from go.mod.
@@ -0,0 +1,61 @@
+//! Mercurial repository detection.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::context::{Vcs, VcsInfo};
+
+/// Detect Mercurial repository information.
+///
+/// The branch is read from `.hg/branch` rather than by running `hg`, so
+/// detection stays cheap in every directory it is tried in.
+pub fn detect(dir: &Path) -> Option<VcsInfo> {
+    let hg_dir = find_hg_dir(dir)?;
+
+    Some(VcsInfo {
+        vcs: Vcs::Mercurial,
+        branch: get_branch_from_file(&hg_dir),
+    })
+}
+
+/// Find the .hg directory by walking up from `dir`.
+pub fn find_hg_dir(dir: &Path) -> Option<PathBuf> {
+    let mut current = dir.to_path_buf();
+    loop {
+        let hg_path = current.join(".hg");
+        if hg_path.is_dir() {
+            return Some(hg_path);
+        }
+        if !current.pop() {
+            break;
+        }
+    }
+    None
+}
+
+/// Get current branch from .hg/branch (empty or missing means "default").
+fn get_branch_from_file(hg_dir: &Path) -> String {
+    fs::read_to_string(hg_dir.join("branch"))
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "default".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_branch_from_hg_dir() {
+        let repo = std::env::temp_dir().join(format!("nosh_context_hg_{}", std::process::id()));
+        fs::create_dir_all(repo.join(".hg")).unwrap();
+        fs::create_dir_all(repo.join("src")).unwrap();
+
+        assert_eq!(detect(&repo.join("src")).unwrap().branch, "default");
+        fs::write(repo.join(".hg/branch"), "stable\n").unwrap();
+        assert_eq!(detect(&repo.join("src")).unwrap().branch, "stable");
+
+        let _ = fs::remove_dir_all(&repo);
+    }
+}
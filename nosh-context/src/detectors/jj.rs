@@ -0,0 +1,64 @@
+//! Jujutsu repository detection.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::context::{Vcs, VcsInfo};
+
+/// Detect Jujutsu repository information.
+///
+/// The "branch" is the short change id of the working-copy commit,
+/// which is how jj identifies where you are.
+pub fn detect(dir: &Path) -> Option<VcsInfo> {
+    find_jj_dir(dir)?;
+
+    let branch = get_change_id(dir).unwrap_or_default();
+
+    Some(VcsInfo {
+        vcs: Vcs::Jujutsu,
+        branch,
+    })
+}
+
+/// Find the .jj directory by walking up from `dir`.
+pub fn find_jj_dir(dir: &Path) -> Option<PathBuf> {
+    let mut current = dir.to_path_buf();
+    loop {
+        let jj_path = current.join(".jj");
+        if jj_path.is_dir() {
+            return Some(jj_path);
+        }
+        if !current.pop() {
+            break;
+        }
+    }
+    None
+}
+
+/// Get the short change id of the working-copy commit using jj command.
+fn get_change_id(dir: &Path) -> Option<String> {
+    let output = Command::new("jj")
+        .args([
+            "log",
+            "-r",
+            "@",
+            "--no-graph",
+            "--ignore-working-copy",
+            "-T",
+            "change_id.short()",
+        ])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let change_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if change_id.is_empty() {
+        None
+    } else {
+        Some(change_id)
+    }
+}
@@ -5,9 +5,213 @@
 pub mod bun;
 pub mod cpp;
 pub mod docker;
+pub mod elixir;
 pub mod git;
 pub mod go;
+pub mod hg;
+pub mod jj;
+pub mod nix;
 pub mod node;
 pub mod package;
 pub mod python;
 pub mod rust;
+pub mod ssh;
+pub mod tool_versions;
+pub mod zig;
+
+use std::io::Read;
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::context::DetectorStatus;
+
+/// Default time limit for a detector subprocess.
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// How often a running subprocess is checked for exit.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+static COMMAND_TIMEOUT_MS: AtomicU64 = AtomicU64::new(DEFAULT_COMMAND_TIMEOUT.as_millis() as u64);
+
+/// Set the time limit for version detector subprocesses (process-wide).
+pub fn set_command_timeout(timeout: Duration) {
+    COMMAND_TIMEOUT_MS.store(timeout.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// Current time limit for version detector subprocesses.
+pub fn command_timeout() -> Duration {
+    Duration::from_millis(COMMAND_TIMEOUT_MS.load(Ordering::Relaxed))
+}
+
+/// Outcome of running a single detector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Detection<T> {
+    /// The project indicators matched and the tool was detected.
+    Found(T),
+    /// No project indicators for this detector in the directory.
+    NotApplicable,
+    /// The detector applied but failed (e.g., the tool isn't installed).
+    Error(String),
+}
+
+impl<T> Detection<T> {
+    /// The detected value, dropping the reason it may be missing.
+    pub fn found(self) -> Option<T> {
+        match self {
+            Detection::Found(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Summarise as a status, describing a found value with `describe`.
+    pub fn status(&self, describe: impl FnOnce(&T) -> String) -> DetectorStatus {
+        match self {
+            Detection::Found(value) => DetectorStatus::Found(describe(value)),
+            Detection::NotApplicable => DetectorStatus::NotApplicable,
+            Detection::Error(message) => DetectorStatus::Error(message.clone()),
+        }
+    }
+}
+
+impl<T> From<Result<T, String>> for Detection<T> {
+    fn from(result: Result<T, String>) -> Self {
+        match result {
+            Ok(value) => Detection::Found(value),
+            Err(message) => Detection::Error(message),
+        }
+    }
+}
+
+/// Run a command, killing it if it doesn't exit within `timeout`.
+///
+/// A wedged tool (e.g. a broken version-manager shim) would otherwise
+/// freeze the prompt. Stdout and stderr are captured as with
+/// [`Command::output`].
+pub fn run_with_timeout(cmd: &mut Command, timeout: Duration) -> Result<Output, String> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run: {}", e))?;
+
+    // Drain the pipes on threads so a chatty child can't block on a full pipe
+    let stdout = child.stdout.take().map(spawn_reader);
+    let stderr = child.stderr.take().map(spawn_reader);
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("timed out after {}ms", timeout.as_millis()));
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(e) => return Err(format!("failed to wait: {}", e)),
+        }
+    };
+
+    let collect = |reader: Option<thread::JoinHandle<Vec<u8>>>| {
+        reader
+            .and_then(|handle| handle.join().ok())
+            .unwrap_or_default()
+    };
+    Ok(Output {
+        status,
+        stdout: collect(stdout),
+        stderr: collect(stderr),
+    })
+}
+
+fn spawn_reader(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+/// Run a version command (subject to [`command_timeout`]) and return its
+/// stdout, or a message describing why it failed.
+pub fn run_version_command(program: &str, args: &[&str]) -> Result<String, String> {
+    let display = std::iter::once(program)
+        .chain(args.iter().copied())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let output = run_with_timeout(Command::new(program).args(args), command_timeout())
+        .map_err(|e| format!("`{}` {}", display, e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        let status = match output.status.code() {
+            Some(code) => format!("exited with status {}", code),
+            None => "was killed by a signal".to_string(),
+        };
+        return Err(if stderr.is_empty() {
+            format!("`{}` {}", display, status)
+        } else {
+            format!("`{}` {}: {}", display, status, stderr)
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Error for version output that couldn't be parsed.
+pub(crate) fn unexpected_output(program: &str, stdout: &str) -> String {
+    format!(
+        "unexpected `{}` output: {:?}",
+        program,
+        stdout.lines().next().unwrap_or("").trim()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failing_subprocess_records_error() {
+        let detection: Detection<String> =
+            run_version_command("sh", &["-c", "echo 'no toolchain' >&2; exit 3"]).into();
+        assert_eq!(
+            detection.status(|v| v.clone()),
+            DetectorStatus::Error(
+                "`sh -c echo 'no toolchain' >&2; exit 3` exited with status 3: no toolchain"
+                    .to_string()
+            )
+        );
+        assert_eq!(detection.found(), None);
+
+        let missing = run_version_command("nosh-no-such-tool", &["--version"]).unwrap_err();
+        assert!(missing.starts_with("`nosh-no-such-tool --version` failed to run"));
+
+        let ok: Detection<String> = run_version_command("sh", &["-c", "echo 1.2.3"]).into();
+        assert_eq!(
+            ok.status(|v| v.trim().to_string()),
+            DetectorStatus::Found("1.2.3".to_string())
+        );
+    }
+
+    #[test]
+    fn test_run_with_timeout_kills_slow_command() {
+        let start = Instant::now();
+        let result = run_with_timeout(Command::new("sleep").arg("5"), Duration::from_millis(100));
+        assert_eq!(result.unwrap_err(), "timed out after 100ms");
+        assert!(start.elapsed() < Duration::from_secs(2));
+
+        let output = run_with_timeout(
+            Command::new("sh").args(["-c", "echo fast"]),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "fast\n");
+    }
+}
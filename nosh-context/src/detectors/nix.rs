@@ -0,0 +1,72 @@
+//! Nix shell detection.
+//!
+//! `nix develop`, `nix-shell` and devenv mark the shells they start with
+//! environment variables, so this looks at the environment rather than the
+//! project directory.
+
+use std::path::Path;
+
+/// Derivation names nix uses for shells that weren't given one.
+const GENERIC_NAMES: &[&str] = &["nix-shell", "nix-shell-env", "shell"];
+
+/// Name of the active Nix shell: the devenv directory name, the shell's
+/// derivation `$name`, or "nix". `None` outside a Nix shell.
+pub fn detect() -> Option<String> {
+    detect_from(|key| std::env::var(key).ok())
+}
+
+/// [`detect`] with environment lookups supplied by `var`.
+pub fn detect_from(var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let set = |key: &str| var(key).filter(|v| !v.is_empty());
+
+    if let Some(root) = set("DEVENV_ROOT") {
+        let name = Path::new(&root)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned());
+        return Some(name.unwrap_or_else(|| "devenv".to_string()));
+    }
+
+    set("IN_NIX_SHELL")?;
+    Some(
+        set("name")
+            .filter(|n| !GENERIC_NAMES.contains(&n.as_str()))
+            .unwrap_or_else(|| "nix".to_string()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        detect_from(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn test_nix_shell_detection() {
+        assert_eq!(env(&[]), None);
+        assert_eq!(env(&[("IN_NIX_SHELL", "impure")]), Some("nix".to_string()));
+        assert_eq!(
+            env(&[("IN_NIX_SHELL", "pure"), ("name", "nix-shell-env")]),
+            Some("nix".to_string())
+        );
+        assert_eq!(
+            env(&[("IN_NIX_SHELL", "impure"), ("name", "myapp-dev")]),
+            Some("myapp-dev".to_string())
+        );
+        assert_eq!(
+            env(&[
+                ("DEVENV_ROOT", "/home/me/webapp"),
+                ("IN_NIX_SHELL", "impure")
+            ]),
+            Some("webapp".to_string())
+        );
+        // `$name` alone isn't enough, many tools set it
+        assert_eq!(env(&[("name", "myapp-dev")]), None);
+    }
+}
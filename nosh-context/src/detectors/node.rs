@@ -2,36 +2,34 @@
 
 use std::fs;
 use std::path::Path;
-use std::process::Command;
 
 use crate::context::ToolInfo;
+use crate::detectors::{Detection, run_version_command, unexpected_output};
 
 /// Detect Node.js toolchain information.
-pub fn detect(dir: &Path) -> Option<ToolInfo> {
+pub fn detect(dir: &Path) -> Detection<ToolInfo> {
     // Verify package.json exists
     if !dir.join("package.json").exists() {
-        return None;
+        return Detection::NotApplicable;
     }
 
     // Get node version
-    let version = get_node_version()?;
-
-    Some(ToolInfo { version })
+    get_node_version()
+        .map(|version| ToolInfo { version })
+        .into()
 }
 
 /// Get Node.js version string.
-fn get_node_version() -> Option<String> {
-    let output = Command::new("node").args(["--version"]).output().ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
+fn get_node_version() -> Result<String, String> {
+    let stdout = run_version_command("node", &["--version"])?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
     // Parse "v20.10.0" -> "20.10.0"
     let version = stdout.trim().trim_start_matches('v').to_string();
+    if version.is_empty() {
+        return Err(unexpected_output("node", &stdout));
+    }
 
-    Some(version)
+    Ok(version)
 }
 
 /// Get package info from package.json.
@@ -4,7 +4,7 @@ use std::collections::HashSet;
 use std::path::Path;
 
 use crate::context::PackageInfo;
-use crate::detectors::{go, node, python, rust};
+use crate::detectors::{elixir, go, node, python, rust};
 
 /// Detect package information from any supported project type.
 ///
@@ -13,6 +13,7 @@ use crate::detectors::{go, node, python, rust};
 /// 2. package.json (Node.js)
 /// 3. pyproject.toml (Python)
 /// 4. go.mod (Go)
+/// 5. mix.exs (Elixir)
 pub fn detect(dir: &Path, files: &HashSet<String>) -> Option<PackageInfo> {
     // Try Rust first
     if files.contains("Cargo.toml")
@@ -42,5 +43,12 @@ pub fn detect(dir: &Path, files: &HashSet<String>) -> Option<PackageInfo> {
         return Some(PackageInfo { name, version });
     }
 
+    // Try Elixir
+    if files.contains("mix.exs")
+        && let Some((name, version)) = elixir::get_mix_project(dir)
+    {
+        return Some(PackageInfo { name, version });
+    }
+
     None
 }
@@ -2,45 +2,39 @@
 
 use std::fs;
 use std::path::Path;
-use std::process::Command;
 
 use crate::context::ToolInfo;
+use crate::detectors::{Detection, run_version_command, unexpected_output};
 
 /// Detect Python toolchain information.
-pub fn detect(dir: &Path) -> Option<ToolInfo> {
+pub fn detect(dir: &Path) -> Detection<ToolInfo> {
     // Verify python project files exist
     let has_pyproject = dir.join("pyproject.toml").exists();
     let has_setup = dir.join("setup.py").exists();
     let has_requirements = dir.join("requirements.txt").exists();
 
     if !has_pyproject && !has_setup && !has_requirements {
-        return None;
+        return Detection::NotApplicable;
     }
 
     // Get python version
-    let version = get_python_version()?;
-
-    Some(ToolInfo { version })
+    get_python_version()
+        .map(|version| ToolInfo { version })
+        .into()
 }
 
 /// Get Python version string.
-fn get_python_version() -> Option<String> {
+fn get_python_version() -> Result<String, String> {
     // Try python3 first, then python
-    let output = Command::new("python3")
-        .args(["--version"])
-        .output()
-        .or_else(|_| Command::new("python").args(["--version"]).output())
-        .ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
+    let stdout = run_version_command("python3", &["--version"])
+        .or_else(|_| run_version_command("python", &["--version"]))?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
     // Parse "Python 3.11.6" -> "3.11.6"
-    let version = stdout.split_whitespace().nth(1).map(|s| s.to_string())?;
-
-    Some(version)
+    stdout
+        .split_whitespace()
+        .nth(1)
+        .map(|s| s.to_string())
+        .ok_or_else(|| unexpected_output("python", &stdout))
 }
 
 /// Get package info from pyproject.toml.
@@ -2,36 +2,33 @@
 
 use std::fs;
 use std::path::Path;
-use std::process::Command;
 
 use crate::context::ToolInfo;
+use crate::detectors::{Detection, run_version_command, unexpected_output};
 
 /// Detect Rust toolchain information.
-pub fn detect(dir: &Path) -> Option<ToolInfo> {
+pub fn detect(dir: &Path) -> Detection<ToolInfo> {
     // Verify Cargo.toml exists
     if !dir.join("Cargo.toml").exists() {
-        return None;
+        return Detection::NotApplicable;
     }
 
     // Get rustc version
-    let version = get_rustc_version()?;
-
-    Some(ToolInfo { version })
+    get_rustc_version()
+        .map(|version| ToolInfo { version })
+        .into()
 }
 
 /// Get rustc version string.
-fn get_rustc_version() -> Option<String> {
-    let output = Command::new("rustc").args(["--version"]).output().ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
+fn get_rustc_version() -> Result<String, String> {
+    let stdout = run_version_command("rustc", &["--version"])?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
     // Parse "rustc 1.75.0 (82e1608df 2023-12-21)" -> "1.75.0"
-    let version = stdout.split_whitespace().nth(1).map(|s| s.to_string())?;
-
-    Some(version)
+    stdout
+        .split_whitespace()
+        .nth(1)
+        .map(|s| s.to_string())
+        .ok_or_else(|| unexpected_output("rustc", &stdout))
 }
 
 /// Get package info from Cargo.toml.
@@ -0,0 +1,33 @@
+//! SSH session detection.
+//!
+//! sshd sets `$SSH_CONNECTION` and, with a terminal, `$SSH_TTY` for the
+//! shells it starts, so remote sessions are told apart by environment.
+
+/// Whether the shell is running in an SSH session.
+pub fn is_ssh() -> bool {
+    is_ssh_from(|key| std::env::var(key).ok())
+}
+
+/// [`is_ssh`] with environment lookups supplied by `var`.
+pub fn is_ssh_from(var: impl Fn(&str) -> Option<String>) -> bool {
+    ["SSH_CONNECTION", "SSH_TTY"]
+        .iter()
+        .any(|key| var(key).is_some_and(|v| !v.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_detection() {
+        assert!(!is_ssh_from(|_| None));
+        assert!(is_ssh_from(|key| {
+            (key == "SSH_CONNECTION").then(|| "10.0.0.2 51234 10.0.0.1 22".to_string())
+        }));
+        assert!(is_ssh_from(
+            |key| (key == "SSH_TTY").then(|| "/dev/pts/3".to_string())
+        ));
+        assert!(!is_ssh_from(|key| (key == "SSH_TTY").then(String::new)));
+    }
+}
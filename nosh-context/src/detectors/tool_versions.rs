@@ -0,0 +1,143 @@
+//! asdf/mise `.tool-versions` detection.
+//!
+//! Reports pinned tool versions and flags mismatches against the
+//! runtime versions found by the per-language detectors.
+
+use std::fs;
+use std::path::Path;
+
+use crate::context::{PinnedTool, ProjectContext, ToolInfo};
+
+/// Detect pinned tools from the nearest `.tool-versions` file.
+pub fn detect(dir: &Path, ctx: &ProjectContext) -> Vec<PinnedTool> {
+    let content = match find_tool_versions(dir) {
+        Some(content) => content,
+        None => return Vec::new(),
+    };
+
+    parse(&content)
+        .into_iter()
+        .map(|(tool, versions)| {
+            let active = active_version(&tool, ctx).map(|t| t.version.clone());
+            let mismatch = active
+                .as_deref()
+                .is_some_and(|active| !versions.iter().any(|v| version_matches(v, active)));
+            PinnedTool {
+                tool,
+                version: versions.into_iter().next().unwrap_or_default(),
+                active,
+                mismatch,
+            }
+        })
+        .collect()
+}
+
+/// Read the nearest `.tool-versions`, walking up from `dir`.
+fn find_tool_versions(dir: &Path) -> Option<String> {
+    let mut current = dir.to_path_buf();
+    loop {
+        let path = current.join(".tool-versions");
+        if path.is_file() {
+            return fs::read_to_string(path).ok();
+        }
+        if !current.pop() {
+            break;
+        }
+    }
+    None
+}
+
+/// Parse `.tool-versions` content into (tool, versions) pairs.
+///
+/// Format is `tool version [fallback-version...]` per line; `#` starts a comment.
+pub fn parse(content: &str) -> Vec<(String, Vec<String>)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut parts = line.split_whitespace();
+            let tool = parts.next()?.to_string();
+            let versions: Vec<String> = parts.map(|s| s.to_string()).collect();
+            if versions.is_empty() {
+                None
+            } else {
+                Some((tool, versions))
+            }
+        })
+        .collect()
+}
+
+/// Map an asdf plugin name to the detected runtime, if any.
+fn active_version<'a>(tool: &str, ctx: &'a ProjectContext) -> Option<&'a ToolInfo> {
+    match tool {
+        "nodejs" | "node" => ctx.node.as_ref(),
+        "python" => ctx.python.as_ref(),
+        "rust" => ctx.rust.as_ref(),
+        "golang" | "go" => ctx.go.as_ref(),
+        "bun" => ctx.bun.as_ref(),
+        "zig" => ctx.zig.as_ref(),
+        "elixir" => ctx.elixir.as_ref(),
+        _ => None,
+    }
+}
+
+/// Check whether a pinned version is satisfied by the active version.
+///
+/// "system" always matches, and a shorter pin like "3.12" matches "3.12.1".
+fn version_matches(pinned: &str, active: &str) -> bool {
+    pinned == "system"
+        || pinned == active
+        || active
+            .strip_prefix(pinned)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_two_tools() {
+        let dir =
+            std::env::temp_dir().join(format!("nosh_context_tool_versions_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join(".tool-versions"),
+            "# pinned tooling\nnodejs 20.11.0\npython 3.12 3.11.7  # fallback\n",
+        )
+        .unwrap();
+
+        let ctx = ProjectContext {
+            node: Some(ToolInfo {
+                version: "18.19.0".to_string(),
+            }),
+            python: Some(ToolInfo {
+                version: "3.12.1".to_string(),
+            }),
+            ..Default::default()
+        };
+
+        let pinned = detect(&dir, &ctx);
+        assert_eq!(pinned.len(), 2);
+
+        assert_eq!(pinned[0].tool, "nodejs");
+        assert_eq!(pinned[0].version, "20.11.0");
+        assert_eq!(pinned[0].active.as_deref(), Some("18.19.0"));
+        assert!(pinned[0].mismatch);
+
+        assert_eq!(pinned[1].tool, "python");
+        assert_eq!(pinned[1].version, "3.12");
+        assert!(!pinned[1].mismatch);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_ignores_comments_and_blank_lines() {
+        let parsed = parse("\n# comment\nruby\nzig 0.13.0\n");
+        assert_eq!(
+            parsed,
+            vec![("zig".to_string(), vec!["0.13.0".to_string()])]
+        );
+    }
+}
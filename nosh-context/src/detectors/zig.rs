@@ -0,0 +1,55 @@
+//! Zig project detection.
+
+use std::path::Path;
+
+use crate::context::ToolInfo;
+use crate::detectors::{Detection, run_version_command, unexpected_output};
+
+/// Detect Zig toolchain information.
+pub fn detect(dir: &Path) -> Detection<ToolInfo> {
+    // Verify build.zig exists
+    if !dir.join("build.zig").exists() {
+        return Detection::NotApplicable;
+    }
+
+    // Get zig version
+    get_zig_version().map(|version| ToolInfo { version }).into()
+}
+
+/// Get Zig version string.
+fn get_zig_version() -> Result<String, String> {
+    let stdout = run_version_command("zig", &["version"])?;
+    parse_zig_version(&stdout).ok_or_else(|| unexpected_output("zig", &stdout))
+}
+
+/// Parse `zig version` output ("0.13.0") into a version string.
+fn parse_zig_version(stdout: &str) -> Option<String> {
+    let version = stdout.trim();
+    if version.is_empty() {
+        None
+    } else {
+        Some(version.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_detect_requires_build_zig() {
+        let dir = std::env::temp_dir().join(format!("nosh_context_zig_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        assert!(matches!(detect(&dir), Detection::NotApplicable));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_zig_version() {
+        assert_eq!(parse_zig_version("0.13.0\n").as_deref(), Some("0.13.0"));
+        assert_eq!(parse_zig_version("  \n"), None);
+    }
+}
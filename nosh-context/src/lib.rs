@@ -2,9 +2,10 @@
 //!
 //! This library provides fast, cached project context detection for shell prompts.
 //! It detects:
-//! - Git branch and status
+//! - Git branch and status (with Mercurial and Jujutsu fallbacks)
 //! - Package information (from Cargo.toml, package.json, etc.)
-//! - Language/tool versions (Rust, Node.js, Go, Python)
+//! - Language/tool versions (Rust, Node.js, Go, Python, Zig, Elixir)
+//! - Pinned versions from `.tool-versions` (asdf/mise)
 //!
 //! # Example
 //!
@@ -22,6 +23,12 @@ mod context;
 pub mod detectors;
 mod scanner;
 
-pub use cache::ContextCache;
-pub use context::{GitInfo, PackageInfo, ProjectContext, ToolInfo};
-pub use scanner::detect;
+pub use cache::{ContextCache, DEFAULT_CAPACITY as DEFAULT_CONTEXT_CACHE_SIZE};
+pub use context::{
+    ComposeInfo, DetectorReport, DetectorStatus, GitInfo, GitStatusCounts, PackageInfo, PinnedTool,
+    ProjectContext, ToolInfo, Vcs, VcsInfo, detector_timings,
+};
+pub use detectors::docker::set_compose_ps;
+pub use detectors::git::{set_fetch_stale_after, set_git_fetch_age, set_git_tag};
+pub use detectors::{DEFAULT_COMMAND_TIMEOUT, set_command_timeout};
+pub use scanner::{detect, detect_nearest, detect_with_report};
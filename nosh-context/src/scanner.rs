@@ -3,18 +3,91 @@
 use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
-use crate::context::ProjectContext;
-use crate::detectors::{bun, cpp, docker, git, go, node, package, python, rust};
+use crate::context::{
+    DetectorReport, DetectorStatus, GitInfo, ProjectContext, ToolInfo, Vcs, VcsInfo,
+};
+use crate::detectors::{
+    Detection, bun, cpp, docker, elixir, git, go, hg, jj, node, package, python, rust,
+    tool_versions, zig,
+};
+
+/// Manifest files checked directly by name, independent of directory size.
+const MANIFEST_FILES: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "bun.lockb",
+    "bun.lock",
+    "bunfig.toml",
+    "go.mod",
+    "pyproject.toml",
+    "setup.py",
+    "requirements.txt",
+    "CMakeLists.txt",
+    "meson.build",
+    "conanfile.txt",
+    "conanfile.py",
+    "Makefile",
+    "makefile",
+    "Dockerfile",
+    ".dockerignore",
+    "docker-compose.yml",
+    "docker-compose.yaml",
+    "compose.yml",
+    "compose.yaml",
+    "build.zig",
+    "mix.exs",
+    ".git",
+];
+
+/// Manifests that mark a project root when walking up from a subdirectory.
+const PROJECT_MARKERS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "go.mod",
+    "pyproject.toml",
+    "setup.py",
+    "CMakeLists.txt",
+    "meson.build",
+    "build.zig",
+    "mix.exs",
+];
+
+/// Maximum directory entries read for pattern-based indicators (e.g., `*.cpp`).
+const MAX_SCAN_ENTRIES: usize = 512;
+
+/// Entries never considered when scanning (large, generated, or VCS internals).
+const IGNORED_ENTRIES: &[&str] = &[
+    "node_modules",
+    "target",
+    ".git",
+    ".hg",
+    ".jj",
+    "vendor",
+    "dist",
+    "build",
+    "__pycache__",
+    ".venv",
+];
 
 /// Detect project context from a directory.
 ///
-/// This performs a single directory scan and then conditionally
-/// parses only the detected project files.
+/// Manifest files are checked by name and a bounded, non-recursive scan
+/// covers pattern-based indicators, so the cost per prompt stays
+/// predictable regardless of directory size. Only detected project files
+/// are then parsed. Detector failures are dropped; use
+/// [`detect_with_report`] to see them.
 pub fn detect(dir: &Path) -> ProjectContext {
+    detect_with_report(dir).0
+}
+
+/// Detect project context, also returning the status of each detector
+/// (found, not applicable, or the error that stopped it).
+pub fn detect_with_report(dir: &Path) -> (ProjectContext, Vec<DetectorReport>) {
     let dir_str = dir.display().to_string();
 
-    // 1. Single readdir - collect all filenames
+    // 1. Manifest lookups plus a bounded readdir
     let files = read_dir_names(dir);
 
     // 2. Check indicators (no I/O, just HashSet lookups)
@@ -40,38 +113,77 @@ pub fn detect(dir: &Path) -> ProjectContext {
         || files.contains("compose.yml")
         || files.contains("compose.yaml")
         || files.iter().any(|f| f.starts_with("Dockerfile."));
+    let has_zig = files.contains("build.zig");
+    let has_mix = files.contains("mix.exs");
     let has_git = files.contains(".git") || is_in_git_repo(dir);
 
     // 3. Parse only detected files
-    let git_info = if has_git { git::detect(dir) } else { None };
-    let package_info = package::detect(dir, &files);
-    let rust_info = if has_cargo { rust::detect(dir) } else { None };
-    let node_info = if has_package_json {
-        node::detect(dir)
+    let (git_info, git_elapsed) = if has_git {
+        let (info, elapsed) = timed(|| git::detect(dir));
+        (info, Some(elapsed))
     } else {
-        None
+        (None, None)
     };
-    let bun_info = if has_bun { bun::detect(dir) } else { None };
-    let go_info = if has_go_mod { go::detect(dir) } else { None };
-    let python_info = if has_python {
-        python::detect(dir)
-    } else {
-        None
-    };
-    let cpp_info = if has_cpp {
-        cpp::detect(dir, &files)
-    } else {
-        None
-    };
-    let docker_info = if has_docker {
-        docker::detect(dir, &files)
-    } else {
-        None
+    let (vcs_info, vcs_elapsed) =
+        timed(|| detect_vcs(dir, git_info.as_ref().map(|g| g.branch.as_str())));
+    let (package_info, package_elapsed) = timed(|| package::detect(dir, &files));
+    let mut report = vec![
+        found_report(
+            "git",
+            git_info.as_ref().map(|g| g.branch.clone()),
+            git_elapsed,
+        ),
+        found_report(
+            "vcs",
+            vcs_info
+                .as_ref()
+                .map(|v| format!("{} {}", v.vcs.name(), v.branch)),
+            Some(vcs_elapsed),
+        ),
+        found_report(
+            "package",
+            package_info
+                .as_ref()
+                .map(|p| format!("{} {}", p.name, p.version).trim().to_string()),
+            Some(package_elapsed),
+        ),
+    ];
+    let mut run = |name: &str, applies: bool, detect: &dyn Fn() -> Detection<ToolInfo>| {
+        let (detection, elapsed) = if applies {
+            let (detection, elapsed) = timed(detect);
+            (detection, Some(elapsed))
+        } else {
+            (Detection::NotApplicable, None)
+        };
+        report.push(DetectorReport {
+            detector: name.to_string(),
+            status: detection.status(|t| t.version.clone()),
+            elapsed,
+        });
+        detection.found()
     };
 
-    ProjectContext {
+    let rust_info = run("rust", has_cargo, &|| rust::detect(dir));
+    let node_info = run("node", has_package_json, &|| node::detect(dir));
+    let bun_info = run("bun", has_bun, &|| bun::detect(dir));
+    let go_info = run("go", has_go_mod, &|| go::detect(dir));
+    let python_info = run("python", has_python, &|| python::detect(dir));
+    let cpp_info = run("cpp", has_cpp, &|| cpp::detect(dir, &files));
+    let docker_info = run("docker", has_docker, &|| docker::detect(dir, &files));
+    let zig_info = run("zig", has_zig, &|| zig::detect(dir));
+    let elixir_info = run("elixir", has_mix, &|| elixir::detect(dir));
+    let (compose_info, compose_elapsed) = timed(|| docker::detect_compose(dir, &files));
+    report.push(found_report(
+        "compose",
+        compose_info.as_ref().map(|c| c.project.clone()),
+        Some(compose_elapsed),
+    ));
+
+    let mut ctx = ProjectContext {
         dir: dir_str,
+        workspace_root: None,
         git: git_info,
+        vcs: vcs_info,
         package: package_info,
         rust: rust_info,
         node: node_info,
@@ -80,16 +192,105 @@ pub fn detect(dir: &Path) -> ProjectContext {
         python: python_info,
         cpp: cpp_info,
         docker: docker_info,
+        compose: compose_info,
+        zig: zig_info,
+        elixir: elixir_info,
+        pinned: Vec::new(),
+    };
+
+    // 4. Compare pinned versions against the detected runtimes
+    ctx.pinned = tool_versions::detect(dir, &ctx);
+
+    (ctx, report)
+}
+
+/// Detect the context of the innermost project containing `path`.
+///
+/// Walks up from `path` to the first directory with a project manifest,
+/// stopping at the git root, and detects that directory. The outermost
+/// project root (the git root, or else the topmost manifest) is reported as
+/// `workspace_root`, so a sub-package of a Cargo or pnpm workspace knows
+/// both. Falls back to `path` itself when no project encloses it.
+pub fn detect_nearest(path: &Path) -> ProjectContext {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let mut innermost = None;
+    let mut outermost = None;
+    for dir in path.ancestors() {
+        let is_git_root = dir.join(".git").exists();
+        if is_git_root || PROJECT_MARKERS.iter().any(|name| dir.join(name).is_file()) {
+            innermost.get_or_insert(dir);
+            outermost = Some(dir);
+        }
+        if is_git_root {
+            break;
+        }
+    }
+
+    let mut ctx = detect(innermost.unwrap_or(&path));
+    ctx.workspace_root = outermost.map(|dir| dir.display().to_string());
+    ctx
+}
+
+/// Detect only the version control part of a context (`git` and `vcs`).
+///
+/// Used by the cache to refresh git info after a branch switch without
+/// re-running the tool detectors.
+pub(crate) fn detect_vcs_context(dir: &Path) -> (Option<GitInfo>, Option<VcsInfo>) {
+    let git_info = if is_in_git_repo(dir) {
+        git::detect(dir)
+    } else {
+        None
+    };
+    let vcs_info = detect_vcs(dir, git_info.as_ref().map(|g| g.branch.as_str()));
+    (git_info, vcs_info)
+}
+
+/// Report for a detector without error reporting (found or not).
+fn found_report(name: &str, summary: Option<String>, elapsed: Option<Duration>) -> DetectorReport {
+    DetectorReport {
+        detector: name.to_string(),
+        status: summary.map_or(DetectorStatus::NotApplicable, DetectorStatus::Found),
+        elapsed,
+    }
+}
+
+/// Run `f`, returning its result and how long it took.
+fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let value = f();
+    (value, start.elapsed())
+}
+
+/// Determine the active VCS. Git is the default fast path; Jujutsu and
+/// Mercurial are only probed when no git repository was found.
+fn detect_vcs(dir: &Path, git_branch: Option<&str>) -> Option<VcsInfo> {
+    if let Some(branch) = git_branch {
+        return Some(VcsInfo {
+            vcs: Vcs::Git,
+            branch: branch.to_string(),
+        });
     }
+
+    jj::detect(dir).or_else(|| hg::detect(dir))
 }
 
-/// Read all filenames in a directory into a HashSet.
+/// Collect indicator filenames in a directory into a HashSet.
+///
+/// Manifest files are found by direct lookup; other names come from at most
+/// `MAX_SCAN_ENTRIES` directory entries, skipping ignored entries. The scan
+/// never descends into subdirectories.
 fn read_dir_names(dir: &Path) -> HashSet<String> {
-    let mut names = HashSet::new();
+    let mut names: HashSet<String> = MANIFEST_FILES
+        .iter()
+        .filter(|name| dir.join(name).exists())
+        .map(|name| name.to_string())
+        .collect();
 
     if let Ok(entries) = fs::read_dir(dir) {
-        for entry in entries.flatten() {
-            if let Some(name) = entry.file_name().to_str() {
+        for entry in entries.take(MAX_SCAN_ENTRIES).flatten() {
+            if let Some(name) = entry.file_name().to_str()
+                && !IGNORED_ENTRIES.contains(&name)
+            {
                 names.insert(name.to_string());
             }
         }
@@ -111,3 +312,127 @@ fn is_in_git_repo(dir: &Path) -> bool {
     }
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("nosh_context_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_read_dir_names_is_bounded() {
+        let dir = temp_dir("bounded");
+        for i in 0..(MAX_SCAN_ENTRIES * 3) {
+            fs::write(dir.join(format!("file_{}.txt", i)), "").unwrap();
+        }
+        fs::write(dir.join("Cargo.toml"), "").unwrap();
+        fs::create_dir_all(dir.join("node_modules").join("pkg")).unwrap();
+
+        let names = read_dir_names(&dir);
+        assert!(names.len() <= MAX_SCAN_ENTRIES + MANIFEST_FILES.len());
+        // Manifests are found regardless of where they fall in readdir order
+        assert!(names.contains("Cargo.toml"));
+        assert!(!names.contains("node_modules"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_detect_vcs_mercurial() {
+        let dir = temp_dir("hg");
+        fs::create_dir_all(dir.join(".hg")).unwrap();
+        fs::create_dir_all(dir.join("sub")).unwrap();
+
+        let info = detect_vcs(&dir.join("sub"), None).unwrap();
+        assert_eq!(info.vcs, Vcs::Mercurial);
+        assert_eq!(info.vcs.name(), "hg");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_detect_vcs_jujutsu() {
+        let dir = temp_dir("jj");
+        fs::create_dir_all(dir.join(".jj")).unwrap();
+
+        let info = detect_vcs(&dir, None).unwrap();
+        assert_eq!(info.vcs, Vcs::Jujutsu);
+        assert_eq!(info.vcs.name(), "jj");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_timings_cover_detectors_that_ran() {
+        let dir = temp_dir("timings");
+        fs::write(dir.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+
+        let (_, report) = detect_with_report(&dir);
+        let timings = crate::detector_timings(&report);
+        for entry in &report {
+            let ran = entry.status != DetectorStatus::NotApplicable
+                || ["vcs", "package", "compose"].contains(&entry.detector.as_str());
+            if ran {
+                assert!(timings.contains_key(&entry.detector), "{}", entry.detector);
+            }
+        }
+        assert!(timings.contains_key("rust"));
+        assert!(!timings.contains_key("node"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_detect_nearest_finds_inner_package() {
+        // A git root ends the walk, so manifests above the temp dir don't count
+        let dir = temp_dir("nearest");
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(
+            dir.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/inner\"]\n",
+        )
+        .unwrap();
+        let inner = dir.join("crates").join("inner");
+        fs::create_dir_all(inner.join("src").join("bin")).unwrap();
+        fs::write(
+            inner.join("Cargo.toml"),
+            "[package]\nname = \"inner\"\nversion = \"0.2.0\"\n",
+        )
+        .unwrap();
+
+        let ctx = detect_nearest(&inner.join("src").join("bin"));
+        assert_eq!(ctx.dir, inner.display().to_string());
+        assert_eq!(ctx.package.as_ref().map(|p| p.name.as_str()), Some("inner"));
+        assert_eq!(ctx.workspace_root, Some(dir.display().to_string()));
+
+        // Without a manifest below the git root, the git root is the project
+        let outside = temp_dir("nearest_none");
+        fs::create_dir_all(outside.join(".git")).unwrap();
+        fs::create_dir_all(outside.join("notes")).unwrap();
+        let ctx = detect_nearest(&outside.join("notes"));
+        assert_eq!(ctx.dir, outside.display().to_string());
+        assert!(ctx.package.is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+        let _ = fs::remove_dir_all(&outside);
+    }
+
+    #[test]
+    fn test_detect_vcs_git_wins() {
+        let dir = temp_dir("git_jj");
+        fs::create_dir_all(dir.join(".jj")).unwrap();
+
+        let info = detect_vcs(&dir, Some("main")).unwrap();
+        assert_eq!(info.vcs, Vcs::Git);
+        assert_eq!(info.branch, "main");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -6,7 +6,10 @@
 use anyhow::Result;
 use std::time::{Duration, Instant};
 
-use crate::safety::{PermissionStore, RiskLevel, parse_command};
+use super::SavedSession;
+use crate::safety::{
+    PermissionStore, RiskLevel, apply_mode, is_auto_approved, is_granted, parse_command,
+};
 
 /// Result of a single agentic step.
 #[derive(Debug, Clone)]
@@ -38,6 +41,10 @@ pub enum CommandPermission {
 pub struct AgenticConfig {
     pub max_iterations: usize,
     pub timeout_seconds: u64,
+    /// Require approval for every non-safe command (paranoid mode)
+    pub paranoid: bool,
+    /// Commands strictly below this risk level run without approval
+    pub auto_approve_below: RiskLevel,
 }
 
 impl Default for AgenticConfig {
@@ -45,6 +52,8 @@ impl Default for AgenticConfig {
         Self {
             max_iterations: 10,
             timeout_seconds: 0, // 0 = no timeout
+            paranoid: false,
+            auto_approve_below: RiskLevel::Low,
         }
     }
 }
@@ -54,8 +63,10 @@ pub struct AgenticSession {
     config: AgenticConfig,
     iterations: usize,
     start_time: Instant,
-    /// Commands executed and their outputs
-    history: Vec<(String, String)>,
+    /// Commands executed, their outputs and why the AI ran them
+    history: Vec<(String, String, Option<String>)>,
+    /// The AI's final response, once it gave one
+    answer: Option<String>,
 }
 
 impl AgenticSession {
@@ -66,6 +77,7 @@ impl AgenticSession {
             iterations: 0,
             start_time: Instant::now(),
             history: Vec::new(),
+            answer: None,
         }
     }
 
@@ -103,8 +115,29 @@ impl AgenticSession {
     }
 
     /// Record a command execution.
-    pub fn record_execution(&mut self, command: &str, output: &str) {
-        self.history.push((command.to_string(), output.to_string()));
+    pub fn record_execution(&mut self, command: &str, reasoning: Option<&str>, output: &str) {
+        self.history.push((
+            command.to_string(),
+            output.to_string(),
+            reasoning.map(str::to_string),
+        ));
+    }
+
+    /// Record the AI's final response.
+    pub fn record_answer(&mut self, message: &str) {
+        self.answer = Some(message.to_string());
+    }
+
+    /// Snapshot the commands run so far for saving and later replay.
+    pub fn to_saved(&self, query: &str, cwd: &str) -> SavedSession {
+        SavedSession {
+            query: query.to_string(),
+            cwd: cwd.to_string(),
+            saved_at: chrono::Local::now().to_rfc3339(),
+            commands: self.history.iter().map(|(cmd, _, _)| cmd.clone()).collect(),
+            reasoning: self.history.iter().map(|(_, _, why)| why.clone()).collect(),
+            answer: self.answer.clone(),
+        }
     }
 
     /// Check if a command is allowed to run.
@@ -114,23 +147,22 @@ impl AgenticSession {
         cwd: &str,
         permissions: &PermissionStore,
     ) -> CommandPermission {
-        let parsed = parse_command(command);
+        let parsed = apply_mode(parse_command(command), self.config.paranoid);
 
         match parsed.risk_level {
-            RiskLevel::Safe => CommandPermission::Allowed,
             RiskLevel::Blocked | RiskLevel::Critical => CommandPermission::Blocked,
+            level
+                if is_auto_approved(
+                    level,
+                    self.config.auto_approve_below,
+                    self.config.paranoid,
+                ) =>
+            {
+                CommandPermission::Allowed
+            }
             _ => {
                 // Check if command is already allowed
-                if permissions
-                    .is_command_allowed(&parsed.info.command, &parsed.info.command_pattern)
-                    || permissions.are_affected_paths_allowed(
-                        &parsed.info.command,
-                        &parsed.info.command_pattern,
-                        &parsed.info.affected_paths,
-                        cwd,
-                    )
-                    || permissions.is_directory_allowed(cwd)
-                {
+                if is_granted(&parsed, permissions, cwd, self.config.paranoid) {
                     CommandPermission::Allowed
                 } else {
                     CommandPermission::NeedsApproval
@@ -149,6 +181,8 @@ mod tests {
         let config = AgenticConfig {
             max_iterations: 3,
             timeout_seconds: 0,
+            paranoid: false,
+            auto_approve_below: RiskLevel::Low,
         };
         let mut session = AgenticSession::new(config);
 
@@ -162,9 +196,58 @@ mod tests {
     #[test]
     fn test_record_execution() {
         let mut session = AgenticSession::new(AgenticConfig::default());
-        session.record_execution("ls -la", "file1.txt\nfile2.txt");
+        session.record_execution("ls -la", None, "file1.txt\nfile2.txt");
 
         assert_eq!(session.history.len(), 1);
         assert_eq!(session.history[0].0, "ls -la");
     }
+
+    #[test]
+    fn test_paranoid_requires_approval_for_low_risk() {
+        let mut permissions = PermissionStore::default();
+        permissions.allow_command("rm", true);
+
+        let session = AgenticSession::new(AgenticConfig::default());
+        assert!(matches!(
+            session.check_permission("rm notes.txt", "/tmp", &permissions),
+            CommandPermission::Allowed
+        ));
+
+        let session = AgenticSession::new(AgenticConfig {
+            paranoid: true,
+            ..AgenticConfig::default()
+        });
+        assert!(matches!(
+            session.check_permission("rm notes.txt", "/tmp", &permissions),
+            CommandPermission::NeedsApproval
+        ));
+        assert!(matches!(
+            session.check_permission("ls", "/tmp", &permissions),
+            CommandPermission::Allowed
+        ));
+    }
+
+    #[test]
+    fn test_auto_approve_below_threshold() {
+        let permissions = PermissionStore::default();
+
+        let session = AgenticSession::new(AgenticConfig::default());
+        assert!(matches!(
+            session.check_permission("rm -r build", "/tmp", &permissions),
+            CommandPermission::NeedsApproval
+        ));
+
+        let session = AgenticSession::new(AgenticConfig {
+            auto_approve_below: RiskLevel::High,
+            ..AgenticConfig::default()
+        });
+        assert!(matches!(
+            session.check_permission("rm -r build", "/tmp", &permissions),
+            CommandPermission::Allowed
+        ));
+        assert!(matches!(
+            session.check_permission("rm -rf /", "/tmp", &permissions),
+            CommandPermission::Blocked
+        ));
+    }
 }
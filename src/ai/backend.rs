@@ -0,0 +1,158 @@
+//! Backends that answer `?` and `??` queries.
+//!
+//! The REPL talks to the AI through [`AiBackend`] so scripted sessions
+//! (`nosh --script FILE`) can swap the cloud for [`StubBackend`] and run
+//! without a network.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+
+use anyhow::{Result, anyhow};
+
+use super::agentic::AgenticStep;
+use super::cloud::CloudClient;
+use super::context::ConversationContext;
+
+/// Boxed future returned by [`AiBackend`] methods.
+pub type AiFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + 'a>>;
+
+/// Something that can translate queries and drive agentic sessions.
+pub trait AiBackend {
+    /// Translate a `?` query into a command, with the tokens remaining.
+    fn translate<'a>(
+        &'a self,
+        input: &'a str,
+        cwd: &'a str,
+        context: Option<&'a ConversationContext>,
+    ) -> AiFuture<'a, (String, i32)>;
+
+    /// Next step of a `??` session given the commands run so far.
+    fn agentic_step<'a>(
+        &'a self,
+        input: &'a str,
+        cwd: &'a str,
+        context: Option<&'a ConversationContext>,
+        executions: &'a [(String, String, i32)],
+    ) -> AiFuture<'a, AgenticStep>;
+}
+
+impl AiBackend for CloudClient {
+    fn translate<'a>(
+        &'a self,
+        input: &'a str,
+        cwd: &'a str,
+        context: Option<&'a ConversationContext>,
+    ) -> AiFuture<'a, (String, i32)> {
+        Box::pin(CloudClient::translate(self, input, cwd, context))
+    }
+
+    fn agentic_step<'a>(
+        &'a self,
+        input: &'a str,
+        cwd: &'a str,
+        context: Option<&'a ConversationContext>,
+        executions: &'a [(String, String, i32)],
+    ) -> AiFuture<'a, AgenticStep> {
+        Box::pin(CloudClient::agentic_step(
+            self, input, cwd, context, executions,
+        ))
+    }
+}
+
+/// Reply from [`StubBackend`] once its canned responses run out.
+const STUB_UNAVAILABLE: &str = "AI is not available in scripted sessions.";
+
+/// Offline backend for scripted sessions and tests.
+///
+/// Answers with canned responses in order; once they run out, `?` queries
+/// fail and `??` sessions end without a request.
+#[derive(Default)]
+pub struct StubBackend {
+    translations: RefCell<VecDeque<String>>,
+    steps: RefCell<VecDeque<AgenticStep>>,
+}
+
+impl StubBackend {
+    /// A stub with no canned responses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer `?` queries with `commands`, one per query.
+    #[cfg(test)]
+    pub fn with_translations(mut self, commands: impl IntoIterator<Item = String>) -> Self {
+        self.translations.get_mut().extend(commands);
+        self
+    }
+
+    /// Answer `??` steps with `steps`, one per step.
+    #[cfg(test)]
+    pub fn with_steps(mut self, steps: impl IntoIterator<Item = AgenticStep>) -> Self {
+        self.steps.get_mut().extend(steps);
+        self
+    }
+}
+
+impl AiBackend for StubBackend {
+    fn translate<'a>(
+        &'a self,
+        _input: &'a str,
+        _cwd: &'a str,
+        _context: Option<&'a ConversationContext>,
+    ) -> AiFuture<'a, (String, i32)> {
+        let next = self.translations.borrow_mut().pop_front();
+        Box::pin(async move {
+            match next {
+                Some(command) => Ok((command, 0)),
+                None => Err(anyhow!(STUB_UNAVAILABLE)),
+            }
+        })
+    }
+
+    fn agentic_step<'a>(
+        &'a self,
+        _input: &'a str,
+        _cwd: &'a str,
+        _context: Option<&'a ConversationContext>,
+        _executions: &'a [(String, String, i32)],
+    ) -> AiFuture<'a, AgenticStep> {
+        let next = self.steps.borrow_mut().pop_front();
+        Box::pin(async move {
+            Ok(next.unwrap_or_else(|| AgenticStep::FinalResponse {
+                message: STUB_UNAVAILABLE.to_string(),
+            }))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stub_replays_canned_responses() {
+        let stub = StubBackend::new()
+            .with_translations(["ls -la".to_string()])
+            .with_steps([AgenticStep::RunCommand {
+                command: "git status".to_string(),
+                reasoning: None,
+            }]);
+
+        let (command, _) = stub.translate("list files", "/", None).await.unwrap();
+        assert_eq!(command, "ls -la");
+        assert!(stub.translate("list files", "/", None).await.is_err());
+
+        let step = stub
+            .agentic_step("check repo", "/", None, &[])
+            .await
+            .unwrap();
+        assert!(matches!(step, AgenticStep::RunCommand { command, .. } if command == "git status"));
+        let step = stub
+            .agentic_step("check repo", "/", None, &[])
+            .await
+            .unwrap();
+        assert!(matches!(step, AgenticStep::FinalResponse { .. }));
+    }
+}
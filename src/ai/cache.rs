@@ -0,0 +1,153 @@
+//! Local cache of AI translations.
+//!
+//! With `[ai] cache_translations = true`, `?` queries are looked up here
+//! by normalized input and working directory before calling the cloud.
+//! Follow-ups depend on the conversation so far and are never cached.
+
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension, params};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// SQLite-backed translation cache.
+pub struct TranslationCache {
+    conn: Connection,
+}
+
+impl TranslationCache {
+    /// Open or create the cache database.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Open a throwaway in-memory cache.
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS translations (
+                input TEXT NOT NULL,
+                context TEXT NOT NULL,
+                command TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (input, context)
+            );",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Cached command for `input` in `cwd`, if stored within `ttl`.
+    pub fn get(&self, input: &str, cwd: &str, ttl: Duration) -> Option<String> {
+        let oldest = now() - ttl.as_secs() as i64;
+        self.conn
+            .query_row(
+                "SELECT command FROM translations
+                 WHERE input = ?1 AND context = ?2 AND created_at >= ?3",
+                params![normalize_input(input), context_key(cwd), oldest],
+                |row| row.get(0),
+            )
+            .optional()
+            .ok()
+            .flatten()
+    }
+
+    /// Store the translation of `input` in `cwd`.
+    pub fn put(&self, input: &str, cwd: &str, command: &str) -> Result<()> {
+        self.put_at(input, cwd, command, now())
+    }
+
+    fn put_at(&self, input: &str, cwd: &str, command: &str, created_at: i64) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO translations (input, context, command, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                normalize_input(input),
+                context_key(cwd),
+                command,
+                created_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Remove every cached translation, returning how many were dropped.
+    pub fn clear(&self) -> Result<usize> {
+        Ok(self.conn.execute("DELETE FROM translations", [])?)
+    }
+}
+
+/// Normalize a query so trivial differences share a cache entry:
+/// lowercase, collapsed whitespace, no trailing `?`, `.` or `!`.
+pub fn normalize_input(input: &str) -> String {
+    input
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+        .trim_end_matches(['?', '.', '!'])
+        .trim_end()
+        .to_string()
+}
+
+/// Stable hash of the directory a query was asked in (FNV-1a).
+fn context_key(cwd: &str) -> String {
+    let hash = cwd.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_input() {
+        assert_eq!(
+            normalize_input("  Find   large FILES here? "),
+            "find large files here"
+        );
+        assert_eq!(normalize_input("list ports!!"), "list ports");
+        assert_eq!(normalize_input("what's ?? up"), "what's ?? up");
+    }
+
+    #[test]
+    fn test_cache_hit_and_miss() {
+        let cache = TranslationCache::open_in_memory().unwrap();
+        let day = Duration::from_secs(86_400);
+
+        assert_eq!(cache.get("find big files", "/srv", day), None);
+
+        cache
+            .put("find big files", "/srv", "du -ah . | sort -h")
+            .unwrap();
+        assert_eq!(
+            cache.get("Find  big files?", "/srv", day).as_deref(),
+            Some("du -ah . | sort -h")
+        );
+        // Different directory, different entry
+        assert_eq!(cache.get("find big files", "/home", day), None);
+
+        // Entries older than the TTL are ignored
+        cache
+            .put_at("show disk usage", "/srv", "df -h", now() - 2 * 86_400)
+            .unwrap();
+        assert_eq!(cache.get("show disk usage", "/srv", day), None);
+
+        assert_eq!(cache.clear().unwrap(), 2);
+        assert_eq!(cache.get("find big files", "/srv", day), None);
+    }
+}
@@ -71,6 +71,8 @@ struct CompleteRequest {
     arch: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     context: Option<Vec<ContextExchange>>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    instructions: String,
 }
 
 #[derive(Deserialize)]
@@ -107,6 +109,8 @@ struct AgenticRequest {
     context: Option<Vec<ContextExchange>>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     executions: Vec<AgenticExecution>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    instructions: String,
 }
 
 #[derive(Deserialize)]
@@ -128,6 +132,8 @@ pub struct CloudClient {
     client: Client,
     base_url: String,
     token: RefCell<String>,
+    /// Extra user instructions (`[ai] instructions`) sent with AI requests
+    instructions: String,
 }
 
 impl CloudClient {
@@ -136,9 +142,16 @@ impl CloudClient {
             client: Client::new(),
             base_url: crate::config::cloud_url(),
             token: RefCell::new(token.to_string()),
+            instructions: String::new(),
         }
     }
 
+    /// Send extra instructions ("prefer ripgrep over grep") with AI requests.
+    pub fn with_instructions(mut self, instructions: &str) -> Self {
+        self.instructions = instructions.trim().to_string();
+        self
+    }
+
     fn current_token(&self) -> String {
         self.token.borrow().clone()
     }
@@ -201,6 +214,7 @@ impl CloudClient {
             os: get_os_info(),
             arch: std::env::consts::ARCH.to_string(),
             context: context_exchanges,
+            instructions: self.instructions.clone(),
         };
 
         let mut response = self
@@ -435,6 +449,7 @@ impl CloudClient {
             arch: std::env::consts::ARCH.to_string(),
             context: context_exchanges,
             executions: exec_list,
+            instructions: self.instructions.clone(),
         };
 
         let mut response = self
@@ -524,3 +539,39 @@ impl CloudClient {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instructions_serialized_when_set() {
+        let request = |instructions: &str| CompleteRequest {
+            input: "find big files".to_string(),
+            cwd: "/tmp".to_string(),
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            context: None,
+            instructions: instructions.to_string(),
+        };
+
+        let json = serde_json::to_value(request("prefer ripgrep over grep")).unwrap();
+        assert_eq!(json["instructions"], "prefer ripgrep over grep");
+
+        let json = serde_json::to_value(request("")).unwrap();
+        assert!(json.get("instructions").is_none());
+
+        let client = CloudClient::new("token").with_instructions("  target macOS\n");
+        let agentic = AgenticRequest {
+            input: "why is the build slow".to_string(),
+            cwd: "/tmp".to_string(),
+            os: "macos".to_string(),
+            arch: "aarch64".to_string(),
+            context: None,
+            executions: Vec::new(),
+            instructions: client.instructions.clone(),
+        };
+        let json = serde_json::to_value(agentic).unwrap();
+        assert_eq!(json["instructions"], "target macOS");
+    }
+}
@@ -1,7 +1,13 @@
 mod agentic;
+mod backend;
+mod cache;
 mod cloud;
 mod context;
+mod session;
 
 pub use agentic::{AgenticConfig, AgenticSession, AgenticStep, CommandPermission};
+pub use backend::{AiBackend, StubBackend};
+pub use cache::TranslationCache;
 pub use cloud::CloudClient;
 pub use context::ConversationContext;
+pub use session::{SavedSession, saved_sessions};
@@ -0,0 +1,163 @@
+//! Saved agentic sessions.
+//!
+//! Each `??` investigation that ran commands is written to
+//! `~/.config/nosh/sessions/` so it can be replayed later with
+//! `nosh --replay FILE` or `/sessions replay N`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::fs_util;
+use crate::paths;
+
+/// Number of sessions kept in the sessions directory.
+const MAX_SAVED_SESSIONS: usize = 50;
+
+/// An agentic session as stored on disk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSession {
+    /// The `??` query that started the session
+    pub query: String,
+    /// Directory the commands ran in
+    pub cwd: String,
+    /// When the session finished (RFC 3339)
+    #[serde(default)]
+    pub saved_at: String,
+    /// Commands that were run, in order
+    pub commands: Vec<String>,
+    /// Why the AI ran each command, parallel to `commands`
+    #[serde(default)]
+    pub reasoning: Vec<Option<String>>,
+    /// The AI's final response
+    #[serde(default)]
+    pub answer: Option<String>,
+}
+
+impl SavedSession {
+    /// Read a session from a JSON file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Write the session to the sessions directory, pruning old ones.
+    pub fn save(&self) -> Result<PathBuf> {
+        let dir = paths::sessions_dir();
+        fs::create_dir_all(&dir)?;
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S%.3f");
+        let path = dir.join(format!("{}.json", stamp));
+        fs_util::write_atomic(&path, serde_json::to_string_pretty(self)?.as_bytes())?;
+
+        for old in saved_sessions().into_iter().skip(MAX_SAVED_SESSIONS) {
+            let _ = fs::remove_file(old);
+        }
+        Ok(path)
+    }
+
+    /// Render the commands as a shell script instead of running them.
+    pub fn to_script(&self) -> String {
+        let mut script = String::from("#!/bin/sh\n");
+        script.push_str(&format!(
+            "# nosh session: {}\n",
+            self.query.replace('\n', " ")
+        ));
+        script.push_str(&format!("# recorded in {}\n", self.cwd));
+        for command in &self.commands {
+            script.push_str(command);
+            script.push('\n');
+        }
+        script
+    }
+
+    /// Write the session up as markdown: the question, each command with
+    /// the reasoning behind it, and the answer.
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("## {}\n\n", self.query.replace('\n', " "));
+        md.push_str(&format!("Investigated in `{}`", self.cwd));
+        if !self.saved_at.is_empty() {
+            md.push_str(&format!(" on {}", self.saved_at));
+        }
+        md.push_str(".\n");
+
+        if !self.commands.is_empty() {
+            md.push_str("\n### Steps\n");
+            for (i, command) in self.commands.iter().enumerate() {
+                md.push('\n');
+                match self.reasoning.get(i).and_then(|r| r.as_deref()) {
+                    Some(why) => md.push_str(&format!("{}. {}\n", i + 1, why.trim())),
+                    None => md.push_str(&format!("{}. Ran:\n", i + 1)),
+                }
+                md.push_str(&format!("\n   ```sh\n   {}\n   ```\n", command));
+            }
+        }
+
+        if let Some(answer) = &self.answer {
+            md.push_str(&format!("\n### Answer\n\n{}\n", answer.trim()));
+        }
+        md
+    }
+}
+
+/// Saved session files, newest first.
+pub fn saved_sessions() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(paths::sessions_dir()) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    // File names are timestamps, so name order is age order
+    files.sort();
+    files.reverse();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_session_replays_commands() {
+        let json = r#"{
+            "query": "why is the disk full",
+            "cwd": "/srv",
+            "saved_at": "2026-01-02T03:04:05+00:00",
+            "commands": ["df -h", "du -sh * | sort -h"]
+        }"#;
+        let session: SavedSession = serde_json::from_str(json).unwrap();
+        assert_eq!(session.commands, vec!["df -h", "du -sh * | sort -h"]);
+
+        let parsed: Vec<_> = session
+            .commands
+            .iter()
+            .map(|c| crate::safety::parse_command(c))
+            .collect();
+        assert!(parsed.iter().all(|p| !p.info.command.is_empty()));
+
+        let script = session.to_script();
+        assert!(script.starts_with("#!/bin/sh\n# nosh session: why is the disk full\n"));
+        assert!(script.ends_with("df -h\ndu -sh * | sort -h\n"));
+    }
+
+    #[test]
+    fn test_markdown_export() {
+        let session = SavedSession {
+            query: "why is the disk full".to_string(),
+            cwd: "/srv".to_string(),
+            saved_at: String::new(),
+            commands: vec!["df -h".to_string(), "du -sh /var/log".to_string()],
+            reasoning: vec![Some("Check which filesystem is full".to_string()), None],
+            answer: Some("/var/log holds 40G of old journal files.".to_string()),
+        };
+        let md = session.to_markdown();
+        assert!(md.starts_with("## why is the disk full\n\nInvestigated in `/srv`.\n"));
+        assert!(md.contains("1. Check which filesystem is full\n\n   ```sh\n   df -h\n   ```\n"));
+        assert!(md.contains("2. Ran:\n\n   ```sh\n   du -sh /var/log\n   ```\n"));
+        assert!(md.ends_with("### Answer\n\n/var/log holds 40G of old journal files.\n"));
+    }
+}
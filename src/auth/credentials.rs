@@ -4,6 +4,7 @@ use std::fs;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 
+use crate::fs_util;
 use crate::paths;
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -31,7 +32,7 @@ impl Credentials {
             fs::create_dir_all(parent)?;
         }
         let content = toml::to_string_pretty(self)?;
-        fs::write(&path, &content)?;
+        fs_util::write_atomic(&path, &content)?;
 
         // Set restrictive permissions (owner read/write only)
         #[cfg(unix)]
@@ -1,11 +1,14 @@
 //! Built-in completers for common completion scenarios.
 
-use std::collections::HashSet;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashSet};
 use std::env;
+use std::ffi::{OsStr, OsString};
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::SystemTime;
 
 use super::Completion;
 
@@ -49,12 +52,13 @@ impl BuiltinCompleter {
         }
     }
 
-    /// Get completions for the given prefix.
-    pub fn complete(&self, prefix: &str) -> Vec<Completion> {
+    /// Get completions for the given prefix. `path_var` is the shell's
+    /// `$PATH`, searched by [`Self::Executables`].
+    pub fn complete(&self, prefix: &str, path_var: &OsStr) -> Vec<Completion> {
         match self {
             Self::Files => complete_files(prefix, false),
             Self::Directories => complete_files(prefix, true),
-            Self::Executables => complete_executables(prefix),
+            Self::Executables => complete_executables(prefix, path_var),
             Self::EnvVars => complete_env_vars(prefix),
             Self::Users => complete_users(prefix),
             Self::Groups => complete_groups(prefix),
@@ -244,42 +248,77 @@ fn complete_glob(pattern: &str, dirs_only: bool) -> Vec<Completion> {
     completions
 }
 
-/// Complete executable commands from PATH.
-fn complete_executables(prefix: &str) -> Vec<Completion> {
-    let mut completions = Vec::new();
-    let mut seen = HashSet::new();
+/// Complete executable commands from `path_var`.
+fn complete_executables(prefix: &str, path_var: &OsStr) -> Vec<Completion> {
+    EXECUTABLES.with(|cache| cache.borrow_mut().complete(prefix, path_var))
+}
 
-    if let Some(path_var) = env::var_os("PATH") {
-        for dir in env::split_paths(&path_var) {
-            if let Ok(entries) = fs::read_dir(&dir) {
-                for entry in entries.flatten() {
-                    let name = entry.file_name().to_string_lossy().to_string();
+thread_local! {
+    static EXECUTABLES: RefCell<ExecutableCache> = RefCell::new(ExecutableCache::default());
+}
 
-                    // Check prefix match
-                    if !name.starts_with(prefix) {
-                        continue;
-                    }
+/// Executables found on `$PATH`, kept between completions.
+///
+/// The set is rebuilt only when `$PATH` or the mtime of one of its
+/// directories changes, so a Tab costs one `stat` per PATH entry rather
+/// than one per file.
+#[derive(Debug, Default)]
+struct ExecutableCache {
+    /// `$PATH` and its directories' mtimes when `names` was built
+    key: Option<(OsString, Vec<Option<SystemTime>>)>,
+    /// Sorted, de-duplicated executable names
+    names: Vec<String>,
+    /// Number of times the set has been built
+    builds: usize,
+}
 
-                    // Skip duplicates
-                    if seen.contains(&name) {
-                        continue;
-                    }
+impl ExecutableCache {
+    fn complete(&mut self, prefix: &str, path_var: &OsStr) -> Vec<Completion> {
+        self.refresh(path_var);
 
-                    // Check if executable
-                    if let Ok(metadata) = entry.metadata() {
-                        let mode = metadata.permissions().mode();
-                        if mode & 0o111 != 0 {
-                            seen.insert(name.clone());
-                            completions.push(Completion::new(name).with_description("command"));
-                        }
-                    }
+        let start = self.names.partition_point(|name| name.as_str() < prefix);
+        self.names[start..]
+            .iter()
+            .take_while(|name| name.starts_with(prefix))
+            .map(|name| Completion::new(name.clone()).with_description("command"))
+            .collect()
+    }
+
+    /// Rebuild the set if `path_var` or a directory on it changed.
+    fn refresh(&mut self, path_var: &OsStr) {
+        let mtimes: Vec<Option<SystemTime>> = env::split_paths(path_var)
+            .map(|dir| fs::metadata(dir).and_then(|m| m.modified()).ok())
+            .collect();
+        if self
+            .key
+            .as_ref()
+            .is_some_and(|(path, times)| path == path_var && *times == mtimes)
+        {
+            return;
+        }
+
+        let mut names = BTreeSet::new();
+        for dir in env::split_paths(path_var) {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if names.contains(&name) {
+                    continue;
+                }
+                if let Ok(metadata) = entry.metadata()
+                    && metadata.permissions().mode() & 0o111 != 0
+                {
+                    names.insert(name);
                 }
             }
         }
-    }
 
-    completions.sort_by(|a, b| a.text.cmp(&b.text));
-    completions
+        self.names = names.into_iter().collect();
+        self.key = Some((path_var.to_os_string(), mtimes));
+        self.builds += 1;
+    }
 }
 
 /// Complete environment variable names.
@@ -510,6 +549,33 @@ mod tests {
         assert!(completions.iter().any(|c| c.text == "$PATH"));
     }
 
+    #[test]
+    fn test_executable_cache() {
+        let dir = env::temp_dir().join(format!("nosh_test_exec_cache_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let tool = dir.join("nosh-test-tool");
+        fs::write(&tool, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(dir.join("nosh-test-notes.txt"), "").unwrap();
+
+        let path_var = dir.clone().into_os_string();
+        let mut cache = ExecutableCache::default();
+        cache.refresh(&path_var);
+        assert_eq!(cache.names, vec!["nosh-test-tool"]);
+        assert_eq!(cache.builds, 1);
+
+        // Same PATH, untouched directories: no rebuild
+        cache.refresh(&path_var);
+        assert_eq!(cache.builds, 1);
+
+        let mut other = path_var.clone();
+        other.push(":/nonexistent");
+        cache.refresh(&other);
+        assert_eq!(cache.builds, 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_complete_signals() {
         let completions = complete_signals("SIGK");
@@ -1,20 +1,76 @@
 //! Completion manager with lazy loading and caching.
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::ffi::OsString;
 use std::fs;
+use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use std::time::{Duration, Instant};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 
 use super::{
     BuiltinCompleter, CommandCompletion, Completion, CompletionContext, CompletionFile,
-    DynamicCompleterDef,
+    DynamicCacheKey, DynamicCompleterDef, OptionCompletion,
 };
+use crate::exec::suggest;
+use crate::fs_util;
 use crate::paths;
 
+/// Default cap on lines kept from a dynamic completer.
+const DEFAULT_DYNAMIC_MAX_LINES: usize = 1000;
+
+/// Cap on bytes read from a dynamic completer, whatever its line limit.
+const DYNAMIC_MAX_BYTES: usize = 1024 * 1024;
+
+/// Commands skipped to complete the command they run (`sudo systemctl ...`).
+pub const DEFAULT_WRAPPERS: &[&str] = &["sudo", "doas", "env", "nice", "nohup", "time"];
+
+/// Wrapper options that take a separate value (`sudo -u root cmd`).
+const WRAPPER_VALUE_OPTIONS: &[(&str, &[&str])] = &[
+    (
+        "sudo",
+        &[
+            "-u",
+            "--user",
+            "-g",
+            "--group",
+            "-C",
+            "--close-from",
+            "-D",
+            "--chdir",
+            "-h",
+            "--host",
+            "-p",
+            "--prompt",
+            "-r",
+            "--role",
+            "-t",
+            "--type",
+            "-T",
+            "--command-timeout",
+            "-U",
+            "--other-user",
+        ],
+    ),
+    ("doas", &["-u", "-C"]),
+    (
+        "env",
+        &["-u", "--unset", "-C", "--chdir", "-S", "--split-string"],
+    ),
+    ("nice", &["-n", "--adjustment"]),
+    ("time", &["-f", "--format", "-o", "--output"]),
+];
+
+/// The default wrapper list, owned.
+pub fn default_wrappers() -> Vec<String> {
+    DEFAULT_WRAPPERS.iter().map(|s| s.to_string()).collect()
+}
+
 /// Cache entry for dynamic completer results.
 struct DynamicCache {
     results: Vec<String>,
@@ -28,14 +84,30 @@ impl DynamicCache {
     }
 }
 
+/// Dynamic completer results saved on disk with the version they came from.
+#[derive(Serialize, Deserialize)]
+struct VersionedResults {
+    version: String,
+    results: Vec<String>,
+}
+
 /// Manager for lazy-loading and caching completions.
 pub struct CompletionManager {
     /// Loaded command completions (lazily populated)
     commands: RefCell<HashMap<String, CommandCompletion>>,
+    /// Source file and its mtime for each loaded command (for live reload)
+    sources: RefCell<HashMap<String, (PathBuf, SystemTime)>>,
     /// Cache for dynamic completer results
     dynamic_cache: RefCell<HashMap<String, DynamicCache>>,
+    /// Commands that run another command (`sudo`, `env`, ...)
+    wrappers: RefCell<Vec<String>>,
     /// Paths to search for completion files
     search_paths: Vec<PathBuf>,
+    /// The shell's `$PATH`, searched for command names
+    path_var: RefCell<OsString>,
+    /// Dynamic completers whose output hit the cap, with the lines kept.
+    /// Listed by `/debug`, since printing mid-edit would garble the line.
+    truncated: RefCell<BTreeMap<String, usize>>,
 }
 
 impl Default for CompletionManager {
@@ -67,9 +139,117 @@ impl CompletionManager {
 
         Self {
             commands: RefCell::new(HashMap::new()),
+            sources: RefCell::new(HashMap::new()),
+            dynamic_cache: RefCell::new(HashMap::new()),
+            wrappers: RefCell::new(default_wrappers()),
+            search_paths,
+            path_var: RefCell::new(env::var_os("PATH").unwrap_or_default()),
+            truncated: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Create a manager that loads completion files from `search_paths` only.
+    #[cfg(test)]
+    pub fn with_search_paths(search_paths: Vec<PathBuf>) -> Self {
+        Self {
+            commands: RefCell::new(HashMap::new()),
+            sources: RefCell::new(HashMap::new()),
             dynamic_cache: RefCell::new(HashMap::new()),
+            wrappers: RefCell::new(default_wrappers()),
             search_paths,
+            path_var: RefCell::new(env::var_os("PATH").unwrap_or_default()),
+            truncated: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Create a manager that only knows the commands defined in `path`.
+    ///
+    /// Used to exercise a completion file outside the search paths.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let file: CompletionFile = toml::from_str(&content)?;
+        if file.completions.is_empty() {
+            anyhow::bail!("No completions found in {}", path.display());
+        }
+
+        let commands = file
+            .completions
+            .into_iter()
+            .map(|(name, def)| (name, CommandCompletion::from_def(def)))
+            .collect();
+
+        Ok(Self {
+            commands: RefCell::new(commands),
+            sources: RefCell::new(HashMap::new()),
+            dynamic_cache: RefCell::new(HashMap::new()),
+            wrappers: RefCell::new(default_wrappers()),
+            search_paths: Vec::new(),
+            path_var: RefCell::new(env::var_os("PATH").unwrap_or_default()),
+            truncated: RefCell::new(BTreeMap::new()),
+        })
+    }
+
+    /// Replace the list of wrapper commands skipped by [`Self::parse_context`].
+    pub fn set_wrappers(&self, wrappers: Vec<String>) {
+        *self.wrappers.borrow_mut() = wrappers;
+    }
+
+    /// Set the `$PATH` searched for command names, refreshed from the
+    /// shell before each prompt so `export PATH=...` takes effect.
+    pub fn set_path_var(&self, path_var: OsString) {
+        *self.path_var.borrow_mut() = path_var;
+    }
+
+    /// The `$PATH` searched for command names.
+    pub fn path_var(&self) -> OsString {
+        self.path_var.borrow().clone()
+    }
+
+    /// Dynamic completers whose output was cut off, with the lines kept.
+    pub fn truncated_completers(&self) -> Vec<(String, usize)> {
+        self.truncated
+            .borrow()
+            .iter()
+            .map(|(name, lines)| (name.clone(), *lines))
+            .collect()
+    }
+
+    /// Load every completion file in the search paths, sorted by command name.
+    /// Earlier search paths win when several define the same command.
+    pub fn load_all(&self) -> Vec<(String, CommandCompletion)> {
+        let mut names: Vec<String> = self
+            .search_paths
+            .iter()
+            .filter_map(|path| fs::read_dir(path).ok())
+            .flat_map(|entries| entries.flatten())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+            .collect();
+        names.sort();
+        names.dedup();
+
+        names
+            .into_iter()
+            .filter_map(|name| {
+                self.ensure_loaded(&name);
+                let completion = self.commands.borrow().get(&name).cloned()?;
+                Some((name, completion))
+            })
+            .collect()
+    }
+
+    /// Suggest the subcommand of `command` nearest to the mistyped `word`.
+    ///
+    /// Returns `None` when `word` is a known subcommand or nothing is close.
+    pub fn suggest_subcommand(&self, command: &str, word: &str) -> Option<String> {
+        self.ensure_loaded(command);
+        let commands = self.commands.borrow();
+        let subcommands = commands.get(command)?.subcommands_at(None)?;
+        if subcommands.contains_key(word) {
+            return None;
         }
+        suggest::nearest(word, subcommands.keys().map(String::as_str))
     }
 
     /// Get completions for given input line and cursor position.
@@ -83,7 +263,7 @@ impl CompletionManager {
         let line = &line[..pos];
 
         // Parse words, handling quotes
-        let words = match shell_words::split(line) {
+        let mut words = match shell_words::split(line) {
             Ok(w) => w,
             Err(_) => {
                 // Unclosed quote - try to parse anyway
@@ -91,6 +271,11 @@ impl CompletionManager {
             }
         };
 
+        // Complete the command under `sudo`, `env FOO=1`, ...
+        let typing = !(line.ends_with(' ') || line.ends_with('\t'));
+        let skip = self.wrapper_prefix_len(&words, typing);
+        words.drain(..skip);
+
         // Find current word prefix
         let prefix = if line.ends_with(' ') || line.ends_with('\t') {
             String::new()
@@ -105,9 +290,16 @@ impl CompletionManager {
 
         let command = words[0].clone();
 
+        // Words before the one being completed
+        let done = if prefix.is_empty() {
+            &words[1..]
+        } else {
+            &words[1..words.len() - 1]
+        };
+        let (subcommand, expects_subcommand) = self.find_subcommand(done, &command);
+
         // Completing an option (starts with -)
         if prefix.starts_with('-') {
-            let subcommand = self.find_subcommand(&words, &command);
             return CompletionContext::Option {
                 command,
                 subcommand,
@@ -118,53 +310,142 @@ impl CompletionManager {
         // Check if previous word was an option that takes a value
         if words.len() >= 2 {
             let prev = &words[words.len() - if prefix.is_empty() { 1 } else { 2 }];
-            if prev.starts_with('-') {
-                let subcommand = self.find_subcommand(&words, &command);
-                if self.option_takes_value(&command, subcommand.as_deref(), prev) {
-                    return CompletionContext::OptionValue {
-                        command,
-                        subcommand,
-                        option: prev.clone(),
-                        prefix,
-                    };
-                }
+            if prev.starts_with('-')
+                && self.option_takes_value(&command, subcommand.as_deref(), prev)
+            {
+                return CompletionContext::OptionValue {
+                    command,
+                    subcommand,
+                    option: prev.clone(),
+                    prefix,
+                };
             }
         }
 
-        // Check if we're completing a subcommand
-        let subcommand = self.find_subcommand(&words, &command);
-
-        if subcommand.is_none() {
-            // Try to complete subcommand if we have one loaded
-            self.ensure_loaded(&command);
-            if let Some(cmd) = self.commands.borrow().get(&command)
-                && !cmd.subcommands.is_empty()
-            {
-                // Could be completing a subcommand
-                return CompletionContext::Subcommand { command, prefix };
-            }
+        // Check if we're completing a (nested) subcommand
+        if expects_subcommand
+            && let Some(cmd) = self.commands.borrow().get(&command)
+            && cmd
+                .subcommands_at(subcommand.as_deref())
+                .is_some_and(|subs| !subs.is_empty())
+        {
+            return CompletionContext::Subcommand {
+                command,
+                parent: subcommand,
+                prefix,
+            };
         }
 
         // Positional argument completion
+        let index = self.positional_index(done, &command, subcommand.as_deref());
         CompletionContext::Positional {
             command,
             subcommand,
+            index,
             prefix,
         }
     }
 
-    /// Find subcommand in the word list.
-    fn find_subcommand(&self, words: &[String], command: &str) -> Option<String> {
-        self.ensure_loaded(command);
+    /// Number of positional arguments among the completed words, not counting
+    /// the subcommand chain, options, or values of options that take one.
+    fn positional_index(&self, words: &[String], command: &str, subcommand: Option<&str>) -> usize {
+        let mut count: usize = 0;
+        let mut expects_value = false;
+        let mut options_done = false;
+        for word in words {
+            if expects_value {
+                expects_value = false;
+            } else if options_done || !word.starts_with('-') {
+                count += 1;
+            } else if word == "--" {
+                options_done = true;
+            } else {
+                expects_value =
+                    !word.contains('=') && self.option_takes_value(command, subcommand, word);
+            }
+        }
+        let chain_len = subcommand.map_or(0, |path| path.split_whitespace().count());
+        count.saturating_sub(chain_len)
+    }
 
-        if let Some(cmd) = self.commands.borrow().get(command) {
-            for word in words.iter().skip(1) {
-                if !word.starts_with('-') && cmd.subcommands.contains_key(word) {
-                    return Some(word.clone());
+    /// Number of leading words that belong to wrapper commands and their own
+    /// options (`sudo -u root`, `env -i FOO=1`). A wrapper is only skipped
+    /// once the wrapped command has started, so its own options and option
+    /// values still complete against the wrapper. `typing` means the last
+    /// word is still being typed.
+    fn wrapper_prefix_len(&self, words: &[String], typing: bool) -> usize {
+        let wrappers = self.wrappers.borrow();
+        let done = if typing {
+            words.len().saturating_sub(1)
+        } else {
+            words.len()
+        };
+        let current = if typing { words.last() } else { None };
+
+        let mut skip = 0;
+        while skip < done && wrappers.contains(&words[skip]) {
+            let wrapper = words[skip].as_str();
+            let value_options = WRAPPER_VALUE_OPTIONS
+                .iter()
+                .find(|(name, _)| *name == wrapper)
+                .map_or(&[][..], |(_, options)| *options);
+
+            let mut next = skip + 1;
+            while next < done {
+                let arg = &words[next];
+                if arg == "--" {
+                    next += 1;
+                    break;
+                }
+                if arg.starts_with('-') {
+                    next += if value_options.contains(&arg.as_str()) {
+                        2
+                    } else {
+                        1
+                    };
+                } else if wrapper == "env" && arg.find('=').is_some_and(|i| i > 0) {
+                    next += 1;
+                } else {
+                    break;
                 }
             }
+
+            // Still inside the wrapper's own arguments
+            if next > done || (next == done && current.is_some_and(|w| w.starts_with('-'))) {
+                break;
+            }
+            skip = next;
+        }
+        skip
+    }
+
+    /// Find the deepest subcommand chain in the completed words (after the command).
+    ///
+    /// Returns the chain (e.g. "remote add") and whether a further subcommand
+    /// may follow, i.e. no positional argument came after the deepest match.
+    fn find_subcommand(&self, words: &[String], command: &str) -> (Option<String>, bool) {
+        self.ensure_loaded(command);
+
+        let commands = self.commands.borrow();
+        let Some(cmd) = commands.get(command) else {
+            return (None, true);
+        };
+
+        let mut chain: Vec<&str> = Vec::new();
+        let mut level = &cmd.subcommands;
+        for word in words.iter().filter(|w| !w.starts_with('-')) {
+            if let Some(sub) = level.get(word) {
+                chain.push(word);
+                level = &sub.subcommands;
+            } else if !chain.is_empty() {
+                // A positional argument ends the chain
+                return (Some(chain.join(" ")), false);
+            }
+            // Top level: keep scanning, the word may be an option value
         }
-        None
+
+        let subcommand = (!chain.is_empty()).then(|| chain.join(" "));
+        (subcommand, true)
     }
 
     /// Check if an option takes a value.
@@ -172,36 +453,24 @@ impl CompletionManager {
         self.ensure_loaded(command);
 
         if let Some(cmd) = self.commands.borrow().get(command) {
-            // Check subcommand options first
-            if let Some(sub_name) = subcommand
-                && let Some(sub) = cmd.subcommands.get(sub_name)
-            {
-                for opt in &sub.options {
-                    if opt.name == option && opt.takes_value {
-                        return true;
-                    }
-                }
-            }
-
-            // Check command options
-            for opt in &cmd.options {
-                if opt.name == option && opt.takes_value {
-                    return true;
-                }
-            }
+            return chain_options(cmd, subcommand)
+                .iter()
+                .any(|opt| opt.matches(option) && opt.takes_value);
         }
 
         false
     }
 
     /// Complete based on parsed context.
-    fn complete_with_context(&self, context: &CompletionContext) -> Vec<Completion> {
+    pub fn complete_with_context(&self, context: &CompletionContext) -> Vec<Completion> {
         match context {
             CompletionContext::Command { prefix } => self.complete_command(prefix),
 
-            CompletionContext::Subcommand { command, prefix } => {
-                self.complete_subcommand(command, prefix)
-            }
+            CompletionContext::Subcommand {
+                command,
+                parent,
+                prefix,
+            } => self.complete_subcommand(command, parent.as_deref(), prefix),
 
             CompletionContext::Option {
                 command,
@@ -219,9 +488,9 @@ impl CompletionManager {
             CompletionContext::Positional {
                 command,
                 subcommand,
+                index,
                 prefix,
-                ..
-            } => self.complete_positional(command, subcommand.as_deref(), prefix),
+            } => self.complete_positional(command, subcommand.as_deref(), *index, prefix),
         }
     }
 
@@ -231,10 +500,11 @@ impl CompletionManager {
     fn complete_command(&self, prefix: &str) -> Vec<Completion> {
         // Path-like prefix: complete files instead of searching PATH
         if prefix.starts_with("./") || prefix.starts_with("../") || prefix.starts_with('/') {
-            return BuiltinCompleter::Files.complete(prefix);
+            return BuiltinCompleter::Files.complete(prefix, &self.path_var.borrow());
         }
 
-        let mut completions = BuiltinCompleter::Executables.complete(prefix);
+        let mut completions =
+            BuiltinCompleter::Executables.complete(prefix, &self.path_var.borrow());
 
         // Enhance with descriptions from our completion files
         for completion in &mut completions {
@@ -249,12 +519,19 @@ impl CompletionManager {
         completions
     }
 
-    /// Complete subcommand names.
-    fn complete_subcommand(&self, command: &str, prefix: &str) -> Vec<Completion> {
+    /// Complete subcommand names, nested under `parent` if given.
+    fn complete_subcommand(
+        &self,
+        command: &str,
+        parent: Option<&str>,
+        prefix: &str,
+    ) -> Vec<Completion> {
         self.ensure_loaded(command);
 
-        if let Some(cmd) = self.commands.borrow().get(command) {
-            cmd.subcommands
+        if let Some(cmd) = self.commands.borrow().get(command)
+            && let Some(subcommands) = cmd.subcommands_at(parent)
+        {
+            subcommands
                 .iter()
                 .filter(|(name, _)| name.starts_with(prefix))
                 .map(|(name, sub)| {
@@ -267,7 +544,7 @@ impl CompletionManager {
                 .collect()
         } else {
             // No subcommands defined - fall back to file completion
-            BuiltinCompleter::Files.complete(prefix)
+            BuiltinCompleter::Files.complete(prefix, &self.path_var.borrow())
         }
     }
 
@@ -283,25 +560,11 @@ impl CompletionManager {
         let mut completions = Vec::new();
 
         if let Some(cmd) = self.commands.borrow().get(command) {
-            // Get subcommand options if present
-            if let Some(sub_name) = subcommand
-                && let Some(sub) = cmd.subcommands.get(sub_name)
-            {
-                for opt in &sub.options {
-                    if opt.name.starts_with(prefix) {
-                        let mut c = Completion::new(&opt.name);
-                        if let Some(desc) = &opt.description {
-                            c = c.with_description(desc);
-                        }
-                        completions.push(c);
-                    }
-                }
-            }
-
-            // Add command-level options
-            for opt in &cmd.options {
-                if opt.name.starts_with(prefix) {
-                    let mut c = Completion::new(&opt.name);
+            // Innermost subcommand options first, then outer levels and the command.
+            // Aliases follow their option and share its description.
+            for opt in chain_options(cmd, subcommand) {
+                for name in opt.names().filter(|name| name.starts_with(prefix)) {
+                    let mut c = Completion::new(name);
                     if let Some(desc) = &opt.description {
                         c = c.with_description(desc);
                     }
@@ -333,7 +596,7 @@ impl CompletionManager {
         }
 
         // Default to file completion for option values
-        BuiltinCompleter::Files.complete(prefix)
+        BuiltinCompleter::Files.complete(prefix, &self.path_var.borrow())
     }
 
     /// Find the completer for an option value.
@@ -343,76 +606,68 @@ impl CompletionManager {
         subcommand: Option<&str>,
         option: &str,
     ) -> Option<String> {
-        // Check subcommand options first
-        if let Some(sub_name) = subcommand
-            && let Some(sub) = cmd.subcommands.get(sub_name)
-        {
-            for opt in &sub.options {
-                if opt.name == option {
-                    return opt.value_completer.clone();
-                }
-            }
-        }
-
-        // Check command options
-        for opt in &cmd.options {
-            if opt.name == option {
-                return opt.value_completer.clone();
-            }
-        }
-
-        None
+        chain_options(cmd, subcommand)
+            .into_iter()
+            .find(|opt| opt.matches(option))
+            .and_then(|opt| opt.value_completer.clone())
     }
 
-    /// Complete positional argument.
+    /// Complete the positional argument at `index`.
     fn complete_positional(
         &self,
         command: &str,
         subcommand: Option<&str>,
+        index: usize,
         prefix: &str,
     ) -> Vec<Completion> {
         self.ensure_loaded(command);
 
         if let Some(cmd) = self.commands.borrow().get(command) {
-            // Check subcommand's positional completer
-            if let Some(sub_name) = subcommand
-                && let Some(sub) = cmd.subcommands.get(sub_name)
-                && let Some(ref completer) = sub.positional
+            // Check the innermost subcommand with a positional completer
+            if let Some(completer) = subcommand
+                .and_then(|path| cmd.subcommand_chain(path))
+                .and_then(|chain| chain.iter().rev().find_map(|sub| sub.positional.clone()))
+                && let Some(name) = completer.completer_at(index)
             {
-                return self.run_completer(command, completer, prefix);
+                return self.run_completer(command, name, prefix);
             }
 
             // Check command's positional completer
-            if let Some(ref completer) = cmd.positional {
-                return self.run_completer(command, completer, prefix);
+            if let Some(name) = cmd
+                .positional
+                .as_ref()
+                .and_then(|completer| completer.completer_at(index))
+            {
+                return self.run_completer(command, name, prefix);
             }
         }
 
         // Default to file completion
-        BuiltinCompleter::Files.complete(prefix)
+        BuiltinCompleter::Files.complete(prefix, &self.path_var.borrow())
     }
 
     /// Run a completer by name (builtin or dynamic).
     fn run_completer(&self, command: &str, completer: &str, prefix: &str) -> Vec<Completion> {
         // Check if it's a builtin
         if let Some(builtin) = BuiltinCompleter::from_name(completer) {
-            return builtin.complete(prefix);
+            return builtin.complete(prefix, &self.path_var.borrow());
         }
 
         // Check if it's a dynamic completer
         if let Some(cmd) = self.commands.borrow().get(command)
             && let Some(dynamic) = cmd.dynamic.get(completer)
         {
-            return self.run_dynamic_completer(completer, dynamic, prefix);
+            return self.run_dynamic_completer(command, completer, dynamic, prefix);
         }
 
         // Unknown completer - default to files
-        BuiltinCompleter::Files.complete(prefix)
+        BuiltinCompleter::Files.complete(prefix, &self.path_var.borrow())
     }
 
     /// Run a dynamic completer (executes shell command).
     fn run_dynamic_completer(
         &self,
+        command: &str,
         name: &str,
         def: &DynamicCompleterDef,
         prefix: &str,
@@ -425,17 +680,24 @@ impl CompletionManager {
             if let Some(entry) = cache.get(&cache_key)
                 && entry.is_valid()
             {
-                return entry
-                    .results
-                    .iter()
-                    .filter(|s| s.starts_with(prefix))
-                    .map(Completion::new)
-                    .collect();
+                return dynamic_completions(&entry.results, prefix);
             }
         }
 
-        // Run the command
-        let results = self.execute_dynamic_command(&def.command);
+        // Run the command, or reuse results saved for this version of the tool
+        let max_lines = def.max_lines.unwrap_or(DEFAULT_DYNAMIC_MAX_LINES);
+        let run = || self.execute_dynamic_command(name, &def.command, max_lines);
+        let results = match def.cache_key {
+            Some(DynamicCacheKey::Version) => match command_version(command) {
+                Some(version) => {
+                    let path =
+                        paths::completion_cache_dir().join(format!("{}.{}.json", command, name));
+                    cached_for_version(&path, &version, run)
+                }
+                None => run(),
+            },
+            None => run(),
+        };
 
         // Cache the results
         let ttl = Duration::from_secs(def.cache_seconds.unwrap_or(5));
@@ -448,31 +710,50 @@ impl CompletionManager {
             },
         );
 
-        results
-            .iter()
-            .filter(|s| s.starts_with(prefix))
-            .map(Completion::new)
-            .collect()
+        dynamic_completions(&results, prefix)
     }
 
     /// Execute a shell command and return lines of output.
-    fn execute_dynamic_command(&self, cmd: &str) -> Vec<String> {
-        let output = Command::new("sh").args(["-c", cmd]).output();
-
-        match output {
-            Ok(out) if out.status.success() => String::from_utf8_lossy(&out.stdout)
-                .lines()
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect(),
+    /// Stops reading (and kills the command) once `max_lines` lines or
+    /// `DYNAMIC_MAX_BYTES` bytes have been read.
+    fn execute_dynamic_command(&self, name: &str, cmd: &str, max_lines: usize) -> Vec<String> {
+        let child = Command::new("sh")
+            .args(["-c", cmd])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        let Ok(mut child) = child else {
+            return Vec::new();
+        };
+        let Some(stdout) = child.stdout.take() else {
+            return Vec::new();
+        };
+
+        let (lines, truncated) = read_capped(BufReader::new(stdout), max_lines, DYNAMIC_MAX_BYTES);
+        if truncated {
+            let _ = child.kill();
+            let _ = child.wait();
+            self.truncated
+                .borrow_mut()
+                .insert(name.to_string(), lines.len());
+            return lines;
+        }
+
+        match child.wait() {
+            Ok(status) if status.success() => lines,
             _ => Vec::new(),
         }
     }
 
     /// Ensure completions for a command are loaded.
+    /// Reparses the source file if it was modified since it was loaded.
     fn ensure_loaded(&self, command: &str) {
         if self.commands.borrow().contains_key(command) {
-            return; // Already loaded
+            if !self.source_changed(command) {
+                return; // Already loaded and up to date
+            }
+            self.commands.borrow_mut().remove(command);
+            self.sources.borrow_mut().remove(command);
         }
 
         // Search for completion file
@@ -481,6 +762,11 @@ impl CompletionManager {
             if file.exists()
                 && let Ok(completion) = self.load_file(&file, command)
             {
+                if let Some(mtime) = file_mtime(&file) {
+                    self.sources
+                        .borrow_mut()
+                        .insert(command.to_string(), (file, mtime));
+                }
                 self.commands
                     .borrow_mut()
                     .insert(command.to_string(), completion);
@@ -489,6 +775,17 @@ impl CompletionManager {
         }
     }
 
+    /// Check if the source file of a loaded command is newer than the cached entry.
+    fn source_changed(&self, command: &str) -> bool {
+        let sources = self.sources.borrow();
+        match sources.get(command) {
+            Some((path, loaded_mtime)) => {
+                file_mtime(path).is_some_and(|mtime| mtime > *loaded_mtime)
+            }
+            None => false,
+        }
+    }
+
     /// Load completion from a TOML file.
     fn load_file(&self, path: &Path, command: &str) -> Result<CommandCompletion> {
         let content = fs::read_to_string(path)?;
@@ -508,10 +805,174 @@ impl CompletionManager {
     }
 }
 
+/// Options for a subcommand chain, innermost level first, then the command's own.
+fn chain_options<'a>(
+    cmd: &'a CommandCompletion,
+    subcommand: Option<&str>,
+) -> Vec<&'a OptionCompletion> {
+    let chain = subcommand
+        .and_then(|path| cmd.subcommand_chain(path))
+        .unwrap_or_default();
+    chain
+        .iter()
+        .rev()
+        .flat_map(|sub| sub.options.iter())
+        .chain(cmd.options.iter())
+        .collect()
+}
+
+/// Turn dynamic completer output into completions matching `prefix`.
+/// A line may be `value<TAB>description`; plain lines have no description.
+fn dynamic_completions(lines: &[String], prefix: &str) -> Vec<Completion> {
+    lines
+        .iter()
+        .filter_map(|line| {
+            let (value, desc) = match line.split_once('\t') {
+                Some((value, desc)) => (value.trim(), desc.trim()),
+                None => (line.as_str(), ""),
+            };
+            if !value.starts_with(prefix) {
+                return None;
+            }
+            let completion = Completion::new(value);
+            Some(if desc.is_empty() {
+                completion
+            } else {
+                completion.with_description(desc)
+            })
+        })
+        .collect()
+}
+
+/// Read trimmed, non-empty lines until `max_lines` lines or `max_bytes`
+/// bytes. Returns the lines and whether output was cut short.
+fn read_capped(
+    mut reader: impl BufRead,
+    max_lines: usize,
+    max_bytes: usize,
+) -> (Vec<String>, bool) {
+    let mut lines = Vec::new();
+    let mut bytes = 0;
+    let mut buf = Vec::new();
+
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) | Err(_) => return (lines, false),
+            Ok(n) => bytes += n,
+        }
+        if bytes > max_bytes {
+            return (lines, true);
+        }
+
+        let line = String::from_utf8_lossy(&buf);
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if lines.len() == max_lines {
+            return (lines, true);
+        }
+        lines.push(line.to_string());
+    }
+}
+
+/// First line of `command --version`, or `None` if it fails.
+fn command_version(command: &str) -> Option<String> {
+    let output = Command::new(command)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout.lines().next()?.trim();
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+/// Results saved at `path` if they came from `version`, otherwise `run`
+/// them and save the new results.
+fn cached_for_version(
+    path: &Path,
+    version: &str,
+    run: impl FnOnce() -> Vec<String>,
+) -> Vec<String> {
+    if let Ok(content) = fs::read_to_string(path)
+        && let Ok(saved) = serde_json::from_str::<VersionedResults>(&content)
+        && saved.version == version
+    {
+        return saved.results;
+    }
+
+    let results = run();
+    if !results.is_empty()
+        && let Some(dir) = path.parent()
+        && fs::create_dir_all(dir).is_ok()
+        && let Ok(json) = serde_json::to_string(&VersionedResults {
+            version: version.to_string(),
+            results: results.clone(),
+        })
+    {
+        let _ = fs_util::write_atomic(path, json);
+    }
+    results
+}
+
+/// Get the modification time of a file.
+fn file_mtime(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).ok()?.modified().ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Create a fresh temp dir holding the given completion files.
+    fn fixture_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nosh_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (file, content) in files {
+            fs::write(dir.join(file), content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_command_completion_follows_path_var() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let bin = fixture_dir("path_var", &[("nosh-venv-tool", "#!/bin/sh\n")]);
+        fs::set_permissions(
+            bin.join("nosh-venv-tool"),
+            fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+        let empty = fixture_dir("path_var_empty", &[]);
+
+        let mgr = CompletionManager::with_search_paths(Vec::new());
+        mgr.set_path_var(empty.clone().into_os_string());
+        assert!(mgr.complete("nosh-venv", 9).is_empty());
+
+        // As after `export PATH=venv/bin:$PATH` inside the shell
+        let mut path_var = bin.clone().into_os_string();
+        path_var.push(":");
+        path_var.push(&empty);
+        mgr.set_path_var(path_var);
+        let texts: Vec<String> = mgr
+            .complete("nosh-venv", 9)
+            .into_iter()
+            .map(|c| c.text)
+            .collect();
+        assert_eq!(texts, vec!["nosh-venv-tool"]);
+
+        let _ = fs::remove_dir_all(&bin);
+        let _ = fs::remove_dir_all(&empty);
+    }
+
     #[test]
     fn test_parse_context_empty() {
         let mgr = CompletionManager::new();
@@ -538,4 +999,371 @@ mod tests {
             _ => panic!("Expected Option context"),
         }
     }
+
+    #[test]
+    fn test_dynamic_output_truncated() {
+        let mgr = CompletionManager::with_search_paths(Vec::new());
+
+        // Far more output than the cap; reading stops early
+        let lines = mgr.execute_dynamic_command("flood", "yes item", 1000);
+        assert_eq!(lines.len(), 1000);
+        assert!(lines.iter().all(|l| l == "item"));
+        assert_eq!(
+            mgr.truncated_completers(),
+            vec![("flood".to_string(), 1000)]
+        );
+
+        // Output within the cap is kept whole
+        let lines = mgr.execute_dynamic_command("small", "printf 'a\\n\\nb\\n'", 1000);
+        assert_eq!(lines, vec!["a", "b"]);
+        assert_eq!(mgr.truncated_completers().len(), 1);
+
+        // Byte cap applies even under the line limit
+        let input = "x".repeat(100) + "\n";
+        let (lines, truncated) = read_capped(input.repeat(10).as_bytes(), 1000, 550);
+        assert_eq!(lines.len(), 5);
+        assert!(truncated);
+    }
+
+    #[test]
+    fn test_dynamic_cache_by_version() {
+        let dir =
+            std::env::temp_dir().join(format!("nosh_test_completion_cache_{}", std::process::id()));
+        let path = dir.join("tool.subcommands.json");
+        let runs = std::cell::Cell::new(0);
+        let run = || {
+            runs.set(runs.get() + 1);
+            vec![format!("build{}", runs.get())]
+        };
+
+        assert_eq!(cached_for_version(&path, "tool 1.0", run), vec!["build1"]);
+        // Same version: served from disk
+        assert_eq!(cached_for_version(&path, "tool 1.0", run), vec!["build1"]);
+        assert_eq!(runs.get(), 1);
+
+        // Upgraded tool: results are regenerated and saved
+        assert_eq!(cached_for_version(&path, "tool 1.1", run), vec!["build2"]);
+        assert_eq!(cached_for_version(&path, "tool 1.1", run), vec!["build2"]);
+        assert_eq!(runs.get(), 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_dynamic_descriptions() {
+        let lines = vec![
+            "main\tInitial commit".to_string(),
+            "dev".to_string(),
+            "feature\t".to_string(),
+        ];
+        let all = dynamic_completions(&lines, "");
+        assert_eq!(all[0].text, "main");
+        assert_eq!(all[0].description.as_deref(), Some("Initial commit"));
+        assert_eq!(all[1].text, "dev");
+        assert_eq!(all[1].description, None);
+        assert_eq!(all[2].text, "feature");
+        assert_eq!(all[2].description, None);
+
+        // The prefix matches the value, not the description
+        let matched = dynamic_completions(&lines, "I");
+        assert!(matched.is_empty());
+        let matched = dynamic_completions(&lines, "ma");
+        assert_eq!(matched.len(), 1);
+    }
+
+    #[test]
+    fn test_option_aliases() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_aliases_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("vc.toml"),
+            r#"
+[completions.vc]
+options = { "--branch" = { description = "Branch to use", takes_value = true, value_completer = "branches", aliases = ["-b"] } }
+
+[completions.vc.subcommands.commit]
+description = "Record changes"
+options = [{ name = "--message", aliases = ["-m"], description = "Commit message", takes_value = true, value_completer = "messages" }]
+
+[completions.vc.dynamic]
+branches = { command = "printf 'main\\ndev\\n'" }
+messages = { command = "echo wip" }
+"#,
+        )
+        .unwrap();
+
+        let mgr = CompletionManager::with_search_paths(vec![dir.clone()]);
+        let texts = |line: &str| -> Vec<String> {
+            mgr.complete(line, line.len())
+                .into_iter()
+                .map(|c| c.text)
+                .collect()
+        };
+
+        // Either name finds the value completer
+        assert_eq!(texts("vc --branch "), vec!["main", "dev"]);
+        assert_eq!(texts("vc -b "), vec!["main", "dev"]);
+        assert_eq!(texts("vc commit -m "), vec!["wip"]);
+        assert_eq!(texts("vc commit --message "), vec!["wip"]);
+
+        // Aliases are listed next to their option with the same description
+        let options = mgr.complete("vc commit -", 11);
+        let names: Vec<&str> = options.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(names, vec!["--message", "-m", "--branch", "-b"]);
+        assert_eq!(options[0].description, options[1].description);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_reload_on_file_change() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_reload_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("mycmd.toml");
+        fs::write(
+            &file,
+            "[completions.mycmd]\n[completions.mycmd.subcommands]\nold = \"Old\"\n",
+        )
+        .unwrap();
+
+        let mgr = CompletionManager::with_search_paths(vec![dir.clone()]);
+
+        let names: Vec<String> = mgr
+            .complete_subcommand("mycmd", None, "")
+            .into_iter()
+            .map(|c| c.text)
+            .collect();
+        assert_eq!(names, vec!["old"]);
+
+        // Rewrite the file with a newer mtime
+        fs::write(
+            &file,
+            "[completions.mycmd]\n[completions.mycmd.subcommands]\nnew = \"New\"\n",
+        )
+        .unwrap();
+        let newer = SystemTime::now() + Duration::from_secs(10);
+        fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(newer)
+            .unwrap();
+
+        let names: Vec<String> = mgr
+            .complete_subcommand("mycmd", None, "")
+            .into_iter()
+            .map(|c| c.text)
+            .collect();
+        assert_eq!(names, vec!["new"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_suggest_subcommand() {
+        let dir =
+            std::env::temp_dir().join(format!("nosh_test_suggest_sub_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("git.toml");
+        fs::write(&path, crate::plugins::builtins::GIT_COMPLETION).unwrap();
+
+        let mgr = CompletionManager::from_file(&path).unwrap();
+        assert_eq!(
+            mgr.suggest_subcommand("git", "stauts").as_deref(),
+            Some("status")
+        );
+        assert_eq!(mgr.suggest_subcommand("git", "status"), None);
+        assert_eq!(mgr.suggest_subcommand("git", "xyzzyq"), None);
+        assert_eq!(mgr.suggest_subcommand("nosuchcmd", "stauts"), None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_wrapper_commands() {
+        let dir = fixture_dir(
+            "wrappers",
+            &[
+                (
+                    "systemctl.toml",
+                    r#"
+[completions.systemctl.subcommands]
+start = "Start units"
+status = "Show unit status"
+stop = "Stop units"
+"#,
+                ),
+                ("git.toml", crate::plugins::builtins::GIT_COMPLETION),
+            ],
+        );
+
+        let mgr = CompletionManager::with_search_paths(vec![dir.clone()]);
+        let texts = |line: &str| -> Vec<String> {
+            let mut t: Vec<String> = mgr
+                .complete(line, line.len())
+                .into_iter()
+                .map(|c| c.text)
+                .collect();
+            t.sort();
+            t
+        };
+
+        assert_eq!(texts("sudo systemctl st"), vec!["start", "status", "stop"]);
+        assert_eq!(texts("sudo -u root systemctl sto"), vec!["stop"]);
+        assert_eq!(
+            texts("nohup nice -n 5 systemctl sta"),
+            vec!["start", "status"]
+        );
+
+        let git = texts("env FOO=bar git ");
+        assert!(git.contains(&"commit".to_string()));
+        assert_eq!(git, texts("git "));
+
+        // The wrapped command itself, and the wrapper's own options
+        assert!(matches!(
+            mgr.parse_context("sudo sys", 8),
+            CompletionContext::Command { prefix } if prefix == "sys"
+        ));
+        assert!(matches!(
+            mgr.parse_context("sudo -", 6),
+            CompletionContext::Option { command, .. } if command == "sudo"
+        ));
+        assert!(matches!(
+            mgr.parse_context("sudo -u ", 8),
+            CompletionContext::Positional { command, .. } if command == "sudo"
+        ));
+
+        // Wrappers can be turned off
+        mgr.set_wrappers(Vec::new());
+        assert!(matches!(
+            mgr.parse_context("sudo systemctl st", 17),
+            CompletionContext::Positional { command, .. } if command == "sudo"
+        ));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_nested_subcommands() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_nested_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("tool.toml"),
+            r#"
+[completions.tool]
+options = { "--verbose" = "Verbose output" }
+
+[completions.tool.subcommands.remote]
+description = "Manage remotes"
+options = [{ name = "-v", description = "Show URLs" }]
+
+[completions.tool.subcommands.remote.subcommands.add]
+description = "Add a remote"
+positional = "hosts"
+options = [
+    { name = "--fetch", description = "Fetch after adding" },
+    { name = "--track", description = "Branch to track", takes_value = true, value_completer = "branches" },
+]
+
+[completions.tool.subcommands.remote.subcommands.remove]
+description = "Remove a remote"
+
+[completions.tool.subcommands.status]
+description = "Show status"
+"#,
+        )
+        .unwrap();
+
+        let mgr = CompletionManager::with_search_paths(vec![dir.clone()]);
+        let texts = |line: &str| -> Vec<String> {
+            let mut t: Vec<String> = mgr
+                .complete(line, line.len())
+                .into_iter()
+                .map(|c| c.text)
+                .collect();
+            t.sort();
+            t
+        };
+
+        // Second level subcommands
+        assert_eq!(texts("tool remote "), vec!["add", "remove"]);
+        assert_eq!(texts("tool remote a"), vec!["add"]);
+
+        // Inner options, then outer levels
+        assert_eq!(
+            texts("tool remote add --"),
+            vec!["--fetch", "--track", "--verbose"]
+        );
+        assert_eq!(
+            texts("tool remote add -"),
+            vec!["--fetch", "--track", "--verbose", "-v"]
+        );
+
+        // Inner option values and positionals resolve against the deepest level
+        match mgr.parse_context("tool remote add --track ", 24) {
+            CompletionContext::OptionValue {
+                subcommand, option, ..
+            } => {
+                assert_eq!(subcommand.as_deref(), Some("remote add"));
+                assert_eq!(option, "--track");
+            }
+            ctx => panic!("Expected OptionValue context, got {:?}", ctx),
+        }
+        match mgr.parse_context("tool remote add origin ", 23) {
+            CompletionContext::Positional { subcommand, .. } => {
+                assert_eq!(subcommand.as_deref(), Some("remote add"))
+            }
+            ctx => panic!("Expected Positional context, got {:?}", ctx),
+        }
+
+        // A leaf subcommand doesn't offer more subcommands
+        assert!(matches!(
+            mgr.parse_context("tool status ", 12),
+            CompletionContext::Positional { .. }
+        ));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_positional_sequence() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_positional_{}", std::process::id()));
+        fs::create_dir_all(dir.join("docs")).unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+        fs::write(
+            dir.join("copy.toml"),
+            r#"
+[completions.copy]
+positional = ["files", "files", "directories"]
+options = { "--mode" = { description = "Mode", takes_value = true } }
+"#,
+        )
+        .unwrap();
+
+        let mgr = CompletionManager::with_search_paths(vec![dir.clone()]);
+        let base = format!("{}/", dir.display());
+        let texts = |line: String| -> Vec<String> {
+            let mut t: Vec<String> = mgr
+                .complete(&line, line.len())
+                .into_iter()
+                .map(|c| c.text.trim_start_matches(&base).to_string())
+                .collect();
+            t.retain(|name| name != "copy.toml");
+            t.sort();
+            t
+        };
+
+        // First positional completes files, the third only directories
+        assert_eq!(texts(format!("copy {}", base)), vec!["docs/", "notes.txt"]);
+        assert_eq!(texts(format!("copy a b {}", base)), vec!["docs/"]);
+
+        // Options and their values don't count; the last completer repeats
+        assert_eq!(
+            texts(format!("copy --mode 644 a -v b {}", base)),
+            vec!["docs/"]
+        );
+        assert_eq!(texts(format!("copy a b c {}", base)), vec!["docs/"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -5,22 +5,32 @@
 
 mod builtins;
 mod manager;
+pub mod shell_export;
+mod tester;
 mod zsh_convert;
 
 pub use builtins::BuiltinCompleter;
-pub use manager::CompletionManager;
+pub use manager::{CompletionManager, default_wrappers};
+pub use tester::test_completion_file;
 pub use zsh_convert::convert_zsh_file;
 
 use serde::Deserialize;
 use std::collections::HashMap;
 
 /// Context for completion - determines what type of completion is needed.
+///
+/// `subcommand` fields hold the full chain of nested subcommands separated by
+/// spaces (e.g. "remote add" for `git remote add`).
 #[derive(Debug, Clone)]
 pub enum CompletionContext {
     /// Completing command name (first word)
     Command { prefix: String },
-    /// Completing subcommand
-    Subcommand { command: String, prefix: String },
+    /// Completing subcommand (nested under `parent`, a space-separated chain like "remote")
+    Subcommand {
+        command: String,
+        parent: Option<String>,
+        prefix: String,
+    },
     /// Completing option (starting with - or --)
     Option {
         command: String,
@@ -38,6 +48,8 @@ pub enum CompletionContext {
     Positional {
         command: String,
         subcommand: Option<String>,
+        /// Index among the positional arguments (0-based)
+        index: usize,
         prefix: String,
     },
 }
@@ -87,8 +99,8 @@ pub struct CommandCompletionDef {
     /// Options for the main command
     #[serde(default)]
     pub options: HashMap<String, OptionValue>,
-    /// Built-in or dynamic completer name for positional args
-    pub positional: Option<String>,
+    /// Built-in or dynamic completer name(s) for positional args
+    pub positional: Option<PositionalValue>,
     /// Dynamic completers (run shell commands)
     #[serde(default)]
     pub dynamic: HashMap<String, DynamicCompleterDef>,
@@ -104,6 +116,29 @@ pub enum SubcommandValue {
     Detailed(SubcommandDef),
 }
 
+/// Completer for positional args - one name for all of them, or a list
+/// applied by argument index whose last entry repeats.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum PositionalValue {
+    /// Same completer for every positional argument
+    Single(String),
+    /// Completer per argument index, e.g. `["files", "directories"]`
+    Sequence(Vec<String>),
+}
+
+impl PositionalValue {
+    /// Completer for the positional argument at `index` (0-based).
+    pub fn completer_at(&self, index: usize) -> Option<&str> {
+        match self {
+            PositionalValue::Single(name) => Some(name),
+            PositionalValue::Sequence(names) => {
+                names.get(index).or(names.last()).map(String::as_str)
+            }
+        }
+    }
+}
+
 /// Detailed subcommand definition with options and positional completers.
 #[derive(Debug, Deserialize, Clone)]
 pub struct SubcommandDef {
@@ -112,8 +147,11 @@ pub struct SubcommandDef {
     /// Options for this subcommand
     #[serde(default)]
     pub options: Vec<OptionDef>,
-    /// Built-in or dynamic completer name for positional args
-    pub positional: Option<String>,
+    /// Built-in or dynamic completer name(s) for positional args
+    pub positional: Option<PositionalValue>,
+    /// Nested subcommands (e.g. "add" under `git remote`)
+    #[serde(default)]
+    pub subcommands: HashMap<String, SubcommandValue>,
 }
 
 /// Value for an option - can be a simple string or detailed definition.
@@ -147,6 +185,13 @@ impl OptionValue {
             OptionValue::Detailed(d) => d.value_completer.as_deref(),
         }
     }
+
+    pub fn aliases(&self) -> &[String] {
+        match self {
+            OptionValue::Simple(_) => &[],
+            OptionValue::Detailed(d) => &d.aliases,
+        }
+    }
 }
 
 /// Detailed option definition.
@@ -157,6 +202,9 @@ pub struct OptionDetailedDef {
     pub takes_value: Option<bool>,
     /// Completer for the option value (built-in or dynamic name)
     pub value_completer: Option<String>,
+    /// Other names for the same option (e.g. "-m" for "--message")
+    #[serde(default)]
+    pub aliases: Vec<String>,
 }
 
 /// Option definition in a list format (for subcommand options).
@@ -171,6 +219,9 @@ pub struct OptionDef {
     pub takes_value: bool,
     /// Completer for the option value
     pub value_completer: Option<String>,
+    /// Other names for the same option (e.g. "-m" for "--message")
+    #[serde(default)]
+    pub aliases: Vec<String>,
 }
 
 /// Dynamic completer that runs a shell command.
@@ -180,6 +231,19 @@ pub struct DynamicCompleterDef {
     pub command: String,
     /// Cache duration in seconds (default: no cache)
     pub cache_seconds: Option<u64>,
+    /// Maximum number of output lines kept (default: 1000)
+    pub max_lines: Option<usize>,
+    /// Keep results on disk until this changes, instead of only for `cache_seconds`
+    #[serde(default)]
+    pub cache_key: Option<DynamicCacheKey>,
+}
+
+/// What a dynamic completer's on-disk cache is keyed on.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DynamicCacheKey {
+    /// Output of `<command> --version`, so results last until an upgrade
+    Version,
 }
 
 /// Resolved command completion (after parsing TOML).
@@ -189,7 +253,7 @@ pub struct CommandCompletion {
     pub description: Option<String>,
     pub subcommands: HashMap<String, SubcommandCompletion>,
     pub options: Vec<OptionCompletion>,
-    pub positional: Option<String>,
+    pub positional: Option<PositionalValue>,
     pub dynamic: HashMap<String, DynamicCompleterDef>,
 }
 
@@ -198,13 +262,16 @@ pub struct CommandCompletion {
 pub struct SubcommandCompletion {
     pub description: Option<String>,
     pub options: Vec<OptionCompletion>,
-    pub positional: Option<String>,
+    pub positional: Option<PositionalValue>,
+    pub subcommands: HashMap<String, SubcommandCompletion>,
 }
 
 /// Resolved option completion.
 #[derive(Debug, Clone)]
 pub struct OptionCompletion {
     pub name: String,
+    /// Alternative names sharing this option's description and value completer
+    pub aliases: Vec<String>,
     pub description: Option<String>,
     pub takes_value: bool,
     pub value_completer: Option<String>,
@@ -218,21 +285,73 @@ impl CommandCompletion {
             .iter()
             .map(|(name, val)| OptionCompletion {
                 name: name.clone(),
+                aliases: val.aliases().to_vec(),
                 description: val.description().map(|s| s.to_string()),
                 takes_value: val.takes_value(),
                 value_completer: val.value_completer().map(|s| s.to_string()),
             })
             .collect();
 
-        let subcommands = def
-            .subcommands
-            .iter()
+        Self {
+            description: def.description,
+            subcommands: SubcommandCompletion::from_defs(&def.subcommands),
+            options,
+            positional: def.positional,
+            dynamic: def.dynamic,
+        }
+    }
+
+    /// Resolve a subcommand chain like "remote add", outermost first.
+    /// Returns `None` if any level is unknown.
+    pub fn subcommand_chain(&self, path: &str) -> Option<Vec<&SubcommandCompletion>> {
+        let mut chain = Vec::new();
+        let mut level = &self.subcommands;
+        for name in path.split_whitespace() {
+            let sub = level.get(name)?;
+            chain.push(sub);
+            level = &sub.subcommands;
+        }
+        Some(chain)
+    }
+
+    /// Subcommands available after a chain (top-level ones for `None`).
+    pub fn subcommands_at(
+        &self,
+        path: Option<&str>,
+    ) -> Option<&HashMap<String, SubcommandCompletion>> {
+        match path {
+            None => Some(&self.subcommands),
+            Some(path) => self
+                .subcommand_chain(path)?
+                .last()
+                .map(|sub| &sub.subcommands),
+        }
+    }
+}
+
+impl OptionCompletion {
+    /// The option's name followed by its aliases.
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.name).chain(self.aliases.iter())
+    }
+
+    /// Whether `option` is this option's name or one of its aliases.
+    pub fn matches(&self, option: &str) -> bool {
+        self.names().any(|name| name == option)
+    }
+}
+
+impl SubcommandCompletion {
+    /// Resolve a map of TOML subcommand definitions, recursively.
+    fn from_defs(defs: &HashMap<String, SubcommandValue>) -> HashMap<String, Self> {
+        defs.iter()
             .map(|(name, val)| {
                 let sub = match val {
                     SubcommandValue::Simple(desc) => SubcommandCompletion {
                         description: Some(desc.clone()),
                         options: vec![],
                         positional: None,
+                        subcommands: HashMap::new(),
                     },
                     SubcommandValue::Detailed(d) => SubcommandCompletion {
                         description: d.description.clone(),
@@ -241,25 +360,19 @@ impl CommandCompletion {
                             .iter()
                             .map(|o| OptionCompletion {
                                 name: o.name.clone(),
+                                aliases: o.aliases.clone(),
                                 description: o.description.clone(),
                                 takes_value: o.takes_value,
                                 value_completer: o.value_completer.clone(),
                             })
                             .collect(),
                         positional: d.positional.clone(),
+                        subcommands: Self::from_defs(&d.subcommands),
                     },
                 };
                 (name.clone(), sub)
             })
-            .collect();
-
-        Self {
-            description: def.description,
-            subcommands,
-            options,
-            positional: def.positional,
-            dynamic: def.dynamic,
-        }
+            .collect()
     }
 }
 
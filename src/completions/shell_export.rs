@@ -0,0 +1,210 @@
+//! Export nosh completions as scripts for bash and zsh.
+//!
+//! `nosh --install-completions` writes one static bash completion script per
+//! command plus an `init.sh` snippet that sources them. zsh loads the same
+//! scripts through `bashcompinit`. Dynamic completers are not exported;
+//! positionals fall back to the shell's default file completion.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use super::{CommandCompletion, OptionCompletion, SubcommandCompletion};
+use crate::fs_util;
+
+/// Generate a bash completion script for `command`.
+pub fn bash_script(command: &str, completion: &CommandCompletion) -> String {
+    let func = format!("_nosh_complete_{}", sanitize(command));
+
+    // Every subcommand chain with the words offered after it
+    let mut levels = vec![(
+        String::new(),
+        level_words(&completion.subcommands, &[], completion),
+    )];
+    collect_levels(completion, &completion.subcommands, "", &[], &mut levels);
+
+    let mut out = format!(
+        "# Generated by nosh from the {command} completion file. Do not edit.\n\
+         {func}() {{\n\
+         \x20   local cur=\"${{COMP_WORDS[COMP_CWORD]}}\" path=\"\" word i words=\"\"\n\
+         \x20   for ((i = 1; i < COMP_CWORD; i++)); do\n\
+         \x20       word=\"${{COMP_WORDS[i]}}\"\n\
+         \x20       case \"$path:$word\" in\n"
+    );
+    for (path, _) in levels.iter().skip(1) {
+        let (parent, name) = path.rsplit_once(' ').unwrap_or(("", path));
+        out.push_str(&format!(
+            "            {}) path=\"${{path:+$path }}$word\" ;;\n",
+            quote(&format!("{}:{}", parent, name))
+        ));
+    }
+    out.push_str("        esac\n    done\n    case \"$path\" in\n");
+    for (path, words) in &levels {
+        out.push_str(&format!(
+            "        {}) words={} ;;\n",
+            quote(path),
+            quote(&words.join(" "))
+        ));
+    }
+    out.push_str(&format!(
+        "    esac\n\
+         \x20   COMPREPLY=($(compgen -W \"$words\" -- \"$cur\"))\n\
+         }}\n\
+         complete -o default -F {func} {command}\n"
+    ));
+    out
+}
+
+/// Generate the snippet that an existing shell sources to load the scripts.
+pub fn integration_snippet(scripts_dir: &Path, commands: &[String]) -> String {
+    let mut out = String::from(
+        "# nosh completions for bash and zsh. Generated by `nosh --install-completions`.\n\
+         if [ -n \"$ZSH_VERSION\" ]; then\n\
+         \x20   autoload -U +X compinit && compinit -i\n\
+         \x20   autoload -U +X bashcompinit && bashcompinit\n\
+         fi\n",
+    );
+    for command in commands {
+        let script = scripts_dir.join(format!("{}.bash", command));
+        out.push_str(&format!(
+            "[ -r {path} ] && . {path}\n",
+            path = quote(&script.to_string_lossy())
+        ));
+    }
+    out
+}
+
+/// Write scripts for `completions` into `dir/completions/` and the snippet to
+/// `dir/init.sh`. Returns the snippet path.
+pub fn install(dir: &Path, completions: &[(String, CommandCompletion)]) -> Result<PathBuf> {
+    let scripts_dir = dir.join("completions");
+    fs::create_dir_all(&scripts_dir)?;
+
+    let mut commands = Vec::new();
+    for (command, completion) in completions {
+        fs_util::write_atomic(
+            &scripts_dir.join(format!("{}.bash", command)),
+            bash_script(command, completion),
+        )?;
+        commands.push(command.clone());
+    }
+
+    let snippet = dir.join("init.sh");
+    fs_util::write_atomic(&snippet, integration_snippet(&scripts_dir, &commands))?;
+    Ok(snippet)
+}
+
+/// Walk nested subcommands, recording each chain and the words it offers.
+fn collect_levels<'a>(
+    root: &CommandCompletion,
+    subcommands: &'a HashMap<String, SubcommandCompletion>,
+    parent: &str,
+    chain: &[&'a SubcommandCompletion],
+    levels: &mut Vec<(String, Vec<String>)>,
+) {
+    let mut names: Vec<&String> = subcommands.keys().collect();
+    names.sort();
+    for name in names {
+        let sub = &subcommands[name];
+        let path = if parent.is_empty() {
+            name.clone()
+        } else {
+            format!("{} {}", parent, name)
+        };
+        let mut chain = chain.to_vec();
+        chain.push(sub);
+        levels.push((path.clone(), level_words(&sub.subcommands, &chain, root)));
+        collect_levels(root, &sub.subcommands, &path, &chain, levels);
+    }
+}
+
+/// Subcommands, then options innermost level first, then the command's own.
+fn level_words(
+    subcommands: &HashMap<String, SubcommandCompletion>,
+    chain: &[&SubcommandCompletion],
+    root: &CommandCompletion,
+) -> Vec<String> {
+    let mut names: Vec<String> = subcommands.keys().cloned().collect();
+    names.sort();
+
+    let options = chain
+        .iter()
+        .rev()
+        .flat_map(|sub| sub.options.iter())
+        .chain(root.options.iter())
+        .flat_map(|opt: &OptionCompletion| opt.names().cloned());
+    for option in options {
+        if !names.contains(&option) {
+            names.push(option);
+        }
+    }
+    names
+}
+
+/// Make a command name usable in a shell function name.
+fn sanitize(command: &str) -> String {
+    command
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Single-quote a string for the shell.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::completions::CompletionFile;
+
+    fn sample() -> CommandCompletion {
+        let file: CompletionFile = toml::from_str(
+            r#"
+[completions.tool]
+options = { "--verbose" = "Verbose output" }
+
+[completions.tool.subcommands.remote]
+description = "Manage remotes"
+options = [{ name = "-v", description = "Show URLs" }]
+
+[completions.tool.subcommands.remote.subcommands]
+add = "Add a remote"
+
+[completions.tool.subcommands]
+status = "Show status"
+"#,
+        )
+        .unwrap();
+        CommandCompletion::from_def(file.completions["tool"].clone())
+    }
+
+    #[test]
+    fn test_bash_script_levels() {
+        let script = bash_script("tool", &sample());
+        assert!(script.contains("':remote') path="));
+        assert!(script.contains("'remote:add') path="));
+        assert!(script.contains("'') words='remote status --verbose' ;;"));
+        assert!(script.contains("'remote') words='add -v --verbose' ;;"));
+        assert!(script.contains("'remote add') words='-v --verbose' ;;"));
+        assert!(script.ends_with("complete -o default -F _nosh_complete_tool tool\n"));
+    }
+
+    #[test]
+    fn test_install_writes_snippet() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_export_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let snippet = install(&dir, &[("tool".to_string(), sample())]).unwrap();
+        let content = fs::read_to_string(&snippet).unwrap();
+        let script = dir.join("completions").join("tool.bash");
+        assert!(content.contains(&format!("'{}'", script.display())));
+        assert!(content.contains("bashcompinit"));
+        assert!(script.exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
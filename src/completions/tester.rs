@@ -0,0 +1,75 @@
+//! Run a completion file against an input line without starting the shell.
+//!
+//! Backs `nosh completions test FILE INPUT`, which prints the detected
+//! context and the candidates so completion authors can iterate quickly.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::CompletionManager;
+
+/// Complete `line` (cursor at the end) using only the commands in `file`
+/// and render a report of the context and candidates.
+pub fn test_completion_file(file: &Path, line: &str) -> Result<String> {
+    let manager = CompletionManager::from_file(file)?;
+    let context = manager.parse_context(line, line.len());
+    let completions = manager.complete_with_context(&context);
+
+    let mut out = format!("Context: {:?}\n", context);
+    if completions.is_empty() {
+        out.push_str("No candidates\n");
+        return Ok(out);
+    }
+
+    out.push_str(&format!("Candidates ({}):\n", completions.len()));
+    let width = completions.iter().map(|c| c.text.len()).max().unwrap_or(0);
+    for completion in &completions {
+        match &completion.description {
+            Some(desc) => out.push_str(&format!("  {:width$}  {}\n", completion.text, desc)),
+            None => out.push_str(&format!("  {}\n", completion.text)),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_completion_file_report() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_tester_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("sample.toml");
+        fs::write(
+            &file,
+            r#"
+[completions.deploy]
+description = "Deploy things"
+
+[completions.deploy.subcommands]
+start = "Start a deployment"
+status = "Show deployment status"
+rollback = "Undo the last deployment"
+"#,
+        )
+        .unwrap();
+
+        let report = test_completion_file(&file, "deploy st").unwrap();
+        assert!(report.starts_with("Context: Subcommand"));
+        assert!(report.contains("prefix: \"st\""));
+        assert!(report.contains("Candidates (2):"));
+        assert!(report.contains("  start   Start a deployment\n"));
+        assert!(report.contains("  status  Show deployment status\n"));
+        assert!(!report.contains("rollback"));
+
+        let report = test_completion_file(&file, "deploy xyz").unwrap();
+        assert!(report.ends_with("No candidates\n"));
+
+        assert!(test_completion_file(&dir.join("missing.toml"), "deploy ").is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -5,6 +5,7 @@
 use anyhow::{Context, Result};
 use regex::Regex;
 use std::collections::HashMap;
+use std::fmt;
 
 /// Parsed zsh completion option.
 #[derive(Debug)]
@@ -21,10 +22,29 @@ struct ZshCompletion {
     description: Option<String>,
     options: Vec<ZshOption>,
     subcommands: HashMap<String, String>,
+    /// Fragments that couldn't be converted.
+    warnings: Vec<Warning>,
+}
+
+/// A fragment of the zsh file the converter skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    /// 1-based line number in the zsh file.
+    pub line: usize,
+    pub fragment: String,
+    pub reason: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} ({})", self.line, self.fragment, self.reason)
+    }
 }
 
 /// Convert zsh completion file content to nosh TOML format.
 ///
+/// Also returns the fragments that were skipped, so they can be fixed by hand.
+///
 /// # Example
 ///
 /// ```ignore
@@ -36,12 +56,12 @@ struct ZshCompletion {
 ///     '--config[Config file]:file:_files'
 /// "#;
 ///
-/// let toml = convert_zsh_completion(zsh).unwrap();
+/// let (toml, _warnings) = convert_zsh_completion(zsh).unwrap();
 /// println!("{}", toml);
 /// ```
-pub fn convert_zsh_completion(zsh_content: &str) -> Result<String> {
+pub fn convert_zsh_completion(zsh_content: &str) -> Result<(String, Vec<Warning>)> {
     let completion = parse_zsh_completion(zsh_content)?;
-    Ok(generate_toml(&completion))
+    Ok((generate_toml(&completion), completion.warnings))
 }
 
 /// Parse zsh completion content.
@@ -73,6 +93,10 @@ fn parse_zsh_completion(content: &str) -> Result<ZshCompletion> {
     // Parse subcommands from _describe or case statements
     parse_subcommands(content, &mut subcommands);
 
+    let mut warnings = unconverted_arguments(content);
+    warnings.extend(unconverted_case_branches(content));
+    warnings.sort_by_key(|w| w.line);
+
     if command.is_empty() {
         anyhow::bail!("No #compdef directive found");
     }
@@ -82,6 +106,7 @@ fn parse_zsh_completion(content: &str) -> Result<ZshCompletion> {
         description,
         options,
         subcommands,
+        warnings,
     })
 }
 
@@ -94,11 +119,7 @@ fn parse_arguments_block(block: &str, options: &mut Vec<ZshOption>) {
     // '(-v --verbose)'{-v,--verbose}'[Verbose]'
     // '--config=[Config file]:config:_files'
 
-    let simple_opt =
-        Regex::new(r"'(-{1,2}[a-zA-Z0-9_-]+)(?:=)?\[([^\]]*)\](?::([^:]+):([^']+))?'").unwrap();
-
-    let combined_opt =
-        Regex::new(r"'\([^)]+\)'\{([^}]+)\}'\[([^\]]*)\](?::([^:]+):([^']+))?'").unwrap();
+    let (simple_opt, combined_opt) = option_patterns();
 
     // Simple options
     for cap in simple_opt.captures_iter(block) {
@@ -129,6 +150,18 @@ fn parse_arguments_block(block: &str, options: &mut Vec<ZshOption>) {
     }
 }
 
+/// Patterns for plain (`'-h[Help]'`) and grouped
+/// (`'(-v --verbose)'{-v,--verbose}'[Verbose]'`) option specs.
+fn option_patterns() -> (Regex, Regex) {
+    let simple_opt =
+        Regex::new(r"'(-{1,2}[a-zA-Z0-9_-]+)(?:=)?\[([^\]]*)\](?::([^:]+):([^']+))?'").unwrap();
+
+    let combined_opt =
+        Regex::new(r"'\([^)]+\)'\{([^}]+)\}'\[([^\]]*)\](?::([^:]+):([^']+))?'").unwrap();
+
+    (simple_opt, combined_opt)
+}
+
 /// Parse subcommands from _describe calls or case statements.
 fn parse_subcommands(content: &str, subcommands: &mut HashMap<String, String>) {
     // Match _describe patterns:
@@ -161,6 +194,134 @@ fn parse_subcommands(content: &str, subcommands: &mut HashMap<String, String>) {
     }
 }
 
+/// `_arguments` fragments the option patterns in [`parse_arguments_block`]
+/// don't understand.
+fn unconverted_arguments(content: &str) -> Vec<Warning> {
+    let (simple_opt, combined_opt) = option_patterns();
+    let mut warnings = Vec::new();
+
+    for call in Regex::new(r"\b_arguments\b").unwrap().find_iter(content) {
+        // The call runs until the first line without a continuation
+        let rest = &content[call.end()..];
+        let end = rest
+            .split_inclusive('\n')
+            .scan(0, |len, line| {
+                *len += line.len();
+                Some((*len, line.trim_end().ends_with('\\')))
+            })
+            .find(|(_, continued)| !continued)
+            .map_or(rest.len(), |(len, _)| len);
+
+        for (offset, word) in shell_words_with_offsets(&rest[..end]) {
+            let reason = if word.starts_with("'-")
+                || word.starts_with("\"-")
+                || word.starts_with("'(")
+                || word.starts_with("'*")
+                || word.starts_with('{')
+            {
+                if word.starts_with("'*:") {
+                    "rest-argument spec isn't converted"
+                } else if simple_opt.is_match(word) || combined_opt.is_match(word) {
+                    continue;
+                } else {
+                    "unrecognized option spec"
+                }
+            } else if word.starts_with("':")
+                || word.starts_with('\'') && word[1..].starts_with(|c: char| c.is_ascii_digit())
+            {
+                "positional argument spec isn't converted"
+            } else {
+                continue;
+            };
+
+            warnings.push(Warning {
+                line: line_of(content, call.end() + offset),
+                fragment: word.to_string(),
+                reason: reason.to_string(),
+            });
+        }
+    }
+
+    warnings
+}
+
+/// `case` branches skipped because they have no `# description` comment.
+fn unconverted_case_branches(content: &str) -> Vec<Warning> {
+    let case_re = Regex::new(r"(?s)\bcase\b.*?\besac\b").unwrap();
+    let branch_re = Regex::new(r"(?m)^\s*\(?([\w|-]+)\)(.*)$").unwrap();
+    let mut warnings = Vec::new();
+
+    for block in case_re.find_iter(content) {
+        for cap in branch_re.captures_iter(block.as_str()) {
+            let pattern = &cap[1];
+            let described = cap[2].trim_start().starts_with('#');
+            if described && !pattern.contains('|') {
+                continue;
+            }
+            let whole = cap.get(0).unwrap();
+            warnings.push(Warning {
+                line: line_of(content, block.start() + whole.start()),
+                fragment: format!("{})", pattern),
+                reason: if pattern.contains('|') {
+                    "alternative patterns aren't converted".to_string()
+                } else {
+                    "case branch without a `# description` comment".to_string()
+                },
+            });
+        }
+    }
+
+    warnings
+}
+
+/// Split shell text into words (keeping quotes), with each word's byte offset.
+/// Line continuations are treated as whitespace.
+fn shell_words_with_offsets(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\\') => {
+                escaped = true;
+                start.get_or_insert(i);
+            }
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                start.get_or_insert(i);
+            }
+            (None, c) if c.is_whitespace() => {
+                if let Some(s) = start.take() {
+                    words.push((s, &text[s..i]));
+                }
+            }
+            (None, _) => {
+                start.get_or_insert(i);
+            }
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, &text[s..]));
+    }
+
+    // A lone `\` is a line continuation, not a word
+    words.retain(|(_, w)| *w != "\\");
+    words
+}
+
+/// 1-based line number of a byte offset.
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset].matches('\n').count() + 1
+}
+
 /// Generate TOML output from parsed completion.
 fn generate_toml(completion: &ZshCompletion) -> String {
     let mut output = String::new();
@@ -208,7 +369,7 @@ fn generate_toml(completion: &ZshCompletion) -> String {
 }
 
 /// Read a zsh completion file and convert it.
-pub fn convert_zsh_file(path: &std::path::Path) -> Result<String> {
+pub fn convert_zsh_file(path: &std::path::Path) -> Result<(String, Vec<Warning>)> {
     let content = std::fs::read_to_string(path)
         .with_context(|| format!("Failed to read {}", path.display()))?;
     convert_zsh_completion(&content)
@@ -242,6 +403,34 @@ mod tests {
         assert!(result.options[0].takes_value);
     }
 
+    #[test]
+    fn test_report_unconverted_fragments() {
+        let content = r#"#compdef tool
+_tool() {
+    _arguments -s \
+        '--verbose[Verbose output]' \
+        '*-I+[Include dir]:dir:_files -/' \
+        '(-q --quiet)'{-q,--quiet}'[Be quiet]'
+    case $words[2] in
+        build) # Build it
+            ;;
+        clean)
+            ;;
+    esac
+}
+"#;
+        let (toml, warnings) = convert_zsh_completion(content).unwrap();
+        assert!(toml.contains("\"--verbose\" = \"Verbose output\""));
+        assert!(toml.contains("\"--quiet\" = \"Be quiet\""));
+
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert_eq!(warnings[0].line, 5);
+        assert_eq!(warnings[0].fragment, "'*-I+[Include dir]:dir:_files -/'");
+        assert_eq!(warnings[0].reason, "unrecognized option spec");
+        assert_eq!(warnings[1].line, 10);
+        assert_eq!(warnings[1].fragment, "clean)");
+    }
+
     #[test]
     fn test_generate_toml() {
         let completion = ZshCompletion {
@@ -253,6 +442,7 @@ mod tests {
                 takes_value: false,
             }],
             subcommands: HashMap::new(),
+            warnings: Vec::new(),
         };
 
         let toml = generate_toml(&completion);
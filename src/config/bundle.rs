@@ -0,0 +1,235 @@
+//! Bundle the nosh config directory into a tar archive to move it between machines.
+//!
+//! Only user configuration is bundled: package contents are left out and
+//! re-cloned from the package registry after import. Credentials are never
+//! included.
+
+use anyhow::{Result, anyhow};
+use std::fs;
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::fs_util;
+use crate::packages::PackageRegistry;
+
+/// Files and directories (relative to the config dir) included in a bundle.
+pub const BUNDLE_ENTRIES: &[&str] = &[
+    "config.toml",
+    "init.sh",
+    "permissions.toml",
+    "packages.toml",
+    "themes",
+    "plugins",
+    "completions",
+];
+
+/// Result of restoring a bundle.
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    /// Files written (new or overwritten), relative to the config dir
+    pub written: Vec<PathBuf>,
+    /// Conflicting files the user chose to keep
+    pub skipped: Vec<PathBuf>,
+    /// Files already identical to the bundled copy
+    pub unchanged: usize,
+}
+
+/// Write the bundled entries of `config_dir` to the tar file `archive`.
+/// Returns the entries that were included.
+pub fn export(config_dir: &Path, archive: &Path) -> Result<Vec<&'static str>> {
+    let entries: Vec<&str> = BUNDLE_ENTRIES
+        .iter()
+        .copied()
+        .filter(|entry| config_dir.join(entry).exists())
+        .collect();
+    if entries.is_empty() {
+        return Err(anyhow!("Nothing to export in {}", config_dir.display()));
+    }
+
+    let output = Command::new("tar")
+        .arg("-cf")
+        .arg(archive)
+        .arg("-C")
+        .arg(config_dir)
+        .args(&entries)
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("Could not create archive: {}", stderr.trim()));
+    }
+
+    Ok(entries)
+}
+
+/// Restore a bundle into `config_dir`. Files that already exist with
+/// different contents are only replaced if `confirm_overwrite` returns true.
+pub fn import(
+    archive: &Path,
+    config_dir: &Path,
+    mut confirm_overwrite: impl FnMut(&Path) -> bool,
+) -> Result<ImportSummary> {
+    // Stage under the config dir's private tmp/, never a shared temp dir
+    let tmp = config_dir.join("tmp");
+    fs_util::create_private_dir(&tmp)?;
+    let staging = tmp.join(format!("import-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&staging);
+    fs::DirBuilder::new().mode(0o700).create(&staging)?;
+
+    let result = extract(archive, &staging).and_then(|()| {
+        // Package names become directory names, so refuse bundles that
+        // would clone outside the packages dir
+        PackageRegistry::load_from(&staging.join("packages.toml"))?.check_names()?;
+
+        let mut files = Vec::new();
+        for entry in BUNDLE_ENTRIES {
+            collect_files(&staging, Path::new(entry), &mut files)?;
+        }
+        restore(&staging, config_dir, &files, &mut confirm_overwrite)
+    });
+
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+/// Unpack `archive` into `dir`.
+fn extract(archive: &Path, dir: &Path) -> Result<()> {
+    let output = Command::new("tar")
+        .arg("-xf")
+        .arg(archive)
+        .arg("-C")
+        .arg(dir)
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("Could not read archive: {}", stderr.trim()));
+    }
+    Ok(())
+}
+
+/// Collect regular files under `root/rel`, as paths relative to `root`.
+/// Symlinks are skipped.
+fn collect_files(root: &Path, rel: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let Ok(meta) = fs::symlink_metadata(root.join(rel)) else {
+        return Ok(());
+    };
+    if meta.is_file() {
+        files.push(rel.to_path_buf());
+    } else if meta.is_dir() {
+        let mut entries: Vec<_> = fs::read_dir(root.join(rel))?.flatten().collect();
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            collect_files(root, &rel.join(entry.file_name()), files)?;
+        }
+    }
+    Ok(())
+}
+
+/// Copy staged files into the config dir, asking before replacing changed files.
+fn restore(
+    staging: &Path,
+    config_dir: &Path,
+    files: &[PathBuf],
+    confirm_overwrite: &mut impl FnMut(&Path) -> bool,
+) -> Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+
+    for rel in files {
+        let contents = fs::read(staging.join(rel))?;
+        let dest = config_dir.join(rel);
+
+        if let Ok(existing) = fs::read(&dest) {
+            if existing == contents {
+                summary.unchanged += 1;
+                continue;
+            }
+            if !confirm_overwrite(rel) {
+                summary.skipped.push(rel.clone());
+                continue;
+            }
+        }
+
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs_util::write_atomic(&dest, &contents)?;
+        summary.written.push(rel.clone());
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let root = std::env::temp_dir().join(format!("nosh_test_bundle_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let src = root.join("src");
+        let dst = root.join("dst");
+        fs::create_dir_all(src.join("themes")).unwrap();
+        fs::create_dir_all(&dst).unwrap();
+
+        fs::write(src.join("config.toml"), "[prompt]\ntheme = \"mine\"\n").unwrap();
+        fs::write(src.join("themes/mine.toml"), "[prompt]\nformat = \"$ \"\n").unwrap();
+        fs::write(src.join("packages.toml"), "[packages]\n").unwrap();
+        fs::write(src.join("credentials.toml"), "token = \"secret\"\n").unwrap();
+
+        let archive = root.join("nosh.tar");
+        let entries = export(&src, &archive).unwrap();
+        assert_eq!(entries, vec!["config.toml", "packages.toml", "themes"]);
+
+        // Restore into an empty dir
+        let summary = import(&archive, &dst, |_| panic!("no conflicts expected")).unwrap();
+        assert_eq!(summary.written.len(), 3);
+        assert_eq!(
+            fs::read_to_string(dst.join("themes/mine.toml")).unwrap(),
+            "[prompt]\nformat = \"$ \"\n"
+        );
+        assert!(!dst.join("credentials.toml").exists());
+
+        // Conflicts are only overwritten when confirmed
+        fs::write(dst.join("config.toml"), "# local edits\n").unwrap();
+        let summary = import(&archive, &dst, |_| false).unwrap();
+        assert_eq!(summary.skipped, vec![PathBuf::from("config.toml")]);
+        assert_eq!(summary.unchanged, 2);
+        assert_eq!(
+            fs::read_to_string(dst.join("config.toml")).unwrap(),
+            "# local edits\n"
+        );
+
+        let summary = import(&archive, &dst, |_| true).unwrap();
+        assert_eq!(summary.written, vec![PathBuf::from("config.toml")]);
+        assert_eq!(
+            fs::read_to_string(dst.join("config.toml")).unwrap(),
+            "[prompt]\ntheme = \"mine\"\n"
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_import_rejects_unsafe_package_names() {
+        let root =
+            std::env::temp_dir().join(format!("nosh_test_bundle_names_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let src = root.join("src");
+        let dst = root.join("dst");
+        fs::create_dir_all(&src).unwrap();
+
+        fs::write(
+            src.join("packages.toml"),
+            "[packages.evil]\nname = \"../../.ssh\"\nsource = \"https://example.com/x.git\"\ninstalled_at = \"0\"\nlast_updated = \"0\"\n",
+        )
+        .unwrap();
+        let archive = root.join("nosh.tar");
+        export(&src, &archive).unwrap();
+
+        assert!(import(&archive, &dst, |_| true).is_err());
+        assert!(!dst.join("packages.toml").exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -1,6 +1,7 @@
+pub mod bundle;
 mod schema;
 
-pub use schema::Config;
+pub use schema::{Config, parse_alias};
 
 /// Default cloud URL (compile-time or fallback)
 const DEFAULT_CLOUD_URL: &str = "https://noshell.dev/api";
@@ -1,8 +1,16 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::path::Path;
 
+use crate::exec::pager::PagingMode;
+use crate::fs_util;
+use crate::history::DedupMode;
 use crate::paths;
+use crate::safety::RiskLevel;
+use crate::ui::AgenticVerbosity;
+use crate::ui::background::BackgroundSetting;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -18,11 +26,21 @@ pub struct Config {
     pub behavior: BehaviorConfig,
     pub prompt: PromptConfig,
     pub history: HistoryConfig,
+    pub ui: UiConfig,
+    pub repl: ReplConfig,
+    pub safety: SafetyConfig,
+    pub context: ContextConfig,
+    /// Custom keybindings: key (e.g. "ctrl-g") -> action (e.g. "clear_context")
+    pub keybindings: HashMap<String, String>,
+    /// Shell aliases: name -> replacement (e.g. "gs" -> "git status")
+    pub aliases: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct AiConfig {
+    /// Enable `?`/`??` AI queries (off hides all AI affordances)
+    pub enabled: bool,
     /// Number of recent exchanges to include as context (default: 10)
     pub context_size: usize,
     /// Enable agentic mode for investigative queries
@@ -31,6 +49,16 @@ pub struct AiConfig {
     pub max_iterations: usize,
     /// Timeout in seconds for agentic queries (0 = no timeout)
     pub timeout: u64,
+    /// Agentic step detail: "quiet", "normal" or "verbose"
+    pub agentic_verbosity: AgenticVerbosity,
+    /// List each part of a chained AI command with its risk before running it
+    pub chain_preview: bool,
+    /// Extra instructions sent with every AI request (e.g. "prefer ripgrep over grep")
+    pub instructions: String,
+    /// Reuse earlier translations of the same `?` query in the same directory
+    pub cache_translations: bool,
+    /// How long cached translations stay valid, in hours
+    pub cache_ttl_hours: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +75,12 @@ pub struct PromptConfig {
     pub theme: String,
     /// Enable syntax highlighting for shell input
     pub syntax_highlighting: bool,
+    /// Time limit in milliseconds for toolchain version checks (`node --version`, ...)
+    pub context_timeout_ms: u64,
+    /// Count running Docker Compose services for `compose_running`
+    pub compose_ps: bool,
+    /// Plugin commands allowed to run at once while rendering the prompt
+    pub max_concurrent_plugins: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,15 +89,83 @@ pub struct HistoryConfig {
     /// Number of recent commands to load on startup for arrow-key navigation.
     /// Full history is always available in SQLite for search.
     pub load_count: usize,
+    /// Don't record commands that start with a space
+    pub ignore_space: bool,
+    /// Don't record a command identical to the previous one
+    pub ignore_dups: bool,
+    /// How repeats are collapsed for arrow-key navigation
+    pub dedup_mode: DedupMode,
+    /// Maximum entries kept in the history database (0 = unlimited)
+    pub max_entries: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// Maximum command output lines shown in agentic mode (0 = no limit)
+    pub output_max_lines: usize,
+    /// Terminal background for theme colors: "auto", "dark" or "light"
+    pub background: BackgroundSetting,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplConfig {
+    /// Page long command output: "auto", "always" or "never"
+    pub paging: PagingMode,
+    /// Commands skipped when completing (`sudo systemctl <tab>` completes systemctl)
+    pub completion_wrappers: Vec<String>,
+    /// Suggest the nearest subcommand after a failed `git stauts`
+    pub suggest_subcommands: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SafetyConfig {
+    /// Require approval for every non-safe command and ignore saved permissions
+    pub paranoid: bool,
+    /// Commands strictly below this risk level run without approval
+    pub auto_approve_below: RiskLevel,
+    /// Explain why with every blocked command and approval prompt
+    pub explain: bool,
+}
+
+impl Default for SafetyConfig {
+    fn default() -> Self {
+        Self {
+            paranoid: false,
+            auto_approve_below: RiskLevel::Low,
+            explain: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextConfig {
+    /// Look up the nearest tag for `git_tag` (runs `git describe`)
+    pub git_tag: bool,
+    /// Track when the repo was last fetched for `git_fetch_age`/`git_fetch_stale`
+    pub git_fetch_age: bool,
+    /// Hours after which the last fetch counts as stale
+    pub git_fetch_stale_hours: u64,
+    /// Directories whose project context is kept in memory
+    pub cache_size: usize,
 }
 
 impl Default for AiConfig {
     fn default() -> Self {
         Self {
+            enabled: true,
             context_size: 10,
             agentic_enabled: true,
             max_iterations: 10,
             timeout: 0, // 0 = no timeout
+            agentic_verbosity: AgenticVerbosity::Normal,
+            chain_preview: true,
+            instructions: String::new(),
+            cache_translations: false,
+            cache_ttl_hours: 24 * 7,
         }
     }
 }
@@ -79,22 +181,63 @@ impl Default for PromptConfig {
         Self {
             theme: "builtins/default".to_string(),
             syntax_highlighting: true,
+            context_timeout_ms: nosh_context::DEFAULT_COMMAND_TIMEOUT.as_millis() as u64,
+            compose_ps: false,
+            max_concurrent_plugins: crate::plugins::loader::DEFAULT_MAX_CONCURRENT,
+        }
+    }
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            output_max_lines: crate::ui::DEFAULT_MAX_LINES,
+            background: BackgroundSetting::default(),
+        }
+    }
+}
+
+impl Default for ReplConfig {
+    fn default() -> Self {
+        Self {
+            paging: PagingMode::default(),
+            completion_wrappers: crate::completions::default_wrappers(),
+            suggest_subcommands: true,
+        }
+    }
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self {
+            git_tag: false,
+            git_fetch_age: false,
+            git_fetch_stale_hours: 24,
+            cache_size: nosh_context::DEFAULT_CONTEXT_CACHE_SIZE,
         }
     }
 }
 
 impl Default for HistoryConfig {
     fn default() -> Self {
-        Self { load_count: 200 }
+        Self {
+            load_count: 200,
+            ignore_space: true,
+            ignore_dups: true,
+            dedup_mode: DedupMode::default(),
+            max_entries: 0,
+        }
     }
 }
 
 impl Config {
     pub fn load() -> Result<Self> {
-        let path = paths::config_file();
+        Self::load_from(&paths::config_file())
+    }
 
+    fn load_from(path: &Path) -> Result<Self> {
         if path.exists() {
-            let content = fs::read_to_string(&path)?;
+            let content = fs::read_to_string(path)?;
             let config: Config = toml::from_str(&content)?;
             Ok(config)
         } else {
@@ -104,12 +247,78 @@ impl Config {
     }
 
     pub fn save(&self) -> Result<()> {
-        let path = paths::config_file();
+        self.save_to(&paths::config_file())
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
         let content = toml::to_string_pretty(self)?;
-        fs::write(&path, content)?;
+        fs_util::write_atomic(path, content)?;
         Ok(())
     }
 }
+
+/// Parse a `/alias` argument like `gs=git status` or `ll='ls -la'`.
+pub fn parse_alias(arg: &str) -> Result<(String, String)> {
+    let Some((name, value)) = arg.split_once('=') else {
+        anyhow::bail!("expected name=value");
+    };
+    let name = name.trim();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        anyhow::bail!("alias name must be a single word (letters, digits, _ - .)");
+    }
+
+    let value = value.trim();
+    let value = ['\'', '"']
+        .iter()
+        .find_map(|&q| value.strip_prefix(q).and_then(|v| v.strip_suffix(q)))
+        .unwrap_or(value);
+    if value.is_empty() {
+        anyhow::bail!("alias '{}' needs a value", name);
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_alias() {
+        assert_eq!(
+            parse_alias("gs=git status").unwrap(),
+            ("gs".to_string(), "git status".to_string())
+        );
+        assert_eq!(parse_alias("ll='ls -la'").unwrap().1, "ls -la");
+        assert_eq!(parse_alias("k=\"kubectl\"").unwrap().1, "kubectl");
+        assert!(parse_alias("gs").is_err());
+        assert!(parse_alias("g s=git status").is_err());
+        assert!(parse_alias("/help=ls").is_err());
+        assert!(parse_alias("gs=").is_err());
+    }
+
+    #[test]
+    fn test_aliases_saved_to_config() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_alias_{}", std::process::id()));
+        let path = dir.join("config.toml");
+
+        let mut config = Config::default();
+        let (name, value) = parse_alias("gs=git status").unwrap();
+        config.aliases.insert(name, value);
+        config.save_to(&path).unwrap();
+
+        let loaded = Config::load_from(&path).unwrap();
+        assert_eq!(
+            loaded.aliases.get("gs").map(String::as_str),
+            Some("git status")
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
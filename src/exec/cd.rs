@@ -0,0 +1,135 @@
+//! Built-in `cd` handled in the nosh process.
+//!
+//! Simple `cd` commands are resolved here so nosh's own working directory
+//! (used for the prompt, context and terminal title) moves together with
+//! the shell's. Anything more involved (`cd $DIR && make`) goes to the shell.
+
+use std::path::{Path, PathBuf};
+
+/// Characters that make a `cd` line something only the shell can run.
+const SHELL_SYNTAX: &[char] = &[
+    ';', '&', '|', '<', '>', '$', '`', '\'', '"', '\\', '*', '?', '[', '(', ')', '{', '}', '#',
+];
+
+/// If `command` is a plain `cd` with at most one argument, return that argument.
+pub fn parse(command: &str) -> Option<Option<&str>> {
+    let mut words = command.split_whitespace();
+    if words.next()? != "cd" || command.contains(SHELL_SYNTAX) {
+        return None;
+    }
+    let arg = words.next();
+    if words.next().is_some() || arg.is_some_and(|a| !is_simple_arg(a)) {
+        return None;
+    }
+    Some(arg)
+}
+
+/// Whether nosh can handle `arg` itself. Options (`-P`, `--`) and `~user`
+/// are left to the shell.
+fn is_simple_arg(arg: &str) -> bool {
+    if arg.starts_with('-') {
+        return arg == "-";
+    }
+    match arg.strip_prefix('~') {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => true,
+    }
+}
+
+/// Resolve a `cd` argument to a directory.
+///
+/// No argument means `home`, `-` means `oldpwd`, and `~` expands to `home`.
+/// Relative paths are joined onto `cwd`; existence is checked by the caller.
+/// Returns `None` for arguments only the shell understands.
+pub fn resolve(
+    arg: Option<&str>,
+    cwd: &Path,
+    home: Option<&Path>,
+    oldpwd: Option<&Path>,
+) -> Option<Result<PathBuf, String>> {
+    if arg.is_some_and(|a| !is_simple_arg(a)) {
+        return None;
+    }
+    let home_or_err = || {
+        home.map(Path::to_path_buf)
+            .ok_or("HOME not set".to_string())
+    };
+    let target = match arg {
+        None | Some("~") => home_or_err(),
+        Some("-") => oldpwd
+            .map(Path::to_path_buf)
+            .ok_or("OLDPWD not set".to_string()),
+        Some(arg) => match arg.strip_prefix("~/") {
+            Some(rest) => home_or_err().map(|home| home.join(rest)),
+            None => Ok(PathBuf::from(arg)),
+        },
+    };
+    Some(target.map(|target| {
+        if target.is_absolute() {
+            target
+        } else {
+            cwd.join(target)
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("cd"), Some(None));
+        assert_eq!(parse("cd -"), Some(Some("-")));
+        assert_eq!(parse("  cd src/bin "), Some(Some("src/bin")));
+        assert_eq!(parse("cd a b"), None);
+        assert_eq!(parse("cd $HOME"), None);
+        assert_eq!(parse("cd foo && make"), None);
+        assert_eq!(parse("cdrecord"), None);
+
+        // Options and other users' homes go to the shell
+        assert_eq!(parse("cd ~user"), None);
+        assert_eq!(parse("cd ~user/src"), None);
+        assert_eq!(parse("cd --"), None);
+        assert_eq!(parse("cd -P dir"), None);
+        assert_eq!(parse("cd -L dir"), None);
+        assert_eq!(parse("cd -P"), None);
+        assert_eq!(parse("cd ~/src"), Some(Some("~/src")));
+    }
+
+    #[test]
+    fn test_resolve_home_and_previous() {
+        let cwd = Path::new("/srv/app");
+        let home = Some(Path::new("/home/me"));
+        let old = Some(Path::new("/tmp"));
+
+        assert_eq!(
+            resolve(None, cwd, home, old).unwrap().unwrap(),
+            Path::new("/home/me")
+        );
+        assert_eq!(
+            resolve(Some("~"), cwd, home, old).unwrap().unwrap(),
+            Path::new("/home/me")
+        );
+        assert_eq!(
+            resolve(Some("~/src"), cwd, home, old).unwrap().unwrap(),
+            Path::new("/home/me/src")
+        );
+        assert_eq!(
+            resolve(Some("-"), cwd, home, old).unwrap().unwrap(),
+            Path::new("/tmp")
+        );
+        assert_eq!(
+            resolve(Some("lib"), cwd, home, old).unwrap().unwrap(),
+            Path::new("/srv/app/lib")
+        );
+
+        assert!(resolve(Some("-"), cwd, home, None).unwrap().is_err());
+        assert!(resolve(None, cwd, None, old).unwrap().is_err());
+
+        // Left to the shell
+        assert!(resolve(Some("~user"), cwd, home, old).is_none());
+        assert!(resolve(Some("--"), cwd, home, old).is_none());
+        assert!(resolve(Some("-P"), cwd, home, old).is_none());
+    }
+}
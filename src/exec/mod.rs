@@ -1,5 +1,9 @@
+pub mod cd;
 pub mod env;
+pub mod pager;
 mod shell;
+pub mod suggest;
 pub mod terminal;
+pub mod timing;
 
-pub use shell::ShellSession;
+pub use shell::{PromptState, ShellSession};
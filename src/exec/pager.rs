@@ -0,0 +1,221 @@
+//! Paging for long command output.
+//!
+//! Pageable commands are piped into nosh's hidden `--pager` mode, which
+//! buffers output until it knows whether it fits on screen. Short output is
+//! printed as-is; long output is handed to `$PAGER` (default `less -R`).
+
+use std::ffi::OsStr;
+use std::io::{self, BufRead, Read, Write};
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use super::suggest::is_on_path;
+
+/// When to page command output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PagingMode {
+    /// Page only when output exceeds the terminal height
+    Auto,
+    /// Always page output
+    Always,
+    /// Never page output
+    #[default]
+    Never,
+}
+
+impl PagingMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PagingMode::Auto => "auto",
+            PagingMode::Always => "always",
+            PagingMode::Never => "never",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(PagingMode::Auto),
+            "always" => Some(PagingMode::Always),
+            "never" => Some(PagingMode::Never),
+            _ => None,
+        }
+    }
+}
+
+/// Programs known to only print output and exit, so piping them is safe.
+/// Anything else may drive the terminal itself (editors, TUIs, REPLs) and
+/// is never paged.
+const OUTPUT_COMMANDS: &[&str] = &[
+    "ls", "cat", "tac", "nl", "grep", "egrep", "fgrep", "rg", "ag", "find", "fd", "tree", "ps",
+    "df", "du", "env", "printenv", "diff", "sort", "uniq", "head", "cut", "column", "seq", "jq",
+    "yq", "dmesg", "lsof", "lsblk", "mount", "ss", "netstat", "dig", "nslookup", "stat", "file",
+    "hexdump", "xxd", "od", "strings", "objdump", "nm", "readelf", "ldd", "id", "groups", "uname",
+    "locale", "cal",
+];
+
+/// Decide whether output of `line_count` lines should be paged.
+///
+/// In auto mode, one line is reserved for the prompt that follows.
+pub fn should_page(mode: PagingMode, line_count: usize, terminal_height: usize) -> bool {
+    match mode {
+        PagingMode::Never => false,
+        PagingMode::Always => line_count > 0,
+        PagingMode::Auto => line_count > terminal_height.saturating_sub(1),
+    }
+}
+
+/// Check whether a command's output can safely be piped into the pager.
+///
+/// Only known output-only commands found on `path_var` (the shell's
+/// `$PATH`) qualify: not builtins,
+/// functions or aliases (which must run in the current shell), and not
+/// commands that already redirect, pipe or background their output.
+pub fn is_pageable(
+    command: &str,
+    path_var: &OsStr,
+    is_shell_command: impl Fn(&str) -> bool,
+) -> bool {
+    if command.contains('|') || command.contains('>') || command.trim_end().ends_with('&') {
+        return false;
+    }
+
+    let Some(word) = shell_words::split(command)
+        .ok()
+        .and_then(|words| words.into_iter().next())
+    else {
+        return false;
+    };
+
+    // Skip leading environment assignments like FOO=1 cmd
+    if word.contains('=') {
+        return false;
+    }
+
+    let name = word.rsplit('/').next().unwrap_or(&word);
+    OUTPUT_COMMANDS.contains(&name) && !is_shell_command(&word) && is_on_path(&word, path_var)
+}
+
+/// Wrap a command so its output goes through nosh's pager.
+/// The command's own exit status is preserved.
+pub fn wrap_command(command: &str, mode: PagingMode) -> Option<String> {
+    let exe = std::env::current_exe().ok()?;
+    let exe = shell_words::quote(&exe.to_string_lossy()).to_string();
+    Some(format!(
+        "{{ {}\n}} | {} --pager {}; (exit ${{PIPESTATUS[0]}})",
+        command,
+        exe,
+        mode.as_str()
+    ))
+}
+
+/// Run the hidden `--pager` mode: read stdin and page it if needed.
+pub fn run_pager(mode: PagingMode) -> io::Result<()> {
+    let height = termimad::crossterm::terminal::size()
+        .map(|(_, h)| h as usize)
+        .unwrap_or(24);
+
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut line_count = 0;
+
+    // Buffer until we know the output doesn't fit (or input ends)
+    loop {
+        let read = input.read_until(b'\n', &mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        line_count += 1;
+        if should_page(mode, line_count, height) {
+            break;
+        }
+    }
+
+    if !should_page(mode, line_count, height) {
+        let mut stdout = io::stdout();
+        stdout.write_all(&buffer)?;
+        return stdout.flush();
+    }
+
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| "less -R".to_string());
+
+    let mut child = match Command::new("sh")
+        .args(["-c", &pager])
+        .stdin(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(_) => {
+            // No usable pager: fall back to plain output
+            let mut stdout = io::stdout();
+            stdout.write_all(&buffer)?;
+            io::copy(&mut input, &mut stdout)?;
+            return stdout.flush();
+        }
+    };
+
+    if let Some(mut pager_stdin) = child.stdin.take() {
+        // The pager may exit early (user pressed q) - ignore broken pipes
+        if pager_stdin.write_all(&buffer).is_ok() {
+            let mut chunk = [0u8; 8192];
+            loop {
+                let n = input.read(&mut chunk)?;
+                if n == 0 || pager_stdin.write_all(&chunk[..n]).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_page() {
+        // Auto pages only when output doesn't fit above the prompt
+        assert!(!should_page(PagingMode::Auto, 10, 24));
+        assert!(!should_page(PagingMode::Auto, 23, 24));
+        assert!(should_page(PagingMode::Auto, 24, 24));
+        assert!(should_page(PagingMode::Auto, 1000, 24));
+
+        assert!(should_page(PagingMode::Always, 1, 24));
+        assert!(!should_page(PagingMode::Always, 0, 24));
+
+        assert!(!should_page(PagingMode::Never, 1000, 24));
+    }
+
+    #[test]
+    fn test_is_pageable() {
+        let no_builtins = |_: &str| false;
+        let path = std::env::var_os("PATH").unwrap_or_default();
+        let path = path.as_os_str();
+        assert!(is_pageable("cat bigfile", path, no_builtins));
+        assert!(is_pageable("/bin/ls -la", path, no_builtins));
+        // Looked up on the shell's PATH, not nosh's
+        assert!(!is_pageable(
+            "cat bigfile",
+            OsStr::new("/nonexistent"),
+            no_builtins
+        ));
+
+        assert!(!is_pageable("vim notes.md", path, no_builtins));
+        assert!(!is_pageable("git log", path, no_builtins));
+        // Unknown programs may be full-screen TUIs
+        assert!(!is_pageable("lazygit", path, no_builtins));
+        assert!(!is_pageable("btop", path, no_builtins));
+        assert!(!is_pageable("cat file | grep x", path, no_builtins));
+        assert!(!is_pageable("cat file > out", path, no_builtins));
+        assert!(!is_pageable("sleep 10 &", path, no_builtins));
+        assert!(!is_pageable("cd /tmp", path, |w| w == "cd"));
+    }
+}
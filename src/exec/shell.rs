@@ -3,16 +3,33 @@ use brush_builtins::{BuiltinSet, default_builtins};
 use brush_core::ProcessGroupPolicy;
 use brush_core::variables::ShellVariable;
 use brush_core::{ExecutionParameters, Shell};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::PathBuf;
 
-use super::terminal;
+use super::{cd, terminal};
 use crate::paths;
 
+/// Shell state shown in the prompt (`{shlvl}`, `{bg_status}`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PromptState {
+    /// `$SHLVL`, how deeply this shell is nested
+    pub shlvl: u32,
+    /// Exit code of the most recently finished background job
+    pub last_job_exit: Option<u8>,
+}
+
 pub struct ShellSession {
     shell: Shell,
     /// Default params (SameProcessGroup, for AI commands)
     params: ExecutionParameters,
     /// Job control params (NewProcessGroup, for shell commands)
     job_control_params: ExecutionParameters,
+    /// Exit code of the most recently reaped background job
+    last_job_exit: Option<u8>,
+    /// Aliases defined from config (replaced on each `set_aliases`)
+    config_aliases: Vec<String>,
 }
 
 impl ShellSession {
@@ -71,23 +88,75 @@ impl ShellSession {
             shell,
             params,
             job_control_params,
+            last_job_exit: None,
+            config_aliases: Vec::new(),
         })
     }
 
     /// Execute a command string with job control (for direct shell commands).
     /// Supports Ctrl+Z to suspend, and fg/bg/jobs builtins.
-    pub async fn execute(&mut self, command: &str) -> Result<()> {
+    /// Returns the exit code of the command.
+    pub async fn execute(&mut self, command: &str) -> Result<u8> {
         self.execute_internal(command, true).await
     }
 
     /// Execute a command without job control (for AI-translated commands).
     /// Ctrl+Z will not suspend these commands.
-    pub async fn execute_no_job_control(&mut self, command: &str) -> Result<()> {
+    pub async fn execute_no_job_control(&mut self, command: &str) -> Result<u8> {
         self.execute_internal(command, false).await
     }
 
+    /// Define the `[aliases]` from config, dropping ones no longer configured.
+    /// Aliases from `init.sh`/`.bashrc` or typed with `alias` are left alone.
+    pub fn set_aliases(&mut self, aliases: &BTreeMap<String, String>) {
+        for name in self.config_aliases.drain(..) {
+            self.shell.aliases.remove(&name);
+        }
+        for (name, value) in aliases {
+            self.shell.aliases.insert(name.clone(), value.clone());
+            self.config_aliases.push(name.clone());
+        }
+    }
+
+    /// Run a plain `cd` in both the shell and the nosh process.
+    ///
+    /// Returns `None` when the shell should run the line itself: it isn't a
+    /// simple `cd`, or `cd` is redefined as an alias or function.
+    pub fn try_builtin_cd(&mut self, command: &str) -> Option<Result<PathBuf, String>> {
+        let arg = cd::parse(command)?;
+        if self.shell.aliases.contains_key("cd") || self.shell.funcs().get("cd").is_some() {
+            return None;
+        }
+
+        let home = self.shell.env_str("HOME").map(|h| PathBuf::from(&*h));
+        let oldpwd = self.shell.env_str("OLDPWD").map(|o| PathBuf::from(&*o));
+        let target = match cd::resolve(
+            arg,
+            self.shell.working_dir(),
+            home.as_deref(),
+            oldpwd.as_deref(),
+        )? {
+            Ok(target) => target,
+            Err(e) => return Some(Err(e)),
+        };
+        if let Err(e) = self.shell.set_working_dir(&target) {
+            return Some(Err(format!("{}: {}", arg.unwrap_or("~"), e)));
+        }
+
+        let cwd = self.shell.working_dir().to_path_buf();
+        let _ = std::env::set_current_dir(&cwd);
+        Some(Ok(cwd))
+    }
+
+    /// Check whether a name is a shell alias, function or builtin (not a PATH lookup).
+    pub fn is_shell_command(&self, name: &str) -> bool {
+        self.shell.aliases.contains_key(name)
+            || self.shell.funcs().get(name).is_some()
+            || self.shell.builtins().contains_key(name)
+    }
+
     /// Internal execution with configurable job control
-    async fn execute_internal(&mut self, command: &str, job_control: bool) -> Result<()> {
+    async fn execute_internal(&mut self, command: &str, job_control: bool) -> Result<u8> {
         let trimmed = command.trim();
 
         // Handle exit/quit
@@ -104,7 +173,11 @@ impl ShellSession {
             &self.params
         };
 
-        let _result = self.shell.run_string(command, params).await?;
+        // Relay resize/termination signals to the foreground job while it runs
+        let forwarder = terminal::SignalForwarder::install();
+        let result = self.shell.run_string(command, params).await;
+        drop(forwarder);
+        let result = result?;
 
         // After command completes (or is stopped), reclaim terminal foreground
         if job_control {
@@ -115,13 +188,51 @@ impl ShellSession {
         let shell_cwd = self.shell.working_dir();
         let _ = std::env::set_current_dir(shell_cwd);
 
-        Ok(())
+        Ok(u8::from(&result.exit_code))
     }
 
     /// Check and report completed background jobs.
     /// Call this after each command to notify user of finished jobs.
     pub fn check_jobs(&mut self) -> Result<()> {
-        self.shell.check_for_completed_jobs()?;
+        for (job, result) in self.shell.jobs.poll()? {
+            if self.shell.options.enable_job_control {
+                writeln!(self.shell.stderr(), "{job}")?;
+            }
+            self.last_job_exit = Some(match result {
+                Ok(result) => u8::from(&result.exit_code),
+                Err(_) => 1,
+            });
+        }
         Ok(())
     }
+
+    /// Shell nesting depth from `$SHLVL` (1 for a top-level shell).
+    pub fn shell_level(&self) -> u32 {
+        self.shell
+            .env_str("SHLVL")
+            .and_then(|level| level.trim().parse().ok())
+            .unwrap_or(1)
+    }
+
+    /// Exit code of the most recently finished background job, if any.
+    pub fn last_job_exit(&self) -> Option<u8> {
+        self.last_job_exit
+    }
+
+    /// The shell's `$PATH`, which `export` updates without touching the
+    /// process environment.
+    pub fn path_var(&self) -> OsString {
+        self.shell
+            .env_str("PATH")
+            .map(|path| OsString::from(&*path))
+            .unwrap_or_default()
+    }
+
+    /// Snapshot of the state the prompt displays.
+    pub fn prompt_state(&self) -> PromptState {
+        PromptState {
+            shlvl: self.shell_level(),
+            last_job_exit: self.last_job_exit(),
+        }
+    }
 }
@@ -0,0 +1,179 @@
+//! Command-not-found suggestions.
+//!
+//! Suggests the nearest known command (PATH executables and history)
+//! by edit distance when a typed command doesn't exist.
+
+use std::collections::HashSet;
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+/// Exit code used by shells when a command isn't found.
+pub const NOT_FOUND_EXIT_CODE: u8 = 127;
+
+/// Maximum edit distance for a suggestion to be considered.
+const MAX_DISTANCE: usize = 2;
+
+/// Compute the Levenshtein edit distance between two strings.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            curr[j + 1] = (prev[j + 1] + 1).min(curr[j] + 1).min(prev[j] + cost);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+/// Find the candidate nearest to `word` within the distance threshold.
+///
+/// Ties are broken alphabetically so results are stable.
+pub fn nearest<'a, I>(word: &str, candidates: I) -> Option<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    // Short words tolerate fewer edits, otherwise everything matches
+    let max_distance = MAX_DISTANCE.min(word.chars().count().saturating_sub(1));
+    if max_distance == 0 {
+        return None;
+    }
+
+    candidates
+        .into_iter()
+        .filter(|c| *c != word)
+        .map(|c| (levenshtein(word, c), c))
+        .filter(|(d, _)| *d <= max_distance)
+        .min_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(b.1)))
+        .map(|(_, c)| c.to_string())
+}
+
+/// Collect all executable names on `path_var`.
+pub fn path_executables(path_var: &OsStr) -> HashSet<String> {
+    let mut names = HashSet::new();
+
+    for dir in env::split_paths(path_var) {
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                if let Ok(metadata) = entry.metadata()
+                    && metadata.permissions().mode() & 0o111 != 0
+                {
+                    names.insert(entry.file_name().to_string_lossy().to_string());
+                }
+            }
+        }
+    }
+
+    names
+}
+
+/// Check whether a command word resolves to an executable on `path_var`,
+/// the shell's `$PATH`.
+pub fn is_on_path(word: &str, path_var: &OsStr) -> bool {
+    if word.contains('/') {
+        return Path::new(word).exists();
+    }
+
+    env::split_paths(path_var).any(|dir| {
+        fs::metadata(dir.join(word))
+            .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+    })
+}
+
+/// Suggest a replacement for a command word that wasn't found.
+///
+/// Candidates are executables on `path_var` plus the first word of each
+/// history entry.
+pub fn suggest_command(word: &str, history: &[String], path_var: &OsStr) -> Option<String> {
+    let mut candidates = path_executables(path_var);
+    candidates.extend(
+        history
+            .iter()
+            .filter_map(|line| line.split_whitespace().next())
+            .map(|s| s.to_string()),
+    );
+
+    nearest(word, candidates.iter().map(|s| s.as_str()))
+}
+
+/// Hint to send a not-found command to the AI instead.
+///
+/// Only offered when `?` queries would actually work: AI enabled and signed in.
+pub fn ask_hint(command: &str, ai_enabled: bool, authenticated: bool) -> Option<String> {
+    (ai_enabled && authenticated).then(|| format!("Did you mean to ask? Try ?{}", command))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("git", "git"), 0);
+        assert_eq!(levenshtein("gti", "git"), 2);
+        assert_eq!(levenshtein("carg", "cargo"), 1);
+        assert_eq!(levenshtein("", "ls"), 2);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_nearest_picks_closest() {
+        let candidates = ["git", "grep", "cargo", "cat"];
+        assert_eq!(nearest("gti", candidates), Some("git".to_string()));
+        assert_eq!(nearest("carg", candidates), Some("cargo".to_string()));
+        assert_eq!(nearest("grpe", candidates), Some("grep".to_string()));
+    }
+
+    #[test]
+    fn test_nearest_rejects_distant_and_short() {
+        let candidates = ["git", "cargo"];
+        assert_eq!(nearest("kubectl", candidates), None);
+        // Single-character words would match almost anything
+        assert_eq!(nearest("x", ["ls", "cd"]), None);
+    }
+
+    #[test]
+    fn test_nearest_tie_breaks_alphabetically() {
+        assert_eq!(nearest("cst", ["cut", "cat"]), Some("cat".to_string()));
+    }
+
+    #[test]
+    fn test_lookups_use_given_path() {
+        let dir = env::temp_dir().join(format!("nosh_test_suggest_path_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let tool = dir.join("nosh-venv-tool");
+        fs::write(&tool, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+
+        // As after `export PATH=...` inside the shell, not in nosh's own env
+        let path_var = dir.clone().into_os_string();
+        assert!(is_on_path("nosh-venv-tool", &path_var));
+        assert!(!is_on_path("nosh-venv-tool", OsStr::new("/nonexistent")));
+        assert_eq!(
+            suggest_command("nosh-venv-tol", &[], &path_var).as_deref(),
+            Some("nosh-venv-tool")
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_ask_hint() {
+        assert_eq!(
+            ask_hint("how do I undo a commit", true, true).as_deref(),
+            Some("Did you mean to ask? Try ?how do I undo a commit")
+        );
+        assert_eq!(ask_hint("whats up", false, true), None);
+        assert_eq!(ask_hint("whats up", true, false), None);
+    }
+}
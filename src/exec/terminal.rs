@@ -4,9 +4,11 @@
 //! (Ctrl+Z, fg, bg, jobs) to work properly, as well as terminal title updates.
 
 use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicI32, Ordering};
 
 use anyhow::Result;
-use nix::sys::signal::{self, SigHandler, Signal};
+use nix::errno::Errno;
+use nix::sys::signal::{self, SaFlags, SigAction, SigHandler, SigSet, Signal};
 use nix::unistd::{self, Pid};
 
 /// Initialize terminal control for job control support.
@@ -65,6 +67,85 @@ pub fn reclaim_foreground() {
     let _ = unistd::tcsetpgrp(std::io::stdin(), unistd::getpgrp());
 }
 
+/// Signals forwarded to the foreground job while a command is running.
+const FORWARDED_SIGNALS: [Signal; 3] = [Signal::SIGWINCH, Signal::SIGTERM, Signal::SIGHUP];
+
+/// Terminating signal (SIGTERM/SIGHUP) received while a command was running.
+static PENDING_EXIT_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// Signal handler that relays the signal to the terminal's foreground process group.
+///
+/// Only async-signal-safe calls are made here, and `errno` is restored before
+/// returning so the interrupted code doesn't see it change. The signal is
+/// never relayed to nosh's own process group, so the REPL doesn't receive it
+/// twice.
+extern "C" fn forward_signal(signo: libc::c_int) {
+    let saved_errno = Errno::last_raw();
+    let foreground = unsafe { libc::tcgetpgrp(libc::STDIN_FILENO) };
+    let own = unsafe { libc::getpgrp() };
+
+    if foreground > 0 && foreground != own {
+        unsafe {
+            libc::kill(-foreground, signo);
+        }
+    }
+
+    if signo != libc::SIGWINCH {
+        PENDING_EXIT_SIGNAL.store(signo, Ordering::SeqCst);
+    }
+    Errno::set_raw(saved_errno);
+}
+
+/// Forwards SIGWINCH/SIGTERM/SIGHUP to the foreground job while alive.
+///
+/// Dropping the guard restores the previous handlers. If a terminating signal
+/// arrived in the meantime, it is re-raised so nosh exits as it would have.
+pub struct SignalForwarder {
+    previous: Vec<(Signal, SigAction)>,
+}
+
+impl SignalForwarder {
+    /// Install forwarding handlers for the duration of a command.
+    pub fn install() -> Self {
+        let mut previous = Vec::new();
+
+        if !std::io::stdin().is_terminal() {
+            return Self { previous };
+        }
+
+        PENDING_EXIT_SIGNAL.store(0, Ordering::SeqCst);
+
+        let action = SigAction::new(
+            SigHandler::Handler(forward_signal),
+            SaFlags::SA_RESTART,
+            SigSet::empty(),
+        );
+
+        for sig in FORWARDED_SIGNALS {
+            if let Ok(old) = unsafe { signal::sigaction(sig, &action) } {
+                previous.push((sig, old));
+            }
+        }
+
+        Self { previous }
+    }
+}
+
+impl Drop for SignalForwarder {
+    fn drop(&mut self) {
+        for (sig, old) in self.previous.drain(..) {
+            let _ = unsafe { signal::sigaction(sig, &old) };
+        }
+
+        let pending = PENDING_EXIT_SIGNAL.swap(0, Ordering::SeqCst);
+        if pending != 0
+            && let Ok(sig) = Signal::try_from(pending)
+        {
+            let _ = signal::raise(sig);
+        }
+    }
+}
+
 /// Set the terminal title to the current working directory.
 /// The path is trimmed if too long, showing just the last few components.
 pub fn set_title_to_cwd() {
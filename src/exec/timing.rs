@@ -0,0 +1,111 @@
+//! Timing for `/time cmd`: wall clock plus child user/sys CPU time.
+
+use std::time::{Duration, Instant};
+
+/// Timing breakdown for a finished command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CommandTiming {
+    pub wall: Duration,
+    /// CPU time in user mode, if it could be measured
+    pub user: Option<Duration>,
+    /// CPU time in kernel mode, if it could be measured
+    pub sys: Option<Duration>,
+}
+
+/// A running timer started before a command.
+pub struct Timer {
+    started_at: Instant,
+    usage: Option<(Duration, Duration)>,
+}
+
+impl Timer {
+    pub fn start() -> Self {
+        Self {
+            started_at: Instant::now(),
+            usage: children_usage(),
+        }
+    }
+
+    /// Stop the timer. User/sys time covers child processes that finished
+    /// in between (shell builtins run in-process and aren't counted).
+    pub fn stop(self) -> CommandTiming {
+        let wall = self.started_at.elapsed();
+        let (user, sys) = match (self.usage, children_usage()) {
+            (Some((user0, sys0)), Some((user1, sys1))) => (
+                Some(user1.saturating_sub(user0)),
+                Some(sys1.saturating_sub(sys0)),
+            ),
+            _ => (None, None),
+        };
+        CommandTiming { wall, user, sys }
+    }
+}
+
+/// Total user and sys time of waited-for child processes.
+fn children_usage() -> Option<(Duration, Duration)> {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+    // SAFETY: getrusage fills the struct on success
+    if unsafe { libc::getrusage(libc::RUSAGE_CHILDREN, usage.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let usage = unsafe { usage.assume_init() };
+    let to_duration = |tv: libc::timeval| {
+        Duration::from_secs(tv.tv_sec as u64) + Duration::from_micros(tv.tv_usec as u64)
+    };
+    Some((to_duration(usage.ru_utime), to_duration(usage.ru_stime)))
+}
+
+/// Format a duration compactly: "850ms", "1.234s", "2m03.5s".
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs_f64();
+    if secs < 1.0 {
+        format!("{}ms", d.as_millis())
+    } else if secs < 60.0 {
+        format!("{:.3}s", secs)
+    } else {
+        format!("{}m{:04.1}s", d.as_secs() / 60, secs % 60.0)
+    }
+}
+
+/// One-line summary like "real 1.234s  user 800ms  sys 120ms".
+pub fn format_timing(timing: &CommandTiming) -> String {
+    let mut parts = vec![format!("real {}", format_duration(timing.wall))];
+    if let Some(user) = timing.user {
+        parts.push(format!("user {}", format_duration(user)));
+    }
+    if let Some(sys) = timing.sys {
+        parts.push(format!("sys {}", format_duration(sys)));
+    }
+    parts.join("  ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timing() {
+        let timing = CommandTiming {
+            wall: Duration::from_millis(1234),
+            user: Some(Duration::from_millis(800)),
+            sys: Some(Duration::from_micros(120_400)),
+        };
+        assert_eq!(format_timing(&timing), "real 1.234s  user 800ms  sys 120ms");
+
+        let timing = CommandTiming {
+            wall: Duration::from_millis(123_500),
+            user: None,
+            sys: None,
+        };
+        assert_eq!(format_timing(&timing), "real 2m03.5s");
+    }
+
+    #[test]
+    fn test_timer_measures_children() {
+        let timer = Timer::start();
+        std::process::Command::new("true").status().unwrap();
+        let timing = timer.stop();
+        assert!(timing.wall > Duration::ZERO);
+        assert!(timing.user.is_some());
+    }
+}
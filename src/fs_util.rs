@@ -0,0 +1,272 @@
+//! Safe file writes for config, permissions and the package registry.
+//!
+//! A plain `fs::write` truncates the file first, so a crash or a concurrent
+//! nosh instance can leave it empty or half-written. Writes here go to a
+//! temp file in the same directory and are renamed into place.
+
+use anyhow::{Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Distinguishes temp files written by different threads of one process.
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Atomically replace `path` with `contents`.
+///
+/// Readers see either the old or the new file, never a partial one.
+/// Existing file permissions are kept; new files are created 0600. A
+/// symlinked `path` (a dotfiles checkout) has its target replaced and the
+/// link left in place.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let target = match fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_symlink() => fs::canonicalize(path)
+            .with_context(|| format!("Failed to resolve symlink {}", path.display()))?,
+        _ => path.to_path_buf(),
+    };
+    let tmp = write_temp(&target, contents.as_ref())?;
+    fs::rename(&tmp, &target).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })?;
+    Ok(())
+}
+
+/// Write `contents` to a new temp file next to `path` and sync it to disk.
+///
+/// The temp file is created exclusively with the mode of `path` (or 0600),
+/// so a planted file or symlink is never followed and the contents are
+/// never readable more widely than the original.
+fn write_temp(path: &Path, contents: &[u8]) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("Invalid path: {}", path.display()))?
+        .to_string_lossy();
+    let tmp = path.with_file_name(format!(
+        ".{}.tmp.{}.{}",
+        file_name,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mode = fs::metadata(path)
+        .ok()
+        .map(|meta| meta.permissions().mode() & 0o7777);
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(mode.unwrap_or(0o600))
+        .open(&tmp)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let result = (|| -> Result<()> {
+        // The umask may have narrowed the mode at creation
+        if let Some(mode) = mode {
+            file.set_permissions(fs::Permissions::from_mode(mode))?;
+        }
+        file.write_all(contents)?;
+        file.sync_all()?;
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.context(format!("Failed to write {}", path.display())));
+    }
+    Ok(tmp)
+}
+
+/// Create `dir` (and its parents) readable only by the current user.
+///
+/// An existing directory has its permissions tightened to 0700.
+pub fn create_private_dir(dir: &Path) -> Result<()> {
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    Ok(())
+}
+
+/// Create a new file readable only by the current user.
+///
+/// Fails if `path` already exists, so a planted file or symlink is never
+/// followed.
+pub fn create_private_file(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))
+}
+
+/// Advisory lock on a file, released when dropped.
+///
+/// Locks a sibling `<name>.lock` file so the lock survives the rename in
+/// [`write_atomic`].
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    /// Block until an exclusive lock for `path` is acquired.
+    pub fn acquire(path: &Path) -> Result<Self> {
+        let mut lock_name = path
+            .file_name()
+            .with_context(|| format!("Invalid path: {}", path.display()))?
+            .to_os_string();
+        lock_name.push(".lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path.with_file_name(lock_name))?;
+
+        // SAFETY: the fd is valid for the lifetime of `file`; the lock is
+        // released when the file is closed.
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Self { _file: file })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nosh_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file() {
+        let dir = temp_dir("atomic_replace");
+        let path = dir.join("permissions.toml");
+
+        write_atomic(&path, "a = 1\n").unwrap();
+        write_atomic(&path, "a = 2\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a = 2\n");
+
+        // No temp files left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_interrupted_write_keeps_original() {
+        let dir = temp_dir("atomic_interrupt");
+        let path = dir.join("packages.toml");
+        fs::write(&path, "original\n").unwrap();
+
+        // Simulate a crash after the temp file is written but before the rename
+        let tmp = write_temp(&path, b"trunc").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original\n");
+
+        // The next save still succeeds
+        fs::remove_file(&tmp).unwrap();
+        write_atomic(&path, "updated\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "updated\n");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_keeps_permissions() {
+        let dir = temp_dir("atomic_perms");
+        let path = dir.join("credentials.toml");
+        fs::write(&path, "token = \"x\"\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        write_atomic(&path, "token = \"y\"\n").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_write_atomic_new_file_is_private() {
+        let dir = temp_dir("atomic_new");
+        let path = dir.join("credentials.toml");
+
+        write_atomic(&path, "token = \"x\"\n").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_write_atomic_keeps_symlink() {
+        let dir = temp_dir("atomic_symlink");
+        let real = dir.join("dotfiles-config.toml");
+        let link = dir.join("config.toml");
+        fs::write(&real, "a = 1\n").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        write_atomic(&link, "a = 2\n").unwrap();
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read_to_string(&real).unwrap(), "a = 2\n");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_concurrent_writes_use_distinct_temp_files() {
+        let dir = temp_dir("atomic_threads");
+        let path = dir.join("permissions.toml");
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || write_atomic(&path, format!("n = {}\n", i)))
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap().unwrap();
+        }
+        assert!(fs::read_to_string(&path).unwrap().starts_with("n = "));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_lock_requires_file_name() {
+        assert!(FileLock::acquire(Path::new("")).is_err());
+        assert!(FileLock::acquire(Path::new("/")).is_err());
+    }
+
+    #[test]
+    fn test_private_dir_and_file() {
+        let dir = temp_dir("private").join("scratch");
+        create_private_dir(&dir).unwrap();
+        let mode = fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        let path = dir.join("edit.sh");
+        create_private_file(&path).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // Existing files (or planted symlinks) are never reused
+        assert!(create_private_file(&path).is_err());
+
+        let _ = fs::remove_dir_all(dir.parent().unwrap());
+    }
+}
@@ -1,17 +1,137 @@
 //! SQLite-based command history with multi-session support.
 //!
-//! Each command is stored with a timestamp, allowing multiple nosh sessions
+//! Each command is stored with a timestamp, cwd and exit code, allowing multiple nosh sessions
 //! to share history in real-time without overwriting each other's entries.
 
 use anyhow::Result;
-use rusqlite::{Connection, params};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 use std::path::Path;
 
+/// How repeated commands are collapsed when browsing history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DedupMode {
+    /// Collapse only back-to-back repeats
+    Consecutive,
+    /// Show each command once, at its most recent position
+    #[default]
+    Global,
+}
+
 /// SQLite-backed command history.
 pub struct History {
     conn: Connection,
     /// Session ID for tracking which session added which commands
     session_id: i64,
+    /// Row ID of the last command added by this session
+    last_id: Cell<Option<i64>>,
+}
+
+/// A single history entry with its metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub command: String,
+    /// Unix timestamp when the command was submitted
+    pub timestamp: i64,
+    pub cwd: Option<String>,
+    /// Exit code, once the command has finished
+    pub exit_code: Option<i32>,
+}
+
+/// A command read from another shell's history file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedCommand {
+    pub command: String,
+    /// Unix timestamp, when the file records one
+    pub timestamp: Option<i64>,
+}
+
+/// Parse a zsh or bash history file.
+///
+/// Understands zsh's extended format (`: 1700000000:0;cmd`, with `\`
+/// continuing multi-line commands), bash's `#1700000000` timestamp comments
+/// (written when `HISTTIMEFORMAT` is set) and plain one-command-per-line files.
+pub fn parse_shell_history(bytes: &[u8]) -> Vec<ImportedCommand> {
+    let content = unmetafy(bytes);
+    let mut commands = Vec::new();
+    let mut bash_timestamp = None;
+    let mut lines = content.lines();
+
+    while let Some(line) = lines.next() {
+        // zsh extended history: ": <start>:<elapsed>;<command>"
+        if let Some(rest) = line.strip_prefix(": ")
+            && let Some((meta, command)) = rest.split_once(';')
+            && let Some((start, _)) = meta.split_once(':')
+            && let Ok(timestamp) = start.trim().parse::<i64>()
+        {
+            let mut command = command.to_string();
+            while command.ends_with('\\') {
+                let Some(next) = lines.next() else { break };
+                command.pop();
+                command.push('\n');
+                command.push_str(next);
+            }
+            push_imported(&mut commands, &command, Some(timestamp));
+            continue;
+        }
+
+        // bash timestamp comment for the next command
+        if let Some(timestamp) = line.strip_prefix('#').and_then(|t| t.parse::<i64>().ok()) {
+            bash_timestamp = Some(timestamp);
+            continue;
+        }
+
+        push_imported(&mut commands, line, bash_timestamp.take());
+    }
+
+    commands
+}
+
+/// Parse a `--since` value: a unix timestamp or a `YYYY-MM-DD` date
+/// (local midnight).
+pub fn parse_since(value: &str) -> Option<i64> {
+    if let Ok(timestamp) = value.parse::<i64>() {
+        return Some(timestamp);
+    }
+    let date = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    date.and_hms_opt(0, 0, 0)?
+        .and_local_timezone(chrono::Local)
+        .earliest()
+        .map(|t| t.timestamp())
+}
+
+fn push_imported(commands: &mut Vec<ImportedCommand>, command: &str, timestamp: Option<i64>) {
+    let command = command.trim();
+    if !command.is_empty() {
+        commands.push(ImportedCommand {
+            command: command.to_string(),
+            timestamp,
+        });
+    }
+}
+
+/// Undo zsh's history "metafication", where NUL and bytes 0x83 to 0xA2 are
+/// written as 0x83 followed by the byte XOR 0x20. Metafied non-ASCII text
+/// isn't valid UTF-8, so valid files (bash, or plain ASCII) are kept as is.
+fn unmetafy(bytes: &[u8]) -> String {
+    const META: u8 = 0x83;
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_string();
+    }
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter();
+    while let Some(&b) = iter.next() {
+        if b == META
+            && let Some(&next) = iter.next()
+        {
+            out.push(next ^ 0x20);
+        } else {
+            out.push(b);
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
 }
 
 impl History {
@@ -27,6 +147,17 @@ impl History {
         // Enable WAL mode for better concurrent access
         conn.execute_batch("PRAGMA journal_mode=WAL;")?;
 
+        Self::init(conn)
+    }
+
+    /// Open a throwaway in-memory database.
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    /// Create tables and register this session.
+    fn init(conn: Connection) -> Result<Self> {
         // Create tables if they don't exist
         conn.execute_batch(
             "CREATE TABLE IF NOT EXISTS history (
@@ -46,12 +177,24 @@ impl History {
             );",
         )?;
 
+        // Migrate older databases that predate the exit_code column
+        let has_exit_code = conn
+            .prepare("SELECT 1 FROM pragma_table_info('history') WHERE name = 'exit_code'")?
+            .exists([])?;
+        if !has_exit_code {
+            conn.execute_batch("ALTER TABLE history ADD COLUMN exit_code INTEGER;")?;
+        }
+
         // Register this session
         let pid = std::process::id() as i64;
         conn.execute("INSERT INTO sessions (pid) VALUES (?1)", params![pid])?;
         let session_id = conn.last_insert_rowid();
 
-        Ok(Self { conn, session_id })
+        Ok(Self {
+            conn,
+            session_id,
+            last_id: Cell::new(None),
+        })
     }
 
     /// Add a command to history.
@@ -64,10 +207,50 @@ impl History {
             "INSERT INTO history (command, cwd, session_id) VALUES (?1, ?2, ?3)",
             params![command, cwd, self.session_id],
         )?;
+        self.last_id.set(Some(self.conn.last_insert_rowid()));
 
         Ok(())
     }
 
+    /// Stop tracking the last added command, so the next exit code
+    /// isn't recorded against it (used when a line isn't saved).
+    pub fn forget_last(&self) {
+        self.last_id.set(None);
+    }
+
+    /// Record the exit code of the last command added by this session.
+    pub fn update_last_exit(&self, code: i32) -> Result<()> {
+        if let Some(id) = self.last_id.get() {
+            self.conn.execute(
+                "UPDATE history SET exit_code = ?1 WHERE id = ?2",
+                params![code, id],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Get the most recent entry with its metadata.
+    pub fn last_entry(&self) -> Result<Option<HistoryEntry>> {
+        let entry = self
+            .conn
+            .query_row(
+                "SELECT command, timestamp, cwd, exit_code FROM history
+                 ORDER BY timestamp DESC, id DESC
+                 LIMIT 1",
+                [],
+                |row| {
+                    Ok(HistoryEntry {
+                        command: row.get(0)?,
+                        timestamp: row.get(1)?,
+                        cwd: row.get(2)?,
+                        exit_code: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(entry)
+    }
+
     /// Get the N most recent commands, newest first.
     pub fn recent(&self, limit: usize) -> Result<Vec<String>> {
         let mut stmt = self.conn.prepare(
@@ -84,12 +267,44 @@ impl History {
     }
 
     /// Get commands for rustyline history (oldest first for proper navigation).
-    pub fn for_readline(&self, limit: usize) -> Result<Vec<String>> {
-        let mut commands = self.recent(limit)?;
+    pub fn for_readline(&self, limit: usize, mode: DedupMode) -> Result<Vec<String>> {
+        let mut commands = match mode {
+            DedupMode::Global => self.recent(limit)?,
+            DedupMode::Consecutive => {
+                let mut stmt = self.conn.prepare(
+                    "SELECT command FROM (
+                        SELECT id, timestamp, command,
+                            LAG(command) OVER (ORDER BY timestamp, id) AS prev
+                        FROM history
+                     )
+                     WHERE prev IS NULL OR prev != command
+                     ORDER BY timestamp DESC, id DESC
+                     LIMIT ?1",
+                )?;
+                stmt.query_map(params![limit as i64], |row| row.get(0))?
+                    .collect::<Result<Vec<String>, _>>()?
+            }
+        };
         commands.reverse(); // Oldest first for readline
         Ok(commands)
     }
 
+    /// Number of entries `for_readline` can return in the given mode.
+    pub fn readline_len(&self, mode: DedupMode) -> Result<i64> {
+        match mode {
+            DedupMode::Global => self.count(),
+            DedupMode::Consecutive => Ok(self.conn.query_row(
+                "SELECT COUNT(*) FROM (
+                    SELECT command, LAG(command) OVER (ORDER BY timestamp, id) AS prev
+                    FROM history
+                 )
+                 WHERE prev IS NULL OR prev != command",
+                [],
+                |row| row.get(0),
+            )?),
+        }
+    }
+
     /// Search history for commands containing the pattern.
     pub fn search(&self, pattern: &str, limit: usize) -> Result<Vec<String>> {
         let mut stmt = self.conn.prepare(
@@ -135,12 +350,100 @@ impl History {
         Ok(count)
     }
 
+    /// Most-run programs (first word of each command), most frequent first.
+    /// AI queries (`?`) and slash commands are not counted.
+    pub fn top_commands(&self, limit: usize) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT program, COUNT(*) AS runs FROM (
+                SELECT CASE WHEN instr(trim(command), ' ') > 0
+                    THEN substr(trim(command), 1, instr(trim(command), ' ') - 1)
+                    ELSE trim(command) END AS program
+                FROM history
+                WHERE command NOT LIKE '?%' AND command NOT LIKE '/%'
+             )
+             WHERE program != ''
+             GROUP BY program
+             ORDER BY runs DESC, program
+             LIMIT ?1",
+        )?;
+
+        let top = stmt
+            .query_map(params![limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(String, i64)>, _>>()?;
+
+        Ok(top)
+    }
+
+    /// Number of entries (including repeats) added at or after a unix timestamp.
+    pub fn count_since(&self, since: i64) -> Result<i64> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM history WHERE timestamp >= ?1",
+            params![since],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    /// Number of AI queries (`?` and `??`) added at or after a unix timestamp.
+    pub fn ai_queries_since(&self, since: i64) -> Result<i64> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM history WHERE timestamp >= ?1 AND command LIKE '?%'",
+            params![since],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
     /// Clear all history.
     pub fn clear(&self) -> Result<()> {
         self.conn.execute("DELETE FROM history", [])?;
         Ok(())
     }
 
+    /// Delete the oldest entries so at most `max` remain (0 = no cap).
+    /// Age is by timestamp, so imported entries count as old even though
+    /// they were inserted last. Returns how many entries were removed.
+    pub fn prune_to(&self, max: usize) -> Result<usize> {
+        if max == 0 {
+            return Ok(0);
+        }
+        let deleted = self.conn.execute(
+            "DELETE FROM history WHERE id NOT IN (
+                SELECT id FROM history ORDER BY timestamp DESC, id DESC LIMIT ?1
+            )",
+            params![max as i64],
+        )?;
+        Ok(deleted)
+    }
+
+    /// Insert commands imported from another shell, skipping ones already
+    /// stored with the same timestamp so re-importing a file only adds new
+    /// entries. Commands without a timestamp are stored as the oldest
+    /// entries; with `since`, only timestamped commands at or after it are
+    /// imported. Returns how many commands were added.
+    pub fn import(&self, commands: &[ImportedCommand], since: Option<i64>) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut added = 0;
+        {
+            let mut exists =
+                tx.prepare("SELECT 1 FROM history WHERE command = ?1 AND timestamp = ?2")?;
+            let mut insert =
+                tx.prepare("INSERT INTO history (command, timestamp) VALUES (?1, ?2)")?;
+            for imported in commands {
+                let timestamp = imported.timestamp.unwrap_or(0);
+                if since.is_some_and(|since| timestamp < since)
+                    || exists.exists(params![imported.command, timestamp])?
+                {
+                    continue;
+                }
+                insert.execute(params![imported.command, timestamp])?;
+                added += 1;
+            }
+        }
+        tx.commit()?;
+        Ok(added)
+    }
+
     /// Remove duplicate consecutive commands (keeps the most recent).
     #[allow(dead_code)]
     pub fn deduplicate(&self) -> Result<usize> {
@@ -213,10 +516,212 @@ mod tests {
         history.add("second").unwrap();
         history.add("third").unwrap();
 
-        let for_rl = history.for_readline(10).unwrap();
+        let for_rl = history.for_readline(10, DedupMode::Global).unwrap();
         assert_eq!(for_rl[0], "first"); // Oldest first for readline
         assert_eq!(for_rl[2], "third");
 
         std::fs::remove_file(&path).ok();
     }
+
+    fn add_at(history: &History, command: &str, timestamp: i64) {
+        history
+            .conn
+            .execute(
+                "INSERT INTO history (command, timestamp) VALUES (?1, ?2)",
+                params![command, timestamp],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_for_readline_dedup_modes() {
+        let history = History::open_in_memory().unwrap();
+
+        add_at(&history, "ls", 100);
+        add_at(&history, "ls", 200);
+        add_at(&history, "pwd", 300);
+        add_at(&history, "ls", 400);
+
+        assert_eq!(
+            history.for_readline(10, DedupMode::Global).unwrap(),
+            vec!["pwd", "ls"]
+        );
+        assert_eq!(history.readline_len(DedupMode::Global).unwrap(), 2);
+        assert_eq!(
+            history.for_readline(10, DedupMode::Consecutive).unwrap(),
+            vec!["ls", "pwd", "ls"]
+        );
+        assert_eq!(history.readline_len(DedupMode::Consecutive).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_import_shell_history() {
+        let zsh = b": 1700000000:0;git status\n: 1700000060:3;echo one\\\ntwo\n";
+        let bash = b"#1700000100\nls -la\ncargo build\n";
+
+        let parsed = parse_shell_history(zsh);
+        assert_eq!(
+            parsed,
+            vec![
+                ImportedCommand {
+                    command: "git status".to_string(),
+                    timestamp: Some(1700000000),
+                },
+                ImportedCommand {
+                    command: "echo one\ntwo".to_string(),
+                    timestamp: Some(1700000060),
+                },
+            ]
+        );
+        let parsed_bash = parse_shell_history(bash);
+        assert_eq!(parsed_bash[0].timestamp, Some(1700000100));
+        assert_eq!(parsed_bash[1].timestamp, None);
+
+        let history = History::open_in_memory().unwrap();
+        history.add("pwd").unwrap();
+        assert_eq!(history.import(&parsed, None).unwrap(), 2);
+        assert_eq!(history.import(&parsed_bash, None).unwrap(), 2);
+
+        // Stored with their timestamps, before commands run in nosh
+        let entry: (String, i64) = history
+            .conn
+            .query_row(
+                "SELECT command, timestamp FROM history WHERE command = 'git status'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(entry, ("git status".to_string(), 1700000000));
+        assert_eq!(
+            history.recent(10).unwrap(),
+            vec![
+                "pwd",
+                "ls -la",
+                "echo one\ntwo",
+                "git status",
+                "cargo build"
+            ]
+        );
+
+        // Re-importing adds nothing; `since` skips older and untimed entries
+        assert_eq!(history.import(&parsed, None).unwrap(), 0);
+        let fresh = History::open_in_memory().unwrap();
+        assert_eq!(fresh.import(&parsed_bash, Some(1700000050)).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_prune_to() {
+        let history = History::open_in_memory().unwrap();
+        for i in 0..15 {
+            history.add(&format!("echo {}", i)).unwrap();
+        }
+
+        assert_eq!(history.prune_to(0).unwrap(), 0);
+        assert_eq!(history.prune_to(10).unwrap(), 5);
+        assert_eq!(history.count().unwrap(), 10);
+        let recent = history.recent(20).unwrap();
+        assert_eq!(recent.first().unwrap(), "echo 14");
+        assert_eq!(recent.last().unwrap(), "echo 5");
+    }
+
+    #[test]
+    fn test_prune_after_import_keeps_newest() {
+        let history = History::open_in_memory().unwrap();
+        history.add("cargo test").unwrap();
+        let imported: Vec<ImportedCommand> = (0..3)
+            .map(|i| ImportedCommand {
+                command: format!("old {}", i),
+                timestamp: Some(1_600_000_000 + i),
+            })
+            .collect();
+        history.import(&imported, None).unwrap();
+
+        // The imports have the highest ids but are the oldest entries
+        assert_eq!(history.prune_to(2).unwrap(), 2);
+        assert_eq!(history.recent(10).unwrap(), vec!["cargo test", "old 2"]);
+    }
+
+    #[test]
+    fn test_top_commands() {
+        let history = History::open_in_memory().unwrap();
+
+        add_at(&history, "git status", 100);
+        add_at(&history, "git log -5", 200);
+        add_at(&history, "  ls -la", 300);
+        add_at(&history, "cargo build", 400);
+        add_at(&history, "git push", 500);
+        add_at(&history, "ls", 600);
+        add_at(&history, "?list large files", 700);
+        add_at(&history, "/stats", 800);
+
+        let top = history.top_commands(2).unwrap();
+        assert_eq!(top, vec![("git".to_string(), 3), ("ls".to_string(), 2)]);
+    }
+
+    #[test]
+    fn test_count_since() {
+        let history = History::open_in_memory().unwrap();
+
+        add_at(&history, "ls", 100);
+        add_at(&history, "ls", 200);
+        add_at(&history, "?what changed", 300);
+        add_at(&history, "??why is the build failing", 400);
+
+        assert_eq!(history.count_since(0).unwrap(), 4);
+        assert_eq!(history.count_since(200).unwrap(), 3);
+        assert_eq!(history.count_since(500).unwrap(), 0);
+        assert_eq!(history.ai_queries_since(0).unwrap(), 2);
+        assert_eq!(history.ai_queries_since(350).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_update_last_exit() {
+        let history = History::open_in_memory().unwrap();
+
+        // No command yet - nothing to update
+        history.update_last_exit(1).unwrap();
+        assert_eq!(history.last_entry().unwrap(), None);
+
+        history.add("false").unwrap();
+        let entry = history.last_entry().unwrap().unwrap();
+        assert_eq!(entry.command, "false");
+        assert_eq!(entry.exit_code, None);
+        assert!(entry.timestamp > 0);
+        assert!(entry.cwd.is_some());
+
+        history.update_last_exit(1).unwrap();
+        assert_eq!(history.last_entry().unwrap().unwrap().exit_code, Some(1));
+
+        history.add("true").unwrap();
+        history.update_last_exit(0).unwrap();
+        assert_eq!(history.last_entry().unwrap().unwrap().exit_code, Some(0));
+    }
+
+    #[test]
+    fn test_migrates_old_schema() {
+        let path = temp_db();
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE history (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    command TEXT NOT NULL,
+                    timestamp INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+                    cwd TEXT,
+                    session_id INTEGER
+                );
+                INSERT INTO history (command) VALUES ('ls');",
+            )
+            .unwrap();
+        }
+
+        let history = History::open(&path).unwrap();
+        assert_eq!(history.last_entry().unwrap().unwrap().exit_code, None);
+        history.add("pwd").unwrap();
+        history.update_last_exit(0).unwrap();
+        assert_eq!(history.last_entry().unwrap().unwrap().exit_code, Some(0));
+
+        drop(history);
+        std::fs::remove_file(&path).ok();
+    }
 }
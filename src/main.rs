@@ -3,6 +3,7 @@ mod auth;
 mod completions;
 mod config;
 mod exec;
+mod fs_util;
 mod history;
 mod onboarding;
 mod packages;
@@ -13,13 +14,14 @@ mod safety;
 mod ui;
 
 use ai::{
-    AgenticConfig, AgenticSession, AgenticStep, CloudClient, CommandPermission, ConversationContext,
+    AgenticConfig, AgenticSession, AgenticStep, AiBackend, CloudClient, CommandPermission,
+    ConversationContext, SavedSession, StubBackend, TranslationCache,
 };
-use dialoguer::{Input, Select, theme::ColorfulTheme};
+use dialoguer::{Confirm, Input, Select, theme::ColorfulTheme};
 use plugins::builtins::{install_builtins, upgrade_builtins};
 use ui::{
-    format_error, format_header, format_output, format_result, format_step,
-    format_translated_command,
+    format_chain_preview, format_error, format_header, format_hint, format_result, format_step,
+    format_step_output, format_translated_command,
 };
 
 fn format_tokens(tokens: i32) -> String {
@@ -61,6 +63,238 @@ fn format_date(iso: &str) -> String {
     iso.to_string()
 }
 
+/// Theme for this session: a `/theme NAME` switch wins over `--theme NAME`,
+/// which wins over `[prompt] theme`.
+fn session_theme<'a>(
+    args: &'a [String],
+    switched: Option<&'a str>,
+    configured: &'a str,
+) -> &'a str {
+    switched.unwrap_or_else(|| {
+        args.iter()
+            .position(|a| a == "--theme")
+            .and_then(|i| args.get(i + 1))
+            .map_or(configured, |name| name.as_str())
+    })
+}
+
+/// Print problems found in the theme's prompt format (the theme still loads).
+fn print_theme_warnings(repl: &Repl) {
+    for warning in repl.theme_warnings() {
+        eprintln!("Theme warning: {}", warning);
+    }
+}
+
+/// Print a `nosh check` report; returns whether every file loaded.
+fn print_check_report(report: &plugins::check::CheckReport) -> bool {
+    for problem in &report.problems {
+        eprintln!("{}", problem);
+    }
+    let errors = report
+        .problems
+        .iter()
+        .filter(|p| p.severity == plugins::check::Severity::Error)
+        .count();
+    println!(
+        "Checked {} theme and plugin files: {} error(s), {} warning(s)",
+        report.checked,
+        errors,
+        report.problems.len() - errors
+    );
+    !report.has_errors()
+}
+
+/// Print the parts of a zsh completion `convert-zsh` skipped (to stderr, so
+/// the TOML on stdout can still be redirected).
+fn print_zsh_warnings(warnings: &[impl std::fmt::Display]) {
+    if warnings.is_empty() {
+        eprintln!("Everything was converted.");
+        return;
+    }
+    eprintln!("Could not convert {} fragment(s):", warnings.len());
+    for warning in warnings {
+        eprintln!("  {}", warning);
+    }
+}
+
+/// Print local usage stats computed from the history database.
+fn print_stats(history: &history::History) -> Result<()> {
+    const DAY: i64 = 24 * 60 * 60;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    let total = history.count_since(0)?;
+    if total == 0 {
+        println!("No history yet.");
+        return Ok(());
+    }
+
+    let today = history.count_since(now - DAY)?;
+    let week = history.count_since(now - 7 * DAY)?;
+    let ai_week = history.ai_queries_since(now - 7 * DAY)?;
+
+    println!("\n\x1b[1mUsage\x1b[0m");
+    println!("  Commands total:    {}", total);
+    println!("  Last 24 hours:     {}", today);
+    println!("  Last 7 days:       {}", week);
+    println!(
+        "  AI queries/day:    {:.1} (last 7 days)",
+        ai_week as f64 / 7.0
+    );
+
+    let top = history.top_commands(10)?;
+    if !top.is_empty() {
+        let width = top.iter().map(|(c, _)| c.len()).max().unwrap_or(0);
+        println!("\n\x1b[1mTop commands\x1b[0m");
+        for (command, runs) in top {
+            println!("  {:width$}  {}", command, runs, width = width);
+        }
+    }
+    println!();
+    Ok(())
+}
+
+/// Print saved agentic sessions, newest first, numbered for `/sessions replay N`.
+fn print_sessions() {
+    let sessions = ai::saved_sessions();
+    if sessions.is_empty() {
+        println!("No saved sessions. Sessions are saved after ??queries that run commands.");
+        return;
+    }
+    for (i, path) in sessions.iter().enumerate() {
+        match SavedSession::load(path) {
+            Ok(s) => println!(
+                "  {:>2}  {}  {} ({} commands)",
+                i + 1,
+                format_date(&s.saved_at),
+                s.query,
+                s.commands.len()
+            ),
+            Err(e) => println!("  {:>2}  {}", i + 1, e),
+        }
+    }
+}
+
+/// Load session `n` as numbered by `/sessions`, printing why if it can't.
+fn load_numbered_session(n: &str) -> Option<SavedSession> {
+    let sessions = ai::saved_sessions();
+    let Some(path) = n
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| sessions.get(n.wrapping_sub(1)))
+    else {
+        eprintln!("No session {}. Run /sessions to list them.", n);
+        return None;
+    };
+    match SavedSession::load(path) {
+        Ok(s) => Some(s),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            None
+        }
+    }
+}
+
+/// Grant a permission choice; returns whether the command may run.
+fn apply_permission_choice(
+    choice: PermissionChoice,
+    parsed: &safety::ParsedCommand,
+    permissions: &mut PermissionStore,
+    cwd: &str,
+) -> bool {
+    match choice {
+        PermissionChoice::AllowOnce => true,
+        PermissionChoice::AllowCommand => {
+            permissions.allow_command(&parsed.info.command, true);
+            true
+        }
+        PermissionChoice::AllowSubcommand => {
+            permissions.allow_command(&parsed.info.command_pattern, true);
+            true
+        }
+        PermissionChoice::AllowCommandHere => {
+            permissions.allow_command_in_directory(&parsed.info.command_pattern, cwd, true);
+            true
+        }
+        PermissionChoice::AllowHere => {
+            permissions.allow_directory(cwd, true);
+            true
+        }
+        PermissionChoice::AllowCommandHost => {
+            permissions.allow_command_for_hosts(&parsed.info.command, &parsed.info.hosts, true);
+            true
+        }
+        PermissionChoice::Deny => false,
+    }
+}
+
+/// Re-run a saved session's commands in the current directory through the
+/// safety layer. Stops at the first blocked or denied command; returns the
+/// exit code of the last command run.
+async fn replay_session(session: &SavedSession, permissions: &mut PermissionStore) -> Result<u8> {
+    let cwd = std::env::current_dir()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| ".".to_string());
+    println!("{}", format_header("Replaying", &session.query));
+
+    let mut exit_code = 0;
+    for command in &session.commands {
+        println!("\n{}", format_translated_command(command));
+        // Gated like `?` commands: the riskiest part decides, and grants
+        // must cover every part
+        let segments: Vec<_> = safety::parse_chain(command)
+            .into_iter()
+            .map(|s| safety::apply_mode(s, safety::is_paranoid()))
+            .collect();
+        let parsed =
+            safety::apply_mode(safety::riskiest(command, &segments), safety::is_paranoid());
+        let approved = match safety::gate(&parsed, &segments, permissions, &cwd) {
+            safety::Gate::Blocked => {
+                safety::prompt::print_blocked(&parsed)?;
+                false
+            }
+            safety::Gate::Critical => safety::prompt::print_critical_warning(&parsed)?,
+            safety::Gate::Approved => true,
+            safety::Gate::Ask => {
+                apply_permission_choice(prompt_for_permission(&parsed)?, &parsed, permissions, &cwd)
+            }
+        };
+        if !approved {
+            println!("Replay stopped.");
+            return Ok(1);
+        }
+
+        let status = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .status()
+            .await?;
+        exit_code = status.code().map_or(1, |c| c as u8);
+        if exit_code != 0 {
+            eprintln!("{}", format_hint(&format!("exit {}", exit_code)));
+        }
+    }
+    Ok(exit_code)
+}
+
+/// Backend for `?`/`??` queries: the cloud when signed in, or the offline
+/// stub when input is scripted.
+fn ai_backend(
+    creds: &Credentials,
+    instructions: &str,
+    scripted: bool,
+) -> Option<Box<dyn AiBackend>> {
+    if scripted {
+        return Some(Box::new(StubBackend::new()));
+    }
+    let token = creds.token.as_ref()?;
+    Some(Box::new(
+        CloudClient::new(token).with_instructions(instructions),
+    ))
+}
+
 async fn show_buy_menu(client: &CloudClient) {
     // Get current plan to show appropriate options
     let plan_info = client.get_plan().await.ok();
@@ -161,8 +395,8 @@ use config::Config;
 use exec::ShellSession;
 use indicatif::{ProgressBar, ProgressStyle};
 use onboarding::{OnboardingChoice, needs_onboarding, run_login, run_onboarding};
-use repl::{ReadlineResult, Repl};
-use safety::{PermissionChoice, PermissionStore, RiskLevel, parse_command, prompt_for_permission};
+use repl::{ReadlineResult, Repl, is_ai_query};
+use safety::{PermissionChoice, PermissionStore, parse_command, prompt_for_permission};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -180,9 +414,38 @@ async fn main() -> Result<()> {
         println!("A modern shell for developers\n");
         println!("Usage: nosh [COMMAND] [OPTIONS]\n");
         println!("Commands:");
-        println!("  convert-zsh FILE   Convert zsh completion file to nosh TOML format");
+        println!("  convert-zsh FILE [--report]");
+        println!("                     Convert zsh completion file to nosh TOML format;");
+        println!("                     --report lists the parts it couldn't convert");
+        println!("  export-config OUT  Bundle config, themes, plugins and package list into a tar");
+        println!("  import-config IN   Restore a bundle from export-config and reinstall packages");
+        println!(
+            "  check              Load every theme and plugin and report errors (exit 1 if any)"
+        );
+        println!("  package install USER/REPO... | upgrade [NAME...] | list [--json]");
+        println!("                     Manage packages from scripts (--quiet, exit 1 on failure)");
+        println!("  completions test FILE INPUT");
+        println!("                     Show completions a TOML file produces for INPUT");
+        println!("  history import FILE [--since DATE]");
+        println!("                     Import a zsh or bash history file (DATE: YYYY-MM-DD)");
+        println!("  prompt --shell zsh|bash");
+        println!("                     Print a snippet that uses the nosh prompt in zsh or bash");
+        println!("  prompt-render --shell zsh|bash [--status N]");
+        println!("                     Print the prompt once for the current directory");
         println!("\nOptions:");
         println!("  --setup            Run setup wizard to sign in");
+        println!("  --headless         Skip interactive first-run setup (or NOSH_HEADLESS=1)");
+        println!("  --install-completions");
+        println!("                     Write nosh completions as bash/zsh scripts");
+        println!("  --paranoid         Ask before every non-safe command (alias: --safe-mode)");
+        println!(
+            "  --no-ai            Turn off ?/?? AI queries for this session (alias: --offline)"
+        );
+        println!("  --theme NAME       Use a theme for this session without changing config");
+        println!("  --replay FILE [--print]");
+        println!("                     Re-run a saved ??session's commands through the safety");
+        println!("                     checks, or print them as a shell script");
+        println!("  --script FILE      Run FILE's lines as if typed, with AI stubbed out");
         println!("  --version          Show version");
         println!("  --help             Show this help message");
         println!("\nIn the shell:");
@@ -197,13 +460,27 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
+    // Hidden pager mode (used internally to page long command output)
+    if args.get(1).map(|s| s.as_str()) == Some("--pager") {
+        let mode = args
+            .get(2)
+            .and_then(|m| exec::pager::PagingMode::from_name(m))
+            .unwrap_or(exec::pager::PagingMode::Auto);
+        let _ = exec::pager::run_pager(mode);
+        return Ok(());
+    }
+
     // Handle convert-zsh subcommand
     if args.get(1).map(|s| s.as_str()) == Some("convert-zsh") {
-        if let Some(path) = args.get(2) {
+        let report = args.iter().any(|a| a == "--report");
+        if let Some(path) = args.iter().skip(2).find(|a| *a != "--report") {
             let path = std::path::Path::new(path);
             match completions::convert_zsh_file(path) {
-                Ok(toml) => {
+                Ok((toml, warnings)) => {
                     println!("{}", toml);
+                    if report {
+                        print_zsh_warnings(&warnings);
+                    }
                     return Ok(());
                 }
                 Err(e) => {
@@ -213,14 +490,231 @@ async fn main() -> Result<()> {
             }
         } else {
             eprintln!("Error: convert-zsh requires a file path");
-            eprintln!("Usage: nosh convert-zsh /path/to/zsh/completion");
+            eprintln!("Usage: nosh convert-zsh /path/to/zsh/completion [--report]");
+            std::process::exit(1);
+        }
+    }
+
+    // Validate every installed theme and plugin
+    if matches!(args.get(1).map(|s| s.as_str()), Some("check" | "--check")) {
+        let ok = print_check_report(&plugins::check::check_installed());
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
+    // Handle scriptable package commands
+    if args.get(1).map(|s| s.as_str()) == Some("package") {
+        std::process::exit(packages::cli::run(&args[2..]));
+    }
+
+    // Handle export-config / import-config subcommands
+    if args.get(1).map(|s| s.as_str()) == Some("export-config") {
+        let Some(out) = args.get(2) else {
+            eprintln!("Usage: nosh export-config OUT.tar");
+            std::process::exit(1);
+        };
+        match config::bundle::export(&paths::nosh_config_dir(), std::path::Path::new(out)) {
+            Ok(entries) => {
+                println!("Exported {} to {}", entries.join(", "), out);
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("Error exporting config: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if args.get(1).map(|s| s.as_str()) == Some("import-config") {
+        let Some(input) = args.get(2) else {
+            eprintln!("Usage: nosh import-config IN.tar");
             std::process::exit(1);
+        };
+        let confirm = |rel: &std::path::Path| {
+            Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!(
+                    "{} differs from the bundle. Overwrite?",
+                    rel.display()
+                ))
+                .default(false)
+                .interact()
+                .unwrap_or(false)
+        };
+        let config_dir = paths::nosh_config_dir();
+        match config::bundle::import(std::path::Path::new(input), &config_dir, confirm) {
+            Ok(summary) => {
+                println!(
+                    "Restored {} files ({} unchanged, {} kept) into {}",
+                    summary.written.len(),
+                    summary.unchanged,
+                    summary.skipped.len(),
+                    config_dir.display()
+                );
+            }
+            Err(e) => {
+                eprintln!("Error importing config: {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        // Re-clone packages from the restored registry, then update the rest
+        match packages::install_missing() {
+            Ok(installed) => {
+                for name in installed {
+                    println!("Installed package '{}'", name);
+                }
+            }
+            Err(e) => eprintln!("Could not reinstall packages: {}", e),
+        }
+        if let Err(e) = packages::upgrade_all() {
+            eprintln!("Could not upgrade packages: {}", e);
+        }
+        return Ok(());
+    }
+
+    // Handle completions test subcommand
+    if args.get(1).map(|s| s.as_str()) == Some("completions") {
+        match (args.get(2).map(|s| s.as_str()), args.get(3), args.get(4)) {
+            (Some("test"), Some(file), Some(input)) => {
+                match completions::test_completion_file(std::path::Path::new(file), input) {
+                    Ok(report) => {
+                        print!("{}", report);
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        eprintln!("Error testing completions: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            _ => {
+                eprintln!("Usage: nosh completions test FILE INPUT");
+                eprintln!("Example: nosh completions test ./git.toml \"git remote a\"");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Import history from zsh or bash
+    if args.get(1).map(|s| s.as_str()) == Some("history") {
+        let usage = || -> ! {
+            eprintln!("Usage: nosh history import FILE [--since YYYY-MM-DD]");
+            std::process::exit(2);
+        };
+        let (Some("import"), Some(file)) = (
+            args.get(2).map(|s| s.as_str()),
+            args.get(3).filter(|a| !a.starts_with('-')),
+        ) else {
+            usage()
+        };
+        let since = args.iter().position(|a| a == "--since").map(|i| {
+            args.get(i + 1)
+                .and_then(|s| history::parse_since(s))
+                .unwrap_or_else(|| usage())
+        });
+
+        let imported = std::fs::read(file)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| {
+                let store = history::History::open(&paths::history_db())?;
+                store.import(&history::parse_shell_history(&bytes), since)
+            });
+        match imported {
+            Ok(added) => {
+                println!("Imported {} command(s) from {}", added, file);
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("Error importing history: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Use the nosh prompt from zsh or bash
+    if matches!(
+        args.get(1).map(|s| s.as_str()),
+        Some("prompt" | "prompt-render")
+    ) {
+        let value = |flag: &str| {
+            args.iter()
+                .position(|a| a == flag)
+                .and_then(|i| args.get(i + 1))
+        };
+        let Some(shell) =
+            value("--shell").and_then(|s| plugins::prompt_hook::HookShell::from_name(s))
+        else {
+            eprintln!("Usage: nosh {} --shell zsh|bash", args[1]);
+            std::process::exit(2);
+        };
+
+        if args[1] == "prompt" {
+            let exe = std::env::current_exe()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|_| "nosh".to_string());
+            print!("{}", shell.snippet(&exe));
+            return Ok(());
+        }
+
+        let config = Config::load().unwrap_or_default();
+        nosh_context::set_command_timeout(std::time::Duration::from_millis(
+            config.prompt.context_timeout_ms,
+        ));
+        let mut plugin_manager = plugins::loader::PluginManager::new();
+        let _ = plugin_manager.load_plugins();
+        let (mut theme, _) = plugins::theme::Theme::load_or_default(session_theme(
+            &args,
+            None,
+            &config.prompt.theme,
+        ));
+        theme.set_background(ui::background::resolve(config.ui.background));
+        if let Some(code) = value("--status").and_then(|s| s.parse().ok()) {
+            theme.set_last_exit(code);
+        }
+        let prompt = plugins::prompt_hook::render(&theme, &mut plugin_manager).await;
+        print!("{}", shell.escape(&prompt));
+        return Ok(());
+    }
+
+    // Export completions for bash/zsh
+    if args.iter().any(|a| a == "--install-completions") {
+        let dir = paths::shell_completions_dir();
+        let loaded = completions::CompletionManager::new().load_all();
+        match completions::shell_export::install(&dir, &loaded) {
+            Ok(snippet) => {
+                println!(
+                    "Wrote {} completion scripts to {}",
+                    loaded.len(),
+                    dir.join("completions").display()
+                );
+                println!("Add this line to ~/.bashrc or ~/.zshrc:\n");
+                println!("  source \"{}\"", snippet.display());
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("Error installing completions: {}", e);
+                std::process::exit(1);
+            }
         }
     }
 
     // Handle --setup flag
     let force_setup = args.iter().any(|a| a == "--setup");
 
+    // Handle --script flag (read input lines from a file instead of the terminal)
+    let script = repl::script_path(&args);
+
+    // Handle --headless flag (non-interactive first run)
+    let headless = args
+        .iter()
+        .any(|a| a == "--headless" || a == "--no-onboarding")
+        || onboarding::headless_from_env()
+        || script.is_some();
+
+    // Handle --paranoid flag (stricter safety checks for this session)
+    let paranoid_flag = args.iter().any(|a| a == "--paranoid" || a == "--safe-mode");
+
+    // Handle --no-ai flag (offline: no AI translation this session)
+    let no_ai_flag = args.iter().any(|a| a == "--no-ai" || a == "--offline");
+
     // Initialize environment (source shell profiles to get PATH)
     // This is needed when nosh is used as a login shell
     exec::env::init();
@@ -234,7 +728,13 @@ async fn main() -> Result<()> {
     let mut permissions = PermissionStore::load().unwrap_or_default();
 
     // Run onboarding if needed or if --setup flag is passed
-    if force_setup || needs_onboarding(&creds) {
+    if headless && !force_setup {
+        if needs_onboarding(&creds)
+            && let Err(e) = onboarding::run_headless_setup()
+        {
+            eprintln!("Warning: Headless setup failed: {}", e);
+        }
+    } else if force_setup || needs_onboarding(&creds) {
         // Install built-in plugins and themes on first run
         let _ = install_builtins();
 
@@ -246,26 +746,86 @@ async fn main() -> Result<()> {
                 // User skipped AI setup - continue with shell only
             }
         }
+        if let Err(e) = onboarding::choose_theme() {
+            eprintln!("Keeping the default theme: {}", e);
+        }
     }
 
     // Load config (created by onboarding if first run)
     let mut config = Config::load().unwrap_or_default();
+    safety::set_paranoid(paranoid_flag || config.safety.paranoid);
+    safety::set_auto_approve_below(config.safety.auto_approve_below);
+    safety::prompt::set_explain(config.safety.explain);
+    nosh_context::set_command_timeout(std::time::Duration::from_millis(
+        config.prompt.context_timeout_ms,
+    ));
+    nosh_context::set_compose_ps(config.prompt.compose_ps);
+    nosh_context::set_git_tag(config.context.git_tag);
+    nosh_context::set_git_fetch_age(config.context.git_fetch_age);
+    nosh_context::set_fetch_stale_after(std::time::Duration::from_secs(
+        config.context.git_fetch_stale_hours * 3600,
+    ));
+
+    // Re-run (or print) a saved agentic session and exit
+    if let Some(i) = args.iter().position(|a| a == "--replay") {
+        let Some(file) = args.get(i + 1) else {
+            eprintln!("Usage: nosh --replay SESSION.json [--print]");
+            std::process::exit(2);
+        };
+        let session = match SavedSession::load(std::path::Path::new(file)) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                std::process::exit(1);
+            }
+        };
+        if args.iter().any(|a| a == "--print") {
+            print!("{}", session.to_script());
+            return Ok(());
+        }
+        let code = replay_session(&session, &mut permissions).await?;
+        std::process::exit(code.into());
+    }
 
     // Show welcome message if configured
     if !config.welcome_message.is_empty() {
         println!("{}\n", config.welcome_message);
     }
 
-    // Initialize REPL with theme from config
+    // Initialize REPL with theme from config (or --theme)
     let mut repl = Repl::new(
-        &config.prompt.theme,
+        session_theme(&args, None, &config.prompt.theme),
         Some(config.history.load_count),
         config.prompt.syntax_highlighting,
+        &config.keybindings,
     )?;
+    // Theme picked with `/theme NAME`, kept across /reload
+    let mut switched_theme: Option<String> = None;
     repl.load_history();
+    repl.set_completion_wrappers(config.repl.completion_wrappers.clone());
+    repl.set_history_ignore_space(config.history.ignore_space);
+    repl.set_history_dedup(config.history.ignore_dups, config.history.dedup_mode);
+    repl.set_history_max_entries(config.history.max_entries);
+    repl.set_max_concurrent_plugins(config.prompt.max_concurrent_plugins);
+    repl.set_context_cache_size(config.context.cache_size);
+    let mut ai_enabled = !no_ai_flag && config.ai.enabled;
+    repl.set_ai_enabled(ai_enabled);
+    repl.set_background(ui::background::resolve(config.ui.background));
+    print_theme_warnings(&repl);
+    if let Some(path) = &script {
+        match repl::ScriptInput::load(std::path::Path::new(path)) {
+            Ok(input) => repl.set_line_source(Box::new(input)),
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                std::process::exit(1);
+            }
+        }
+    }
 
     // Create persistent shell session (brush-based bash interpreter)
     let mut shell = ShellSession::new().await?;
+    shell.set_aliases(&config.aliases);
+    repl.set_shell_state(shell.prompt_state());
 
     // Create conversation context for AI
     let mut ai_context = ConversationContext::new(config.ai.context_size);
@@ -279,6 +839,9 @@ async fn main() -> Result<()> {
         exec::terminal::set_title_to_cwd();
         exec::terminal::notify_cwd();
 
+        // Commands complete and highlight against the shell's own PATH
+        repl.set_path_var(shell.path_var());
+
         match repl.readline().await? {
             ReadlineResult::Eof => break,
             ReadlineResult::Interrupted => {
@@ -326,10 +889,30 @@ async fn main() -> Result<()> {
                 println!("  /upgrade            Upgrade nosh to latest version");
                 println!("  /sync               Sync config, builtins, and packages");
                 println!("  /packages           List and manage installed packages");
+                println!("  /packages rollback NAME");
+                println!("                      Undo a package's last upgrade");
+                println!("  /plugins            Enable or disable plugins in the current theme");
                 println!("  /convert-zsh FILE   Convert zsh completion to nosh TOML");
                 println!("  /clear              Clear AI conversation context");
+                println!("  /clear --cache      Purge cached AI translations");
                 println!("  /reload             Reload config and theme");
+                println!("  /refresh            Recompute prompt variables and project context");
+                println!("  /edit               Edit last command in $EDITOR (Ctrl+X Ctrl+E)");
+                println!("  /paranoid [on|off]  Toggle paranoid safety mode");
+                println!("  /alias [name=value] List aliases (and remove one), or add one");
+                println!("  /stats              Show local usage stats from history");
+                println!("  /sessions           List saved ??sessions");
+                println!(
+                    "  /sessions replay N  Re-run session N's commands (--print: as a script)"
+                );
+                println!("  /sessions export N FILE.md");
+                println!("                      Write session N up as markdown");
+                println!("  /time <command>     Run a command and show real/user/sys time");
+                println!("  /theme              List installed themes");
+                println!("  /theme NAME         Switch theme for this session");
+                println!("  /theme lint         Check the theme format and preview the prompt");
                 println!("  /debug [plugin]     Debug plugins and theme");
+                println!("  /debug all          Check every installed theme and plugin file");
                 println!("  /version            Show nosh version");
                 println!("  /help               Show this help");
                 println!("  exit                Quit nosh");
@@ -347,6 +930,133 @@ async fn main() -> Result<()> {
                 println!("AI context cleared.");
                 continue;
             }
+            ReadlineResult::Line(line) if line == "/refresh" => {
+                repl.refresh_context().await;
+                continue;
+            }
+            ReadlineResult::Line(line) if line == "/clear --cache" => {
+                match TranslationCache::open(&paths::ai_cache_db()).and_then(|c| c.clear()) {
+                    Ok(n) => println!("Removed {} cached translation(s).", n),
+                    Err(e) => eprintln!("Failed to clear the translation cache: {}", e),
+                }
+                continue;
+            }
+            ReadlineResult::Line(line) if line == "/paranoid" || line.starts_with("/paranoid ") => {
+                let arg = line.strip_prefix("/paranoid").unwrap_or("").trim();
+                let enabled = match arg {
+                    "" => !safety::is_paranoid(),
+                    "on" => true,
+                    "off" => false,
+                    _ => {
+                        eprintln!("Usage: /paranoid [on|off]");
+                        continue;
+                    }
+                };
+                safety::set_paranoid(enabled);
+                if enabled {
+                    println!("Paranoid mode on: every non-safe command needs approval.");
+                } else {
+                    println!("Paranoid mode off.");
+                }
+                continue;
+            }
+            ReadlineResult::Line(line) if line == "/alias" || line.starts_with("/alias ") => {
+                let arg = line.strip_prefix("/alias").unwrap_or("").trim();
+                if !arg.is_empty() {
+                    let (name, value) = match config::parse_alias(arg) {
+                        Ok(alias) => alias,
+                        Err(e) => {
+                            eprintln!("Error: {}", e);
+                            eprintln!("Usage: /alias name=value");
+                            continue;
+                        }
+                    };
+                    if repl::is_slash_command(&name) {
+                        eprintln!("Error: '{}' would shadow /{}", name, name);
+                        continue;
+                    }
+                    config.aliases.insert(name.clone(), value.clone());
+                    match config.save() {
+                        Ok(()) => println!("Alias added: {} = {}", name, value),
+                        Err(e) => eprintln!("Failed to save config: {}", e),
+                    }
+                    shell.set_aliases(&config.aliases);
+                    continue;
+                }
+
+                if config.aliases.is_empty() {
+                    println!("\nNo aliases. Add one with /alias name=value\n");
+                    continue;
+                }
+
+                println!("\nAliases:\n");
+                let mut options: Vec<String> = vec!["Done".to_string()];
+                for (name, value) in &config.aliases {
+                    println!("  {} = {}", name, value);
+                    options.push(format!("Remove {}", name));
+                }
+                println!();
+
+                let selection = Select::with_theme(&ColorfulTheme::default())
+                    .items(&options)
+                    .default(0)
+                    .interact_opt();
+
+                if let Ok(Some(idx)) = selection
+                    && idx > 0
+                    && let Some(name) = config.aliases.keys().nth(idx - 1).cloned()
+                {
+                    config.aliases.remove(&name);
+                    match config.save() {
+                        Ok(()) => println!("\nRemoved alias: {}", name),
+                        Err(e) => eprintln!("Failed to save config: {}", e),
+                    }
+                    shell.set_aliases(&config.aliases);
+                }
+                continue;
+            }
+            ReadlineResult::Line(line) if line == "/sessions" || line.starts_with("/sessions ") => {
+                let args: Vec<&str> = line.split_whitespace().skip(1).collect();
+                match args.as_slice() {
+                    [] => print_sessions(),
+                    ["replay", n, rest @ ..] if rest.is_empty() || rest == ["--print"] => {
+                        let Some(session) = load_numbered_session(n) else {
+                            continue;
+                        };
+                        if rest.is_empty() {
+                            repl.start_command();
+                            let code = replay_session(&session, &mut permissions).await?;
+                            repl.end_command();
+                            repl.record_exit(code);
+                        } else {
+                            print!("{}", session.to_script());
+                        }
+                    }
+                    ["export", n, out] => {
+                        let Some(session) = load_numbered_session(n) else {
+                            continue;
+                        };
+                        match std::fs::write(out, session.to_markdown()) {
+                            Ok(()) => println!("Exported session {} to {}", n, out),
+                            Err(e) => eprintln!("Could not write {}: {}", out, e),
+                        }
+                    }
+                    _ => eprintln!("Usage: /sessions [replay N [--print] | export N FILE.md]"),
+                }
+                continue;
+            }
+            ReadlineResult::Line(line) if line == "/stats" => {
+                if let Err(e) = print_stats(repl.history_store()) {
+                    eprintln!("Error reading history: {}", e);
+                }
+                continue;
+            }
+            ReadlineResult::Line(line) if line == "/edit" => {
+                if let Err(e) = repl.edit_last_command() {
+                    eprintln!("Error: {}", e);
+                }
+                continue;
+            }
             ReadlineResult::Line(line) if line == "/version" => {
                 println!("nosh v{}", env!("CARGO_PKG_VERSION"));
                 continue;
@@ -355,14 +1065,82 @@ async fn main() -> Result<()> {
                 match Config::load() {
                     Ok(new_config) => {
                         config = new_config;
+                        safety::set_paranoid(paranoid_flag || config.safety.paranoid);
+                        safety::set_auto_approve_below(config.safety.auto_approve_below);
+                        safety::prompt::set_explain(config.safety.explain);
+                        nosh_context::set_command_timeout(std::time::Duration::from_millis(
+                            config.prompt.context_timeout_ms,
+                        ));
+                        nosh_context::set_compose_ps(config.prompt.compose_ps);
+                        nosh_context::set_git_tag(config.context.git_tag);
+                        nosh_context::set_git_fetch_age(config.context.git_fetch_age);
+                        nosh_context::set_fetch_stale_after(std::time::Duration::from_secs(
+                            config.context.git_fetch_stale_hours * 3600,
+                        ));
                         ai_context = ConversationContext::new(config.ai.context_size);
-                        repl.reload(&config.prompt.theme);
+                        repl.set_background(ui::background::resolve(config.ui.background));
+                        repl.set_max_concurrent_plugins(config.prompt.max_concurrent_plugins);
+                        repl.set_context_cache_size(config.context.cache_size);
+                        repl.reload(session_theme(
+                            &args,
+                            switched_theme.as_deref(),
+                            &config.prompt.theme,
+                        ));
+                        repl.set_completion_wrappers(config.repl.completion_wrappers.clone());
+                        repl.set_history_ignore_space(config.history.ignore_space);
+                        repl.set_history_dedup(
+                            config.history.ignore_dups,
+                            config.history.dedup_mode,
+                        );
+                        repl.set_history_max_entries(config.history.max_entries);
+                        shell.set_aliases(&config.aliases);
+                        ai_enabled = !no_ai_flag && config.ai.enabled;
+                        repl.set_ai_enabled(ai_enabled);
                         println!("Config reloaded.");
+                        print_theme_warnings(&repl);
                     }
                     Err(e) => eprintln!("Error reloading config: {}", e),
                 }
                 continue;
             }
+            ReadlineResult::Line(line) if line == "/theme lint" => {
+                println!("\nTheme: {}", config.prompt.theme);
+                println!("Format: {:?}", repl.theme_format());
+                let warnings = repl.theme_warnings();
+                if warnings.is_empty() {
+                    println!("No problems found.");
+                } else {
+                    for warning in &warnings {
+                        println!("  \x1b[33mwarning:\x1b[0m {}", warning);
+                    }
+                }
+                println!("\nPreview:\n{}\n", repl.prompt().await);
+                continue;
+            }
+            ReadlineResult::Line(line) if line.starts_with("/theme ") => {
+                let name = line.strip_prefix("/theme ").unwrap_or("").trim();
+                if name.is_empty() || name.contains(char::is_whitespace) {
+                    eprintln!("Usage: /theme NAME | /theme lint");
+                    continue;
+                }
+                // Falls back to the default theme with a warning if it doesn't load
+                repl.reload(name);
+                switched_theme = Some(name.to_string());
+                println!(
+                    "Theme: {} (this session only; set [prompt] theme to keep it)",
+                    name
+                );
+                print_theme_warnings(&repl);
+                continue;
+            }
+            ReadlineResult::Line(line) if line == "/theme" => {
+                eprintln!("Usage: /theme NAME | /theme lint");
+                println!("\nInstalled themes:");
+                for name in plugins::theme::theme_names() {
+                    println!("  {}", name);
+                }
+                continue;
+            }
             ReadlineResult::Line(line) if line == "/debug" => {
                 // Show loaded plugins and theme info
                 println!("\nTheme: {}", config.prompt.theme);
@@ -396,7 +1174,42 @@ async fn main() -> Result<()> {
                     }
                 }
 
-                println!("\nUse '/debug <plugin>' to test a specific plugin.");
+                let collisions = repl.plugin_collisions();
+                if !collisions.is_empty() {
+                    println!();
+                    for (base, names) in collisions {
+                        println!(
+                            "Warning: plugin '{}' is provided by {}; reference it by full name",
+                            base,
+                            names.join(", ")
+                        );
+                    }
+                }
+
+                let truncated = repl.truncated_completers();
+                if !truncated.is_empty() {
+                    println!();
+                    for (name, lines) in truncated {
+                        println!(
+                            "Warning: completer '{}' output was truncated to {} lines; raise its max_lines",
+                            name, lines
+                        );
+                    }
+                }
+
+                println!(
+                    "\nUse '/debug <plugin>' to test a specific plugin, '/debug all' to check every file."
+                );
+                continue;
+            }
+            ReadlineResult::Line(line) if line == "/debug all" => {
+                println!();
+                print_check_report(&plugins::check::check_installed());
+                continue;
+            }
+            ReadlineResult::Line(line) if line == "/debug timing" => {
+                // Hidden: time each plugin variable on the next prompt render
+                repl.profile_next_prompt();
                 continue;
             }
             ReadlineResult::Line(line) if line.starts_with("/debug ") => {
@@ -408,6 +1221,13 @@ async fn main() -> Result<()> {
 
                 println!("\nDebugging plugin: {}\n", plugin_name);
 
+                let base = plugin_name.rsplit('/').next().unwrap_or(plugin_name);
+                let same_name: Vec<String> = repl
+                    .plugins_named(base)
+                    .into_iter()
+                    .filter(|name| name != plugin_name)
+                    .collect();
+
                 match repl.debug_plugin(plugin_name).await {
                     Some(results) => {
                         for (var_name, provider_desc, result) in results {
@@ -419,6 +1239,20 @@ async fn main() -> Result<()> {
                             }
                             println!();
                         }
+                        if !same_name.is_empty() {
+                            println!(
+                                "Warning: '{}' is also provided as {}; use full names in themes to avoid mixing them up.\n",
+                                base,
+                                same_name.join(", ")
+                            );
+                        }
+                    }
+                    None if !same_name.is_empty() => {
+                        eprintln!(
+                            "Plugin '{}' not found. Did you mean {}?",
+                            plugin_name,
+                            same_name.join(" or ")
+                        );
                     }
                     None => {
                         eprintln!("Plugin '{}' not found.", plugin_name);
@@ -431,20 +1265,29 @@ async fn main() -> Result<()> {
                 continue;
             }
             ReadlineResult::Line(line) if line.starts_with("/convert-zsh ") => {
-                let path = line.strip_prefix("/convert-zsh ").unwrap().trim();
+                let arg = line.strip_prefix("/convert-zsh ").unwrap().trim();
+                let (path, report) = match arg.strip_suffix("--report") {
+                    Some(path) => (path.trim(), true),
+                    None => (arg, false),
+                };
                 if path.is_empty() {
-                    eprintln!("Usage: /convert-zsh /path/to/zsh/completion");
+                    eprintln!("Usage: /convert-zsh /path/to/zsh/completion [--report]");
                     continue;
                 }
                 let path = std::path::Path::new(path);
                 match completions::convert_zsh_file(path) {
-                    Ok(toml) => println!("{}", toml),
+                    Ok((toml, warnings)) => {
+                        println!("{}", toml);
+                        if report {
+                            print_zsh_warnings(&warnings);
+                        }
+                    }
                     Err(e) => eprintln!("Error: {}", e),
                 }
                 continue;
             }
             ReadlineResult::Line(line) if line == "/convert-zsh" => {
-                eprintln!("Usage: /convert-zsh /path/to/zsh/completion");
+                eprintln!("Usage: /convert-zsh /path/to/zsh/completion [--report]");
                 continue;
             }
             ReadlineResult::Line(line) if line == "/create" => {
@@ -808,7 +1651,11 @@ description = "Show version"
                                 }
 
                                 // Reload plugins
-                                repl.reload(&config.prompt.theme);
+                                repl.reload(session_theme(
+                                    &args,
+                                    switched_theme.as_deref(),
+                                    &config.prompt.theme,
+                                ));
                             }
                             Err(e) => eprintln!("Could not create symlink: {}", e),
                         }
@@ -1066,8 +1913,20 @@ description = "Show version"
                             println!("  [{{{}/{}:variable}}](color)", name, plugins[0]);
                         }
 
+                        let conflicts = packages::find_conflicts(&name);
+                        if !conflicts.is_empty() {
+                            println!();
+                            for conflict in conflicts {
+                                println!("Warning: {}", conflict);
+                            }
+                        }
+
                         // Reload plugins
-                        repl.reload(&config.prompt.theme);
+                        repl.reload(session_theme(
+                            &args,
+                            switched_theme.as_deref(),
+                            &config.prompt.theme,
+                        ));
                     }
                     Err(e) => eprintln!("Error: {}", e),
                 }
@@ -1196,12 +2055,39 @@ description = "Show version"
                 if total_updated > 0 {
                     println!("\n{} item(s) updated.", total_updated);
                     // Reload plugins after updates
-                    repl.reload(&config.prompt.theme);
+                    repl.reload(session_theme(
+                        &args,
+                        switched_theme.as_deref(),
+                        &config.prompt.theme,
+                    ));
                 } else {
                     println!("\nEverything is up to date.");
                 }
                 continue;
             }
+            ReadlineResult::Line(line) if line.starts_with("/packages rollback") => {
+                let name = line.strip_prefix("/packages rollback").unwrap().trim();
+                if name.is_empty() {
+                    eprintln!("Usage: /packages rollback NAME");
+                    continue;
+                }
+                match packages::rollback_package(name) {
+                    Ok(commit) => {
+                        println!(
+                            "Rolled back {} to {}",
+                            name,
+                            &commit[..commit.len().min(12)]
+                        );
+                        repl.reload(session_theme(
+                            &args,
+                            switched_theme.as_deref(),
+                            &config.prompt.theme,
+                        ));
+                    }
+                    Err(e) => eprintln!("Error: {}", e),
+                }
+                continue;
+            }
             ReadlineResult::Line(line) if line == "/packages" => {
                 let registry = packages::PackageRegistry::load().unwrap_or_default();
                 let packages_list = registry.list();
@@ -1254,7 +2140,11 @@ description = "Show version"
                             Ok(_) => {
                                 println!("\nRemoved package: {}", name);
                                 // Reload plugins after removal
-                                repl.reload(&config.prompt.theme);
+                                repl.reload(session_theme(
+                                    &args,
+                                    switched_theme.as_deref(),
+                                    &config.prompt.theme,
+                                ));
                             }
                             Err(e) => eprintln!("Error: {}", e),
                         }
@@ -1262,13 +2152,89 @@ description = "Show version"
                 }
                 continue;
             }
-            ReadlineResult::Line(line) if line.starts_with('/') => {
+            ReadlineResult::Line(line) if line == "/plugins" => {
+                let mut names: Vec<String> = repl
+                    .list_plugins()
+                    .into_iter()
+                    .map(|(name, _, _)| name.to_string())
+                    .collect();
+                names.sort();
+
+                if names.is_empty() {
+                    println!("\nNo plugins loaded.\n");
+                    continue;
+                }
+
+                println!("\nPlugins in theme '{}':\n", config.prompt.theme);
+                let mut options: Vec<String> = vec!["Done".to_string()];
+                for name in &names {
+                    let enabled = repl.plugin_enabled(name);
+                    println!(
+                        "  {} {}",
+                        if enabled { "\x1b[32m●\x1b[0m" } else { "○" },
+                        name
+                    );
+                    options.push(format!(
+                        "{} {}",
+                        if enabled { "Disable" } else { "Enable" },
+                        name
+                    ));
+                }
+                println!();
+
+                let selection = Select::with_theme(&ColorfulTheme::default())
+                    .items(&options)
+                    .default(0)
+                    .interact_opt();
+
+                if let Ok(Some(idx)) = selection
+                    && idx > 0
+                {
+                    let name = &names[idx - 1];
+                    let enabled = !repl.plugin_enabled(name);
+                    let path = plugins::theme::theme_path(&config.prompt.theme);
+                    match plugins::theme::Theme::set_plugin_enabled(&path, name, enabled) {
+                        Ok(()) => {
+                            println!(
+                                "\n{} {} in {}",
+                                if enabled { "Enabled" } else { "Disabled" },
+                                name,
+                                path.display()
+                            );
+                            if config.prompt.theme.contains('/') {
+                                println!(
+                                    "Note: this theme comes from a package, so /sync or upgrading it may undo the change."
+                                );
+                            }
+                            repl.reload(session_theme(
+                                &args,
+                                switched_theme.as_deref(),
+                                &config.prompt.theme,
+                            ));
+                        }
+                        Err(e) => eprintln!("Error: {}", e),
+                    }
+                }
+                continue;
+            }
+            ReadlineResult::Line(line) if line == "/time" => {
+                eprintln!("Usage: /time <command>");
+                continue;
+            }
+            ReadlineResult::Line(line) if line.starts_with('/') && !line.starts_with("/time ") => {
                 // Unknown built-in command
                 eprintln!("Unknown command: {}", line);
                 eprintln!("Type /help for available commands.");
                 continue;
             }
-            ReadlineResult::Line(line) if line.starts_with("??") => {
+            ReadlineResult::Line(line) if line.starts_with('?') && !ai_enabled => {
+                eprintln!("AI is turned off (--no-ai or [ai] enabled = false).");
+                eprintln!("Type the command directly, or re-enable AI in config.toml and /reload.");
+                continue;
+            }
+            ReadlineResult::Line(line)
+                if is_ai_query(&line, ai_enabled) && line.starts_with("??") =>
+            {
                 // Agentic mode - AI investigates before answering
                 let input = line[2..].trim();
                 if input.is_empty() {
@@ -1282,24 +2248,25 @@ description = "Show version"
                     continue;
                 }
 
-                let token = match &creds.token {
-                    Some(t) => t.clone(),
-                    None => {
-                        eprintln!("Not signed in. Run /login to sign in.");
-                        continue;
-                    }
+                let Some(client) = ai_backend(&creds, &config.ai.instructions, repl.is_scripted())
+                else {
+                    eprintln!("Not signed in. Run /login to sign in.");
+                    continue;
                 };
-
-                let client = CloudClient::new(&token);
                 let agentic_config = AgenticConfig {
                     max_iterations: config.ai.max_iterations,
                     timeout_seconds: config.ai.timeout,
+                    paranoid: safety::is_paranoid(),
+                    auto_approve_below: safety::auto_approve_below(),
                 };
                 let mut session = AgenticSession::new(agentic_config);
                 let mut executions: Vec<(String, String, i32)> = Vec::new();
 
                 println!("{}", format_header("Investigating", input));
 
+                // Grants made during the run are saved together once it ends
+                permissions.begin_batch();
+
                 // Agentic loop
                 loop {
                     // Check limits
@@ -1350,39 +2317,32 @@ description = "Show version"
                                 }
                                 CommandPermission::NeedsApproval => {
                                     // Show the command and ask for permission
-                                    let parsed = parse_command(&command);
+                                    let parsed = safety::apply_mode(
+                                        parse_command(&command),
+                                        safety::is_paranoid(),
+                                    );
                                     println!(
                                         "\n\x1b[33m[Approval needed]\x1b[0m AI wants to run: {}",
                                         command
                                     );
-                                    match prompt_for_permission(&parsed)? {
-                                        PermissionChoice::AllowOnce => true,
-                                        PermissionChoice::AllowCommand => {
-                                            permissions.allow_command(&parsed.info.command, true);
-                                            true
-                                        }
-                                        PermissionChoice::AllowSubcommand => {
-                                            permissions
-                                                .allow_command(&parsed.info.command_pattern, true);
-                                            true
-                                        }
-                                        PermissionChoice::AllowCommandHere => {
-                                            permissions.allow_command_in_directory(
-                                                &parsed.info.command_pattern,
-                                                &cwd,
-                                                true,
-                                            );
-                                            true
-                                        }
-                                        PermissionChoice::AllowHere => {
-                                            permissions.allow_directory(&cwd, true);
-                                            true
-                                        }
-                                        PermissionChoice::Deny => {
-                                            println!("Command denied. Stopping agentic mode.");
-                                            false
+                                    let choice = match prompt_for_permission(&parsed) {
+                                        Ok(choice) => choice,
+                                        Err(_) => {
+                                            // Ctrl+C at the prompt cancels the run
+                                            println!("\nCancelled.");
+                                            break;
                                         }
+                                    };
+                                    let allowed = apply_permission_choice(
+                                        choice,
+                                        &parsed,
+                                        &mut permissions,
+                                        &cwd,
+                                    );
+                                    if !allowed {
+                                        println!("Command denied. Stopping agentic mode.");
                                     }
+                                    allowed
                                 }
                             };
 
@@ -1393,9 +2353,15 @@ description = "Show version"
                             }
 
                             // Execute the command and capture output
+                            let verbosity = config.ai.agentic_verbosity;
                             println!(
                                 "{}",
-                                format_step(session.iterations(), &command, reasoning.as_deref())
+                                format_step(
+                                    session.iterations(),
+                                    &command,
+                                    reasoning.as_deref(),
+                                    verbosity
+                                )
                             );
 
                             // Show spinner while command runs
@@ -1409,6 +2375,7 @@ description = "Show version"
                             spinner.enable_steady_tick(std::time::Duration::from_millis(100));
 
                             // Capture output by running through shell (Ctrl+C cancels agentic loop)
+                            let timer = exec::timing::Timer::start();
                             let cmd_fut = tokio::process::Command::new("sh")
                                 .arg("-c")
                                 .arg(&command)
@@ -1427,12 +2394,19 @@ description = "Show version"
                                         };
 
                                         // Print output in dimmed box
-                                        let formatted = format_output(&combined);
+                                        let code = out.status.code().unwrap_or(1);
+                                        let formatted = format_step_output(
+                                            &combined,
+                                            code,
+                                            &timer.stop(),
+                                            config.ui.output_max_lines,
+                                            verbosity,
+                                        );
                                         if !formatted.is_empty() {
                                             println!("{}", formatted);
                                         }
 
-                                        (combined, out.status.code().unwrap_or(1))
+                                        (combined, code)
                                     }
                                     Err(e) => {
                                         spinner.finish_and_clear();
@@ -1446,11 +2420,12 @@ description = "Show version"
                                 }
                             };
 
-                            session.record_execution(&command, &output.0);
+                            session.record_execution(&command, reasoning.as_deref(), &output.0);
                             executions.push((command, output.0, output.1));
                         }
                         AgenticStep::FinalResponse { message } => {
                             println!("{}", format_result(&message));
+                            session.record_answer(&message);
                             // Record in context
                             ai_context.add_exchange(input, &format!("[agentic] {}", message));
                             break;
@@ -1461,21 +2436,44 @@ description = "Show version"
                         }
                     }
                 }
+
+                if let Err(e) = permissions.finish_batch() {
+                    eprintln!("Could not save permissions: {}", e);
+                }
+
+                // Save the commands that ran so the session can be replayed
+                let saved = session.to_saved(input, &cwd);
+                if !saved.commands.is_empty()
+                    && let Err(e) = saved.save()
+                {
+                    eprintln!("Could not save session: {}", e);
+                }
                 continue;
             }
-            ReadlineResult::Line(line) if line.starts_with('?') => {
+            ReadlineResult::Line(line) if is_ai_query(&line, ai_enabled) => {
                 // AI request - translate and run through safety layer
                 let input = line[1..].trim();
                 if input.is_empty() {
                     continue;
                 }
 
+                // Reuse a cached translation of the same query, if enabled. The
+                // cache key has no conversation in it, so follow-ups skip it
+                let cache = (config.ai.cache_translations && ai_context.is_empty())
+                    .then(|| TranslationCache::open(&paths::ai_cache_db()).ok())
+                    .flatten();
+                let cache_ttl = std::time::Duration::from_secs(config.ai.cache_ttl_hours * 3600);
+                let cached = cache.as_ref().and_then(|c| c.get(input, &cwd, cache_ttl));
+
                 // Show spinner while waiting for AI
                 let spinner = ui::spinner::create();
 
                 // AI translation with conversation context (Ctrl+C cancels)
-                let result = if let Some(token) = &creds.token {
-                    let client = CloudClient::new(token);
+                let result = if let Some(cmd) = cached.clone() {
+                    Ok(cmd)
+                } else if let Some(client) =
+                    ai_backend(&creds, &config.ai.instructions, repl.is_scripted())
+                {
                     let fut = client.translate(input, &cwd, Some(&ai_context));
                     tokio::select! {
                         res = fut => res.map(|(cmd, _)| cmd),
@@ -1493,7 +2491,14 @@ description = "Show version"
 
                 let command = match result {
                     Ok(cmd) => {
-                        println!("{}", format_translated_command(&cmd));
+                        if cached.is_some() {
+                            println!("{}", ui::format_cached_command(&cmd));
+                        } else {
+                            println!("{}", format_translated_command(&cmd));
+                            if let Some(cache) = &cache {
+                                let _ = cache.put(input, &cwd, &cmd);
+                            }
+                        }
                         // Record exchange in context (before execution, in case it fails)
                         ai_context.add_exchange(input, &cmd);
                         cmd
@@ -1504,81 +2509,148 @@ description = "Show version"
                     }
                 };
 
-                // Safety layer for AI-generated commands
-                let parsed = parse_command(&command);
+                // Show each part of a chained command with its own risk
+                let segments: Vec<_> = safety::parse_chain(&command)
+                    .into_iter()
+                    .map(|s| safety::apply_mode(s, safety::is_paranoid()))
+                    .collect();
+                if config.ai.chain_preview {
+                    let preview = format_chain_preview(&segments);
+                    if !preview.is_empty() {
+                        println!("{}", preview);
+                    }
+                }
+
+                // Safety layer for AI-generated commands: the riskiest part decides,
+                // and grants must cover every part
+                let parsed = safety::apply_mode(
+                    safety::riskiest(&command, &segments),
+                    safety::is_paranoid(),
+                );
 
-                let should_execute = match parsed.risk_level {
-                    RiskLevel::Safe => true,
-                    RiskLevel::Blocked => {
+                let should_execute = match safety::gate(&parsed, &segments, &permissions, &cwd) {
+                    safety::Gate::Blocked => {
                         safety::prompt::print_blocked(&parsed)?;
                         false
                     }
-                    RiskLevel::Critical => safety::prompt::print_critical_warning(&parsed)?,
-                    _ => {
-                        // Check permissions in order: global command, command+directory (checking actual paths), all-directory
-                        if permissions
-                            .is_command_allowed(&parsed.info.command, &parsed.info.command_pattern)
-                            || permissions.are_affected_paths_allowed(
-                                &parsed.info.command,
-                                &parsed.info.command_pattern,
-                                &parsed.info.affected_paths,
-                                &cwd,
-                            )
-                            || permissions.is_directory_allowed(&cwd)
-                        {
-                            true
-                        } else {
-                            match prompt_for_permission(&parsed)? {
-                                PermissionChoice::AllowOnce => true,
-                                PermissionChoice::AllowCommandHere => {
-                                    // Allow this command/pattern in this directory only
-                                    permissions.allow_command_in_directory(
-                                        &parsed.info.command_pattern,
-                                        &cwd,
-                                        true,
-                                    );
-                                    true
-                                }
-                                PermissionChoice::AllowSubcommand => {
-                                    // Allow specific subcommand pattern globally (e.g., "git log")
-                                    permissions.allow_command(&parsed.info.command_pattern, true);
-                                    true
-                                }
-                                PermissionChoice::AllowCommand => {
-                                    // Allow base command globally (all subcommands)
-                                    permissions.allow_command(&parsed.info.command, true);
-                                    true
-                                }
-                                PermissionChoice::AllowHere => {
-                                    // Allow all commands in this directory
-                                    permissions.allow_directory(&cwd, true);
-                                    true
-                                }
-                                PermissionChoice::Deny => false,
-                            }
-                        }
-                    }
+                    safety::Gate::Critical => safety::prompt::print_critical_warning(&parsed)?,
+                    safety::Gate::Approved => true,
+                    safety::Gate::Ask => apply_permission_choice(
+                        prompt_for_permission(&parsed)?,
+                        &parsed,
+                        &mut permissions,
+                        &cwd,
+                    ),
                 };
 
                 if should_execute {
                     repl.start_command();
                     // AI commands run without job control (Ctrl+Z won't suspend)
-                    if let Err(e) = shell.execute_no_job_control(&command).await {
-                        eprintln!("Execution error: {}", e);
-                    }
+                    let exit_code = match shell.execute_no_job_control(&command).await {
+                        Ok(code) => code,
+                        Err(e) => {
+                            eprintln!("Execution error: {}", e);
+                            1
+                        }
+                    };
                     repl.end_command();
+                    repl.record_exit(exit_code);
                 }
             }
             ReadlineResult::Line(command) => {
+                // "/time cmd" runs cmd and prints a timing summary afterwards
+                let (command, timed) = match command.strip_prefix("/time ") {
+                    Some(cmd) => (cmd.trim().to_string(), true),
+                    None => (command, false),
+                };
+
+                // Plain `cd` runs in-process so the prompt and context follow it
+                if !timed && let Some(result) = shell.try_builtin_cd(&command) {
+                    let exit_code = match result {
+                        Ok(dir) => {
+                            if command.split_whitespace().nth(1) == Some("-") {
+                                println!("{}", dir.display());
+                            }
+                            0
+                        }
+                        Err(e) => {
+                            eprintln!("cd: {}", e);
+                            1
+                        }
+                    };
+                    repl.record_exit(exit_code);
+                    // Same tail as other commands: report finished jobs
+                    let _ = shell.check_jobs();
+                    repl.set_shell_state(shell.prompt_state());
+                    continue;
+                }
+
                 // Direct command - execute with job control (Ctrl+Z suspends)
+                // Route long output through the pager when enabled
+                let paging = config.repl.paging;
+                let to_run = if paging != exec::pager::PagingMode::Never
+                    && exec::pager::is_pageable(&command, &shell.path_var(), |w| {
+                        shell.is_shell_command(w)
+                    }) {
+                    exec::pager::wrap_command(&command, paging).unwrap_or_else(|| command.clone())
+                } else {
+                    command.clone()
+                };
+
                 repl.start_command();
-                if let Err(e) = shell.execute(&command).await {
-                    eprintln!("Execution error: {}", e);
-                }
+                let timer = timed.then(exec::timing::Timer::start);
+                let exit_code = match shell.execute(&to_run).await {
+                    Ok(code) => code,
+                    Err(e) => {
+                        eprintln!("Execution error: {}", e);
+                        1
+                    }
+                };
                 repl.end_command();
+                if let Some(timer) = timer {
+                    let timing = exec::timing::format_timing(&timer.stop());
+                    eprintln!("{}", format_hint(&timing));
+                }
+                repl.record_exit(exit_code);
+
+                // Suggest a nearby command if the command word wasn't found
+                if exit_code == exec::suggest::NOT_FOUND_EXIT_CODE
+                    && let Some(word) = shell_words::split(&command)
+                        .ok()
+                        .and_then(|words| words.into_iter().next())
+                    && !exec::suggest::is_on_path(&word, &shell.path_var())
+                    && !shell.is_shell_command(&word)
+                {
+                    let history = repl.recent_commands(500);
+                    if let Some(suggestion) =
+                        exec::suggest::suggest_command(&word, &history, &shell.path_var())
+                    {
+                        eprintln!(
+                            "{}",
+                            format_hint(&format!("Did you mean '{}'?", suggestion))
+                        );
+                    }
+                    if let Some(hint) =
+                        exec::suggest::ask_hint(&command, ai_enabled, creds.is_authenticated())
+                    {
+                        eprintln!("{}", format_hint(&hint));
+                    }
+                } else if exit_code != 0
+                    && config.repl.suggest_subcommands
+                    && let Ok(words) = shell_words::split(&command)
+                    && let [program, word, ..] = words.as_slice()
+                    && !word.starts_with('-')
+                    && let Some(suggestion) = repl.suggest_subcommand(program, word)
+                {
+                    eprintln!(
+                        "{}",
+                        format_hint(&format!("Did you mean '{} {}'?", program, suggestion))
+                    );
+                }
 
                 // Check for completed background jobs
                 let _ = shell.check_jobs();
+                repl.set_shell_state(shell.prompt_state());
             }
         }
     }
@@ -1587,3 +2659,22 @@ description = "Show version"
     println!("Goodbye!");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_theme_override() {
+        let args: Vec<String> = ["nosh", "--theme", "catppuccin"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(session_theme(&args, None, "default"), "catppuccin");
+        assert_eq!(session_theme(&args[..1], None, "default"), "default");
+        // A trailing --theme without a name keeps the configured theme
+        assert_eq!(session_theme(&args[..2], None, "default"), "default");
+        // `/theme NAME` outlasts /reload
+        assert_eq!(session_theme(&args, Some("nord"), "default"), "nord");
+    }
+}
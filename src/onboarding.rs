@@ -1,12 +1,17 @@
 use crate::auth::Credentials;
 use crate::config::Config;
+use crate::fs_util;
+use crate::paths;
+use crate::plugins::builtins::{GALLERY_THEMES, install_builtins};
 use anyhow::{Result, anyhow};
 use crossterm::ExecutableCommand;
 use crossterm::style::{Color, ResetColor, SetForegroundColor};
 use dialoguer::{Input, Select, theme::ColorfulTheme};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::fs;
 use std::io::{self, Write};
+use std::path::Path;
 use std::time::Duration;
 
 pub enum OnboardingChoice {
@@ -93,6 +98,22 @@ pub async fn run_onboarding() -> Result<OnboardingChoice> {
     }
 }
 
+/// Let a new user pick one of the built-in themes.
+pub fn choose_theme() -> Result<()> {
+    let items: Vec<&str> = GALLERY_THEMES.iter().map(|(_, label, _)| *label).collect();
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Pick a prompt theme (switch later with /theme)")
+        .items(&items)
+        .default(0)
+        .interact()?;
+
+    let (name, _, _) = GALLERY_THEMES[selection];
+    let mut config = Config::load().unwrap_or_default();
+    config.prompt.theme = format!("builtins/{}", name);
+    config.save()?;
+    Ok(())
+}
+
 fn get_cloud_url() -> String {
     crate::config::cloud_url()
 }
@@ -259,3 +280,64 @@ pub fn needs_onboarding(creds: &Credentials) -> bool {
     let config = Config::load().unwrap_or_default();
     !config.onboarding_complete
 }
+
+/// Check if headless mode was requested via `NOSH_HEADLESS=1`.
+pub fn headless_from_env() -> bool {
+    std::env::var("NOSH_HEADLESS").is_ok_and(|v| v == "1")
+}
+
+/// Non-interactive first run for CI and dotfile bootstrapping.
+///
+/// Installs builtins and writes a default config (with onboarding marked
+/// complete) without prompting. An existing config is left untouched.
+pub fn run_headless_setup() -> Result<()> {
+    install_builtins()?;
+    write_default_config_if_missing(&paths::config_file())?;
+    Ok(())
+}
+
+/// Write a default config to `path` unless one already exists.
+/// Returns true if a config was written.
+fn write_default_config_if_missing(path: &Path) -> Result<bool> {
+    if path.exists() {
+        return Ok(false);
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let config = Config {
+        onboarding_complete: true,
+        ..Config::default()
+    };
+    fs_util::write_atomic(path, toml::to_string_pretty(&config)?)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headless_config_is_valid_and_not_clobbered() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_headless_{}", std::process::id()));
+        let path = dir.join("config.toml");
+        let _ = fs::remove_dir_all(&dir);
+
+        // First run writes a valid config with onboarding complete
+        assert!(write_default_config_if_missing(&path).unwrap());
+        let content = fs::read_to_string(&path).unwrap();
+        let config: Config = toml::from_str(&content).unwrap();
+        assert!(config.onboarding_complete);
+        assert_eq!(config.prompt.theme, Config::default().prompt.theme);
+
+        // Existing config is left untouched
+        fs::write(&path, "welcome_message = \"custom\"\n").unwrap();
+        assert!(!write_default_config_if_missing(&path).unwrap());
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "welcome_message = \"custom\"\n");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
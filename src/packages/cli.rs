@@ -0,0 +1,149 @@
+//! Non-interactive `nosh package ...` commands for scripts.
+//!
+//! Unlike the `/install`, `/upgrade` and `/packages` slash commands these
+//! never prompt, print one result per line (or JSON), send errors to stderr
+//! and report failure through the exit code.
+
+use anyhow::Result;
+
+use super::{PackageRegistry, find_conflicts, install_package, upgrade_package};
+
+const USAGE: &str = "Usage: nosh package install USER/REPO... [--quiet]
+       nosh package upgrade [NAME...] [--quiet]
+       nosh package list [--json]";
+
+/// Run `nosh package <args>` and return the process exit code:
+/// 0 on success, 1 if any package operation failed, 2 for usage errors.
+pub fn run(args: &[String]) -> i32 {
+    let quiet = args.iter().any(|a| a == "--quiet" || a == "-q");
+    let json = args.iter().any(|a| a == "--json");
+    let mut words = args
+        .iter()
+        .filter(|a| !a.starts_with('-'))
+        .map(String::as_str);
+    let command = words.next();
+    let names: Vec<&str> = words.collect();
+
+    match command {
+        Some("install") if !names.is_empty() => install(&names, quiet),
+        Some("upgrade") => upgrade(&names, quiet),
+        Some("list") => match PackageRegistry::load().and_then(|r| list(&r, json)) {
+            Ok(output) => {
+                print!("{}", output);
+                0
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                1
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            2
+        }
+    }
+}
+
+fn install(sources: &[&str], quiet: bool) -> i32 {
+    let mut code = 0;
+    for source in sources {
+        match install_package(source) {
+            Ok(name) if !quiet => {
+                println!("installed {}", name);
+                for conflict in find_conflicts(&name) {
+                    eprintln!("Warning: {}", conflict);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Error installing '{}': {}", source, e);
+                code = 1;
+            }
+        }
+    }
+    code
+}
+
+fn upgrade(names: &[&str], quiet: bool) -> i32 {
+    let names: Vec<String> = if names.is_empty() {
+        match PackageRegistry::load() {
+            Ok(registry) => sorted(&registry)
+                .into_iter()
+                .map(|p| p.name.clone())
+                .collect(),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return 1;
+            }
+        }
+    } else {
+        names.iter().map(|n| n.to_string()).collect()
+    };
+
+    let mut code = 0;
+    for name in names {
+        match upgrade_package(&name) {
+            Ok(updated) if !quiet => {
+                println!("{} {}", if updated { "updated" } else { "current" }, name)
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Error upgrading '{}': {}", name, e);
+                code = 1;
+            }
+        }
+    }
+    code
+}
+
+/// Installed packages, one `name<TAB>source` line each, or a JSON array.
+fn list(registry: &PackageRegistry, json: bool) -> Result<String> {
+    let packages = sorted(registry);
+    if json {
+        return Ok(serde_json::to_string_pretty(&packages)? + "\n");
+    }
+    Ok(packages
+        .iter()
+        .map(|p| format!("{}\t{}\n", p.name, p.source))
+        .collect())
+}
+
+fn sorted(registry: &PackageRegistry) -> Vec<&super::Package> {
+    let mut packages = registry.list();
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    packages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packages::Package;
+
+    #[test]
+    fn test_list_json() {
+        let mut registry = PackageRegistry::default();
+        for name in ["zeta", "alpha"] {
+            registry.add(Package {
+                name: name.to_string(),
+                source: format!("https://github.com/user/{}.git", name),
+                installed_at: "1700000000".to_string(),
+                last_updated: "1700000100".to_string(),
+                previous_commit: None,
+            });
+        }
+
+        let parsed: serde_json::Value =
+            serde_json::from_str(&list(&registry, true).unwrap()).unwrap();
+        let packages = parsed.as_array().unwrap();
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0]["name"], "alpha");
+        assert_eq!(packages[0]["source"], "https://github.com/user/alpha.git");
+        assert_eq!(packages[1]["last_updated"], "1700000100");
+
+        assert_eq!(
+            list(&registry, false).unwrap(),
+            "alpha\thttps://github.com/user/alpha.git\nzeta\thttps://github.com/user/zeta.git\n"
+        );
+        assert_eq!(list(&PackageRegistry::default(), true).unwrap(), "[]\n");
+    }
+}
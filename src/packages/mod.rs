@@ -5,12 +5,99 @@
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
+use std::path::{Component, Path};
 use std::process::Command;
 use std::time::SystemTime;
 
+use crate::fs_util::{self, FileLock};
 use crate::paths;
 
+pub mod cli;
+
+/// Why installing, upgrading or removing a package failed.
+#[derive(Debug)]
+pub enum PackageError {
+    /// The package is already in the registry
+    AlreadyInstalled(String),
+    /// The package isn't installed, or its directory is gone
+    NotFound(String),
+    /// The install source isn't `user/repo` or a URL
+    InvalidSource(String),
+    /// `git clone` or `git pull` failed; holds git's stderr
+    CloneFailed(String),
+    /// Another git command on an installed package failed
+    GitFailed { args: String, stderr: String },
+    /// git isn't installed
+    GitMissing,
+    /// The repository has no themes, plugins or completions
+    NotANoshPackage(String),
+    /// No upgrade was recorded for the package, so there's nothing to roll back to
+    NoPreviousCommit(String),
+    /// Reading or writing the registry or package files failed
+    Io(anyhow::Error),
+}
+
+impl fmt::Display for PackageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackageError::AlreadyInstalled(name) => write!(
+                f,
+                "Package '{}' is already installed. Use /upgrade to update it.",
+                name
+            ),
+            PackageError::NotFound(name) => write!(
+                f,
+                "Package '{}' is not installed. Try installing it with /install.",
+                name
+            ),
+            PackageError::InvalidSource(reason) => write!(f, "{}", reason),
+            PackageError::CloneFailed(stderr) => write!(
+                f,
+                "Could not fetch repository. Check the URL and your internet connection.\n{}",
+                stderr
+            ),
+            PackageError::GitFailed { args, stderr } => {
+                write!(
+                    f,
+                    "`git {}` failed in the package directory.\n{}",
+                    args, stderr
+                )
+            }
+            PackageError::GitMissing => write!(
+                f,
+                "Git is not installed. Please install git to use package management."
+            ),
+            PackageError::NotANoshPackage(name) => write!(
+                f,
+                "'{}' is not a nosh package (no themes/, plugins/ or completions/ directory).",
+                name
+            ),
+            PackageError::NoPreviousCommit(name) => write!(
+                f,
+                "Package '{}' has no previous version to roll back to.",
+                name
+            ),
+            PackageError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for PackageError {}
+
+impl From<anyhow::Error> for PackageError {
+    fn from(e: anyhow::Error) -> Self {
+        PackageError::Io(e)
+    }
+}
+
+impl From<std::io::Error> for PackageError {
+    fn from(e: std::io::Error) -> Self {
+        PackageError::Io(e.into())
+    }
+}
+
 /// Get current timestamp as a string.
 fn get_timestamp() -> String {
     SystemTime::now()
@@ -26,6 +113,9 @@ pub struct Package {
     pub source: String,
     pub installed_at: String,
     pub last_updated: String,
+    /// Commit checked out before the last upgrade, for `/packages rollback`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_commit: Option<String>,
 }
 
 /// Registry of installed packages.
@@ -38,20 +128,32 @@ pub struct PackageRegistry {
 impl PackageRegistry {
     /// Load the package registry from disk.
     pub fn load() -> Result<Self> {
-        let path = paths::packages_file();
+        Self::load_from(&paths::packages_file())
+    }
+
+    /// Load a package registry from `path` (empty if it doesn't exist).
+    pub fn load_from(path: &Path) -> Result<Self> {
         if !path.exists() {
             return Ok(Self::default());
         }
-        let content = fs::read_to_string(&path)?;
+        let content = fs::read_to_string(path)?;
         let registry: PackageRegistry = toml::from_str(&content)?;
         Ok(registry)
     }
 
     /// Save the package registry to disk.
     pub fn save(&self) -> Result<()> {
-        let path = paths::packages_file();
+        self.save_to(&paths::packages_file())
+    }
+
+    /// Save the package registry to `path`.
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let _lock = FileLock::acquire(path)?;
         let content = toml::to_string_pretty(self)?;
-        fs::write(&path, content)?;
+        fs_util::write_atomic(path, content)?;
         Ok(())
     }
 
@@ -74,6 +176,24 @@ impl PackageRegistry {
     pub fn contains(&self, name: &str) -> bool {
         self.packages.contains_key(name)
     }
+
+    /// Fail if any package name could point outside the packages dir.
+    pub fn check_names(&self) -> Result<()> {
+        for (key, pkg) in &self.packages {
+            if !is_valid_package_name(key) || !is_valid_package_name(&pkg.name) {
+                return Err(anyhow!("Invalid package name '{}'", pkg.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `name` is a single normal path component, safe to join onto the
+/// packages dir.
+pub fn is_valid_package_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(components.next(), Some(Component::Normal(c)) if c == name)
+        && components.next().is_none()
 }
 
 /// Parse an install source into (URL, package name).
@@ -132,51 +252,53 @@ fn extract_repo_name(url: &str) -> Result<String> {
 }
 
 /// Check if git is available.
-pub fn check_git_available() -> Result<()> {
-    let output = Command::new("git").arg("--version").output();
+pub fn check_git_available() -> Result<(), PackageError> {
+    git_available(GIT)
+}
 
-    match output {
+/// The git binary package commands run.
+const GIT: &str = "git";
+
+fn git_available(git: &str) -> Result<(), PackageError> {
+    match Command::new(git).arg("--version").output() {
         Ok(out) if out.status.success() => Ok(()),
-        _ => Err(anyhow!(
-            "Git is not installed. Please install git to use package management."
-        )),
+        _ => Err(PackageError::GitMissing),
     }
 }
 
 /// Install a package from a Git repository.
 ///
 /// Returns the package name on success.
-pub fn install_package(source: &str) -> Result<String> {
-    check_git_available()?;
+pub fn install_package(source: &str) -> Result<String, PackageError> {
+    install_package_with(GIT, &paths::packages_dir(), &paths::packages_file(), source)
+}
 
-    let (url, name) = parse_install_source(source)?;
+fn install_package_with(
+    git: &str,
+    packages_dir: &Path,
+    registry_file: &Path,
+    source: &str,
+) -> Result<String, PackageError> {
+    let (url, name) =
+        parse_install_source(source).map_err(|e| PackageError::InvalidSource(e.to_string()))?;
 
     // Check if already installed
-    let mut registry = PackageRegistry::load()?;
+    let mut registry = PackageRegistry::load_from(registry_file)?;
     if registry.contains(&name) {
-        return Err(anyhow!(
-            "Package '{}' is already installed. Use /upgrade to update it.",
-            name
-        ));
+        return Err(PackageError::AlreadyInstalled(name));
     }
 
-    // Create packages directory if needed
-    let packages_dir = paths::packages_dir();
-    fs::create_dir_all(&packages_dir)?;
-
-    // Clone the repository
-    let target_dir = packages_dir.join(&name);
-    let output = Command::new("git")
-        .args(["clone", "--depth", "1", &url])
-        .arg(&target_dir)
-        .output()?;
+    git_available(git)?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow!(
-            "Could not clone repository. Check the URL and your internet connection.\n{}",
-            stderr.trim()
-        ));
+    // Create packages directory if needed
+    fs::create_dir_all(packages_dir)?;
+
+    // Clone the repository, keeping it only if it looks like a package
+    let package_dir = packages_dir.join(&name);
+    clone_repository(git, &url, &package_dir)?;
+    if !is_nosh_package(&package_dir) {
+        let _ = fs::remove_dir_all(&package_dir);
+        return Err(PackageError::NotANoshPackage(name));
     }
 
     // Register the package
@@ -187,57 +309,176 @@ pub fn install_package(source: &str) -> Result<String> {
         source: url,
         installed_at: timestamp.clone(),
         last_updated: timestamp,
+        previous_commit: None,
     };
 
     registry.add(package);
-    registry.save()?;
+    registry.save_to(registry_file)?;
 
     Ok(name)
 }
 
-/// Upgrade a specific package.
+/// Whether a cloned repository has anything nosh loads.
+fn is_nosh_package(package_dir: &Path) -> bool {
+    ["themes", "plugins", "completions"]
+        .iter()
+        .any(|dir| package_dir.join(dir).is_dir())
+}
+
+/// Shallow-clone a package repository into `target_dir`.
+fn clone_repository(git: &str, url: &str, target_dir: &Path) -> Result<(), PackageError> {
+    let output = Command::new(git)
+        .args(["clone", "--depth", "1", url])
+        .arg(target_dir)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(PackageError::CloneFailed(stderr.trim().to_string()));
+    }
+    Ok(())
+}
+
+/// Clone registered packages whose directory is missing, e.g. after
+/// importing a config bundle on a new machine.
 ///
-/// Returns true if changes were pulled, false if already up to date.
-pub fn upgrade_package(name: &str) -> Result<bool> {
-    check_git_available()?;
+/// Returns the names of the packages that were cloned.
+pub fn install_missing() -> Result<Vec<String>> {
+    let registry = PackageRegistry::load()?;
+    let packages_dir = paths::packages_dir();
+    let missing: Vec<&Package> = registry
+        .packages
+        .values()
+        .filter(|pkg| {
+            if !is_valid_package_name(&pkg.name) {
+                eprintln!("Skipping package with invalid name '{}'", pkg.name);
+                return false;
+            }
+            !packages_dir.join(&pkg.name).exists()
+        })
+        .collect();
 
-    let mut registry = PackageRegistry::load()?;
-    if !registry.contains(name) {
-        return Err(anyhow!("Package '{}' is not installed.", name));
+    if missing.is_empty() {
+        return Ok(Vec::new());
     }
+    check_git_available()?;
+    fs::create_dir_all(&packages_dir)?;
 
-    let package_dir = paths::packages_dir().join(name);
-    if !package_dir.exists() {
-        return Err(anyhow!(
-            "Package directory not found. Try reinstalling with /install."
-        ));
+    let mut installed = Vec::new();
+    for pkg in missing {
+        match clone_repository(GIT, &pkg.source, &packages_dir.join(&pkg.name)) {
+            Ok(()) => installed.push(pkg.name.clone()),
+            Err(e) => eprintln!("Error installing '{}': {}", pkg.name, e),
+        }
     }
 
-    // Run git pull
-    let output = Command::new("git")
-        .args(["pull", "--ff-only"])
-        .current_dir(&package_dir)
-        .output()?;
+    Ok(installed)
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow!("Failed to update package: {}", stderr.trim()));
+/// Upgrade a specific package.
+///
+/// Returns true if changes were pulled, false if already up to date.
+pub fn upgrade_package(name: &str) -> Result<bool, PackageError> {
+    upgrade_package_with(GIT, &paths::packages_dir(), &paths::packages_file(), name)
+}
+
+fn upgrade_package_with(
+    git: &str,
+    packages_dir: &Path,
+    registry_file: &Path,
+    name: &str,
+) -> Result<bool, PackageError> {
+    git_available(git)?;
+
+    let mut registry = PackageRegistry::load_from(registry_file)?;
+    let package_dir = packages_dir.join(name);
+    if !registry.contains(name) || !package_dir.exists() {
+        return Err(PackageError::NotFound(name.to_string()));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let updated = !stdout.contains("Already up to date");
+    // Run git pull, remembering where we were
+    let before = head_commit(git, &package_dir);
+    let output = run_git(git, &package_dir, &["pull", "--ff-only"]).map_err(|e| match e {
+        // A failed pull is a fetch problem, reported like a failed clone
+        PackageError::GitFailed { stderr, .. } => PackageError::CloneFailed(stderr),
+        e => e,
+    })?;
+    let updated = !output.contains("Already up to date");
 
-    // Update timestamp in registry
+    // Update timestamp and previous commit in registry
     if updated {
         if let Some(pkg) = registry.packages.get_mut(name) {
             pkg.last_updated = get_timestamp();
+            if before.is_some() {
+                pkg.previous_commit = before;
+            }
         }
-        registry.save()?;
+        registry.save_to(registry_file)?;
     }
 
     Ok(updated)
 }
 
+/// Reset a package to the commit it had before its last upgrade.
+///
+/// Returns the commit that is now checked out.
+pub fn rollback_package(name: &str) -> Result<String, PackageError> {
+    rollback_package_with(GIT, &paths::packages_dir(), &paths::packages_file(), name)
+}
+
+fn rollback_package_with(
+    git: &str,
+    packages_dir: &Path,
+    registry_file: &Path,
+    name: &str,
+) -> Result<String, PackageError> {
+    let mut registry = PackageRegistry::load_from(registry_file)?;
+    let package_dir = packages_dir.join(name);
+    let Some(pkg) = registry.packages.get_mut(name) else {
+        return Err(PackageError::NotFound(name.to_string()));
+    };
+    if !package_dir.exists() {
+        return Err(PackageError::NotFound(name.to_string()));
+    }
+    let Some(commit) = pkg.previous_commit.clone() else {
+        return Err(PackageError::NoPreviousCommit(name.to_string()));
+    };
+
+    git_available(git)?;
+    run_git(git, &package_dir, &["reset", "--hard", &commit])?;
+
+    pkg.previous_commit = None;
+    pkg.last_updated = get_timestamp();
+    registry.save_to(registry_file)?;
+
+    Ok(commit)
+}
+
+/// The commit checked out in a package directory.
+fn head_commit(git: &str, package_dir: &Path) -> Option<String> {
+    run_git(git, package_dir, &["rev-parse", "HEAD"])
+        .ok()
+        .map(|out| out.trim().to_string())
+        .filter(|commit| !commit.is_empty())
+}
+
+/// Run git in a package directory, returning its stdout.
+fn run_git(git: &str, package_dir: &Path, args: &[&str]) -> Result<String, PackageError> {
+    let output = Command::new(git)
+        .args(args)
+        .current_dir(package_dir)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(PackageError::GitFailed {
+            args: args.join(" "),
+            stderr: stderr.trim().to_string(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
 /// Upgrade all installed packages.
 ///
 /// Returns a list of (package name, was_updated) tuples.
@@ -266,10 +507,10 @@ pub fn upgrade_all() -> Result<Vec<(String, bool)>> {
 }
 
 /// Remove a package.
-pub fn remove_package(name: &str) -> Result<()> {
+pub fn remove_package(name: &str) -> Result<(), PackageError> {
     let mut registry = PackageRegistry::load()?;
     if !registry.contains(name) {
-        return Err(anyhow!("Package '{}' is not installed.", name));
+        return Err(PackageError::NotFound(name.to_string()));
     }
 
     // Remove the directory
@@ -285,9 +526,49 @@ pub fn remove_package(name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Themes and plugins that package `name` shares with other installed
+/// packages, as ready-to-print warnings.
+pub fn find_conflicts(name: &str) -> Vec<String> {
+    find_conflicts_in(&paths::packages_dir(), name)
+}
+
+fn find_conflicts_in(packages_dir: &std::path::Path, name: &str) -> Vec<String> {
+    let (themes, plugins) = package_contents_in(&packages_dir.join(name));
+    let Ok(entries) = fs::read_dir(packages_dir) else {
+        return Vec::new();
+    };
+    let mut others: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().to_str().map(String::from))
+        .filter(|other| other != name)
+        .collect();
+    others.sort();
+
+    let mut warnings = Vec::new();
+    for other in others {
+        let (other_themes, other_plugins) = package_contents_in(&packages_dir.join(&other));
+        for (kind, mine, theirs) in [
+            ("plugin", &plugins, &other_plugins),
+            ("theme", &themes, &other_themes),
+        ] {
+            for item in mine.iter().filter(|item| theirs.contains(item)) {
+                warnings.push(format!(
+                    "package '{}' also provides {} '{}'; reference it as {}/{}",
+                    other, kind, item, name, item
+                ));
+            }
+        }
+    }
+    warnings
+}
+
 /// Get info about what a package contains (themes, plugins).
 pub fn get_package_contents(name: &str) -> (Vec<String>, Vec<String>) {
-    let package_dir = paths::packages_dir().join(name);
+    package_contents_in(&paths::packages_dir().join(name))
+}
+
+fn package_contents_in(package_dir: &std::path::Path) -> (Vec<String>, Vec<String>) {
     let mut themes = Vec::new();
     let mut plugins = Vec::new();
 
@@ -323,3 +604,128 @@ pub fn get_package_contents(name: &str) -> (Vec<String>, Vec<String>) {
 
     (themes, plugins)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A git binary that doesn't exist.
+    const NO_GIT: &str = "nosh-test-missing-git";
+
+    #[test]
+    fn test_is_valid_package_name() {
+        assert!(is_valid_package_name("theme-pack"));
+        assert!(is_valid_package_name(".dotfiles"));
+        for name in ["", ".", "..", "../x", "a/b", "/abs", "x/"] {
+            assert!(!is_valid_package_name(name), "{name:?} accepted");
+        }
+    }
+
+    #[test]
+    fn test_install_errors() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_pkg_errors_{}", std::process::id()));
+        let packages_dir = dir.join("packages");
+        let registry_file = dir.join("packages.toml");
+        let mut registry = PackageRegistry::default();
+        registry.add(Package {
+            name: "theme-pack".to_string(),
+            source: "https://github.com/user/theme-pack.git".to_string(),
+            installed_at: "1700000000".to_string(),
+            last_updated: "1700000000".to_string(),
+            previous_commit: None,
+        });
+        registry.save_to(&registry_file).unwrap();
+
+        let err = install_package_with(NO_GIT, &packages_dir, &registry_file, "user/theme-pack")
+            .unwrap_err();
+        assert!(matches!(err, PackageError::AlreadyInstalled(ref name) if name == "theme-pack"));
+        assert!(err.to_string().contains("already installed"));
+
+        let err =
+            install_package_with(NO_GIT, &packages_dir, &registry_file, "user/other").unwrap_err();
+        assert!(matches!(err, PackageError::GitMissing));
+        assert!(!packages_dir.join("other").exists());
+
+        let err =
+            upgrade_package_with(NO_GIT, &packages_dir, &registry_file, "theme-pack").unwrap_err();
+        assert!(matches!(err, PackageError::GitMissing));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_upgrade_records_rollback_target() {
+        if git_available(GIT).is_err() {
+            return;
+        }
+        let dir =
+            std::env::temp_dir().join(format!("nosh_test_pkg_rollback_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let origin = dir.join("origin");
+        let packages_dir = dir.join("packages");
+        let registry_file = dir.join("packages.toml");
+        fs::create_dir_all(origin.join("themes")).unwrap();
+        fs::create_dir_all(&packages_dir).unwrap();
+
+        let git = |cwd: &Path, args: &[&str]| {
+            let status = Command::new(GIT)
+                .args(["-c", "user.name=nosh", "-c", "user.email=nosh@example.com"])
+                .args(args)
+                .current_dir(cwd)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?}", args);
+        };
+        let commit = |message: &str| {
+            fs::write(origin.join("themes").join("pack.toml"), message).unwrap();
+            git(&origin, &["add", "-A"]);
+            git(&origin, &["commit", "-q", "-m", message]);
+            head_commit(GIT, &origin).unwrap()
+        };
+
+        git(&origin, &["init", "-q"]);
+        let first = commit("first");
+        git(&dir, &["clone", "-q", "origin", "packages/pack"]);
+        let mut registry = PackageRegistry::default();
+        registry.add(Package {
+            name: "pack".to_string(),
+            source: origin.display().to_string(),
+            installed_at: "1700000000".to_string(),
+            last_updated: "1700000000".to_string(),
+            previous_commit: None,
+        });
+        registry.save_to(&registry_file).unwrap();
+
+        // Nothing to roll back to before an upgrade
+        let err = rollback_package_with(GIT, &packages_dir, &registry_file, "pack").unwrap_err();
+        assert!(matches!(err, PackageError::NoPreviousCommit(_)));
+
+        let second = commit("second");
+        assert!(upgrade_package_with(GIT, &packages_dir, &registry_file, "pack").unwrap());
+        let registry = PackageRegistry::load_from(&registry_file).unwrap();
+        assert_eq!(
+            registry.packages["pack"].previous_commit,
+            Some(first.clone())
+        );
+        assert_eq!(head_commit(GIT, &packages_dir.join("pack")), Some(second));
+
+        let target = rollback_package_with(GIT, &packages_dir, &registry_file, "pack").unwrap();
+        assert_eq!(target, first);
+        assert_eq!(head_commit(GIT, &packages_dir.join("pack")), Some(first));
+        let mut registry = PackageRegistry::load_from(&registry_file).unwrap();
+        assert_eq!(registry.packages["pack"].previous_commit, None);
+
+        // A failed local reset isn't reported as a network problem
+        registry.packages.get_mut("pack").unwrap().previous_commit =
+            Some("0000000000000000000000000000000000000000".to_string());
+        registry.save_to(&registry_file).unwrap();
+        let err = rollback_package_with(GIT, &packages_dir, &registry_file, "pack").unwrap_err();
+        assert!(
+            matches!(err, PackageError::GitFailed { ref args, .. } if args.starts_with("reset"))
+        );
+        assert!(!err.to_string().contains("internet"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,17 +1,51 @@
 //! Configuration path resolution for nosh.
 //!
-//! Prefers `~/.config/nosh/` with `~/.nosh/` fallback (all OSes).
+//! `$NOSH_CONFIG_DIR` overrides everything. Otherwise prefers
+//! `$XDG_CONFIG_HOME/nosh/` (default `~/.config/nosh/`) with `~/.nosh/`
+//! fallback (all OSes). Every path nosh reads or writes is derived from
+//! [`nosh_config_dir`], so the override applies to all of them.
 
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that overrides the config directory.
+pub const CONFIG_DIR_ENV: &str = "NOSH_CONFIG_DIR";
 
 /// Returns the nosh configuration directory.
 ///
-/// Prefers `~/.config/nosh/` if it exists or if `~/.nosh/` doesn't exist.
-/// Falls back to `~/.nosh/` if it exists and `~/.config/nosh/` doesn't.
+/// Uses `$NOSH_CONFIG_DIR` if set. Otherwise prefers `$XDG_CONFIG_HOME/nosh/`
+/// (or `~/.config/nosh/`) if it exists or if `~/.nosh/` doesn't exist, and
+/// falls back to `~/.nosh/` if it exists and the primary doesn't.
 pub fn nosh_config_dir() -> PathBuf {
+    config_dir_from(std::env::var_os(CONFIG_DIR_ENV))
+}
+
+/// Resolve the config directory given the value of `$NOSH_CONFIG_DIR`.
+fn config_dir_from(env_value: Option<OsString>) -> PathBuf {
     let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    resolve_config_dir(
+        env_value.map(PathBuf::from),
+        std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from),
+        &home,
+    )
+}
+
+/// Resolve the config directory from the override, XDG base and home directory.
+fn resolve_config_dir(
+    override_dir: Option<PathBuf>,
+    xdg_config_home: Option<PathBuf>,
+    home: &Path,
+) -> PathBuf {
+    if let Some(dir) = override_dir.filter(|d| !d.as_os_str().is_empty()) {
+        return dir;
+    }
+
+    // XDG says relative paths are invalid and should be ignored
+    let config_home = xdg_config_home
+        .filter(|d| d.is_absolute())
+        .unwrap_or_else(|| home.join(".config"));
 
-    let primary = home.join(".config").join("nosh");
+    let primary = config_home.join("nosh");
     let fallback = home.join(".nosh");
 
     if primary.exists() || !fallback.exists() {
@@ -39,6 +73,12 @@ pub fn history_db() -> PathBuf {
     nosh_config_dir().join("history.db")
 }
 
+/// Returns the path to the AI translation cache database.
+/// `~/.config/nosh/ai_cache.db`
+pub fn ai_cache_db() -> PathBuf {
+    nosh_config_dir().join("ai_cache.db")
+}
+
 /// Returns the path to the legacy history file (for migration).
 /// `~/.config/nosh/history`
 #[allow(dead_code)]
@@ -76,8 +116,97 @@ pub fn packages_dir() -> PathBuf {
     nosh_config_dir().join("packages")
 }
 
+/// Returns the directory for completion scripts generated for other shells.
+/// `~/.config/nosh/shell-completions/`
+pub fn shell_completions_dir() -> PathBuf {
+    nosh_config_dir().join("shell-completions")
+}
+
+/// Returns the directory where agentic sessions are saved for replay.
+/// `~/.config/nosh/sessions/`
+pub fn sessions_dir() -> PathBuf {
+    nosh_config_dir().join("sessions")
+}
+
+/// Returns the directory for completer results cached by command version.
+/// `~/.config/nosh/completion-cache/`
+pub fn completion_cache_dir() -> PathBuf {
+    nosh_config_dir().join("completion-cache")
+}
+
+/// Returns the private scratch directory for temp files.
+/// `~/.config/nosh/tmp/`
+pub fn tmp_dir() -> PathBuf {
+    nosh_config_dir().join("tmp")
+}
+
 /// Returns the path to the packages registry file.
 /// `~/.config/nosh/packages.toml`
 pub fn packages_file() -> PathBuf {
     nosh_config_dir().join("packages.toml")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_config_dir() {
+        let home = std::env::temp_dir().join(format!("nosh_test_paths_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&home);
+
+        // Override wins over everything
+        let custom = home.join("work-profile");
+        assert_eq!(
+            resolve_config_dir(Some(custom.clone()), Some(home.join("xdg")), &home),
+            custom
+        );
+
+        // XDG base, ignoring relative and empty values
+        assert_eq!(
+            resolve_config_dir(Some(PathBuf::new()), Some(home.join("xdg")), &home),
+            home.join("xdg").join("nosh")
+        );
+        assert_eq!(
+            resolve_config_dir(None, Some(PathBuf::from("relative")), &home),
+            home.join(".config").join("nosh")
+        );
+
+        // Legacy ~/.nosh is used only when it exists and the primary doesn't
+        std::fs::create_dir_all(home.join(".nosh")).unwrap();
+        assert_eq!(resolve_config_dir(None, None, &home), home.join(".nosh"));
+        std::fs::create_dir_all(home.join(".config").join("nosh")).unwrap();
+        assert_eq!(
+            resolve_config_dir(None, None, &home),
+            home.join(".config").join("nosh")
+        );
+
+        let _ = std::fs::remove_dir_all(&home);
+    }
+
+    #[test]
+    fn test_env_override_applies_to_derived_paths() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_config_dir_{}", std::process::id()));
+        assert_eq!(config_dir_from(Some(dir.clone().into_os_string())), dir);
+        assert_ne!(config_dir_from(Some(OsString::new())), PathBuf::new());
+
+        let dir = nosh_config_dir();
+        for path in [
+            config_file(),
+            credentials_file(),
+            history_db(),
+            permissions_file(),
+            plugins_dir(),
+            themes_dir(),
+            init_file(),
+            packages_dir(),
+            packages_file(),
+        ] {
+            assert!(
+                path.starts_with(&dir),
+                "{} not under override",
+                path.display()
+            );
+        }
+    }
+}
@@ -13,6 +13,9 @@ pub const GIT_PLUGIN: &str = include_str!("data/git.toml");
 pub const EXEC_TIME_PLUGIN: &str = include_str!("data/exec_time.toml");
 pub const CONTEXT_PLUGIN: &str = include_str!("data/context.toml");
 pub const DEFAULT_THEME: &str = include_str!("data/default_theme.toml");
+pub const MINIMAL_THEME: &str = include_str!("data/minimal_theme.toml");
+pub const POWERLINE_THEME: &str = include_str!("data/powerline_theme.toml");
+pub const EMOJI_THEME: &str = include_str!("data/emoji_theme.toml");
 pub const INIT_SCRIPT: &str = include_str!("data/init.sh");
 
 /// Embedded completion files.
@@ -21,9 +24,39 @@ pub const CARGO_COMPLETION: &str = include_str!("../completions/data/cargo.toml"
 pub const NPM_COMPLETION: &str = include_str!("../completions/data/npm.toml");
 pub const DOCKER_COMPLETION: &str = include_str!("../completions/data/docker.toml");
 
+/// Built-in themes offered during onboarding: (name, description, content).
+/// Installed as `builtins/<name>`.
+pub const GALLERY_THEMES: &[(&str, &str, &str)] = &[
+    (
+        "default",
+        "Default - directory, git and tool versions",
+        DEFAULT_THEME,
+    ),
+    (
+        "minimal",
+        "Minimal - directory and branch only",
+        MINIMAL_THEME,
+    ),
+    (
+        "powerline",
+        "Powerline - arrow separators (needs a Nerd Font)",
+        POWERLINE_THEME,
+    ),
+    ("emoji", "Emoji - an icon for everything", EMOJI_THEME),
+];
+
 /// Install built-in plugins to the packages/builtins directory.
 pub fn install_builtins() -> Result<()> {
-    let builtins_dir = paths::packages_dir().join("builtins");
+    install_builtins_to(&paths::packages_dir().join("builtins"))?;
+
+    // Install init script
+    install_if_missing(&paths::init_file(), INIT_SCRIPT)?;
+
+    Ok(())
+}
+
+/// Install built-in plugins, themes and completions into `builtins_dir`.
+fn install_builtins_to(builtins_dir: &Path) -> Result<()> {
     let builtins_plugins = builtins_dir.join("plugins");
     let builtins_themes = builtins_dir.join("themes");
     let builtins_completions = builtins_dir.join("completions");
@@ -38,11 +71,10 @@ pub fn install_builtins() -> Result<()> {
     install_if_missing(&builtins_plugins.join("exec_time.toml"), EXEC_TIME_PLUGIN)?;
     install_if_missing(&builtins_plugins.join("context.toml"), CONTEXT_PLUGIN)?;
 
-    // Install default theme
-    install_if_missing(&builtins_themes.join("default.toml"), DEFAULT_THEME)?;
-
-    // Install init script
-    install_if_missing(&paths::init_file(), INIT_SCRIPT)?;
+    // Install the default theme and the rest of the gallery
+    for (name, _, content) in GALLERY_THEMES {
+        install_if_missing(&builtins_themes.join(format!("{}.toml", name)), content)?;
+    }
 
     // Install completions
     install_if_missing(&builtins_completions.join("git.toml"), GIT_COMPLETION)?;
@@ -65,6 +97,9 @@ fn install_if_missing(path: &Path, content: &str) -> Result<()> {
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ConfigFile {
     Theme,
+    MinimalTheme,
+    PowerlineTheme,
+    EmojiTheme,
     GitPlugin,
     ExecTimePlugin,
     ContextPlugin,
@@ -80,6 +115,9 @@ impl ConfigFile {
         let builtins_dir = paths::packages_dir().join("builtins");
         match self {
             ConfigFile::Theme => builtins_dir.join("themes").join("default.toml"),
+            ConfigFile::MinimalTheme => builtins_dir.join("themes").join("minimal.toml"),
+            ConfigFile::PowerlineTheme => builtins_dir.join("themes").join("powerline.toml"),
+            ConfigFile::EmojiTheme => builtins_dir.join("themes").join("emoji.toml"),
             ConfigFile::GitPlugin => builtins_dir.join("plugins").join("git.toml"),
             ConfigFile::ExecTimePlugin => builtins_dir.join("plugins").join("exec_time.toml"),
             ConfigFile::ContextPlugin => builtins_dir.join("plugins").join("context.toml"),
@@ -94,6 +132,9 @@ impl ConfigFile {
     pub fn content(&self) -> &'static str {
         match self {
             ConfigFile::Theme => DEFAULT_THEME,
+            ConfigFile::MinimalTheme => MINIMAL_THEME,
+            ConfigFile::PowerlineTheme => POWERLINE_THEME,
+            ConfigFile::EmojiTheme => EMOJI_THEME,
             ConfigFile::GitPlugin => GIT_PLUGIN,
             ConfigFile::ExecTimePlugin => EXEC_TIME_PLUGIN,
             ConfigFile::ContextPlugin => CONTEXT_PLUGIN,
@@ -108,6 +149,9 @@ impl ConfigFile {
     pub fn display_name(&self) -> &'static str {
         match self {
             ConfigFile::Theme => "Default theme",
+            ConfigFile::MinimalTheme => "Minimal theme",
+            ConfigFile::PowerlineTheme => "Powerline theme",
+            ConfigFile::EmojiTheme => "Emoji theme",
             ConfigFile::GitPlugin => "Git plugin",
             ConfigFile::ExecTimePlugin => "Exec time plugin",
             ConfigFile::ContextPlugin => "Context plugin",
@@ -146,6 +190,9 @@ pub fn config_needs_update(file: ConfigFile) -> bool {
 pub fn upgrade_builtins() -> Vec<(&'static str, bool)> {
     let builtins = [
         ConfigFile::Theme,
+        ConfigFile::MinimalTheme,
+        ConfigFile::PowerlineTheme,
+        ConfigFile::EmojiTheme,
         ConfigFile::GitPlugin,
         ConfigFile::ExecTimePlugin,
         ConfigFile::ContextPlugin,
@@ -207,12 +254,33 @@ mod tests {
         assert_eq!(plugin.plugin.name, "exec_time");
     }
 
+    #[test]
+    fn test_git_completion_valid_toml() {
+        let file: Result<crate::completions::CompletionFile, _> = toml::from_str(GIT_COMPLETION);
+        assert!(file.is_ok(), "git.toml completion should be valid TOML");
+    }
+
     #[test]
     fn test_default_theme_valid_toml() {
         let theme: Result<crate::plugins::theme::Theme, _> = toml::from_str(DEFAULT_THEME);
         assert!(theme.is_ok(), "default_theme.toml should be valid TOML");
     }
 
+    #[test]
+    fn test_gallery_themes_installed() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_builtins_{}", std::process::id()));
+        install_builtins_to(&dir).unwrap();
+
+        for name in ["default", "minimal", "powerline", "emoji"] {
+            let path = dir.join("themes").join(format!("{}.toml", name));
+            let content = fs::read_to_string(&path).unwrap();
+            let theme: Result<crate::plugins::theme::Theme, _> = toml::from_str(&content);
+            assert!(theme.is_ok(), "{}.toml should be a valid theme", name);
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_init_script_content() {
         // Init script should source ~/.bashrc
@@ -0,0 +1,180 @@
+//! Validate every installed theme and plugin (`nosh check`, `/debug all`).
+//!
+//! Normal loading skips files that fail to parse; this reports them with
+//! their paths, and runs the format lint on each theme.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::loader::{PluginManager, load_plugin_file};
+use super::theme::Theme;
+use crate::paths;
+
+/// How serious a problem is. Only errors fail `nosh check`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The file doesn't load.
+    Error,
+    /// The file loads, but the theme lint found something.
+    Warning,
+}
+
+/// A problem with one theme or plugin file.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub path: PathBuf,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}: {}", self.path.display(), label, self.message)
+    }
+}
+
+/// Result of checking a config directory.
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    /// Number of files checked.
+    pub checked: usize,
+    pub problems: Vec<Problem>,
+}
+
+impl CheckReport {
+    /// Whether any file failed to load.
+    pub fn has_errors(&self) -> bool {
+        self.problems.iter().any(|p| p.severity == Severity::Error)
+    }
+
+    fn push(&mut self, path: &Path, severity: Severity, message: String) {
+        self.problems.push(Problem {
+            path: path.to_path_buf(),
+            severity,
+            message,
+        });
+    }
+}
+
+/// Check the themes and plugins in the nosh config directory.
+pub fn check_installed() -> CheckReport {
+    let mut plugin_manager = PluginManager::new();
+    let _ = plugin_manager.load_plugins();
+    check_config_dir(&paths::nosh_config_dir(), &plugin_manager)
+}
+
+/// Check every plugin (`plugins/community/`, `packages/*/plugins/`) and
+/// theme (`themes/`, `packages/*/themes/`) under `config_dir`. Theme
+/// formats are linted against `plugin_manager`.
+pub fn check_config_dir(config_dir: &Path, plugin_manager: &PluginManager) -> CheckReport {
+    let packages = package_dirs(&config_dir.join("packages"));
+    let mut report = CheckReport::default();
+
+    let plugin_dirs = std::iter::once(config_dir.join("plugins").join("community"))
+        .chain(packages.iter().map(|p| p.join("plugins")));
+    for path in plugin_dirs.flat_map(|dir| toml_files(&dir)) {
+        report.checked += 1;
+        if let Err(e) = load_plugin_file(&path) {
+            report.push(&path, Severity::Error, format!("{:#}", e));
+        }
+    }
+
+    let theme_dirs =
+        std::iter::once(config_dir.join("themes")).chain(packages.iter().map(|p| p.join("themes")));
+    for path in theme_dirs.flat_map(|dir| toml_files(&dir)) {
+        report.checked += 1;
+        match Theme::load_file(&path) {
+            Ok(theme) => {
+                for warning in theme.lint(plugin_manager) {
+                    report.push(&path, Severity::Warning, warning.to_string());
+                }
+            }
+            Err(e) => report.push(&path, Severity::Error, format!("{:#}", e)),
+        }
+    }
+
+    report
+}
+
+/// Package directories, sorted.
+fn package_dirs(packages_dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(packages_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// `.toml` files directly in `dir`, sorted.
+fn toml_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_malformed_plugin() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_check_{}", std::process::id()));
+        let plugins = dir.join("packages").join("mypkg").join("plugins");
+        let themes = dir.join("themes");
+        fs::create_dir_all(&plugins).unwrap();
+        fs::create_dir_all(&themes).unwrap();
+
+        fs::write(
+            plugins.join("good.toml"),
+            "[plugin]\nname = \"good\"\n\n[provides]\nhello = { command = \"echo hi\" }\n",
+        )
+        .unwrap();
+        fs::write(
+            plugins.join("broken.toml"),
+            "[plugin]\nname = \"broken\"\n\n[provides\nhello = 1\n",
+        )
+        .unwrap();
+        fs::write(
+            themes.join("mine.toml"),
+            "[prompt]\nformat = \"[{dir}](blue $ \"\n",
+        )
+        .unwrap();
+
+        let report = check_config_dir(&dir, &PluginManager::new());
+        assert_eq!(report.checked, 3);
+        assert!(report.has_errors());
+
+        let errors: Vec<&Problem> = report
+            .problems
+            .iter()
+            .filter(|p| p.severity == Severity::Error)
+            .collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, plugins.join("broken.toml"));
+
+        // The theme parses, but its format is linted
+        assert!(
+            report
+                .problems
+                .iter()
+                .any(|p| p.severity == Severity::Warning && p.path == themes.join("mine.toml"))
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,127 @@
+//! Debounced plugin variables.
+//!
+//! A variable with `debounce = "30s"` re-runs its command at most once per
+//! interval, and only when files under the current directory have changed
+//! since the last run. Expensive checks like `cargo check` can then back a
+//! prompt segment without running on every prompt.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Most entries looked at when computing a directory signature.
+const MAX_ENTRIES: usize = 5000;
+
+/// Directories skipped when computing a signature (build output, deps).
+const SKIP_DIRS: &[&str] = &["target", "node_modules", "__pycache__"];
+
+/// Cheap fingerprint of the files under a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirSignature {
+    dir: PathBuf,
+    /// Newest modification time seen
+    newest: Option<SystemTime>,
+    /// Number of entries seen (catches deletes)
+    entries: usize,
+}
+
+impl DirSignature {
+    /// Walk `dir`, skipping hidden and build directories.
+    pub fn scan(dir: &Path) -> Self {
+        let mut signature = DirSignature {
+            dir: dir.to_path_buf(),
+            newest: None,
+            entries: 0,
+        };
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            let Ok(read) = fs::read_dir(&current) else {
+                continue;
+            };
+            for entry in read.flatten() {
+                if signature.entries >= MAX_ENTRIES {
+                    return signature;
+                }
+                let Ok(meta) = entry.metadata() else {
+                    continue;
+                };
+                if meta.is_dir() {
+                    let name = entry.file_name();
+                    let name = name.to_string_lossy();
+                    if name.starts_with('.') || SKIP_DIRS.contains(&name.as_ref()) {
+                        continue;
+                    }
+                    pending.push(entry.path());
+                }
+                signature.entries += 1;
+                if let Ok(modified) = meta.modified() {
+                    signature.newest = signature.newest.max(Some(modified));
+                }
+            }
+        }
+        signature
+    }
+}
+
+/// When a debounced variable last ran, and what the directory looked like.
+#[derive(Debug, Clone)]
+pub struct DebounceState {
+    checked_at: Instant,
+    signature: DirSignature,
+}
+
+impl DebounceState {
+    /// State for a run starting now in `dir`.
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            checked_at: Instant::now(),
+            signature: DirSignature::scan(dir),
+        }
+    }
+
+    /// Whether the variable should run again in `dir`, updating the state
+    /// when it should. Moving to another directory always re-runs; otherwise
+    /// the interval must have passed and the files must have changed. An
+    /// unchanged check restarts the interval so the directory isn't
+    /// rescanned on every prompt.
+    pub fn should_run(&mut self, interval: Duration, dir: &Path) -> bool {
+        if self.signature.dir == dir && self.checked_at.elapsed() < interval {
+            return false;
+        }
+        self.checked_at = Instant::now();
+        let signature = DirSignature::scan(dir);
+        if signature == self.signature {
+            return false;
+        }
+        self.signature = signature;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_tracks_changes() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_debounce_{}", std::process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("target")).unwrap();
+        fs::write(dir.join("src/main.rs"), "fn main() {}").unwrap();
+
+        let mut state = DebounceState::new(&dir);
+        assert!(!state.should_run(Duration::ZERO, &dir));
+
+        // Build output doesn't count as a change
+        fs::write(dir.join("target/out"), "bin").unwrap();
+        assert!(!state.should_run(Duration::ZERO, &dir));
+
+        fs::write(dir.join("src/lib.rs"), "").unwrap();
+        assert!(!state.should_run(Duration::from_secs(3600), &dir));
+        assert!(state.should_run(Duration::ZERO, &dir));
+        assert!(!state.should_run(Duration::ZERO, &dir));
+        assert!(state.should_run(Duration::from_secs(3600), &dir.join("src")));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
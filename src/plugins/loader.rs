@@ -4,16 +4,19 @@
 //! Supports async parallel execution with soft/hard timeouts.
 
 use anyhow::Result;
-use nosh_context::ContextCache;
+use nosh_context::{ContextCache, ProjectContext};
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Semaphore};
 use tokio::task::JoinHandle;
 use tokio::time::Instant;
 
+use super::debounce::DebounceState;
+use super::theme::SummaryConfig;
+use super::transform::{self, Pipeline};
 use super::{CacheDuration, Plugin, VariableProvider, parse_duration};
 use crate::paths;
 
@@ -23,6 +26,9 @@ const SOFT_TIMEOUT: Duration = Duration::from_millis(100);
 /// Hard timeout - kill task after this duration.
 const HARD_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// Default number of plugin commands allowed to run at once.
+pub const DEFAULT_MAX_CONCURRENT: usize = 6;
+
 /// Default cache duration for variable values.
 const CACHE_DURATION: Duration = Duration::from_millis(500);
 
@@ -40,13 +46,90 @@ struct RunningTask {
     started_at: Instant,
 }
 
+/// How a variable's value was resolved during a prompt render.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimingSource {
+    /// Computed synchronously (context and internal providers).
+    Internal,
+    /// Served from the value cache without running anything.
+    Cached,
+    /// Task completed (spawn to resolve).
+    Completed,
+    /// Task was still running when the prompt was rendered.
+    Pending,
+}
+
+impl TimingSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TimingSource::Internal => "internal",
+            TimingSource::Cached => "cached",
+            TimingSource::Completed => "completed",
+            TimingSource::Pending => "pending",
+        }
+    }
+}
+
+/// Timing of a single variable during a prompt render.
+#[derive(Debug, Clone, Copy)]
+pub struct VariableTiming {
+    pub duration: Duration,
+    pub source: TimingSource,
+}
+
+/// Per-variable timings collected while building a prompt.
+#[derive(Debug, Clone, Default)]
+pub struct VariableTimings {
+    entries: HashMap<String, VariableTiming>,
+}
+
+impl VariableTimings {
+    /// Record the timing for a variable, replacing any previous entry.
+    pub fn record(&mut self, key: &str, duration: Duration, source: TimingSource) {
+        self.entries
+            .insert(key.to_string(), VariableTiming { duration, source });
+    }
+
+    /// Get timings sorted by duration, slowest first.
+    pub fn sorted(&self) -> Vec<(&str, VariableTiming)> {
+        let mut entries: Vec<(&str, VariableTiming)> = self
+            .entries
+            .iter()
+            .map(|(key, timing)| (key.as_str(), *timing))
+            .collect();
+        entries.sort_by(|a, b| b.1.duration.cmp(&a.1.duration).then(a.0.cmp(b.0)));
+        entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
 /// Plugin manager that loads and executes plugins.
 pub struct PluginManager {
     plugins: HashMap<String, Plugin>,
     cache: Arc<Mutex<HashMap<String, CacheEntry>>>,
     running_tasks: Arc<Mutex<HashMap<String, RunningTask>>>,
+    /// How long each spawned task took from spawn to completion
+    task_durations: Arc<Mutex<HashMap<String, Duration>>>,
     last_command_duration: Option<Duration>,
     context_cache: ContextCache,
+    /// Whether to collect per-variable timings in `get_variables`
+    profiling: bool,
+    timings: VariableTimings,
+    /// Limits how many variable tasks run their provider at once
+    task_slots: Arc<Semaphore>,
+    /// Upper bound on how long `get_variables` waits for any variable
+    timeout_cap: Option<Duration>,
+    /// Last run of each `debounce` variable
+    debounced: HashMap<String, DebounceState>,
+    /// The theme's `[icons]` overrides for `*_icon` context variables
+    icons: HashMap<String, String>,
 }
 
 impl PluginManager {
@@ -56,11 +139,48 @@ impl PluginManager {
             plugins: HashMap::new(),
             cache: Arc::new(Mutex::new(HashMap::new())),
             running_tasks: Arc::new(Mutex::new(HashMap::new())),
+            task_durations: Arc::new(Mutex::new(HashMap::new())),
             last_command_duration: None,
             context_cache: ContextCache::new(),
+            profiling: false,
+            timings: VariableTimings::default(),
+            task_slots: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT)),
+            timeout_cap: None,
+            debounced: HashMap::new(),
+            icons: HashMap::new(),
         }
     }
 
+    /// Use the theme's `[icons]` in place of the built-in context icons.
+    pub fn set_icons(&mut self, icons: HashMap<String, String>) {
+        self.icons = icons;
+    }
+
+    /// Never wait longer than `cap` for a variable, whatever its plugin's
+    /// `timeout` says. Used by one-shot renders that can't show late values.
+    pub fn set_timeout_cap(&mut self, cap: Duration) {
+        self.timeout_cap = Some(cap);
+    }
+
+    /// Forget cached variable values and project context so the next
+    /// prompt recomputes everything (`/refresh`).
+    pub async fn clear_caches(&mut self) {
+        self.cache.lock().await.clear();
+        self.context_cache.invalidate();
+        self.debounced.clear();
+    }
+
+    /// Keep the project context of up to `size` directories (at least one).
+    pub fn set_context_cache_size(&mut self, size: usize) {
+        self.context_cache = ContextCache::with_capacity(size);
+    }
+
+    /// Limit how many plugin commands run at the same time (at least one).
+    /// Tasks over the limit wait for a slot, still bounded by the prompt deadline.
+    pub fn set_max_concurrent(&mut self, limit: usize) {
+        self.task_slots = Arc::new(Semaphore::new(limit.max(1)));
+    }
+
     /// Load all plugins from plugins directory and packages.
     pub fn load_plugins(&mut self) -> Result<()> {
         // Load from community subdirectory (user's local plugins from /create)
@@ -70,9 +190,42 @@ impl PluginManager {
         }
 
         // Load from packages (includes builtins and git-installed packages)
-        let packages_dir = paths::packages_dir();
+        self.load_packages(&paths::packages_dir())?;
+        Ok(())
+    }
+
+    /// Start `prefetch = true` variables of enabled plugins in the background
+    /// so their values are cached before the first prompt. Does nothing
+    /// outside a tokio runtime.
+    pub fn prefetch(&self, is_enabled: impl Fn(&str) -> bool) {
+        if tokio::runtime::Handle::try_current().is_err() {
+            return;
+        }
+        let Ok(mut running) = self.running_tasks.try_lock() else {
+            return;
+        };
+
+        for (plugin_name, plugin) in &self.plugins {
+            if !is_enabled(plugin_name) {
+                continue;
+            }
+            for (var_name, provider) in &plugin.provides {
+                if let VariableProvider::Command { prefetch: true, .. }
+                | VariableProvider::Script { prefetch: true, .. } = provider
+                {
+                    let key = format!("{}:{}", plugin_name, var_name);
+                    if let Some(task) = self.start_variable_task(&key) {
+                        running.insert(key, task);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Load the plugins of every package under `packages_dir`.
+    fn load_packages(&mut self, packages_dir: &Path) -> Result<()> {
         if packages_dir.exists()
-            && let Ok(entries) = fs::read_dir(&packages_dir)
+            && let Ok(entries) = fs::read_dir(packages_dir)
         {
             for entry in entries.flatten() {
                 let package_path = entry.path();
@@ -120,11 +273,9 @@ impl PluginManager {
         Ok(())
     }
 
-    /// Load a single plugin from a TOML file.
+    /// Load a single plugin from a TOML file, resolving `extends`.
     fn load_plugin(&self, path: &Path) -> Result<Plugin> {
-        let content = fs::read_to_string(path)?;
-        let plugin: Plugin = toml::from_str(&content)?;
-        Ok(plugin)
+        load_plugin_file(path)
     }
 
     /// Set the duration of the last executed command.
@@ -132,12 +283,27 @@ impl PluginManager {
         self.last_command_duration = Some(duration);
     }
 
+    /// Enable or disable per-variable timing collection.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiling = enabled;
+        self.timings.clear();
+    }
+
+    /// Timings collected during the last `get_variables` call (when profiling).
+    pub fn timings(&self) -> &VariableTimings {
+        &self.timings
+    }
+
     /// Get all variables needed for prompt, with parallel execution and per-variable timeout.
     /// Returns a map of variable key -> value.
     pub async fn get_variables(&mut self, keys: Vec<String>) -> HashMap<String, String> {
         // First, clean up any stale tasks
         self.cleanup_stale_tasks().await;
 
+        if self.profiling {
+            self.timings.clear();
+        }
+
         let mut results = HashMap::new();
         let mut tasks_to_spawn: Vec<(String, Duration)> = Vec::new(); // (key, timeout)
         let mut internal_keys: Vec<String> = Vec::new();
@@ -161,6 +327,36 @@ impl PluginManager {
                     if let Some(entry) = cache.get(key) {
                         results.insert(key.clone(), entry.value.clone());
                     }
+                    if self.profiling
+                        && let Some(task) = running.get(key)
+                    {
+                        let elapsed = task.started_at.elapsed();
+                        self.timings.record(key, elapsed, TimingSource::Pending);
+                    }
+                    continue;
+                }
+
+                // Debounced variables keep their last value until they're due
+                if let Some(interval) = self.get_variable_debounce(key) {
+                    let dir = std::env::current_dir().unwrap_or_default();
+                    let due = match self.debounced.get_mut(key) {
+                        Some(state) => state.should_run(interval, &dir),
+                        None => {
+                            self.debounced.insert(key.clone(), DebounceState::new(&dir));
+                            true
+                        }
+                    };
+                    if !due {
+                        if let Some(entry) = cache.get(key) {
+                            results.insert(key.clone(), entry.value.clone());
+                        }
+                        if self.profiling {
+                            self.timings
+                                .record(key, Duration::ZERO, TimingSource::Cached);
+                        }
+                        continue;
+                    }
+                    tasks_to_spawn.push((key.clone(), self.get_variable_timeout(key)));
                     continue;
                 }
 
@@ -172,6 +368,10 @@ impl PluginManager {
                     };
                     if is_valid {
                         results.insert(key.clone(), entry.value.clone());
+                        if self.profiling {
+                            self.timings
+                                .record(key, Duration::ZERO, TimingSource::Cached);
+                        }
                         continue;
                     }
                 }
@@ -184,12 +384,19 @@ impl PluginManager {
 
         // Process internal variables (needs &mut self, done outside locks)
         for key in internal_keys {
-            if let Some(value) = self.get_internal_variable(&key) {
+            let started_at = Instant::now();
+            let value = self.get_internal_variable(&key);
+            if self.profiling {
+                self.timings
+                    .record(&key, started_at.elapsed(), TimingSource::Internal);
+            }
+            if let Some(value) = value {
                 results.insert(key, value);
             }
         }
 
         // Phase 2: Spawn tasks for variables that need fetching
+        let spawned_at = Instant::now();
         for (key, _) in &tasks_to_spawn {
             self.spawn_variable_task(key.clone()).await;
         }
@@ -231,13 +438,34 @@ impl PluginManager {
                     }
                 }
             }
+
+            if self.profiling {
+                let durations = self.task_durations.lock().await;
+                for (key, _) in &tasks_to_spawn {
+                    match durations.get(key) {
+                        Some(duration) => {
+                            self.timings.record(key, *duration, TimingSource::Completed)
+                        }
+                        None => {
+                            self.timings
+                                .record(key, spawned_at.elapsed(), TimingSource::Pending)
+                        }
+                    }
+                }
+            }
         }
 
         results
     }
 
-    /// Get the timeout duration for a variable.
+    /// Get the timeout duration for a variable, bounded by the timeout cap.
     fn get_variable_timeout(&self, key: &str) -> Duration {
+        let timeout = self.configured_timeout(key);
+        self.timeout_cap.map_or(timeout, |cap| timeout.min(cap))
+    }
+
+    /// The timeout a variable's plugin asks for.
+    fn configured_timeout(&self, key: &str) -> Duration {
         let parts: Vec<&str> = key.split(':').collect();
         if parts.len() != 2 {
             return SOFT_TIMEOUT;
@@ -247,7 +475,10 @@ impl PluginManager {
         let var_name = parts[1];
 
         if let Some(plugin) = self.plugins.get(plugin_name)
-            && let Some(VariableProvider::Command { timeout, .. }) = plugin.provides.get(var_name)
+            && let Some(
+                VariableProvider::Command { timeout, .. }
+                | VariableProvider::Script { timeout, .. },
+            ) = plugin.provides.get(var_name)
             && let Some(timeout_str) = timeout
         {
             return parse_duration(timeout_str).unwrap_or(SOFT_TIMEOUT);
@@ -267,7 +498,9 @@ impl PluginManager {
         let var_name = parts[1];
 
         if let Some(plugin) = self.plugins.get(plugin_name)
-            && let Some(VariableProvider::Command { cache, .. }) = plugin.provides.get(var_name)
+            && let Some(
+                VariableProvider::Command { cache, .. } | VariableProvider::Script { cache, .. },
+            ) = plugin.provides.get(var_name)
             && let Some(cache_str) = cache
         {
             return CacheDuration::parse(cache_str)
@@ -277,6 +510,22 @@ impl PluginManager {
         CacheDuration::Duration(CACHE_DURATION)
     }
 
+    /// Get the debounce interval for a variable, if it has one.
+    fn get_variable_debounce(&self, key: &str) -> Option<Duration> {
+        let (plugin_name, var_name) = key.split_once(':')?;
+        match self.plugins.get(plugin_name)?.provides.get(var_name)? {
+            VariableProvider::Command {
+                debounce: Some(interval),
+                ..
+            }
+            | VariableProvider::Script {
+                debounce: Some(interval),
+                ..
+            } => parse_duration(interval),
+            _ => None,
+        }
+    }
+
     /// Check if a variable key refers to an internal (synchronous) variable.
     fn is_internal_variable(&self, key: &str) -> bool {
         let parts: Vec<&str> = key.split(':').collect();
@@ -355,58 +604,35 @@ impl PluginManager {
     fn get_context_variable(&mut self, var_name: &str) -> Option<String> {
         let dir = std::env::current_dir().ok()?;
         let ctx = self.context_cache.get(&dir);
+        context_value_with_icons(&ctx, var_name, &self.icons)
+    }
 
-        match var_name {
-            // Git information
-            "git_branch" => ctx.git.as_ref().map(|g| g.branch.clone()),
-            "git_status" => ctx.git.as_ref().map(|g| g.status_indicator()),
-
-            // Package information
-            "package_name" => ctx.package.as_ref().map(|p| p.name.clone()),
-            "package_version" => ctx.package.as_ref().map(|p| p.version.clone()),
-            "package_icon" => ctx.package.as_ref().map(|_| "📦".to_string()),
-
-            // Rust
-            "rust_version" => ctx.rust.as_ref().map(|r| r.version.clone()),
-            "rust_icon" => ctx.rust.as_ref().map(|_| "🦀".to_string()),
-
-            // Node.js
-            "node_version" => ctx.node.as_ref().map(|n| n.version.clone()),
-            "node_icon" => ctx.node.as_ref().map(|_| "⬢".to_string()),
-
-            // Bun
-            "bun_version" => ctx.bun.as_ref().map(|b| b.version.clone()),
-            "bun_icon" => ctx.bun.as_ref().map(|_| "🥟".to_string()),
-
-            // Go
-            "go_version" => ctx.go.as_ref().map(|g| g.version.clone()),
-            "go_icon" => ctx.go.as_ref().map(|_| "🐹".to_string()),
-
-            // Python
-            "python_version" => ctx.python.as_ref().map(|p| p.version.clone()),
-            "python_icon" => ctx.python.as_ref().map(|_| "🐍".to_string()),
-
-            // C++
-            "cpp_version" => ctx.cpp.as_ref().map(|c| c.version.clone()),
-            "cpp_icon" => ctx.cpp.as_ref().map(|_| "⚙️".to_string()),
-
-            // Docker
-            "docker_version" => ctx.docker.as_ref().map(|d| d.version.clone()),
-            "docker_icon" => ctx.docker.as_ref().map(|_| "🐳".to_string()),
-
-            _ => None,
-        }
+    /// Compact context summary for `{status_summary}`.
+    pub fn context_summary(&mut self, config: &SummaryConfig) -> String {
+        let Ok(dir) = std::env::current_dir() else {
+            return String::new();
+        };
+        summarize_context(&self.context_cache.get(&dir), config, &self.icons)
     }
 
     /// Spawn a background task to fetch a variable value.
     async fn spawn_variable_task(&self, key: String) {
+        if let Some(task) = self.start_variable_task(&key) {
+            self.running_tasks.lock().await.insert(key, task);
+        }
+    }
+
+    /// Spawn the task for a variable; the caller records it as running.
+    fn start_variable_task(&self, key: &str) -> Option<RunningTask> {
         let cache = Arc::clone(&self.cache);
         let running = Arc::clone(&self.running_tasks);
+        let durations = Arc::clone(&self.task_durations);
+        let slots = Arc::clone(&self.task_slots);
 
         // Get plugin info needed for the task
         let parts: Vec<&str> = key.split(':').collect();
         if parts.len() != 2 {
-            return;
+            return None;
         }
 
         let plugin_name = parts[0];
@@ -414,22 +640,33 @@ impl PluginManager {
 
         let plugin = match self.plugins.get(plugin_name) {
             Some(p) => p.clone(),
-            None => return,
+            None => return None,
         };
 
         let provider = match plugin.provides.get(var_name) {
             Some(p) => p.clone(),
-            None => return,
+            None => return None,
         };
 
         // Get cache duration for this variable
-        let cache_duration = self.get_variable_cache_duration(&key);
+        let cache_duration = self.get_variable_cache_duration(key);
 
-        let key_clone = key.clone();
+        let key_clone = key.to_string();
 
         let var_name_owned = var_name.to_string();
+        let started_at = Instant::now();
         let handle = tokio::spawn(async move {
+            durations.lock().await.remove(&key_clone);
+            // Tasks start in spawn order as slots free up
+            let permit = slots.acquire_owned().await.ok();
             let result = execute_provider_async(&plugin, &var_name_owned, &provider).await;
+            drop(permit);
+
+            // Record how long the task took (spawn to completion)
+            durations
+                .lock()
+                .await
+                .insert(key_clone.clone(), started_at.elapsed());
 
             // Update cache based on cache duration setting
             if let Some(ref value) = result {
@@ -455,14 +692,7 @@ impl PluginManager {
             result
         });
 
-        // Add to running tasks
-        self.running_tasks.lock().await.insert(
-            key,
-            RunningTask {
-                handle,
-                started_at: Instant::now(),
-            },
-        );
+        Some(RunningTask { handle, started_at })
     }
 
     /// Try to get a result for a key within a timeout.
@@ -557,27 +787,21 @@ impl PluginManager {
 
                 let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
 
-                // Apply transform
-                match transform.as_deref() {
-                    Some("non_empty") => {
-                        if stdout.is_empty() {
-                            plugin.icons.get("clean").cloned()
-                        } else {
-                            plugin.icons.get("dirty").cloned()
-                        }
-                    }
-                    Some("with_icon") => {
-                        if stdout.is_empty() {
-                            None // Hide entirely when empty
-                        } else if let Some(icon) = plugin.icons.get(var_name) {
-                            Some(format!("{} {}", icon, stdout))
-                        } else {
-                            Some(stdout)
-                        }
-                    }
-                    Some("trim") => Some(stdout),
-                    _ => Some(stdout),
-                }
+                transform::apply(transform.as_deref(), plugin, var_name, stdout)
+            }
+            VariableProvider::Script {
+                script, transform, ..
+            } => {
+                // Passed as an argument, so nothing is written to disk
+                let output = std::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(script)
+                    .output()
+                    .ok()?;
+
+                let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+                transform::apply(transform.as_deref(), plugin, var_name, stdout)
             }
             VariableProvider::Internal { source } => match source.as_str() {
                 "internal" => {
@@ -606,6 +830,48 @@ impl PluginManager {
         }
     }
 
+    /// Whether a loaded plugin provides `var_name`; `None` if the plugin isn't
+    /// loaded. Context variables are resolved internally (some, like
+    /// `asdf_<tool>`, are dynamic), so they are always accepted.
+    pub fn plugin_provides(&self, plugin_name: &str, var_name: &str) -> Option<bool> {
+        if plugin_name == "context" || plugin_name == "builtins/context" {
+            return Some(true);
+        }
+        let plugin = self.plugins.get(plugin_name)?;
+        Some(plugin.provides.contains_key(var_name))
+    }
+
+    /// Loaded plugins whose name without the package prefix is `base`,
+    /// sorted (e.g. "git" -> ["builtins/git", "mypkg/git"]).
+    pub fn plugins_named(&self, base: &str) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .plugins
+            .keys()
+            .filter(|name| name.rsplit('/').next() == Some(base))
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Plugin names provided by more than one package (or a package and a
+    /// local plugin), with the full names of each provider.
+    pub fn collisions(&self) -> Vec<(String, Vec<String>)> {
+        let mut bases: Vec<&str> = self
+            .plugins
+            .keys()
+            .filter_map(|name| name.rsplit('/').next())
+            .collect();
+        bases.sort();
+        bases.dedup();
+
+        bases
+            .into_iter()
+            .map(|base| (base.to_string(), self.plugins_named(base)))
+            .filter(|(_, names)| names.len() > 1)
+            .collect()
+    }
+
     /// Get list of loaded plugins with their info.
     pub fn list_plugins(&self) -> Vec<(&str, &str, Vec<&str>)> {
         self.plugins
@@ -629,25 +895,55 @@ impl PluginManager {
         for (var_name, provider) in &plugin.provides {
             let (provider_desc, result) = match provider {
                 VariableProvider::Command {
-                    command,
+                    command: body,
+                    transform,
+                    timeout,
+                    cache,
+                    prefetch,
+                    debounce,
+                }
+                | VariableProvider::Script {
+                    script: body,
                     transform,
                     timeout,
                     cache,
+                    prefetch,
+                    debounce,
                 } => {
-                    let mut desc = format!("command: {}", command);
+                    let is_script = matches!(provider, VariableProvider::Script { .. });
+                    let mut desc = if is_script {
+                        format!("script: {} lines", body.lines().count())
+                    } else {
+                        format!("command: {}", body)
+                    };
+                    let pipeline = transform.as_deref().map(Pipeline::parse);
                     if let Some(t) = transform {
                         desc.push_str(&format!(" (transform: {})", t));
                     }
+                    if let Some(p) = &pipeline {
+                        for stage in p.unknown() {
+                            desc.push_str(&format!(
+                                " (unknown transform stage '{}' ignored)",
+                                stage
+                            ));
+                        }
+                    }
                     if let Some(t) = timeout {
                         desc.push_str(&format!(" (timeout: {})", t));
                     }
                     if let Some(c) = cache {
                         desc.push_str(&format!(" (cache: {})", c));
                     }
+                    if *prefetch {
+                        desc.push_str(" (prefetch)");
+                    }
+                    if let Some(d) = debounce {
+                        desc.push_str(&format!(" (debounce: {})", d));
+                    }
 
                     let output = tokio::process::Command::new("sh")
                         .arg("-c")
-                        .arg(command)
+                        .arg(body)
                         .output()
                         .await;
 
@@ -664,33 +960,17 @@ impl PluginManager {
                                     Ok("(empty)".to_string())
                                 } else {
                                     // Apply transform for display
-                                    match transform.as_deref() {
-                                        Some("non_empty") => {
-                                            let icon = if stdout.is_empty() {
-                                                plugin
-                                                    .icons
-                                                    .get("clean")
-                                                    .cloned()
-                                                    .unwrap_or_default()
-                                            } else {
-                                                plugin
-                                                    .icons
-                                                    .get("dirty")
-                                                    .cloned()
-                                                    .unwrap_or_default()
-                                            };
-                                            Ok(format!("{} (raw: {})", icon, stdout))
-                                        }
-                                        Some("with_icon") => {
-                                            if stdout.is_empty() {
-                                                Ok("(empty - will be hidden)".to_string())
-                                            } else if let Some(icon) = plugin.icons.get(var_name) {
-                                                Ok(format!("{} {}", icon, stdout))
-                                            } else {
-                                                Ok(format!("{} (no icon defined)", stdout))
+                                    match &pipeline {
+                                        Some(p) => {
+                                            match p.apply(plugin, var_name, stdout.clone()) {
+                                                Some(value) if value == stdout => Ok(value),
+                                                Some(value) => {
+                                                    Ok(format!("{} (raw: {})", value, stdout))
+                                                }
+                                                None => Ok("(empty - will be hidden)".to_string()),
                                             }
                                         }
-                                        _ => Ok(stdout),
+                                        None => Ok(stdout),
                                     }
                                 }
                             } else {
@@ -747,27 +1027,21 @@ async fn execute_provider_async(
 
             let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
 
-            // Apply transform
-            match transform.as_deref() {
-                Some("non_empty") => {
-                    if stdout.is_empty() {
-                        plugin.icons.get("clean").cloned()
-                    } else {
-                        plugin.icons.get("dirty").cloned()
-                    }
-                }
-                Some("with_icon") => {
-                    if stdout.is_empty() {
-                        None // Hide entirely when empty
-                    } else if let Some(icon) = plugin.icons.get(var_name) {
-                        Some(format!("{} {}", icon, stdout))
-                    } else {
-                        Some(stdout)
-                    }
-                }
-                Some("trim") => Some(stdout),
-                _ => Some(stdout),
-            }
+            transform::apply(transform.as_deref(), plugin, var_name, stdout)
+        }
+        VariableProvider::Script {
+            script, transform, ..
+        } => {
+            let output = tokio::process::Command::new("sh")
+                .arg("-c")
+                .arg(script)
+                .output()
+                .await
+                .ok()?;
+
+            let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+            transform::apply(transform.as_deref(), plugin, var_name, stdout)
         }
         VariableProvider::Internal { .. } => {
             // Internal providers should be handled synchronously
@@ -776,6 +1050,241 @@ async fn execute_provider_async(
     }
 }
 
+/// Load a plugin file, resolving `extends`.
+pub fn load_plugin_file(path: &Path) -> Result<Plugin> {
+    load_plugin_with_depth(path, 0)
+}
+
+/// Load a plugin file with inheritance depth tracking to prevent infinite loops.
+fn load_plugin_with_depth(path: &Path, depth: usize) -> Result<Plugin> {
+    const MAX_INHERITANCE_DEPTH: usize = 10;
+    if depth > MAX_INHERITANCE_DEPTH {
+        anyhow::bail!(
+            "Plugin inheritance too deep (max {}). Check for circular inheritance.",
+            MAX_INHERITANCE_DEPTH
+        );
+    }
+
+    let content = fs::read_to_string(path)?;
+    let mut plugin: Plugin = toml::from_str(&content)?;
+
+    if let Some(parent_name) = plugin.extends.clone() {
+        let parent_path = parent_plugin_path(path, &parent_name);
+        if !parent_path.exists() {
+            anyhow::bail!(
+                "Parent plugin '{}' not found at {}",
+                parent_name,
+                parent_path.display()
+            );
+        }
+        let parent = load_plugin_with_depth(&parent_path, depth + 1)?;
+        plugin = plugin.merge_with_parent(parent);
+    }
+
+    Ok(plugin)
+}
+
+/// Resolve an `extends` reference: `package/plugin` loads from that package's
+/// plugins directory, a bare name from the same directory as the child.
+fn parent_plugin_path(child: &Path, name: &str) -> PathBuf {
+    match name.split_once('/') {
+        Some((package, plugin)) => paths::packages_dir()
+            .join(package)
+            .join("plugins")
+            .join(format!("{}.toml", plugin)),
+        None => child
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(format!("{}.toml", name)),
+    }
+}
+
+/// Value of a `builtins/context` variable for `ctx`.
+fn context_value(ctx: &ProjectContext, var_name: &str) -> Option<String> {
+    match var_name {
+        // Git information
+        "git_branch" => ctx.git.as_ref().map(|g| g.branch.clone()),
+        "git_status" => ctx.git.as_ref().map(|g| g.status_indicator()),
+        "git_remote_host" => ctx.git.as_ref().and_then(|g| g.remote_host.clone()),
+        "git_worktree" => ctx
+            .git
+            .as_ref()
+            .and_then(|g| g.is_worktree.then(|| "worktree".to_string())),
+        "git_submodules" => ctx
+            .git
+            .as_ref()
+            .and_then(|g| g.submodules_dirty.then(|| "submodules".to_string())),
+        "git_upstream" => ctx.git.as_ref().and_then(|g| g.upstream.clone()),
+        "git_tag" => ctx.git.as_ref().and_then(|g| g.tag.clone()),
+        "git_fetch_age" => ctx.git.as_ref()?.fetch_age().map(format_age),
+        "git_fetch_stale" => ctx.git.as_ref().and_then(|g| {
+            g.fetch_stale(nosh_context::detectors::git::fetch_stale_after())
+                .then(|| "stale".to_string())
+        }),
+        "git_staged" => ctx.git.as_ref().and_then(|g| nonzero(g.counts.staged)),
+        "git_modified" => ctx.git.as_ref().and_then(|g| nonzero(g.counts.modified)),
+        "git_untracked" => ctx.git.as_ref().and_then(|g| nonzero(g.counts.untracked)),
+        "git_conflicted" => ctx.git.as_ref().and_then(|g| nonzero(g.counts.conflicted)),
+
+        // Active VCS (git, hg or jj)
+        "vcs_branch" => ctx.vcs.as_ref().map(|v| v.branch.clone()),
+        "vcs_name" => ctx.vcs.as_ref().map(|v| v.vcs.name().to_string()),
+
+        // Package information
+        "package_name" => ctx.package.as_ref().map(|p| p.name.clone()),
+        "package_version" => ctx.package.as_ref().map(|p| p.version.clone()),
+        "package_icon" => ctx.package.as_ref().map(|_| "📦".to_string()),
+
+        // Rust
+        "rust_version" => ctx.rust.as_ref().map(|r| r.version.clone()),
+        "rust_icon" => ctx.rust.as_ref().map(|_| "🦀".to_string()),
+
+        // Node.js
+        "node_version" => ctx.node.as_ref().map(|n| n.version.clone()),
+        "node_icon" => ctx.node.as_ref().map(|_| "⬢".to_string()),
+
+        // Bun
+        "bun_version" => ctx.bun.as_ref().map(|b| b.version.clone()),
+        "bun_icon" => ctx.bun.as_ref().map(|_| "🥟".to_string()),
+
+        // Go
+        "go_version" => ctx.go.as_ref().map(|g| g.version.clone()),
+        "go_icon" => ctx.go.as_ref().map(|_| "🐹".to_string()),
+
+        // Python
+        "python_version" => ctx.python.as_ref().map(|p| p.version.clone()),
+        "python_icon" => ctx.python.as_ref().map(|_| "🐍".to_string()),
+
+        // C++
+        "cpp_version" => ctx.cpp.as_ref().map(|c| c.version.clone()),
+        "cpp_icon" => ctx.cpp.as_ref().map(|_| "⚙️".to_string()),
+
+        // Docker
+        "docker_version" => ctx.docker.as_ref().map(|d| d.version.clone()),
+        "docker_icon" => ctx.docker.as_ref().map(|_| "🐳".to_string()),
+        "compose_project" => ctx.compose.as_ref().map(|c| c.project.clone()),
+        "compose_running" => ctx
+            .compose
+            .as_ref()
+            .and_then(|c| c.running)
+            .and_then(nonzero),
+
+        // Zig
+        "zig_version" => ctx.zig.as_ref().map(|z| z.version.clone()),
+        "zig_icon" => ctx.zig.as_ref().map(|_| "⚡".to_string()),
+
+        // Elixir
+        "elixir_version" => ctx.elixir.as_ref().map(|e| e.version.clone()),
+        "elixir_icon" => ctx.elixir.as_ref().map(|_| "💧".to_string()),
+
+        // Nix shell (from the environment, not the directory)
+        "nix_shell" => nosh_context::detectors::nix::detect(),
+        "nix_shell_icon" => nosh_context::detectors::nix::detect().map(|_| "❄".to_string()),
+
+        // Remote session (from the environment)
+        "is_ssh" => nosh_context::detectors::ssh::is_ssh().then(|| "ssh".to_string()),
+
+        // Whether nosh itself runs inside a container
+        "in_container" => nosh_context::detectors::docker::in_container().map(str::to_string),
+        "in_container_icon" => {
+            nosh_context::detectors::docker::in_container().map(|_| "🐳".to_string())
+        }
+
+        // Pinned versions from .tool-versions (asdf_<tool>, asdf_<tool>_mismatch)
+        "asdf_mismatch" => {
+            let tools: Vec<&str> = ctx
+                .pinned
+                .iter()
+                .filter(|p| p.mismatch)
+                .map(|p| p.tool.as_str())
+                .collect();
+            if tools.is_empty() {
+                None
+            } else {
+                Some(tools.join(","))
+            }
+        }
+        _ => {
+            let name = var_name.strip_prefix("asdf_")?;
+            if let Some(tool) = name.strip_suffix("_mismatch") {
+                let pinned = ctx.pinned.iter().find(|p| p.tool == tool)?;
+                return pinned.mismatch.then(|| "!".to_string());
+            }
+            ctx.pinned
+                .iter()
+                .find(|p| p.tool == name)
+                .map(|p| p.version.clone())
+        }
+    }
+}
+
+/// [`context_value`], with `*_icon` variables taken from `icons` (keyed by
+/// context type, e.g. `rust`) when the theme overrides them.
+fn context_value_with_icons(
+    ctx: &ProjectContext,
+    var_name: &str,
+    icons: &HashMap<String, String>,
+) -> Option<String> {
+    let value = context_value(ctx, var_name)?;
+    let icon = var_name
+        .strip_suffix("_icon")
+        .and_then(|kind| icons.get(kind));
+    Some(icon.cloned().unwrap_or(value))
+}
+
+/// Assemble `{status_summary}` from the configured context pieces, e.g.
+/// `⎇main* 🦀1.78`. Absent pieces are skipped. Icons come from the summary's
+/// own `icons`, then the theme's `[icons]`, then the built-in ones.
+fn summarize_context(
+    ctx: &ProjectContext,
+    config: &SummaryConfig,
+    icons: &HashMap<String, String>,
+) -> String {
+    config
+        .items
+        .iter()
+        .filter_map(|item| {
+            let value = match item.as_str() {
+                "git" => ctx
+                    .git
+                    .as_ref()
+                    .map(|g| format!("{}{}", g.branch, if g.dirty { "*" } else { "" }))?,
+                "package" => context_value(ctx, "package_version")?,
+                "compose" => context_value(ctx, "compose_project")?,
+                tool => context_value(ctx, &format!("{}_version", tool))?,
+            };
+            let icon = config
+                .icons
+                .get(item)
+                .cloned()
+                .or_else(|| match item.as_str() {
+                    "git" => Some(icons.get("git").map_or("⎇", String::as_str).to_string()),
+                    "compose" => context_value_with_icons(ctx, "docker_icon", icons),
+                    _ => context_value_with_icons(ctx, &format!("{}_icon", item), icons),
+                })
+                .unwrap_or_default();
+            Some(format!("{}{}", icon, value))
+        })
+        .collect::<Vec<_>>()
+        .join(&config.separator)
+}
+
+/// Render a count, or `None` when it is zero so the segment collapses.
+fn nonzero(count: usize) -> Option<String> {
+    (count > 0).then(|| count.to_string())
+}
+
+/// Format a long duration in its largest whole unit ("45s", "12m", "3h", "2d").
+fn format_age(age: std::time::Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
 /// Format a duration for display.
 fn format_duration(duration: std::time::Duration) -> String {
     let secs = duration.as_secs();
@@ -791,3 +1300,403 @@ fn format_duration(duration: std::time::Duration) -> String {
         format!("{}ms", ms)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_name_collisions() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_collide_{}", std::process::id()));
+        for (package, plugin) in [("alpha", "git"), ("beta", "git"), ("beta", "weather")] {
+            let plugins = dir.join(package).join("plugins");
+            fs::create_dir_all(&plugins).unwrap();
+            fs::write(
+                plugins.join(format!("{}.toml", plugin)),
+                format!(
+                    "[plugin]\nname = \"{}\"\n\n[provides]\nbranch = {{ command = \"echo x\" }}\n",
+                    plugin
+                ),
+            )
+            .unwrap();
+        }
+
+        let mut manager = PluginManager::new();
+        manager.load_packages(&dir).unwrap();
+
+        assert_eq!(
+            manager.collisions(),
+            vec![(
+                "git".to_string(),
+                vec!["alpha/git".to_string(), "beta/git".to_string()]
+            )]
+        );
+        assert_eq!(manager.plugins_named("weather"), vec!["beta/weather"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_plugin_extends() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_extends_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("base.toml"),
+            r#"
+[plugin]
+name = "base"
+description = "Shared definitions"
+
+[provides]
+version = { command = "echo 1.0" }
+name = { command = "echo base" }
+
+[icons]
+version = "v"
+clean = "✓"
+
+[config]
+min_ms = 500
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("child.toml"),
+            r#"
+extends = "base"
+
+[plugin]
+name = "child"
+
+[provides]
+version = { command = "echo 2.0" }
+
+[icons]
+clean = "ok"
+"#,
+        )
+        .unwrap();
+
+        let plugin = load_plugin_with_depth(&dir.join("child.toml"), 0).unwrap();
+        assert_eq!(plugin.plugin.name, "child");
+        assert_eq!(plugin.plugin.description, "Shared definitions");
+
+        // Inherited icon and provider, overridden icon and provider
+        assert_eq!(plugin.icons.get("version").map(String::as_str), Some("v"));
+        assert_eq!(plugin.icons.get("clean").map(String::as_str), Some("ok"));
+        assert!(plugin.provides.contains_key("name"));
+        match plugin.provides.get("version") {
+            Some(VariableProvider::Command { command, .. }) => assert_eq!(command, "echo 2.0"),
+            other => panic!("Expected command provider, got {:?}", other),
+        }
+        assert_eq!(
+            plugin.config.get("min_ms").and_then(|v| v.as_integer()),
+            Some(500)
+        );
+
+        // Circular inheritance is rejected
+        fs::write(
+            dir.join("loop.toml"),
+            "extends = \"loop\"\n[plugin]\nname = \"loop\"\n",
+        )
+        .unwrap();
+        assert!(load_plugin_with_depth(&dir.join("loop.toml"), 0).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_fills_cache_before_first_prompt() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_prefetch_{}", std::process::id()));
+        let plugins = dir.join("pkg").join("plugins");
+        fs::create_dir_all(&plugins).unwrap();
+        fs::write(
+            plugins.join("host.toml"),
+            r#"
+[plugin]
+name = "host"
+
+[provides]
+label = { command = "echo build-box", cache = "never", prefetch = true }
+lazy = { command = "echo later", cache = "never" }
+"#,
+        )
+        .unwrap();
+        fs::write(
+            plugins.join("off.toml"),
+            r#"
+[plugin]
+name = "off"
+
+[provides]
+label = { command = "echo unused", cache = "never", prefetch = true }
+"#,
+        )
+        .unwrap();
+
+        let mut manager = PluginManager::new();
+        manager.load_packages(&dir).unwrap();
+        // The theme turned `off` off
+        manager.prefetch(|name| name != "pkg/off");
+        assert!(
+            !manager
+                .running_tasks
+                .lock()
+                .await
+                .contains_key("pkg/off:label")
+        );
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !manager.cache.lock().await.contains_key("pkg/host:label") {
+            assert!(Instant::now() < deadline, "prefetch never finished");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let cache = manager.cache.lock().await;
+        assert_eq!(cache["pkg/host:label"].value, "build-box");
+        assert!(!cache.contains_key("pkg/host:lazy"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrent_tasks() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_slots_{}", std::process::id()));
+        let running = dir.join("running");
+        fs::create_dir_all(&running).unwrap();
+        let counts = dir.join("counts");
+
+        // Each provider notes how many providers are running mid-way through
+        let provides: Vec<String> = (0..4)
+            .map(|i| {
+                format!(
+                    "v{i} = {{ command = \"touch {r}/{i}; sleep 0.1; ls {r} | wc -l >> {c}; sleep 0.1; rm {r}/{i}\" }}",
+                    r = running.display(),
+                    c = counts.display(),
+                )
+            })
+            .collect();
+        fs::write(
+            dir.join("slow.toml"),
+            format!(
+                "[plugin]\nname = \"slow\"\n\n[provides]\n{}\n",
+                provides.join("\n")
+            ),
+        )
+        .unwrap();
+
+        let mut manager = PluginManager::new();
+        let plugin = load_plugin_with_depth(&dir.join("slow.toml"), 0).unwrap();
+        manager.plugins.insert("slow".to_string(), plugin);
+        manager.set_max_concurrent(2);
+
+        let keys = (0..4).map(|i| format!("slow:v{}", i)).collect();
+        manager.get_variables(keys).await;
+
+        // Wait for the tasks that outlived the prompt deadline
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let lines = loop {
+            let content = fs::read_to_string(&counts).unwrap_or_default();
+            if content.lines().count() == 4 || Instant::now() > deadline {
+                break content;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        };
+        let seen: Vec<usize> = lines.lines().map(|l| l.trim().parse().unwrap()).collect();
+        assert_eq!(seen.len(), 4);
+        assert!(seen.iter().all(|&n| n <= 2), "{:?}", seen);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_debounce_skips_rerun_within_interval() {
+        let dir =
+            std::env::temp_dir().join(format!("nosh_test_debounce_var_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let runs = dir.join("runs");
+        fs::write(
+            dir.join("check.toml"),
+            format!(
+                "[plugin]\nname = \"check\"\n\n[provides]\nstatus = {{ command = \"echo run >> {r}; wc -l < {r}\", timeout = \"2s\", cache = \"always\", debounce = \"1h\" }}\n",
+                r = runs.display(),
+            ),
+        )
+        .unwrap();
+
+        let mut manager = PluginManager::new();
+        let plugin = load_plugin_with_depth(&dir.join("check.toml"), 0).unwrap();
+        manager.plugins.insert("check".to_string(), plugin);
+
+        let keys = vec!["check:status".to_string()];
+        let first = manager.get_variables(keys.clone()).await;
+        assert_eq!(first["check:status"].trim(), "1");
+
+        // Still inside the interval: the expired value is reused, nothing runs
+        let second = manager.get_variables(keys).await;
+        assert_eq!(second["check:status"].trim(), "1");
+        assert_eq!(fs::read_to_string(&runs).unwrap().lines().count(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_script_provider() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_script_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("multi.toml"),
+            r#"
+[plugin]
+name = "multi"
+
+[provides.greeting]
+script = """
+name="nosh"
+echo "hello $name"
+"""
+transform = "uppercase"
+timeout = "2s"
+"#,
+        )
+        .unwrap();
+
+        let mut manager = PluginManager::new();
+        let plugin = load_plugin_with_depth(&dir.join("multi.toml"), 0).unwrap();
+        assert!(matches!(
+            plugin.provides.get("greeting"),
+            Some(VariableProvider::Script { .. })
+        ));
+        manager.plugins.insert("multi".to_string(), plugin);
+
+        let values = manager
+            .get_variables(vec!["multi:greeting".to_string()])
+            .await;
+        assert_eq!(values["multi:greeting"], "HELLO NOSH");
+        assert_eq!(
+            manager.get_variable("multi:greeting").as_deref(),
+            Some("HELLO NOSH")
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_variable_timings_sorted_slowest_first() {
+        let mut timings = VariableTimings::default();
+        assert!(timings.is_empty());
+
+        timings.record(
+            "git:branch",
+            Duration::from_millis(40),
+            TimingSource::Completed,
+        );
+        timings.record(
+            "context:rust_version",
+            Duration::from_millis(2),
+            TimingSource::Internal,
+        );
+        timings.record(
+            "git:status",
+            Duration::from_millis(120),
+            TimingSource::Pending,
+        );
+        timings.record("exec_time:took", Duration::ZERO, TimingSource::Cached);
+
+        let sorted = timings.sorted();
+        let keys: Vec<&str> = sorted.iter().map(|(k, _)| *k).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "git:status",
+                "git:branch",
+                "context:rust_version",
+                "exec_time:took"
+            ]
+        );
+        assert_eq!(sorted[0].1.source, TimingSource::Pending);
+
+        // Re-recording replaces the previous entry
+        timings.record("git:status", Duration::from_millis(1), TimingSource::Cached);
+        assert_eq!(timings.sorted().len(), 4);
+        assert_eq!(timings.sorted()[0].0, "git:branch");
+
+        timings.clear();
+        assert!(timings.is_empty());
+    }
+
+    #[test]
+    fn test_status_summary() {
+        use nosh_context::{GitInfo, ToolInfo};
+
+        let ctx = ProjectContext {
+            git: Some(GitInfo {
+                branch: "main".to_string(),
+                dirty: true,
+                ..Default::default()
+            }),
+            rust: Some(ToolInfo {
+                version: "1.78".to_string(),
+            }),
+            ..Default::default()
+        };
+        let mut config = SummaryConfig {
+            items: vec!["git".into(), "node".into(), "rust".into()],
+            ..Default::default()
+        };
+        let no_icons = HashMap::new();
+        assert_eq!(summarize_context(&ctx, &config, &no_icons), "⎇main* 🦀1.78");
+
+        config.icons.insert("rust".into(), "rs:".into());
+        config.separator = " | ".into();
+        assert_eq!(
+            summarize_context(&ctx, &config, &no_icons),
+            "⎇main* | rs:1.78"
+        );
+        assert_eq!(
+            summarize_context(&ProjectContext::default(), &config, &no_icons),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_theme_icon_overrides() {
+        use nosh_context::ToolInfo;
+
+        let ctx = ProjectContext {
+            rust: Some(ToolInfo {
+                version: "1.78".to_string(),
+            }),
+            ..Default::default()
+        };
+        let icons: HashMap<String, String> = [
+            ("rust".to_string(), "\u{e7a8}".to_string()),
+            ("git".to_string(), "git:".to_string()),
+        ]
+        .into();
+
+        assert_eq!(
+            context_value_with_icons(&ctx, "rust_icon", &HashMap::new()).as_deref(),
+            Some("🦀")
+        );
+        assert_eq!(
+            context_value_with_icons(&ctx, "rust_icon", &icons).as_deref(),
+            Some("\u{e7a8}")
+        );
+        assert_eq!(
+            context_value_with_icons(&ctx, "rust_version", &icons).as_deref(),
+            Some("1.78")
+        );
+        // No override shows up for a tool that isn't detected
+        let node_icons: HashMap<String, String> = [("node".to_string(), "N".to_string())].into();
+        assert_eq!(
+            context_value_with_icons(&ctx, "node_icon", &node_icons),
+            None
+        );
+
+        let config = SummaryConfig {
+            items: vec!["rust".into()],
+            ..Default::default()
+        };
+        assert_eq!(summarize_context(&ctx, &config, &icons), "\u{e7a8}1.78");
+    }
+}
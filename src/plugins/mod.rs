@@ -3,8 +3,12 @@
 //! Plugins provide prompt variables via commands or internal sources.
 
 pub mod builtins;
+pub mod check;
+pub mod debounce;
 pub mod loader;
+pub mod prompt_hook;
 pub mod theme;
+pub mod transform;
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -13,6 +17,9 @@ use std::time::Duration;
 /// A nosh plugin that provides prompt variables.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Plugin {
+    /// Parent plugin to inherit from ("package/plugin", or a sibling file name)
+    #[serde(default)]
+    pub extends: Option<String>,
     pub plugin: PluginMeta,
     #[serde(default)]
     pub provides: HashMap<String, VariableProvider>,
@@ -22,6 +29,30 @@ pub struct Plugin {
     pub config: HashMap<String, toml::Value>,
 }
 
+impl Plugin {
+    /// Merge this plugin with a parent. `provides`, `icons` and `config` are
+    /// unioned with the child winning; the name is always the child's.
+    pub fn merge_with_parent(mut self, parent: Plugin) -> Self {
+        if self.plugin.description.is_empty() {
+            self.plugin.description = parent.plugin.description;
+        }
+
+        let mut provides = parent.provides;
+        provides.extend(self.provides);
+        self.provides = provides;
+
+        let mut icons = parent.icons;
+        icons.extend(self.icons);
+        self.icons = icons;
+
+        let mut config = parent.config;
+        config.extend(self.config);
+        self.config = config;
+
+        self
+    }
+}
+
 /// Plugin metadata.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginMeta {
@@ -47,6 +78,28 @@ pub enum VariableProvider {
         /// "always" = no caching (always fetch fresh), "never" = cache forever, default = "500ms"
         #[serde(default)]
         cache: Option<String>,
+        /// Start fetching when plugins load, so the first prompt can use it.
+        #[serde(default)]
+        prefetch: bool,
+        /// Re-run at most once per interval, and only when files under the
+        /// current directory changed (e.g. "30s").
+        #[serde(default)]
+        debounce: Option<String>,
+    },
+    /// Variable provided by a multi-line shell script, run with `sh -c`.
+    /// Takes the same options as `Command`.
+    Script {
+        script: String,
+        #[serde(default)]
+        transform: Option<String>,
+        #[serde(default)]
+        timeout: Option<String>,
+        #[serde(default)]
+        cache: Option<String>,
+        #[serde(default)]
+        prefetch: bool,
+        #[serde(default)]
+        debounce: Option<String>,
     },
     /// Variable provided internally by nosh.
     Internal { source: String },
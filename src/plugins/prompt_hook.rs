@@ -0,0 +1,170 @@
+//! Use the nosh prompt from another shell.
+//!
+//! `nosh prompt --shell zsh` prints a snippet for `~/.zshrc` that sets the
+//! prompt from `nosh prompt-render` before each command, so themes and
+//! plugins work without switching shells.
+
+use std::time::Duration;
+
+use super::loader::PluginManager;
+use super::theme::Theme;
+
+/// Longest a one-shot render waits for any plugin variable.
+pub const RENDER_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Shell the prompt is rendered for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookShell {
+    Zsh,
+    Bash,
+}
+
+impl HookShell {
+    /// Parse a `--shell` value.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "zsh" => Some(HookShell::Zsh),
+            "bash" => Some(HookShell::Bash),
+            _ => None,
+        }
+    }
+
+    /// Snippet for the shell's rc file that renders the prompt with `exe`.
+    pub fn snippet(&self, exe: &str) -> String {
+        let exe = format!("'{}'", exe.replace('\'', r"'\''"));
+        match self {
+            // The render goes through a variable: with prompt_subst, text
+            // assigned to PROMPT directly (a `$(...)` directory name) would run
+            HookShell::Zsh => format!(
+                "# nosh prompt for zsh - add to ~/.zshrc\n\
+                 _nosh_precmd() {{ _nosh_prompt=\"$({exe} prompt-render --shell zsh --status $?)\" }}\n\
+                 setopt prompt_subst\n\
+                 PROMPT='${{_nosh_prompt}}'\n\
+                 autoload -Uz add-zsh-hook\n\
+                 add-zsh-hook precmd _nosh_precmd\n"
+            ),
+            HookShell::Bash => format!(
+                "# nosh prompt for bash - add to ~/.bashrc\n\
+                 _nosh_prompt() {{ PS1=\"$({exe} prompt-render --shell bash --status $?)\"; }}\n\
+                 PROMPT_COMMAND=\"_nosh_prompt${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}\"\n"
+            ),
+        }
+    }
+
+    /// Escape a rendered prompt for the shell: escape sequences are marked
+    /// as zero-width and characters the shell expands are quoted.
+    pub fn escape(&self, prompt: &str) -> String {
+        let (open, close) = match self {
+            HookShell::Zsh => ("%{", "%}"),
+            HookShell::Bash => (r"\[", r"\]"),
+        };
+        let mut out = String::with_capacity(prompt.len());
+        let mut chars = prompt.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                out.push_str(open);
+                out.push(c);
+                match chars.next() {
+                    // CSI: parameters up to a final byte
+                    Some('[') => {
+                        out.push('[');
+                        for c in chars.by_ref() {
+                            out.push(c);
+                            if ('\x40'..='\x7e').contains(&c) {
+                                break;
+                            }
+                        }
+                    }
+                    // OSC: up to BEL or ESC \
+                    Some(']') => {
+                        out.push(']');
+                        while let Some(c) = chars.next() {
+                            out.push(c);
+                            if c == '\x07' {
+                                break;
+                            }
+                            if c == '\x1b' && chars.peek() == Some(&'\\') {
+                                out.push(chars.next().unwrap_or('\\'));
+                                break;
+                            }
+                        }
+                    }
+                    Some(c) => out.push(c),
+                    None => {}
+                }
+                out.push_str(close);
+                continue;
+            }
+            match (self, c) {
+                (HookShell::Zsh, '%') => out.push_str("%%"),
+                (HookShell::Bash, '\\') => out.push_str(r"\\\\"),
+                (HookShell::Bash, '$') => out.push_str(r"\\$"),
+                (HookShell::Bash, '`') => out.push_str(r"\\`"),
+                _ => out.push(c),
+            }
+        }
+        out
+    }
+}
+
+/// Render the prompt once for the current directory, waiting at most
+/// [`RENDER_TIMEOUT`] for plugin variables.
+pub async fn render(theme: &Theme, plugin_manager: &mut PluginManager) -> String {
+    plugin_manager.set_timeout_cap(RENDER_TIMEOUT);
+    plugin_manager.set_icons(theme.icons.clone());
+    let values = plugin_manager
+        .get_variables(theme.get_plugin_variables())
+        .await;
+    theme.format_prompt_with_values(&values, plugin_manager)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[tokio::test]
+    async fn test_render_with_theme() {
+        let dir =
+            std::env::temp_dir().join(format!("nosh_test_prompt_hook_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("hook.toml");
+        fs::write(
+            &path,
+            "[prompt]\nformat = \"[{user}](green) 100% [{prompt:char}](blue) \"\nchar = \">\"\n",
+        )
+        .unwrap();
+
+        let theme = Theme::load_file(&path).unwrap();
+        let prompt = render(&theme, &mut PluginManager::new()).await;
+        assert!(!prompt.trim().is_empty());
+        assert!(prompt.contains("\x1b[32m"));
+
+        let zsh = HookShell::Zsh.escape(&prompt);
+        assert!(zsh.starts_with("%{\x1b[32m%}"));
+        assert!(zsh.contains("100%% "));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_zsh_prompt_not_substituted() {
+        let snippet = HookShell::Zsh.snippet("/usr/local/bin/nosh");
+        assert!(snippet.contains("_nosh_prompt=\"$('/usr/local/bin/nosh' prompt-render"));
+        assert!(snippet.contains("\nPROMPT='${_nosh_prompt}'\n"));
+        assert!(!snippet.contains("PROMPT=\""));
+
+        // Shown as-is: only `%` needs escaping once it's behind the variable
+        let escaped = HookShell::Zsh.escape("~/src/$(touch x)/`id` 50% ");
+        assert_eq!(escaped, "~/src/$(touch x)/`id` 50%% ");
+    }
+
+    #[test]
+    fn test_bash_escape() {
+        let escaped = HookShell::Bash.escape("\x1b[1;34m$HOME\x1b[0m `x` \\ ");
+        assert_eq!(
+            escaped,
+            "\\[\x1b[1;34m\\]\\\\$HOME\\[\x1b[0m\\] \\\\`x\\\\` \\\\\\\\ "
+        );
+    }
+}
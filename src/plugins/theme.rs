@@ -3,17 +3,74 @@
 //! Handles theme loading, format string expansion, and ANSI color application.
 
 use anyhow::Result;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
+use std::path::{Path, PathBuf};
 
 use super::loader::PluginManager;
+use crate::exec::PromptState;
+use crate::fs_util;
 use crate::paths;
+use crate::ui::background::Background;
 
 /// ANSI reset escape code.
 pub const RESET: &str = "\x1b[0m";
 
+/// Variables expanded by the theme itself rather than a plugin.
+const BUILTIN_VARS: &[&str] = &[
+    "cwd",
+    "cwd_short",
+    "dir",
+    "user",
+    "host",
+    "ssh",
+    "newline",
+    "prompt:char",
+    "prompt:mode",
+    "time",
+    "date",
+    "shlvl",
+    "bg_status",
+    "status_summary",
+];
+
+/// Conditions accepted by `{#if COND}...{/if}` blocks (negate with `!`).
+const CONDITIONS: &[&str] = &["error", "success", "bg_failed", "ssh"];
+
+/// Format used by `{time}`.
+const DEFAULT_TIME_FORMAT: &str = "%H:%M:%S";
+
+/// Format used by `{date}`.
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Longest `{env:NAME}` value shown before it is cut off with an ellipsis.
+const ENV_VALUE_MAX_CHARS: usize = 64;
+
+/// A problem found in a theme's prompt format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintWarning {
+    pub message: String,
+}
+
+impl LintWarning {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
 /// A color rule with conditions for conditional coloring.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ColorRule {
@@ -213,18 +270,106 @@ pub struct Theme {
     pub plugins: HashMap<String, PluginConfig>,
     #[serde(default)]
     pub colors: ColorConfig,
+    /// Replacements for the built-in `*_icon` context icons, by context type
+    /// (`rust = ""`), for Nerd Fonts or plain-text setups
+    #[serde(default)]
+    pub icons: HashMap<String, String>,
+    /// Terminal background used to pick `[colors.light]`/`[colors.dark]`
+    #[serde(skip)]
+    background: Background,
+    /// Exit code of the last command, for `{prompt:char}` and `{#if error}`
+    #[serde(skip)]
+    last_exit: i32,
+    /// Shell level and background job status, for `{shlvl}` and `{bg_status}`
+    #[serde(skip)]
+    shell_state: PromptState,
 }
 
 /// Prompt configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PromptConfig {
-    pub format: String,
+    pub format: PromptFormat,
     /// Prompt character (default: "❯")
     #[serde(default = "default_prompt_char")]
     pub char: String,
     /// Prompt character shown after failed command (default: "❯")
     #[serde(default = "default_prompt_char")]
     pub char_error: String,
+    /// Keep repeated spaces and line indentation instead of collapsing them
+    #[serde(default)]
+    pub preserve_whitespace: bool,
+    /// What `{status_summary}` shows
+    #[serde(default)]
+    pub summary: SummaryConfig,
+}
+
+/// The `[prompt.summary]` section: context pieces `{status_summary}`
+/// combines, in order, each as icon followed by value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SummaryConfig {
+    /// `git`, `package`, `compose`, or a tool like `rust` or `node`
+    #[serde(default = "default_summary_items")]
+    pub items: Vec<String>,
+    /// Icons replacing the built-in ones, by item
+    #[serde(default)]
+    pub icons: HashMap<String, String>,
+    #[serde(default = "default_summary_separator")]
+    pub separator: String,
+}
+
+impl Default for SummaryConfig {
+    fn default() -> Self {
+        Self {
+            items: default_summary_items(),
+            icons: HashMap::new(),
+            separator: default_summary_separator(),
+        }
+    }
+}
+
+fn default_summary_items() -> Vec<String> {
+    ["git", "rust", "node", "bun", "python", "go", "docker"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+fn default_summary_separator() -> String {
+    " ".to_string()
+}
+
+/// The prompt format: one string, or an array with one string per line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PromptFormat {
+    /// Lines separated by `\n` or `{newline}`
+    Single(String),
+    /// One entry per line, each expanded and cleaned up on its own
+    Lines(Vec<String>),
+}
+
+impl PromptFormat {
+    /// The whole format as one string, lines joined with newlines.
+    pub fn joined(&self) -> String {
+        match self {
+            PromptFormat::Single(format) => format.clone(),
+            PromptFormat::Lines(lines) => lines.join("\n"),
+        }
+    }
+
+    /// Whether the format is unset (an empty string or empty array).
+    pub fn is_empty(&self) -> bool {
+        match self {
+            PromptFormat::Single(format) => format.is_empty(),
+            PromptFormat::Lines(lines) => lines.is_empty(),
+        }
+    }
+}
+
+impl From<&str> for PromptFormat {
+    fn from(format: &str) -> Self {
+        PromptFormat::Single(format.to_string())
+    }
 }
 
 fn default_prompt_char() -> String {
@@ -267,22 +412,44 @@ pub struct ColorConfig {
     #[serde(default)]
     pub ai_command: Option<String>,
 
+    // Per-background overrides: color name -> replacement color
+    #[serde(default)]
+    pub light: HashMap<String, String>,
+    #[serde(default)]
+    pub dark: HashMap<String, String>,
+
     // Conditional colors (new feature)
     #[serde(flatten)]
     pub conditional: HashMap<String, ConditionalColor>,
 }
 
+impl ColorConfig {
+    /// Color overrides for a terminal background.
+    pub fn overrides(&self, background: Background) -> &HashMap<String, String> {
+        match background {
+            Background::Light => &self.light,
+            Background::Dark => &self.dark,
+        }
+    }
+}
+
 impl Default for Theme {
     fn default() -> Self {
         Self {
             extends: None,
             prompt: PromptConfig {
-                format: "{cwd_short} $ ".to_string(),
+                format: "{cwd_short} $ ".into(),
                 char: default_prompt_char(),
                 char_error: default_prompt_char(),
+                preserve_whitespace: false,
+                summary: SummaryConfig::default(),
             },
             plugins: HashMap::new(),
             colors: ColorConfig::default(),
+            icons: HashMap::new(),
+            background: Background::default(),
+            last_exit: 0,
+            shell_state: PromptState::default(),
         }
     }
 }
@@ -293,7 +460,7 @@ impl Theme {
     pub fn get_plugin_variables(&self) -> Vec<String> {
         let mut vars = Vec::new();
         let mut start = 0;
-        let format = &self.prompt.format;
+        let format = &self.prompt.format.joined();
 
         while let Some(open) = format[start..].find('{') {
             let open_idx = start + open;
@@ -328,21 +495,41 @@ impl Theme {
         values: &HashMap<String, String>,
         plugin_manager: &mut PluginManager,
     ) -> String {
-        let mut result = self.prompt.format.clone();
+        self.render_lines(|line| {
+            // Expand built-in variables
+            let mut result = self.expand_builtin_vars(line);
+            result = self.expand_summary(&result, plugin_manager);
 
-        // Expand built-in variables
-        result = self.expand_builtin_vars(&result);
+            // Expand plugin variables using pre-fetched values
+            result = self.expand_plugin_vars_with_values(&result, values, plugin_manager);
 
-        // Expand plugin variables using pre-fetched values
-        result = self.expand_plugin_vars_with_values(&result, values, plugin_manager);
+            // Apply styled segments [text](color) -> ANSI colored text
+            result = self.expand_styled_segments(&result);
 
-        // Apply styled segments [text](color) -> ANSI colored text
-        result = self.expand_styled_segments(&result);
+            // Clean up empty segments and extra whitespace
+            self.cleanup_empty_segments(&result)
+        })
+    }
 
-        // Clean up empty segments and extra whitespace
-        result = self.cleanup_empty_segments(&result);
+    /// Expand `{status_summary}` from the project context.
+    fn expand_summary(&self, format: &str, plugin_manager: &mut PluginManager) -> String {
+        if !format.contains("{status_summary}") {
+            return format.to_string();
+        }
+        let summary = plugin_manager.context_summary(&self.prompt.summary);
+        format.replace("{status_summary}", &summary)
+    }
 
-        result
+    /// Render the format with `render`, once per line for array formats.
+    fn render_lines(&self, mut render: impl FnMut(&str) -> String) -> String {
+        match &self.prompt.format {
+            PromptFormat::Single(format) => render(format),
+            PromptFormat::Lines(lines) => lines
+                .iter()
+                .map(|line| render(line))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
     }
 
     /// Expand plugin variables using pre-fetched values.
@@ -399,11 +586,7 @@ impl Theme {
         }
 
         // Clean up multiple spaces
-        while result.contains("  ") {
-            result = result.replace("  ", " ");
-        }
-
-        result
+        self.collapse_spaces(result)
     }
 
     /// Load a theme by name from the themes directory.
@@ -417,6 +600,70 @@ impl Theme {
         Self::load_with_depth(name, 0)
     }
 
+    /// Load a theme, falling back to the built-in default theme if it
+    /// can't be loaded. Also returns a one-line warning naming the file
+    /// and the error, so a broken theme never stops the shell starting.
+    pub fn load_or_default(name: &str) -> (Self, Option<String>) {
+        Self::or_builtin_default(&theme_path(name), Self::load(name))
+    }
+
+    fn or_builtin_default(path: &Path, result: Result<Self>) -> (Self, Option<String>) {
+        match result {
+            Ok(theme) => (theme, None),
+            Err(e) => {
+                let fallback = toml::from_str(super::builtins::DEFAULT_THEME).unwrap_or_default();
+                let warning = format!(
+                    "Warning: couldn't load theme {} ({}), using the built-in default",
+                    path.display(),
+                    one_line_error(&e)
+                );
+                (fallback, Some(warning))
+            }
+        }
+    }
+
+    /// Enable or disable a plugin in a theme file's `[plugins]` table.
+    ///
+    /// The rest of the file (comments, formatting) is kept as is. Symlinked and
+    /// read-only theme files are refused.
+    pub fn set_plugin_enabled(path: &Path, plugin: &str, enabled: bool) -> Result<()> {
+        let meta = fs::symlink_metadata(path)
+            .map_err(|_| anyhow::anyhow!("Theme file {} not found", path.display()))?;
+        if meta.file_type().is_symlink() {
+            anyhow::bail!(
+                "{} is a symlink. Edit the file it points to instead.",
+                path.display()
+            );
+        }
+        if meta.permissions().readonly() {
+            anyhow::bail!("{} is read-only", path.display());
+        }
+
+        let content = fs::read_to_string(path)?;
+        let mut doc: toml_edit::DocumentMut = content.parse()?;
+        let plugins = doc
+            .entry("plugins")
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .ok_or_else(|| anyhow::anyhow!("[plugins] in {} is not a table", path.display()))?;
+
+        match plugins
+            .get_mut(plugin)
+            .and_then(|item| item.as_table_like_mut())
+        {
+            Some(entry) => {
+                entry.insert("enabled", toml_edit::value(enabled));
+            }
+            None => {
+                let mut entry = toml_edit::InlineTable::new();
+                entry.insert("enabled", enabled.into());
+                plugins.insert(plugin, toml_edit::value(entry));
+            }
+        }
+
+        fs_util::write_atomic(path, doc.to_string())
+    }
+
     /// Load a theme with inheritance depth tracking to prevent infinite loops.
     fn load_with_depth(name: &str, depth: usize) -> Result<Self> {
         const MAX_INHERITANCE_DEPTH: usize = 10;
@@ -427,33 +674,10 @@ impl Theme {
             );
         }
 
-        let theme_path = if name.contains('/') {
-            // Package theme: package/theme format
-            let parts: Vec<&str> = name.splitn(2, '/').collect();
-            if parts.len() != 2 {
-                anyhow::bail!("Invalid theme format. Use 'package/theme' or 'theme'.");
-            }
-            let (package_name, theme_name) = (parts[0], parts[1]);
-            paths::packages_dir()
-                .join(package_name)
-                .join("themes")
-                .join(format!("{}.toml", theme_name))
-        } else {
-            // Local theme
-            paths::themes_dir().join(format!("{}.toml", name))
-        };
+        let theme_path = theme_path(name);
 
         if theme_path.exists() {
-            let content = fs::read_to_string(&theme_path)?;
-            let mut theme: Theme = toml::from_str(&content)?;
-
-            // Handle inheritance
-            if let Some(ref parent_name) = theme.extends.clone() {
-                let parent = Self::load_with_depth(parent_name, depth + 1)?;
-                theme = theme.merge_with_parent(parent);
-            }
-
-            Ok(theme)
+            Self::load_file_with_depth(&theme_path, depth)
         } else if name.contains('/') {
             // Package theme not found - give specific error
             let parts: Vec<&str> = name.splitn(2, '/').collect();
@@ -468,12 +692,31 @@ impl Theme {
         }
     }
 
+    /// Load a theme file directly, resolving `extends` by theme name.
+    pub fn load_file(path: &Path) -> Result<Self> {
+        Self::load_file_with_depth(path, 0)
+    }
+
+    fn load_file_with_depth(path: &Path, depth: usize) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let mut theme: Theme = toml::from_str(&content)?;
+
+        // Handle inheritance
+        if let Some(ref parent_name) = theme.extends.clone() {
+            let parent = Self::load_with_depth(parent_name, depth + 1)?;
+            theme = theme.merge_with_parent(parent);
+        }
+
+        Ok(theme)
+    }
+
     /// Merge this theme with a parent theme. Child values override parent values.
     fn merge_with_parent(mut self, parent: Theme) -> Self {
         // Prompt: use child's values, but keep parent's if child doesn't specify
         // For prompt, we consider empty strings as "not specified"
         if self.prompt.format.is_empty() {
             self.prompt.format = parent.prompt.format;
+            self.prompt.preserve_whitespace = parent.prompt.preserve_whitespace;
         }
         if self.prompt.char == default_prompt_char() && parent.prompt.char != default_prompt_char()
         {
@@ -492,6 +735,11 @@ impl Theme {
         }
         self.plugins = merged_plugins;
 
+        // Icons: merge, child overrides parent for same type
+        let mut merged_icons = parent.icons;
+        merged_icons.extend(self.icons);
+        self.icons = merged_icons;
+
         // Colors: merge simple colors
         if self.colors.path.is_none() {
             self.colors.path = parent.colors.path;
@@ -525,9 +773,63 @@ impl Theme {
         }
         self.colors.conditional = merged_conditional;
 
+        // Background overrides: child overrides parent for same color
+        let mut merged_light = parent.colors.light;
+        merged_light.extend(self.colors.light);
+        self.colors.light = merged_light;
+        let mut merged_dark = parent.colors.dark;
+        merged_dark.extend(self.colors.dark);
+        self.colors.dark = merged_dark;
+
         self
     }
 
+    /// Set the terminal background used when resolving colors.
+    pub fn set_background(&mut self, background: Background) {
+        self.background = background;
+    }
+
+    /// Record the last command's exit code.
+    pub fn set_last_exit(&mut self, code: i32) {
+        self.last_exit = code;
+    }
+
+    /// Exit code of the last command (0 before any has run).
+    pub fn last_exit(&self) -> i32 {
+        self.last_exit
+    }
+
+    /// Record the shell state shown by `{shlvl}` and `{bg_status}`.
+    pub fn set_shell_state(&mut self, state: PromptState) {
+        self.shell_state = state;
+    }
+
+    /// Shell state last passed to `set_shell_state`.
+    pub fn shell_state(&self) -> PromptState {
+        self.shell_state
+    }
+
+    /// Exit code of the last background job if it failed.
+    fn failed_job_exit(&self) -> Option<u8> {
+        self.shell_state.last_job_exit.filter(|&code| code != 0)
+    }
+
+    /// Evaluate a `{#if COND}` condition; unknown conditions are false.
+    fn condition_holds(&self, condition: &str) -> bool {
+        let (negated, name) = match condition.trim().strip_prefix('!') {
+            Some(name) => (true, name.trim()),
+            None => (false, condition.trim()),
+        };
+        let holds = match name {
+            "error" => self.last_exit != 0,
+            "success" => self.last_exit == 0,
+            "bg_failed" => self.failed_job_exit().is_some(),
+            "ssh" => nosh_context::detectors::ssh::is_ssh(),
+            _ => return false,
+        };
+        holds != negated
+    }
+
     /// Check if a plugin is enabled in this theme.
     pub fn is_plugin_enabled(&self, name: &str) -> bool {
         self.plugins.get(name).map(|p| p.enabled).unwrap_or(true) // Enabled by default
@@ -537,26 +839,30 @@ impl Theme {
     /// Note: Prefer `format_prompt_with_values` for async operation with pre-fetched values.
     #[allow(dead_code)]
     pub fn format_prompt(&self, plugin_manager: &mut PluginManager) -> String {
-        let mut result = self.prompt.format.clone();
-
-        // Expand built-in variables
-        result = self.expand_builtin_vars(&result);
+        self.render_lines(|line| {
+            // Expand built-in variables
+            let mut result = self.expand_builtin_vars(line);
+            result = self.expand_summary(&result, plugin_manager);
 
-        // Expand plugin variables
-        result = self.expand_plugin_vars(&result, plugin_manager);
+            // Expand plugin variables
+            result = self.expand_plugin_vars(&result, plugin_manager);
 
-        // Apply styled segments [text](color) -> ANSI colored text
-        result = self.expand_styled_segments(&result);
+            // Apply styled segments [text](color) -> ANSI colored text
+            result = self.expand_styled_segments(&result);
 
-        // Clean up empty segments and extra whitespace
-        result = self.cleanup_empty_segments(&result);
-
-        result
+            // Clean up empty segments and extra whitespace
+            self.cleanup_empty_segments(&result)
+        })
     }
 
-    /// Expand built-in variables like {cwd}, {cwd_short}, {user}, {host}, {newline}, {dir}, {prompt:char}.
+    /// Expand built-in variables like {cwd}, {cwd_short}, {user}, {host}, {ssh}, {newline}, {dir}, {prompt:char}, {prompt:mode}, {time}, {date}, {env:NAME}.
     fn expand_builtin_vars(&self, format: &str) -> String {
-        let mut result = format.to_string();
+        // {#if error}...{/if} - keep or drop blocks by the last exit status
+        let mut result = if format.contains("{#if ") {
+            expand_conditionals(format, |cond| self.condition_holds(cond))
+        } else {
+            format.to_string()
+        };
 
         // {newline} - line break
         result = result.replace("{newline}", "\n");
@@ -599,9 +905,58 @@ impl Theme {
             result = result.replace("{host}", &host);
         }
 
-        // {prompt:char} - prompt character
+        // {ssh} - "ssh" in a remote session, otherwise empty
+        if result.contains("{ssh}") {
+            let ssh = if nosh_context::detectors::ssh::is_ssh() {
+                "ssh"
+            } else {
+                ""
+            };
+            result = result.replace("{ssh}", ssh);
+        }
+
+        // {prompt:char} - prompt character (char_error after a failed command)
         if result.contains("{prompt:char}") {
-            result = result.replace("{prompt:char}", &self.prompt.char);
+            let char = if self.last_exit != 0 {
+                &self.prompt.char_error
+            } else {
+                &self.prompt.char
+            };
+            result = result.replace("{prompt:char}", char);
+        }
+
+        // {prompt:mode} - safety mode marker (empty unless paranoid)
+        if result.contains("{prompt:mode}") {
+            let mode = if crate::safety::is_paranoid() {
+                "paranoid"
+            } else {
+                ""
+            };
+            result = result.replace("{prompt:mode}", mode);
+        }
+
+        // {shlvl} - shell nesting depth
+        if result.contains("{shlvl}") {
+            result = result.replace("{shlvl}", &self.shell_state.shlvl.to_string());
+        }
+
+        // {bg_status} - exit code of the last background job, if it failed
+        if result.contains("{bg_status}") {
+            let status = self
+                .failed_job_exit()
+                .map(|code| code.to_string())
+                .unwrap_or_default();
+            result = result.replace("{bg_status}", &status);
+        }
+
+        // {time}, {date}, {time:%H:%M} - local clock, refreshed on each redraw
+        if result.contains("{time") || result.contains("{date") {
+            result = expand_time_vars(&result, &Local::now());
+        }
+
+        // {env:NAME} - value of $NAME (empty if unset)
+        if result.contains("{env:") {
+            result = expand_env_vars(&result, |name| std::env::var(name).ok());
         }
 
         result
@@ -669,11 +1024,7 @@ impl Theme {
         }
 
         // Clean up multiple spaces
-        while result.contains("  ") {
-            result = result.replace("  ", " ");
-        }
-
-        result
+        self.collapse_spaces(result)
     }
 
     /// Expand styled segments: [content](color) -> ANSI colored content.
@@ -696,15 +1047,37 @@ impl Theme {
         .to_string()
     }
 
-    /// Resolve a color name, potentially using conditional color rules.
+    /// Resolve a color name, potentially using conditional color rules and
+    /// the override table for the current background.
     fn resolve_color(&self, color_name: &str, content: &str) -> String {
+        let overrides = self.colors.overrides(self.background);
+        if let Some(color) = overrides.get(color_name) {
+            return color.clone();
+        }
+
         // Check if it's a conditional color
-        if let Some(conditional) = self.colors.conditional.get(color_name) {
-            conditional.resolve(content).to_string()
+        let resolved = if let Some(conditional) = self.colors.conditional.get(color_name) {
+            conditional.resolve(content)
         } else {
-            // Return the color name as-is (simple color)
-            color_name.to_string()
+            // Use the color name as-is (simple color)
+            color_name
+        };
+        overrides
+            .get(resolved)
+            .cloned()
+            .unwrap_or_else(|| resolved.to_string())
+    }
+
+    /// Collapse runs of spaces left behind by hidden segments, unless the
+    /// theme preserves whitespace.
+    fn collapse_spaces(&self, mut text: String) -> String {
+        if self.prompt.preserve_whitespace {
+            return text;
         }
+        while text.contains("  ") {
+            text = text.replace("  ", " ");
+        }
+        text
     }
 
     /// Clean up empty segments and excessive whitespace.
@@ -715,15 +1088,678 @@ impl Theme {
         let empty_re = Regex::new(r"\[\s*\]\([^)]+\)").unwrap();
         result = empty_re.replace_all(&result, "").to_string();
 
-        // Clean up multiple spaces (but preserve intentional newlines)
-        while result.contains("  ") {
-            result = result.replace("  ", " ");
+        if self.prompt.preserve_whitespace {
+            return result;
         }
 
+        // Clean up multiple spaces (but preserve intentional newlines)
+        result = self.collapse_spaces(result);
+
         // Clean up spaces at the start of lines (after newlines)
         let line_start_re = Regex::new(r"\n +").unwrap();
         result = line_start_re.replace_all(&result, "\n").to_string();
 
         result
     }
+
+    /// Check the prompt format for authoring mistakes: unbalanced `{}`,
+    /// `[]` or `()`, unknown built-in variables, and plugin variables that
+    /// no loaded plugin provides. Disabled plugins are not checked.
+    pub fn lint(&self, plugin_manager: &PluginManager) -> Vec<LintWarning> {
+        let format = &self.prompt.format.joined();
+        let mut warnings = lint_delimiters(format);
+
+        let var_re = Regex::new(r"\{([^{}]*)\}").unwrap();
+        for caps in var_re.captures_iter(format) {
+            let var = &caps[1];
+            if BUILTIN_VARS.contains(&var) || var == "/if" {
+                continue;
+            }
+            if let Some(condition) = var.strip_prefix("#if ") {
+                let name = condition.trim().trim_start_matches('!').trim();
+                if !CONDITIONS.contains(&name) {
+                    warnings.push(LintWarning::new(format!(
+                        "{{{}}}: unknown condition '{}', expected one of {}",
+                        var,
+                        name,
+                        CONDITIONS.join(", ")
+                    )));
+                }
+                continue;
+            }
+            if let Some(name) = var.strip_prefix("env:") {
+                if !is_env_name(name) {
+                    warnings.push(LintWarning::new(format!(
+                        "malformed variable {{{}}}, expected {{env:NAME}}",
+                        var
+                    )));
+                }
+                continue;
+            }
+            if let Some(fmt) = var
+                .strip_prefix("time:")
+                .or_else(|| var.strip_prefix("date:"))
+            {
+                if format_datetime(&Local::now(), fmt).is_none() {
+                    warnings.push(LintWarning::new(format!(
+                        "{{{}}}: invalid strftime format '{}'",
+                        var, fmt
+                    )));
+                }
+                continue;
+            }
+            let Some((plugin, name)) = var.split_once(':') else {
+                warnings.push(LintWarning::new(format!(
+                    "unknown built-in variable {{{}}}",
+                    var
+                )));
+                continue;
+            };
+            if plugin.is_empty() || name.is_empty() || name.contains(':') {
+                warnings.push(LintWarning::new(format!(
+                    "malformed variable {{{}}}, expected {{plugin:variable}}",
+                    var
+                )));
+                continue;
+            }
+            if !self.is_plugin_enabled(plugin) {
+                continue;
+            }
+            match plugin_manager.plugin_provides(plugin, name) {
+                Some(true) => {}
+                Some(false) => warnings.push(LintWarning::new(format!(
+                    "{{{}}}: plugin '{}' has no variable '{}'",
+                    var, plugin, name
+                ))),
+                None => {
+                    let candidates = plugin_manager.plugins_named(plugin);
+                    warnings.push(LintWarning::new(if candidates.is_empty() {
+                        format!("{{{}}}: plugin '{}' is not loaded", var, plugin)
+                    } else {
+                        format!(
+                            "{{{}}}: plugin '{}' is not loaded; did you mean {}?",
+                            var,
+                            plugin,
+                            candidates.join(" or ")
+                        )
+                    }))
+                }
+            }
+        }
+
+        warnings
+    }
+}
+
+/// Keep or drop `{#if COND}...{/if}` blocks, keeping the body when
+/// `holds(COND)` is true. Blocks don't nest; an unterminated block is left
+/// as written.
+fn expand_conditionals(format: &str, holds: impl Fn(&str) -> bool) -> String {
+    let mut result = String::with_capacity(format.len());
+    let mut rest = format;
+
+    while let Some(open) = rest.find("{#if ") {
+        let after_open = &rest[open..];
+        let Some(cond_end) = after_open.find('}') else {
+            break;
+        };
+        let body = &after_open[cond_end + 1..];
+        let Some(close) = body.find("{/if}") else {
+            break;
+        };
+
+        result.push_str(&rest[..open]);
+        if holds(&after_open["{#if ".len()..cond_end]) {
+            result.push_str(&body[..close]);
+        }
+        rest = &body[close + "{/if}".len()..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Expand `{env:NAME}` tokens in a single left-to-right scan.
+///
+/// Values are inserted verbatim and never rescanned, and braces are dropped
+/// from them so later passes can't treat the value as another variable.
+/// Long values are truncated with an ellipsis. Tokens whose name isn't a
+/// valid variable name are left as written.
+fn expand_env_vars(format: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    expand_tokens(format, "env", |name| {
+        is_env_name(name).then(|| sanitize_env_value(&lookup(name).unwrap_or_default()))
+    })
+}
+
+/// Replace `{prefix:ARG}` tokens in a single left-to-right scan.
+///
+/// `expand` returns `None` to leave a token as written.
+fn expand_tokens(format: &str, prefix: &str, expand: impl Fn(&str) -> Option<String>) -> String {
+    let opener = format!("{{{}:", prefix);
+    let mut result = String::with_capacity(format.len());
+    let mut rest = format;
+    while let Some(start) = rest.find(&opener) {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + opener.len()..];
+        match after
+            .find('}')
+            .and_then(|end| Some((end, expand(&after[..end])?)))
+        {
+            Some((end, value)) => {
+                result.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                result.push_str(&opener);
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Expand `{time}`, `{date}` and their `{time:FORMAT}` / `{date:FORMAT}`
+/// strftime variants against `now`. Invalid formats are left as written.
+fn expand_time_vars(format: &str, now: &DateTime<Local>) -> String {
+    let mut result = format
+        .replace("{time}", &now.format(DEFAULT_TIME_FORMAT).to_string())
+        .replace("{date}", &now.format(DEFAULT_DATE_FORMAT).to_string());
+    for prefix in ["time", "date"] {
+        result = expand_tokens(&result, prefix, |fmt| format_datetime(now, fmt));
+    }
+    result
+}
+
+/// Format `now` with a strftime string, or `None` if the string is invalid.
+fn format_datetime(now: &DateTime<Local>, fmt: &str) -> Option<String> {
+    let items: Vec<Item> = StrftimeItems::new(fmt).collect();
+    if items.contains(&Item::Error) {
+        return None;
+    }
+    Some(now.format_with_items(items.into_iter()).to_string())
+}
+
+/// Whether `name` looks like an environment variable name.
+fn is_env_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Make an environment value safe to splice into a prompt format.
+fn sanitize_env_value(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .filter(|c| !matches!(c, '{' | '}') && !c.is_control())
+        .collect();
+    if cleaned.chars().count() > ENV_VALUE_MAX_CHARS {
+        let mut truncated: String = cleaned.chars().take(ENV_VALUE_MAX_CHARS - 1).collect();
+        truncated.push('…');
+        truncated
+    } else {
+        cleaned
+    }
+}
+
+/// Names of installed themes (`name` for local, `package/name` for packages), sorted.
+pub fn theme_names() -> Vec<String> {
+    theme_names_in(&paths::nosh_config_dir())
+}
+
+/// Theme names under a config directory's `themes/` and `packages/*/themes/`.
+pub fn theme_names_in(config_dir: &Path) -> Vec<String> {
+    let stems = |dir: &Path| -> Vec<String> {
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+            .collect()
+    };
+
+    let mut names = stems(&config_dir.join("themes"));
+    for entry in fs::read_dir(config_dir.join("packages"))
+        .into_iter()
+        .flatten()
+        .flatten()
+    {
+        let package = entry.file_name().to_string_lossy().to_string();
+        for theme in stems(&entry.path().join("themes")) {
+            names.push(format!("{}/{}", package, theme));
+        }
+    }
+    names.sort();
+    names
+}
+
+/// Path of the file for a theme name.
+///
+/// `package/theme` resolves to `~/.config/nosh/packages/package/themes/theme.toml`,
+/// a plain name to `~/.config/nosh/themes/name.toml`.
+pub fn theme_path(name: &str) -> PathBuf {
+    match name.split_once('/') {
+        // Package theme: package/theme format
+        Some((package_name, theme_name)) => paths::packages_dir()
+            .join(package_name)
+            .join("themes")
+            .join(format!("{}.toml", theme_name)),
+        // Local theme
+        None => paths::themes_dir().join(format!("{}.toml", name)),
+    }
+}
+
+/// Condense an error (TOML errors include a multi-line source snippet) to one line.
+fn one_line_error(e: &anyhow::Error) -> String {
+    let first_line = |s: String| s.lines().next().unwrap_or_default().trim().to_string();
+    match e.downcast_ref::<toml::de::Error>() {
+        Some(toml_err) => {
+            let location = first_line(toml_err.to_string());
+            let message = toml_err.message().trim();
+            if location == message {
+                location
+            } else {
+                format!("{}: {}", location, message)
+            }
+        }
+        None => first_line(e.to_string()),
+    }
+}
+
+/// Check that `{}` pairs are balanced and every `[text]` is followed by a
+/// closed `(style)`.
+fn lint_delimiters(format: &str) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    let chars: Vec<char> = format.chars().collect();
+    let mut brace: Option<usize> = None;
+    let mut bracket: Option<usize> = None;
+
+    let mut i = 0;
+    while i < chars.len() {
+        let col = i + 1;
+        match chars[i] {
+            '{' => {
+                if let Some(open) = brace {
+                    warnings.push(LintWarning::new(format!(
+                        "'{{' at column {} is never closed",
+                        open
+                    )));
+                }
+                brace = Some(col);
+            }
+            '}' if brace.take().is_none() => {
+                warnings.push(LintWarning::new(format!(
+                    "unmatched '}}' at column {}",
+                    col
+                )));
+            }
+            '[' if brace.is_none() => {
+                if let Some(open) = bracket {
+                    warnings.push(LintWarning::new(format!(
+                        "'[' at column {} is never closed",
+                        open
+                    )));
+                }
+                bracket = Some(col);
+            }
+            ']' if brace.is_none() => {
+                let Some(open) = bracket.take() else {
+                    warnings.push(LintWarning::new(format!("unmatched ']' at column {}", col)));
+                    i += 1;
+                    continue;
+                };
+                if chars.get(i + 1) != Some(&'(') {
+                    warnings.push(LintWarning::new(format!(
+                        "segment '[' at column {} has no (style) after it",
+                        open
+                    )));
+                } else {
+                    match chars[i + 1..].iter().position(|&c| c == ')') {
+                        Some(len) => i += len + 1,
+                        None => warnings.push(LintWarning::new(format!(
+                            "'(' at column {} is never closed",
+                            col + 1
+                        ))),
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    if let Some(open) = brace {
+        warnings.push(LintWarning::new(format!(
+            "'{{' at column {} is never closed",
+            open
+        )));
+    }
+    if let Some(open) = bracket {
+        warnings.push(LintWarning::new(format!(
+            "'[' at column {} is never closed",
+            open
+        )));
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_conditional() {
+        let mut theme = Theme::default();
+        theme.prompt.char_error = "✗".to_string();
+        let format = "{#if error}[✗ failed](red) {/if}{#if !error}ok {/if}{prompt:char}";
+
+        assert_eq!(theme.expand_builtin_vars(format), "ok ❯");
+
+        theme.set_last_exit(127);
+        assert_eq!(theme.expand_builtin_vars(format), "[✗ failed](red) ✗");
+
+        theme.set_last_exit(0);
+        assert_eq!(theme.expand_builtin_vars(format), "ok ❯");
+
+        // Unknown conditions hide the block, unterminated blocks stay as written
+        assert_eq!(expand_conditionals("a{#if nope}b{/if}c", |_| true), "abc");
+        assert_eq!(theme.expand_builtin_vars("a{#if nope}b{/if}c"), "ac");
+        assert_eq!(
+            expand_conditionals("a{#if error}b", |_| true),
+            "a{#if error}b"
+        );
+    }
+
+    #[test]
+    fn test_ssh_conditional() {
+        let theme = Theme::default();
+        let format = "{#if ssh}[{user}@{host}](dim) {/if}{ssh}";
+        let expanded = theme.expand_builtin_vars(format);
+        if nosh_context::detectors::ssh::is_ssh() {
+            assert!(expanded.starts_with('[') && expanded.ends_with("(dim) ssh"));
+        } else {
+            assert_eq!(expanded, "");
+        }
+    }
+
+    #[test]
+    fn test_shell_state_vars() {
+        let mut theme = Theme::default();
+        let format = "{shlvl}|{bg_status}|{#if bg_failed}bg!{/if}";
+        assert_eq!(theme.expand_builtin_vars(format), "0||");
+
+        theme.set_shell_state(PromptState {
+            shlvl: 2,
+            last_job_exit: Some(0),
+        });
+        assert_eq!(theme.expand_builtin_vars(format), "2||");
+
+        theme.set_shell_state(PromptState {
+            shlvl: 2,
+            last_job_exit: Some(3),
+        });
+        assert_eq!(theme.expand_builtin_vars(format), "2|3|bg!");
+    }
+
+    #[test]
+    fn test_malformed_theme_falls_back_to_default() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_bad_theme_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("broken.toml");
+        fs::write(&path, "[prompt]\nformat = \"{cwd} $ \nchar = 1\n").unwrap();
+
+        let (theme, warning) = Theme::or_builtin_default(&path, Theme::load_file(&path));
+        let builtin: Theme = toml::from_str(super::super::builtins::DEFAULT_THEME).unwrap();
+        assert_eq!(theme.prompt.format.joined(), builtin.prompt.format.joined());
+
+        let warning = warning.unwrap();
+        assert!(warning.contains("broken.toml"), "{}", warning);
+        assert!(warning.contains("line 2"), "{}", warning);
+        assert!(!warning.contains('\n'));
+
+        let (_, warning) = Theme::or_builtin_default(&path, Ok(Theme::default()));
+        assert_eq!(warning, None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    fn theme_with(format: &str) -> Theme {
+        let mut theme = Theme::default();
+        theme.prompt.format = format.into();
+        theme
+    }
+
+    #[test]
+    fn test_preserve_whitespace() {
+        let format = "[{user}](green)    {env:NOSH_TEST_UNSET_VAR}{dir}\n    [>](blue) ";
+        let values = HashMap::new();
+        let mut manager = PluginManager::new();
+
+        let mut theme = theme_with(format);
+        let collapsed = theme.format_prompt_with_values(&values, &mut manager);
+        assert!(!collapsed.contains("  "));
+        assert!(collapsed.contains("\n\x1b[34m>"));
+
+        theme.prompt.preserve_whitespace = true;
+        let preserved = theme.format_prompt_with_values(&values, &mut manager);
+        assert!(preserved.contains(&format!("{}    ", RESET)));
+        assert!(preserved.contains("\n    \x1b[34m>"));
+    }
+
+    #[test]
+    fn test_format_lines_match_joined_string() {
+        let lines: Theme = toml::from_str(
+            r#"
+[prompt]
+format = [
+    "[{user}](green) [{env:NOSH_TEST_UNSET_VAR}](red) {dir}",
+    "[{prompt:char}](blue) ",
+]
+"#,
+        )
+        .unwrap();
+        assert!(matches!(lines.prompt.format, PromptFormat::Lines(_)));
+        let single: Theme = toml::from_str(
+            r#"
+[prompt]
+format = "[{user}](green) [{env:NOSH_TEST_UNSET_VAR}](red) {dir}\n[{prompt:char}](blue) "
+"#,
+        )
+        .unwrap();
+        assert!(matches!(single.prompt.format, PromptFormat::Single(_)));
+
+        let values = HashMap::new();
+        let mut manager = PluginManager::new();
+        let rendered = lines.format_prompt_with_values(&values, &mut manager);
+        assert_eq!(
+            rendered,
+            single.format_prompt_with_values(&values, &mut manager)
+        );
+        assert_eq!(rendered.lines().count(), 2);
+        assert_eq!(lines.get_plugin_variables(), single.get_plugin_variables());
+    }
+
+    #[test]
+    fn test_expand_env_vars() {
+        let lookup = |name: &str| match name {
+            "AWS_PROFILE" => Some("staging".to_string()),
+            "NESTED" => Some("{env:AWS_PROFILE}".to_string()),
+            "LONG" => Some("x".repeat(200)),
+            _ => None,
+        };
+        assert_eq!(
+            expand_env_vars(
+                "[{env:AWS_PROFILE}](yellow) [{env:NOSH_UNSET}](red)",
+                lookup
+            ),
+            "[staging](yellow) [](red)"
+        );
+        // Values are not expanded again
+        assert_eq!(expand_env_vars("{env:NESTED}", lookup), "env:AWS_PROFILE");
+        let long = expand_env_vars("{env:LONG}", lookup);
+        assert_eq!(long.chars().count(), ENV_VALUE_MAX_CHARS);
+        assert!(long.ends_with('…'));
+        assert_eq!(
+            expand_env_vars("{env:} {env:A B}", lookup),
+            "{env:} {env:A B}"
+        );
+
+        let theme = theme_with("[{env:HOME}](blue) [{env:bad name}](red)");
+        let warnings = theme.lint(&PluginManager::new());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].to_string().contains("{env:NAME}"));
+    }
+
+    #[test]
+    fn test_time_vars() {
+        let theme = theme_with("");
+        let year = theme.expand_builtin_vars("{time:%Y}");
+        assert_eq!(year.len(), 4, "{year:?}");
+        assert!(year.chars().all(|c| c.is_ascii_digit()));
+
+        let now = Local::now();
+        let clock = expand_time_vars("[{time}](dim) {date} {time:%H:%M}", &now);
+        assert_eq!(
+            clock,
+            format!(
+                "[{}](dim) {} {}",
+                now.format("%H:%M:%S"),
+                now.format("%Y-%m-%d"),
+                now.format("%H:%M")
+            )
+        );
+        assert_eq!(expand_time_vars("{time:%Q}", &now), "{time:%Q}");
+
+        let warnings = theme_with("{date:%d/%m} {time:%Q}").lint(&PluginManager::new());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].to_string().contains("invalid strftime"));
+    }
+
+    #[test]
+    fn test_background_overrides() {
+        let mut theme: Theme = toml::from_str(
+            r#"
+[prompt]
+format = "[{dir}](path) [{user}](white)"
+
+[colors.light]
+path = "blue"
+white = "black"
+
+[colors.dark]
+path = "cyan"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(theme.resolve_color("path", "src"), "cyan");
+        assert_eq!(theme.resolve_color("white", "me"), "white");
+
+        theme.set_background(Background::Light);
+        assert_eq!(theme.resolve_color("path", "src"), "blue");
+        assert_eq!(theme.resolve_color("white", "me"), "black");
+        assert_eq!(theme.resolve_color("red", "x"), "red");
+        assert!(!theme.colors.conditional.contains_key("light"));
+    }
+
+    #[test]
+    fn test_set_plugin_enabled_persists() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_toggle_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mine.toml");
+        fs::write(
+            &path,
+            r#"# My theme
+[prompt]
+format = "[{dir}](blue) $ "
+
+[plugins]
+# Keep the timer quiet for short commands
+"builtins/exec_time" = { enabled = true, min_ms = 1000 }
+"#,
+        )
+        .unwrap();
+
+        Theme::set_plugin_enabled(&path, "builtins/exec_time", false).unwrap();
+        Theme::set_plugin_enabled(&path, "builtins/context", false).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("# Keep the timer quiet for short commands"));
+        assert!(content.contains("min_ms = 1000"));
+
+        let theme: Theme = toml::from_str(&content).unwrap();
+        assert!(!theme.is_plugin_enabled("builtins/exec_time"));
+        assert!(!theme.is_plugin_enabled("builtins/context"));
+        assert!(theme.is_plugin_enabled("other"));
+
+        // Toggling back on
+        Theme::set_plugin_enabled(&path, "builtins/exec_time", true).unwrap();
+        let theme: Theme = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(theme.is_plugin_enabled("builtins/exec_time"));
+
+        // Symlinked themes are refused
+        let link = dir.join("linked.toml");
+        std::os::unix::fs::symlink(&path, &link).unwrap();
+        assert!(Theme::set_plugin_enabled(&link, "builtins/context", true).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_lint_clean_format() {
+        let theme = theme_with("[{dir}](blue bold) [{prompt:char}](green) ");
+        assert!(theme.lint(&PluginManager::new()).is_empty());
+    }
+
+    #[test]
+    fn test_lint_unbalanced_format() {
+        let messages = |format: &str| -> Vec<String> {
+            theme_with(format)
+                .lint(&PluginManager::new())
+                .into_iter()
+                .map(|w| w.message)
+                .collect()
+        };
+
+        assert_eq!(
+            messages("[{dir}](blue $ "),
+            vec!["'(' at column 8 is never closed"]
+        );
+        assert_eq!(messages("{dir $ "), vec!["'{' at column 1 is never closed"]);
+        assert_eq!(messages("dir} $ "), vec!["unmatched '}' at column 4"]);
+        assert_eq!(
+            messages("[{dir}(blue) $ "),
+            vec!["'[' at column 1 is never closed"]
+        );
+        assert_eq!(
+            messages("[{dir}] $ "),
+            vec!["segment '[' at column 1 has no (style) after it"]
+        );
+        assert_eq!(
+            messages("{dri} $ "),
+            vec!["unknown built-in variable {dri}"]
+        );
+    }
+
+    #[test]
+    fn test_lint_unknown_plugin() {
+        let theme = theme_with("{dir} [{foo:bar}](red) ");
+        let warnings = theme.lint(&PluginManager::new());
+        assert_eq!(
+            warnings,
+            vec![LintWarning::new("{foo:bar}: plugin 'foo' is not loaded")]
+        );
+
+        // Disabled plugins are skipped
+        let mut theme = theme;
+        theme.plugins.insert(
+            "foo".to_string(),
+            PluginConfig {
+                enabled: false,
+                style: None,
+                min_ms: None,
+            },
+        );
+        assert!(theme.lint(&PluginManager::new()).is_empty());
+    }
 }
@@ -0,0 +1,185 @@
+//! Transform pipelines for command-based plugin variables.
+//!
+//! A `transform` is one or more stages separated by `|`, applied left to
+//! right to the command's output, e.g. `"trim|truncate:10|uppercase"`.
+
+use super::Plugin;
+
+/// A single transform stage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Stage {
+    /// Trim surrounding whitespace.
+    Trim,
+    /// Replace the value with the `dirty` or `clean` icon.
+    NonEmpty,
+    /// Prefix the variable's icon; hide the variable when empty.
+    WithIcon,
+    /// Keep at most N characters, marking cut values with `…`.
+    Truncate(usize),
+    Uppercase,
+    Lowercase,
+}
+
+impl Stage {
+    fn parse(spec: &str) -> Option<Self> {
+        let (name, arg) = match spec.split_once(':') {
+            Some((name, arg)) => (name.trim(), Some(arg.trim())),
+            None => (spec, None),
+        };
+        match (name, arg) {
+            ("trim", None) => Some(Stage::Trim),
+            ("non_empty", None) => Some(Stage::NonEmpty),
+            ("with_icon", None) => Some(Stage::WithIcon),
+            ("truncate", Some(n)) => n.parse().ok().map(Stage::Truncate),
+            ("uppercase", None) => Some(Stage::Uppercase),
+            ("lowercase", None) => Some(Stage::Lowercase),
+            _ => None,
+        }
+    }
+
+    fn apply(&self, plugin: &Plugin, var_name: &str, value: String) -> Option<String> {
+        match self {
+            Stage::Trim => Some(value.trim().to_string()),
+            Stage::NonEmpty => {
+                let icon = if value.is_empty() { "clean" } else { "dirty" };
+                plugin.icons.get(icon).cloned()
+            }
+            Stage::WithIcon => {
+                if value.is_empty() {
+                    None // Hide entirely when empty
+                } else if let Some(icon) = plugin.icons.get(var_name) {
+                    Some(format!("{} {}", icon, value))
+                } else {
+                    Some(value)
+                }
+            }
+            Stage::Truncate(max) => {
+                if value.chars().count() > *max {
+                    let cut: String = value.chars().take(*max).collect();
+                    Some(format!("{}…", cut))
+                } else {
+                    Some(value)
+                }
+            }
+            Stage::Uppercase => Some(value.to_uppercase()),
+            Stage::Lowercase => Some(value.to_lowercase()),
+        }
+    }
+}
+
+/// A parsed `transform` value.
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    stages: Vec<Stage>,
+    unknown: Vec<String>,
+}
+
+impl Pipeline {
+    /// Parse a `|`-separated list of stages. Unknown stages are recorded
+    /// and skipped when the pipeline is applied.
+    pub fn parse(spec: &str) -> Self {
+        let mut pipeline = Pipeline::default();
+        for part in spec.split('|').map(str::trim).filter(|p| !p.is_empty()) {
+            match Stage::parse(part) {
+                Some(stage) => pipeline.stages.push(stage),
+                None => pipeline.unknown.push(part.to_string()),
+            }
+        }
+        pipeline
+    }
+
+    /// Stages that weren't recognised (shown by `/debug`).
+    pub fn unknown(&self) -> &[String] {
+        &self.unknown
+    }
+
+    /// Run `output` through each stage in order. `None` hides the variable.
+    pub fn apply(&self, plugin: &Plugin, var_name: &str, output: String) -> Option<String> {
+        self.stages
+            .iter()
+            .try_fold(output, |value, stage| stage.apply(plugin, var_name, value))
+    }
+}
+
+/// Apply an optional `transform` to a command's output.
+pub fn apply(
+    transform: Option<&str>,
+    plugin: &Plugin,
+    var_name: &str,
+    output: String,
+) -> Option<String> {
+    match transform {
+        Some(spec) => Pipeline::parse(spec).apply(plugin, var_name, output),
+        None => Some(output),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin() -> Plugin {
+        toml::from_str(
+            r#"
+[plugin]
+name = "test"
+
+[provides]
+branch = { command = "echo main" }
+
+[icons]
+dirty = "*"
+clean = ""
+branch = ""
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_three_stage_pipeline() {
+        let plugin = plugin();
+        let pipeline = Pipeline::parse("trim|truncate:10|uppercase");
+        assert!(pipeline.unknown().is_empty());
+        assert_eq!(
+            pipeline.apply(&plugin, "branch", "  feature/long-branch-name \n".into()),
+            Some("FEATURE/LO…".to_string())
+        );
+        assert_eq!(
+            pipeline.apply(&plugin, "branch", " main ".into()),
+            Some("MAIN".to_string())
+        );
+    }
+
+    #[test]
+    fn test_single_transforms_unchanged() {
+        let plugin = plugin();
+        assert_eq!(
+            apply(Some("non_empty"), &plugin, "status", "M file".into()),
+            Some("*".to_string())
+        );
+        assert_eq!(
+            apply(Some("with_icon"), &plugin, "branch", "main".into()),
+            Some(" main".to_string())
+        );
+        assert_eq!(
+            apply(Some("with_icon"), &plugin, "branch", String::new()),
+            None
+        );
+        assert_eq!(
+            apply(None, &plugin, "branch", "main".into()),
+            Some("main".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unknown_stage_is_noop() {
+        let plugin = plugin();
+        let pipeline = Pipeline::parse("uppercase|sparkle|truncate:x");
+        assert_eq!(pipeline.unknown(), ["sparkle", "truncate:x"]);
+        assert_eq!(
+            pipeline.apply(&plugin, "branch", "main".into()),
+            Some("MAIN".to_string())
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Editing commands in `$EDITOR`.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Result, bail};
+
+use crate::fs_util;
+use crate::paths;
+
+/// Open `command` in `$EDITOR` and return the edited text.
+///
+/// Returns `None` if the editor failed, or the text was emptied or left unchanged.
+pub fn edit_command(command: &str) -> Result<Option<String>> {
+    let dir = paths::tmp_dir();
+    fs_util::create_private_dir(&dir)?;
+    let path = dir.join(format!("edit-{}.sh", std::process::id()));
+    // Left over from a crashed session with the same pid
+    let _ = fs::remove_file(&path);
+    writeln!(fs_util::create_private_file(&path)?, "{}", command)?;
+
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(&path)
+        .status();
+
+    let edited = match status {
+        Ok(status) if status.success() => read_edited(&path, command),
+        Ok(_) => None,
+        Err(e) => {
+            let _ = fs::remove_file(&path);
+            bail!("Could not open editor '{}': {}", editor, e);
+        }
+    };
+
+    let _ = fs::remove_file(&path);
+    Ok(edited)
+}
+
+/// Read the edited command back from the temp file.
+///
+/// Trailing whitespace is trimmed and multi-line edits are kept as-is.
+fn read_edited(path: &Path, original: &str) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    let edited = content.trim_end().to_string();
+
+    if edited.trim().is_empty() || edited == original {
+        None
+    } else {
+        Some(edited)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_edited() {
+        let path = std::env::temp_dir().join(format!("nosh_test_edit_{}", std::process::id()));
+
+        fs::write(&path, "git commit -m 'fixed'\n\n").unwrap();
+        assert_eq!(
+            read_edited(&path, "git comit -m 'fixed'").as_deref(),
+            Some("git commit -m 'fixed'")
+        );
+
+        // Unchanged or emptied means nothing to load
+        assert_eq!(read_edited(&path, "git commit -m 'fixed'"), None);
+        fs::write(&path, "  \n").unwrap();
+        assert_eq!(read_edited(&path, "ls"), None);
+
+        let _ = fs::remove_file(&path);
+        assert_eq!(read_edited(&path, "ls"), None);
+    }
+}
@@ -1,17 +1,21 @@
 //! NoshHelper for rustyline - implements Completer, Hinter, Highlighter, and Validator.
 
 use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::collections::HashSet;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
 use std::rc::Rc;
 
 use rustyline::completion::Completer;
 use rustyline::highlight::{CmdKind, Highlighter};
 use rustyline::hint::Hinter;
+use rustyline::line_buffer::LineBuffer;
 use rustyline::validate::{ValidationContext, ValidationResult, Validator};
-use rustyline::{Context, Helper};
+use rustyline::{Changeset, Context, Helper};
 
 use super::words;
-use crate::completions::{Completion, CompletionManager};
+use crate::completions::{BuiltinCompleter, Completion, CompletionManager};
 
 /// Shell builtins recognized as valid commands.
 const SHELL_BUILTINS: &[&str] = &[
@@ -76,22 +80,61 @@ const SHELL_BUILTINS: &[&str] = &[
 pub struct NoshHelper {
     completion_manager: Rc<CompletionManager>,
     syntax_highlighting: bool,
-    command_cache: HashSet<String>,
+    command_cache: RefCell<CommandCache>,
+    /// End of the word being completed, from the last `complete` call
+    word_end: Cell<usize>,
+    /// Candidate texts from the last `complete` call
+    candidates: RefCell<Vec<String>>,
+    /// Whether `?`/`??` lines are AI queries
+    ai_enabled: Cell<bool>,
+    /// Loaded plugin names, for `/debug <tab>`
+    plugin_names: RefCell<Vec<String>>,
+    /// Installed theme names, for `/theme <tab>`
+    theme_names: RefCell<Vec<String>>,
 }
 
 impl NoshHelper {
     pub fn new(completion_manager: Rc<CompletionManager>, syntax_highlighting: bool) -> Self {
-        let command_cache = if syntax_highlighting {
-            build_command_cache()
-        } else {
-            HashSet::new()
-        };
+        let mut command_cache = CommandCache::default();
+        if syntax_highlighting {
+            command_cache.refresh(&completion_manager.path_var().to_string_lossy());
+        }
         Self {
             completion_manager,
             syntax_highlighting,
-            command_cache,
+            command_cache: RefCell::new(command_cache),
+            word_end: Cell::new(0),
+            candidates: RefCell::new(Vec::new()),
+            ai_enabled: Cell::new(true),
+            plugin_names: RefCell::new(Vec::new()),
+            theme_names: RefCell::new(Vec::new()),
         }
     }
+
+    /// Set the plugin and theme names offered as slash command arguments.
+    pub fn set_slash_arguments(&self, plugins: Vec<String>, themes: Vec<String>) {
+        *self.plugin_names.borrow_mut() = plugins;
+        *self.theme_names.borrow_mut() = themes;
+    }
+
+    /// Turn AI query styling, hints and word completion on or off.
+    pub fn set_ai_enabled(&self, enabled: bool) {
+        self.ai_enabled.set(enabled);
+    }
+}
+
+/// Whether a line is a `?`/`??` AI query. With AI disabled, nothing is.
+pub fn is_ai_query(line: &str, ai_enabled: bool) -> bool {
+    ai_enabled && line.starts_with('?')
+}
+
+/// Whether `/name` is one of nosh's slash commands.
+pub fn is_slash_command(name: &str) -> bool {
+    SLASH_COMMANDS.iter().any(|(cmd, _)| {
+        cmd.strip_prefix('/')
+            .and_then(|c| c.split_whitespace().next())
+            == Some(name)
+    })
 }
 
 /// Completion candidate for rustyline.
@@ -140,9 +183,21 @@ const SLASH_COMMANDS: &[(&str, &str)] = &[
     ("/upgrade", "Upgrade nosh to latest version"),
     ("/sync", "Sync config, builtins, and packages"),
     ("/packages", "List and manage installed packages"),
+    ("/plugins", "Enable or disable theme plugins"),
     ("/convert-zsh", "Convert zsh completion to TOML"),
-    ("/clear", "Clear AI conversation context"),
+    (
+        "/clear",
+        "Clear AI conversation context (--cache: purge cached translations)",
+    ),
     ("/reload", "Reload config and theme"),
+    ("/refresh", "Recompute prompt variables and project context"),
+    ("/edit", "Edit last command in $EDITOR"),
+    ("/paranoid", "Toggle paranoid safety mode"),
+    ("/alias", "List, add or remove aliases"),
+    ("/stats", "Show local usage stats"),
+    ("/sessions", "List, replay or export saved ?? sessions"),
+    ("/time", "Run a command and show its timing"),
+    ("/theme", "Switch theme for this session (lint: check it)"),
     ("/debug", "Debug plugins and theme"),
     ("/help", "Show help"),
 ];
@@ -156,23 +211,28 @@ impl Completer for NoshHelper {
         pos: usize,
         _ctx: &Context<'_>,
     ) -> rustyline::Result<(usize, Vec<Self::Candidate>)> {
-        // Check if we're in AI mode (line starts with ? or ??)
-        if line.starts_with('?') {
-            return self.complete_ai_mode(line, pos);
-        }
-
-        // Check if we're completing a slash command
-        if line.starts_with('/') {
-            return self.complete_slash_command(line, pos);
-        }
-
-        let completions = self.completion_manager.complete(line, pos);
-        let start = find_word_start(line, pos);
-
-        let candidates = completions.into_iter().map(NoshCandidate::new).collect();
+        let (start, candidates) = if is_ai_query(line, self.ai_enabled.get()) {
+            // AI mode (line starts with ? or ??)
+            self.complete_ai_mode(line, pos)?
+        } else if line.starts_with('/') {
+            self.complete_slash_command(line, pos)?
+        } else {
+            let completions = self.completion_manager.complete(line, pos);
+            let range = word_range(line, pos);
+            self.word_end.set(range.end);
+            let candidates = completions.into_iter().map(NoshCandidate::new).collect();
+            (range.start, candidates)
+        };
 
+        *self.candidates.borrow_mut() = candidates.iter().map(|c| c.text.clone()).collect();
         Ok((start, candidates))
     }
+
+    fn update(&self, line: &mut LineBuffer, start: usize, elected: &str, cl: &mut Changeset) {
+        let full = self.candidates.borrow().iter().any(|c| c == elected);
+        let range = replacement_range(line.as_str(), start, line.pos(), self.word_end.get(), full);
+        line.replace(range, elected, cl);
+    }
 }
 
 impl NoshHelper {
@@ -183,6 +243,11 @@ impl NoshHelper {
         pos: usize,
     ) -> rustyline::Result<(usize, Vec<NoshCandidate>)> {
         let prefix = &line[..pos];
+        self.word_end.set(find_word_end_simple(line, pos));
+
+        if let Some((command, arg)) = prefix.split_once(' ') {
+            return Ok(self.complete_slash_argument(command, arg, pos));
+        }
 
         let candidates: Vec<NoshCandidate> = SLASH_COMMANDS
             .iter()
@@ -196,6 +261,63 @@ impl NoshHelper {
         Ok((0, candidates))
     }
 
+    /// Complete the argument of a slash command. `arg` is the text typed
+    /// after the command, up to the cursor at `pos`.
+    fn complete_slash_argument(
+        &self,
+        command: &str,
+        arg: &str,
+        pos: usize,
+    ) -> (usize, Vec<NoshCandidate>) {
+        let arg_start = pos - arg.len();
+
+        // `/time` takes a whole shell command
+        if command == "/time" {
+            let completions = self.completion_manager.complete(arg, arg.len());
+            let range = word_range(arg, arg.len());
+            self.word_end.set(arg_start + range.end);
+            let candidates = completions.into_iter().map(NoshCandidate::new).collect();
+            return (arg_start + range.start, candidates);
+        }
+
+        // Everything else takes a single argument
+        if arg.contains([' ', '\t']) {
+            return (pos, Vec::new());
+        }
+        let values: Vec<String> = match command {
+            "/debug" => std::iter::once("all".to_string())
+                .chain(self.plugin_names.borrow().iter().cloned())
+                .collect(),
+            "/theme" => std::iter::once("lint".to_string())
+                .chain(self.theme_names.borrow().iter().cloned())
+                .collect(),
+            "/paranoid" => vec!["on".to_string(), "off".to_string()],
+            "/clear" => vec!["--cache".to_string()],
+            "/sessions" => vec!["replay".to_string(), "export".to_string()],
+            "/convert-zsh" => {
+                return (
+                    arg_start,
+                    BuiltinCompleter::Files
+                        .complete(arg, &self.completion_manager.path_var())
+                        .into_iter()
+                        .map(NoshCandidate::new)
+                        .collect(),
+                );
+            }
+            _ => Vec::new(),
+        };
+
+        let candidates = values
+            .into_iter()
+            .filter(|v| v.starts_with(arg))
+            .map(|v| NoshCandidate {
+                display: v.clone(),
+                text: v,
+            })
+            .collect();
+        (arg_start, candidates)
+    }
+
     /// Complete in AI mode using English word list.
     fn complete_ai_mode(
         &self,
@@ -205,6 +327,7 @@ impl NoshHelper {
         // Find the start of the current word (space-separated for natural language)
         let start = find_word_start_simple(line, pos);
         let prefix = &line[start..pos];
+        self.word_end.set(find_word_end_simple(line, pos));
 
         // Get word completions
         let candidates: Vec<NoshCandidate> = words::complete_words(prefix)
@@ -231,6 +354,13 @@ fn find_word_start_simple(line: &str, pos: usize) -> usize {
     start
 }
 
+/// Find word end for natural language (simple space-based).
+fn find_word_end_simple(line: &str, pos: usize) -> usize {
+    line[pos..]
+        .find([' ', '\t'])
+        .map_or(line.len(), |i| pos + i)
+}
+
 impl Hinter for NoshHelper {
     type Hint = String;
 
@@ -254,7 +384,7 @@ impl Hinter for NoshHelper {
         }
 
         // AI mode hints
-        if line.starts_with('?') {
+        if is_ai_query(line, self.ai_enabled.get()) {
             let word_start = find_word_start_simple(line, pos);
             let current_word = &line[word_start..pos];
 
@@ -285,22 +415,25 @@ impl Hinter for NoshHelper {
 impl Highlighter for NoshHelper {
     fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
         // Style ?? and ? with elegant formatting (preserve length for cursor)
-        if let Some(rest) = line.strip_prefix("??") {
+        if !is_ai_query(line, self.ai_enabled.get()) {
+            if self.syntax_highlighting {
+                Cow::Owned(self.highlight_shell(line))
+            } else {
+                Cow::Borrowed(line)
+            }
+        } else if let Some(rest) = line.strip_prefix("??") {
             // Bold purple "??" with glow effect
             Cow::Owned(format!(
                 "\x1b[1m\x1b[38;5;135m??\x1b[0m\x1b[38;5;250m{}\x1b[0m",
                 rest
             ))
-        } else if let Some(rest) = line.strip_prefix('?') {
+        } else {
             // Bold cyan "?" with glow effect
+            let rest = &line[1..];
             Cow::Owned(format!(
                 "\x1b[1m\x1b[38;5;45m?\x1b[0m\x1b[38;5;250m{}\x1b[0m",
                 rest
             ))
-        } else if self.syntax_highlighting {
-            Cow::Owned(self.highlight_shell(line))
-        } else {
-            Cow::Borrowed(line)
         }
     }
 
@@ -343,7 +476,7 @@ impl Validator for NoshHelper {
         let line = ctx.input();
 
         // Skip quote validation for AI queries - they're natural language, not shell commands
-        if line.starts_with('?') || line.starts_with('/') {
+        if is_ai_query(line, self.ai_enabled.get()) || line.starts_with('/') {
             return Ok(ValidationResult::Valid(None));
         }
 
@@ -381,28 +514,48 @@ impl Validator for NoshHelper {
 
 impl Helper for NoshHelper {}
 
-/// Build a cache of valid commands by scanning PATH directories.
-fn build_command_cache() -> HashSet<String> {
-    let mut commands: HashSet<String> = SHELL_BUILTINS.iter().map(|s| s.to_string()).collect();
+/// Valid command names for highlighting, rebuilt when `$PATH` changes
+/// (e.g. after activating a virtualenv).
+#[derive(Default)]
+struct CommandCache {
+    /// Hash of the `$PATH` the set was built from
+    path_hash: Option<u64>,
+    commands: HashSet<String>,
+    /// Number of times the set has been built
+    builds: usize,
+}
+
+impl CommandCache {
+    /// Rebuild the set if `path_var` differs from the last build.
+    fn refresh(&mut self, path_var: &str) {
+        let mut hasher = DefaultHasher::new();
+        path_var.hash(&mut hasher);
+        let hash = hasher.finish();
+        if self.path_hash == Some(hash) {
+            return;
+        }
 
-    if let Ok(path_var) = std::env::var("PATH") {
+        self.commands = SHELL_BUILTINS.iter().map(|s| s.to_string()).collect();
         for dir in path_var.split(':') {
             if let Ok(entries) = std::fs::read_dir(dir) {
                 for entry in entries.filter_map(|e| e.ok()) {
                     if let Some(name) = entry.file_name().to_str() {
-                        commands.insert(name.to_string());
+                        self.commands.insert(name.to_string());
                     }
                 }
             }
         }
+        self.path_hash = Some(hash);
+        self.builds += 1;
     }
-
-    commands
 }
 
 impl NoshHelper {
     /// Syntax-highlight a shell input line.
     fn highlight_shell(&self, line: &str) -> String {
+        self.command_cache
+            .borrow_mut()
+            .refresh(&self.completion_manager.path_var().to_string_lossy());
         let mut result = String::with_capacity(line.len() * 2);
         let bytes = line.as_bytes();
         let len = bytes.len();
@@ -516,7 +669,7 @@ impl NoshHelper {
 
             if expect_command {
                 // Command position: bold if known, red if unknown
-                if word.contains('/') || self.command_cache.contains(word) {
+                if word.contains('/') || self.command_cache.borrow().commands.contains(word) {
                     // Known command or path — bold
                     result.push_str("\x1b[1m");
                     result.push_str(word);
@@ -577,11 +730,134 @@ fn find_word_start(line: &str, pos: usize) -> usize {
     start
 }
 
+/// Find the end of the word under the cursor, honoring quotes.
+fn find_word_end(line: &str, start: usize, pos: usize) -> usize {
+    let mut quote = None;
+    for (i, &c) in line.as_bytes().iter().enumerate().skip(start) {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                b'"' | b'\'' => quote = Some(c),
+                b' ' | b'\t' if i >= pos => return i,
+                _ => {}
+            },
+        }
+    }
+    line.len()
+}
+
+/// Range of the word around the cursor that a completion replaces.
+fn word_range(line: &str, pos: usize) -> Range<usize> {
+    let start = find_word_start(line, pos);
+    start..find_word_end(line, start, pos)
+}
+
+/// Range of `line` to replace with the chosen completion.
+///
+/// A full candidate replaces the whole word around the cursor. A common
+/// prefix (`full == false`) only replaces up to the cursor, so text after the
+/// cursor is kept until a candidate is chosen.
+fn replacement_range(
+    line: &str,
+    start: usize,
+    pos: usize,
+    word_end: usize,
+    full: bool,
+) -> Range<usize> {
+    if full {
+        start..word_end.clamp(pos, line.len())
+    } else {
+        start..pos
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use rustyline::completion::Candidate;
 
+    #[test]
+    fn test_ai_disabled_treats_question_mark_as_shell() {
+        let helper = NoshHelper::new(Rc::new(CompletionManager::new()), false);
+        assert!(is_ai_query("?foo", true));
+        assert!(helper.highlight("?foo", 4).contains("\x1b[38;5;45m"));
+
+        helper.set_ai_enabled(false);
+        assert!(!is_ai_query("?foo", false));
+        assert!(!is_ai_query("??why", false));
+        assert_eq!(helper.highlight("?foo", 4), "?foo");
+    }
+
+    fn candidate_texts(helper: &NoshHelper, line: &str) -> (usize, Vec<String>) {
+        let (start, candidates) = helper.complete_slash_command(line, line.len()).unwrap();
+        (start, candidates.into_iter().map(|c| c.text).collect())
+    }
+
+    #[test]
+    fn test_debug_completes_plugin_names() {
+        let helper = NoshHelper::new(Rc::new(CompletionManager::new()), false);
+        helper.set_slash_arguments(vec!["context".to_string(), "git".to_string()], Vec::new());
+
+        assert_eq!(
+            candidate_texts(&helper, "/debug "),
+            (7, vec!["all".to_string(), "context".into(), "git".into()])
+        );
+        assert_eq!(
+            candidate_texts(&helper, "/debug g"),
+            (7, vec!["git".to_string()])
+        );
+        assert!(candidate_texts(&helper, "/debug git x").1.is_empty());
+    }
+
+    #[test]
+    fn test_theme_completes_theme_names() {
+        let helper = NoshHelper::new(Rc::new(CompletionManager::new()), false);
+        helper.set_slash_arguments(
+            Vec::new(),
+            vec!["builtins/default".to_string(), "mytheme".to_string()],
+        );
+
+        assert_eq!(
+            candidate_texts(&helper, "/theme "),
+            (
+                7,
+                vec![
+                    "lint".to_string(),
+                    "builtins/default".into(),
+                    "mytheme".into()
+                ]
+            )
+        );
+        assert_eq!(candidate_texts(&helper, "/theme my").1, vec!["mytheme"]);
+        // Command names still complete before the space
+        assert_eq!(candidate_texts(&helper, "/them").1, vec!["/theme"]);
+    }
+
+    #[test]
+    fn test_command_cache_rebuilds_on_path_change() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_cmd_cache_{}", std::process::id()));
+        let (first, second) = (dir.join("a"), dir.join("b"));
+        std::fs::create_dir_all(&first).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+        std::fs::write(second.join("nosh-venv-tool"), "").unwrap();
+
+        let mut cache = CommandCache::default();
+        let path = first.display().to_string();
+        cache.refresh(&path);
+        cache.refresh(&path);
+        assert_eq!(cache.builds, 1);
+        assert!(cache.commands.contains("cd"));
+        assert!(!cache.commands.contains("nosh-venv-tool"));
+
+        // Activating an environment prepends to PATH
+        cache.refresh(&format!("{}:{}", second.display(), path));
+        assert_eq!(cache.builds, 2);
+        assert!(cache.commands.contains("nosh-venv-tool"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_find_word_start() {
         assert_eq!(find_word_start("git commit", 10), 4);
@@ -590,6 +866,46 @@ mod tests {
         assert_eq!(find_word_start("echo \"hello world\"", 18), 5);
     }
 
+    #[test]
+    fn test_word_range_mid_word() {
+        assert_eq!(word_range("git commit", 7), 4..10);
+        assert_eq!(word_range("git com --amend", 6), 4..7);
+        assert_eq!(word_range("git  --amend", 4), 4..4);
+        assert_eq!(word_range("cat \"my file\" x", 13), 4..13);
+        assert_eq!(find_word_end_simple("/paranoid on", 3), 9);
+    }
+
+    #[test]
+    fn test_replacement_range_mid_word() {
+        let apply = |line: &str, pos: usize, elected: &str, full: bool| {
+            let range = word_range(line, pos);
+            let replace = replacement_range(line, range.start, pos, range.end, full);
+            let mut out = line.to_string();
+            out.replace_range(replace, elected);
+            out
+        };
+
+        // Cursor inside a word: the rest of the word is replaced, not duplicated
+        assert_eq!(
+            apply("git comit --amend", 6, "commit", true),
+            "git commit --amend"
+        );
+        assert_eq!(
+            apply("git co --amend", 5, "commit", true),
+            "git commit --amend"
+        );
+        assert_eq!(apply("git commit", 6, "commit", true), "git commit");
+
+        // A common prefix keeps text after the cursor
+        assert_eq!(apply("git cxyz", 5, "che", false), "git chexyz");
+
+        // Choosing a candidate replaces the whole word
+        assert_eq!(apply("git commit-tree", 6, "commit", true), "git commit");
+
+        // Cursor at end of line behaves as before
+        assert_eq!(apply("git com", 7, "commit", true), "git commit");
+    }
+
     #[test]
     fn test_nosh_candidate() {
         let c = Completion::new("test").with_description("Test completion");
@@ -0,0 +1,334 @@
+//! Fuzzy history picker (Ctrl+R).
+//!
+//! An fzf-style inline picker over the SQLite history: type to filter,
+//! arrows to move, Enter to pick, Esc to cancel. Matched characters are
+//! highlighted and the chosen command is loaded into the prompt buffer.
+
+use std::io::{self, Write};
+
+use anyhow::Result;
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
+    style::Print,
+    terminal::{self, ClearType},
+};
+
+use crate::history::History;
+
+/// How many history entries the picker searches.
+const HISTORY_LIMIT: usize = 10_000;
+
+/// Maximum number of matches shown at once.
+const MAX_VISIBLE: usize = 10;
+
+/// A history entry matching the query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickerMatch {
+    pub command: String,
+    /// Character indices of matched characters, for highlighting
+    pub positions: Vec<usize>,
+    score: i64,
+}
+
+/// Fuzzy-match `query` against `candidate`.
+///
+/// Every query character must appear in order. Contiguous runs and matches
+/// at word starts score higher; an exact substring beats a scattered match.
+/// Matching is case-insensitive unless the query has uppercase letters.
+fn fuzzy_match(query: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let case_sensitive = query.chars().any(|c| c.is_uppercase());
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_ascii_lowercase()
+        }
+    };
+    let query: Vec<char> = query.chars().map(fold).collect();
+    let chars: Vec<char> = candidate.chars().map(fold).collect();
+
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    let is_word_start =
+        |i: usize| i == 0 || matches!(chars[i - 1], ' ' | '/' | '-' | '_' | '.' | '=');
+
+    // Exact substring: prefer the occurrence at a word start
+    let substring_starts: Vec<usize> = (0..chars.len().saturating_sub(query.len() - 1))
+        .filter(|&i| chars[i..i + query.len()] == query[..])
+        .collect();
+    if let Some(&start) = substring_starts
+        .iter()
+        .find(|&&i| is_word_start(i))
+        .or(substring_starts.first())
+    {
+        let mut score = 100 + 10 * query.len() as i64 - start as i64;
+        if is_word_start(start) {
+            score += 50;
+        }
+        return Some((score, (start..start + query.len()).collect()));
+    }
+
+    // Scattered subsequence, preferring word starts when the rest still matches
+    let matches_from = |start: usize, rest: &[char]| {
+        let mut it = chars[start..].iter();
+        rest.iter().all(|q| it.any(|c| c == q))
+    };
+    let mut positions: Vec<usize> = Vec::with_capacity(query.len());
+    let mut score = 0i64;
+    let mut next = 0;
+    for (qi, &q) in query.iter().enumerate() {
+        let mut hits = (next..chars.len()).filter(|&i| chars[i] == q);
+        let first = hits.next()?;
+        let i = std::iter::once(first)
+            .chain(hits)
+            .find(|&i| is_word_start(i) && matches_from(i + 1, &query[qi + 1..]))
+            .unwrap_or(first);
+
+        if let Some(&prev) = positions.last() {
+            if i == prev + 1 {
+                score += 8;
+            } else {
+                score -= (i - prev - 1).min(10) as i64;
+            }
+        }
+        if is_word_start(i) {
+            score += 6;
+        }
+        score += 1;
+        positions.push(i);
+        next = i + 1;
+    }
+
+    Some((score, positions))
+}
+
+/// Rank candidates (newest first) against the query, best match first.
+/// Ties keep recency order.
+pub fn rank_matches(query: &str, candidates: &[String], limit: usize) -> Vec<PickerMatch> {
+    let mut matches: Vec<PickerMatch> = candidates
+        .iter()
+        .filter_map(|c| {
+            fuzzy_match(query, c).map(|(score, positions)| PickerMatch {
+                command: c.clone(),
+                positions,
+                score,
+            })
+        })
+        .collect();
+
+    // Stable sort keeps newer entries first among equal scores
+    matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+    matches.truncate(limit);
+    matches
+}
+
+/// Run the picker below the cursor. Returns the chosen command, or `None`
+/// if cancelled.
+pub fn pick(history: &History, query: &str) -> Result<Option<String>> {
+    let candidates = history.recent(HISTORY_LIMIT)?;
+    let mut query = query.to_string();
+    let mut selected = 0usize;
+
+    terminal::enable_raw_mode()?;
+    let mut stdout = io::stdout();
+
+    // Reserve space below the cursor so redraws don't scroll the screen
+    let rows = terminal::size().map(|(_, h)| h as usize).unwrap_or(24);
+    let visible = MAX_VISIBLE.min(rows.saturating_sub(2)).max(1);
+    queue!(
+        stdout,
+        Print("\r\n".repeat(visible)),
+        cursor::MoveUp(visible as u16)
+    )?;
+
+    let result = (|| -> Result<Option<String>> {
+        loop {
+            let matches = rank_matches(&query, &candidates, visible);
+            selected = selected.min(matches.len().saturating_sub(1));
+            draw(&mut stdout, &query, &matches, selected, candidates.len())?;
+
+            let Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) = event::read()?
+            else {
+                continue;
+            };
+            let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+
+            match code {
+                KeyCode::Enter => return Ok(matches.get(selected).map(|m| m.command.clone())),
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Char('c' | 'g') if ctrl => return Ok(None),
+                KeyCode::Up => selected = selected.saturating_sub(1),
+                KeyCode::Char('p' | 'r') if ctrl => selected = selected.saturating_sub(1),
+                KeyCode::Down | KeyCode::Tab => selected += 1,
+                KeyCode::Char('n') if ctrl => selected += 1,
+                KeyCode::Char('u') if ctrl => {
+                    query.clear();
+                    selected = 0;
+                }
+                KeyCode::Backspace => {
+                    query.pop();
+                    selected = 0;
+                }
+                KeyCode::Char(c) if !ctrl => {
+                    query.push(c);
+                    selected = 0;
+                }
+                _ => {}
+            }
+        }
+    })();
+
+    // Erase the picker and restore the terminal
+    let _ = queue!(
+        stdout,
+        cursor::MoveToColumn(0),
+        terminal::Clear(ClearType::FromCursorDown)
+    );
+    let _ = stdout.flush();
+    let _ = terminal::disable_raw_mode();
+
+    result
+}
+
+/// Draw the query line and matches, leaving the cursor after the query.
+fn draw(
+    stdout: &mut impl Write,
+    query: &str,
+    matches: &[PickerMatch],
+    selected: usize,
+    total: usize,
+) -> Result<()> {
+    let width = terminal::size().map(|(w, _)| w as usize).unwrap_or(80);
+
+    queue!(
+        stdout,
+        cursor::MoveToColumn(0),
+        terminal::Clear(ClearType::FromCursorDown),
+        Print(format!(
+            "\x1b[36mhistory>\x1b[0m {}  \x1b[90m{}/{}\x1b[0m",
+            query,
+            matches.len(),
+            total
+        ))
+    )?;
+
+    if matches.is_empty() {
+        queue!(stdout, Print("\r\n  \x1b[90mNo matches\x1b[0m"))?;
+    }
+
+    for (i, m) in matches.iter().enumerate() {
+        let marker = if i == selected {
+            "\x1b[1;35m❯\x1b[0m "
+        } else {
+            "  "
+        };
+        queue!(
+            stdout,
+            Print("\r\n"),
+            Print(marker),
+            Print(highlight(m, width.saturating_sub(3), i == selected))
+        )?;
+    }
+
+    let lines = matches.len().max(1) as u16;
+    queue!(
+        stdout,
+        cursor::MoveUp(lines),
+        cursor::MoveToColumn(("history> ".len() + query.chars().count()) as u16)
+    )?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Render a match, highlighting matched characters and truncating to `width`.
+fn highlight(m: &PickerMatch, width: usize, selected: bool) -> String {
+    let base = if selected { "\x1b[1m" } else { "" };
+    let mut out = String::from(base);
+    for (i, c) in m.command.chars().enumerate() {
+        if i >= width {
+            out.push('…');
+            break;
+        }
+        // Multi-line commands are shown on one line
+        let c = if c == '\n' { '⏎' } else { c };
+        if m.positions.contains(&i) {
+            out.push_str(&format!("\x1b[33m{}\x1b[0m{}", c, base));
+        } else {
+            out.push(c);
+        }
+    }
+    out.push_str("\x1b[0m");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(ranked: &[PickerMatch]) -> Vec<&str> {
+        ranked.iter().map(|m| m.command.as_str()).collect()
+    }
+
+    #[test]
+    fn test_rank_matches() {
+        // Newest first, as returned by the history store
+        let history: Vec<String> = [
+            "cargo test --workspace",
+            "git checkout main",
+            "grep -r todo src",
+            "git commit -m 'fix tests'",
+            "docker compose up",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        // Word-start substring beats mid-word substring
+        let ranked = rank_matches("test", &history, 10);
+        assert_eq!(
+            commands(&ranked),
+            vec!["cargo test --workspace", "git commit -m 'fix tests'"]
+        );
+
+        // Scattered matches are found, contiguous ones rank higher
+        let ranked = rank_matches("gco", &history, 10);
+        assert_eq!(
+            commands(&ranked),
+            vec!["git commit -m 'fix tests'", "git checkout main"]
+        );
+
+        // Highlight positions point at matched characters
+        let ranked = rank_matches("dcu", &history, 10);
+        assert_eq!(ranked[0].command, "docker compose up");
+        assert_eq!(ranked[0].positions, vec![0, 7, 15]);
+
+        // Empty query keeps recency order; limit applies
+        let ranked = rank_matches("", &history, 2);
+        assert_eq!(
+            commands(&ranked),
+            vec!["cargo test --workspace", "git checkout main"]
+        );
+
+        // No matches
+        assert!(rank_matches("zzz", &history, 10).is_empty());
+    }
+
+    #[test]
+    fn test_smart_case() {
+        let history = vec!["echo Hello".to_string(), "echo hello".to_string()];
+        assert_eq!(rank_matches("hello", &history, 10).len(), 2);
+        assert_eq!(
+            commands(&rank_matches("Hello", &history, 10)),
+            vec!["echo Hello"]
+        );
+    }
+}
@@ -0,0 +1,270 @@
+//! Configurable keybindings for common REPL actions.
+//!
+//! Keys are written like "ctrl-g", "alt-f", "ctrl-alt-x", "f2" or "up".
+//! Actions are either rustyline editing commands or nosh actions, which
+//! are delivered to the main loop as the equivalent slash command (or, for
+//! the fuzzy history picker, handled by the REPL itself).
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Result, anyhow, bail};
+use rustyline::{
+    Cmd, ConditionalEventHandler, Event, EventContext, KeyCode, KeyEvent, Modifiers, Movement,
+    RepeatCount,
+};
+
+/// A bindable REPL action.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyAction {
+    /// Clear the screen
+    ClearScreen,
+    /// Prefix-based history search backward
+    HistorySearchBackward,
+    /// Prefix-based history search forward
+    HistorySearchForward,
+    /// Incremental reverse history search (rustyline's built-in)
+    ReverseSearch,
+    /// Fuzzy history picker
+    FuzzySearch,
+    /// Accept the current autosuggestion hint
+    AcceptHint,
+    /// Trigger completion
+    Complete,
+    /// Delete the whole line
+    KillLine,
+    /// Clear the AI conversation context (/clear)
+    ClearContext,
+    /// Reload config, theme and plugins (/reload)
+    Reload,
+    /// Edit the last command in $EDITOR (/edit)
+    Edit,
+}
+
+/// All action names, for error messages.
+const ACTION_NAMES: &[&str] = &[
+    "clear_screen",
+    "history_search_backward",
+    "history_search_forward",
+    "reverse_search",
+    "fuzzy_search",
+    "accept_hint",
+    "complete",
+    "kill_line",
+    "clear_context",
+    "reload",
+    "edit",
+];
+
+impl KeyAction {
+    /// Parse an action name from config.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "clear_screen" => Some(KeyAction::ClearScreen),
+            "history_search_backward" => Some(KeyAction::HistorySearchBackward),
+            "history_search_forward" => Some(KeyAction::HistorySearchForward),
+            "reverse_search" => Some(KeyAction::ReverseSearch),
+            "fuzzy_search" => Some(KeyAction::FuzzySearch),
+            "accept_hint" => Some(KeyAction::AcceptHint),
+            "complete" => Some(KeyAction::Complete),
+            "kill_line" => Some(KeyAction::KillLine),
+            "clear_context" => Some(KeyAction::ClearContext),
+            "reload" => Some(KeyAction::Reload),
+            "edit" => Some(KeyAction::Edit),
+            _ => None,
+        }
+    }
+
+    /// The slash command this action runs, for nosh actions.
+    pub fn slash_command(&self) -> Option<&'static str> {
+        match self {
+            KeyAction::ClearContext => Some("/clear"),
+            KeyAction::Reload => Some("/reload"),
+            KeyAction::Edit => Some("/edit"),
+            _ => None,
+        }
+    }
+
+    /// The rustyline command for editing actions.
+    fn cmd(&self) -> Option<Cmd> {
+        match self {
+            KeyAction::ClearScreen => Some(Cmd::ClearScreen),
+            KeyAction::HistorySearchBackward => Some(Cmd::HistorySearchBackward),
+            KeyAction::HistorySearchForward => Some(Cmd::HistorySearchForward),
+            KeyAction::ReverseSearch => Some(Cmd::ReverseSearchHistory),
+            KeyAction::AcceptHint => Some(Cmd::CompleteHint),
+            KeyAction::Complete => Some(Cmd::Complete),
+            KeyAction::KillLine => Some(Cmd::Kill(Movement::WholeLine)),
+            KeyAction::FuzzySearch
+            | KeyAction::ClearContext
+            | KeyAction::Reload
+            | KeyAction::Edit => None,
+        }
+    }
+}
+
+/// Parse a key description like "ctrl-g" into a key event.
+pub fn parse_key(key: &str) -> Result<KeyEvent> {
+    let lower = key.trim().to_lowercase();
+    let mut parts: Vec<&str> = lower.split('-').collect();
+
+    // "ctrl--" binds the minus key
+    if lower.ends_with("--") {
+        parts.pop();
+        if let Some(last) = parts.last_mut() {
+            *last = "-";
+        }
+    }
+
+    let name = parts
+        .pop()
+        .filter(|n| !n.is_empty())
+        .ok_or_else(|| anyhow!("Invalid key '{}'", key))?;
+
+    let mut mods = Modifiers::NONE;
+    for part in parts {
+        match part {
+            "ctrl" | "c" => mods |= Modifiers::CTRL,
+            "alt" | "meta" | "m" => mods |= Modifiers::ALT,
+            "shift" | "s" => mods |= Modifiers::SHIFT,
+            _ => bail!(
+                "Unknown modifier '{}' in key '{}' (use ctrl, alt or shift)",
+                part,
+                key
+            ),
+        }
+    }
+
+    let code = match name {
+        "tab" => KeyCode::Tab,
+        "esc" | "escape" => KeyCode::Esc,
+        "enter" | "return" => KeyCode::Enter,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "space" => KeyCode::Char(' '),
+        _ => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => bail!("Unknown key '{}'", key),
+                },
+            }
+        }
+    };
+
+    Ok(KeyEvent::normalize(KeyEvent(code, mods)))
+}
+
+/// Parse a single keybinding entry from config.
+pub fn parse_binding(key: &str, action: &str) -> Result<(KeyEvent, KeyAction)> {
+    let event = parse_key(key)?;
+    let action = KeyAction::from_name(action).ok_or_else(|| {
+        anyhow!(
+            "Unknown action '{}' for key '{}'. Available actions: {}",
+            action,
+            key,
+            ACTION_NAMES.join(", ")
+        )
+    })?;
+    Ok((event, action))
+}
+
+/// A nosh action requested by a keybinding while readline was active.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PendingAction {
+    /// Run a slash command
+    Slash(&'static str),
+    /// Open the fuzzy history picker, seeded with the current line
+    FuzzySearch(String),
+}
+
+/// Event handler for a configured binding.
+///
+/// Editing actions map directly to a rustyline command. Nosh actions record
+/// the pending action and interrupt readline so the REPL can run it.
+pub struct ActionHandler {
+    action: KeyAction,
+    pending: Arc<Mutex<Option<PendingAction>>>,
+}
+
+impl ActionHandler {
+    pub fn new(action: KeyAction, pending: Arc<Mutex<Option<PendingAction>>>) -> Self {
+        Self { action, pending }
+    }
+}
+
+impl ConditionalEventHandler for ActionHandler {
+    fn handle(
+        &self,
+        _evt: &Event,
+        _n: RepeatCount,
+        _positive: bool,
+        ctx: &EventContext,
+    ) -> Option<Cmd> {
+        if let Some(cmd) = self.action.cmd() {
+            return Some(cmd);
+        }
+
+        let action = match self.action {
+            KeyAction::FuzzySearch => PendingAction::FuzzySearch(ctx.line().to_string()),
+            _ => PendingAction::Slash(self.action.slash_command()?),
+        };
+        if let Ok(mut pending) = self.pending.lock() {
+            *pending = Some(action);
+        }
+        Some(Cmd::Interrupt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_keybinding_config() {
+        let config: HashMap<String, String> = toml::from_str(
+            r#"
+            "ctrl-g" = "clear_context"
+            "alt-r" = "reverse_search"
+            "f5" = "reload"
+            "#,
+        )
+        .unwrap();
+
+        let mut bindings: Vec<(KeyEvent, KeyAction)> = config
+            .iter()
+            .map(|(key, action)| parse_binding(key, action).unwrap())
+            .collect();
+        bindings.sort_by_key(|(_, action)| format!("{:?}", action));
+
+        assert_eq!(
+            bindings,
+            vec![
+                (KeyEvent::ctrl('G'), KeyAction::ClearContext),
+                (KeyEvent(KeyCode::F(5), Modifiers::NONE), KeyAction::Reload),
+                (KeyEvent::alt('r'), KeyAction::ReverseSearch),
+            ]
+        );
+        assert_eq!(KeyAction::ClearContext.slash_command(), Some("/clear"));
+    }
+
+    #[test]
+    fn test_parse_binding_rejects_unknown() {
+        let err = parse_binding("ctrl-g", "launch_rockets").unwrap_err();
+        assert!(err.to_string().contains("Unknown action 'launch_rockets'"));
+        assert!(err.to_string().contains("clear_context"));
+
+        assert!(parse_binding("hyper-g", "clear_screen").is_err());
+        assert!(parse_binding("f13", "clear_screen").is_err());
+    }
+}
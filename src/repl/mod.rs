@@ -1,6 +1,12 @@
+mod edit;
 mod helper;
+mod history_picker;
+mod keybindings;
 mod readline;
+mod script;
 mod sqlite_history;
 mod words;
 
+pub use helper::{is_ai_query, is_slash_command};
 pub use readline::{ReadlineResult, Repl};
+pub use script::{ScriptInput, script_path};
@@ -1,17 +1,24 @@
+use std::collections::HashMap;
 use std::rc::Rc;
+use std::sync::{Arc, LazyLock, Mutex};
 use std::time::Instant;
 
 use anyhow::Result;
 use rustyline::error::ReadlineError;
 use rustyline::history::History;
-use rustyline::{Cmd, Config, Editor, EventHandler, KeyCode, KeyEvent, Modifiers};
+use rustyline::{Cmd, Config, Editor, Event, EventHandler, KeyCode, KeyEvent, Modifiers};
 
+use super::edit;
 use super::helper::NoshHelper;
+use super::history_picker;
+use super::keybindings::{self, ActionHandler, KeyAction, PendingAction};
+use super::script::LineSource;
 use super::sqlite_history::SqliteRustylineHistory;
 use crate::completions::CompletionManager;
 use crate::paths;
 use crate::plugins::loader::PluginManager;
 use crate::plugins::theme::Theme;
+use crate::ui::background::Background;
 
 /// Result of a readline operation
 pub enum ReadlineResult {
@@ -28,8 +35,23 @@ pub struct Repl {
     plugin_manager: PluginManager,
     theme: Theme,
     last_command_start: Option<Instant>,
+    /// Print variable timings after the next prompt render
+    profile_next_prompt: bool,
+    /// Print variable timings after every prompt render (NOSH_PROFILE=1)
+    profile_always: bool,
+    /// Slash command requested by a keybinding during readline
+    pending_action: Arc<Mutex<Option<PendingAction>>>,
+    /// Text to pre-fill the next prompt with (e.g. after /edit)
+    initial_input: Option<String>,
     #[allow(dead_code)]
     completion_manager: Rc<CompletionManager>,
+    /// Terminal background, reapplied to the theme on reload
+    background: Background,
+    /// Plugin command concurrency limit, reapplied on reload
+    max_concurrent_plugins: usize,
+    context_cache_size: usize,
+    /// Scripted input read instead of the terminal (`--script`)
+    line_source: Option<Box<dyn LineSource>>,
 }
 
 impl Repl {
@@ -37,6 +59,7 @@ impl Repl {
         theme_name: &str,
         _history_load_count: Option<usize>,
         syntax_highlighting: bool,
+        keybindings: &HashMap<String, String>,
     ) -> Result<Self> {
         // Create SQLite-backed history with lazy loading
         let history = SqliteRustylineHistory::open(&paths::history_db())
@@ -65,19 +88,81 @@ impl Repl {
             EventHandler::Simple(Cmd::HistorySearchForward),
         );
 
+        // Ctrl+X Ctrl+E edits the last command in $EDITOR (like bash)
+        let pending_action = Arc::new(Mutex::new(None));
+        editor.bind_sequence(
+            Event::KeySeq(vec![KeyEvent::ctrl('X'), KeyEvent::ctrl('E')]),
+            EventHandler::Conditional(Box::new(ActionHandler::new(
+                KeyAction::Edit,
+                Arc::clone(&pending_action),
+            ))),
+        );
+
+        // Ctrl+R opens the fuzzy history picker
+        editor.bind_sequence(
+            KeyEvent::ctrl('R'),
+            EventHandler::Conditional(Box::new(ActionHandler::new(
+                KeyAction::FuzzySearch,
+                Arc::clone(&pending_action),
+            ))),
+        );
+
+        // Apply custom keybindings from config (after defaults so they can override)
+        for (key, action) in keybindings {
+            match keybindings::parse_binding(key, action) {
+                Ok((event, action)) => {
+                    let handler = ActionHandler::new(action, Arc::clone(&pending_action));
+                    editor.bind_sequence(event, EventHandler::Conditional(Box::new(handler)));
+                }
+                Err(e) => eprintln!("Warning: Invalid keybinding: {}", e),
+            }
+        }
+
         // Load plugins and theme
         let mut plugin_manager = PluginManager::new();
         let _ = plugin_manager.load_plugins();
 
-        let theme = Theme::load(theme_name).unwrap_or_default();
+        let (theme, warning) = Theme::load_or_default(theme_name);
+        if let Some(warning) = warning {
+            eprintln!("{}", warning);
+        }
+        plugin_manager.set_icons(theme.icons.clone());
+        plugin_manager.prefetch(|name| theme.is_plugin_enabled(name));
+
+        let profile_always = std::env::var("NOSH_PROFILE").is_ok_and(|v| v == "1");
+        plugin_manager.set_profiling(profile_always);
 
-        Ok(Self {
+        let repl = Self {
             editor,
             plugin_manager,
             theme,
             last_command_start: None,
+            profile_next_prompt: false,
+            profile_always,
+            pending_action,
+            initial_input: None,
             completion_manager,
-        })
+            background: Background::default(),
+            max_concurrent_plugins: crate::plugins::loader::DEFAULT_MAX_CONCURRENT,
+            context_cache_size: nosh_context::DEFAULT_CONTEXT_CACHE_SIZE,
+            line_source: None,
+        };
+        repl.update_slash_arguments();
+        Ok(repl)
+    }
+
+    /// Refresh the plugin and theme names offered by `/debug` and `/theme` completion.
+    fn update_slash_arguments(&self) {
+        if let Some(helper) = self.editor.helper() {
+            let mut plugins: Vec<String> = self
+                .plugin_manager
+                .list_plugins()
+                .into_iter()
+                .map(|(name, _, _)| name.to_string())
+                .collect();
+            plugins.sort();
+            helper.set_slash_arguments(plugins, crate::plugins::theme::theme_names());
+        }
     }
 
     /// No-op: SQLite history loads lazily on demand.
@@ -112,27 +197,125 @@ impl Repl {
         let vars = self.theme.get_plugin_variables();
 
         // Fetch all variables in parallel with soft timeout
+        let started_at = Instant::now();
         let values = self.plugin_manager.get_variables(vars).await;
 
         // Format prompt with fetched values
-        self.theme
-            .format_prompt_with_values(&values, &mut self.plugin_manager)
+        let prompt = self
+            .theme
+            .format_prompt_with_values(&values, &mut self.plugin_manager);
+
+        if self.profile_next_prompt || self.profile_always {
+            self.print_timings(started_at.elapsed());
+            self.profile_next_prompt = false;
+            self.plugin_manager.set_profiling(self.profile_always);
+        }
+
+        prompt
+    }
+
+    /// Recompute every plugin variable and the project context on the next prompt.
+    pub async fn refresh_context(&mut self) {
+        self.plugin_manager.clear_caches().await;
+    }
+
+    /// Collect and print variable timings for the next prompt render.
+    pub fn profile_next_prompt(&mut self) {
+        self.profile_next_prompt = true;
+        self.plugin_manager.set_profiling(true);
+    }
+
+    /// Print a table of per-variable timings, slowest first.
+    fn print_timings(&self, total: std::time::Duration) {
+        let timings = self.plugin_manager.timings();
+
+        println!(
+            "\nPrompt timing ({:.1}ms total):",
+            total.as_secs_f64() * 1000.0
+        );
+        if timings.is_empty() {
+            println!("  (no plugin variables in theme)");
+            return;
+        }
+
+        let sorted = timings.sorted();
+        let width = sorted.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+        for (key, timing) in sorted {
+            println!(
+                "  {:<width$}  {:>8.1}ms  {}",
+                key,
+                timing.duration.as_secs_f64() * 1000.0,
+                timing.source.label(),
+                width = width
+            );
+        }
+        println!();
+    }
+
+    /// Read lines from `source` instead of the terminal.
+    pub fn set_line_source(&mut self, source: Box<dyn LineSource>) {
+        self.line_source = Some(source);
+    }
+
+    /// Whether input comes from a script rather than the terminal.
+    pub fn is_scripted(&self) -> bool {
+        self.line_source.is_some()
     }
 
     pub async fn readline(&mut self) -> Result<ReadlineResult> {
         let prompt = self.prompt().await;
-        match self.editor.readline(&prompt) {
-            Ok(line) => {
-                let line = line.trim().to_string();
-                if !line.is_empty() {
-                    // Add to history (SQLite handles persistence)
-                    let _ = self.editor.history_mut().add(&line);
+        if let Some(source) = &mut self.line_source {
+            // Echo scripted lines after the prompt, like a terminal transcript
+            return Ok(match source.next_line() {
+                Some(line) => {
+                    println!("{}{}", prompt, line);
+                    ReadlineResult::Line(line.trim().to_string())
+                }
+                None => ReadlineResult::Eof,
+            });
+        }
+        loop {
+            if self.initial_input.is_none() {
+                let typeahead = crate::ui::background::take_typeahead();
+                if !typeahead.is_empty() {
+                    self.initial_input = Some(typeahead);
                 }
-                Ok(ReadlineResult::Line(line))
             }
-            Err(ReadlineError::Interrupted) => Ok(ReadlineResult::Interrupted), // Ctrl+C
-            Err(ReadlineError::Eof) => Ok(ReadlineResult::Eof),                 // Ctrl+D
-            Err(e) => Err(e.into()),
+            let result = match self.initial_input.take() {
+                Some(initial) => self.editor.readline_with_initial(&prompt, (&initial, "")),
+                None => self.editor.readline(&prompt),
+            };
+            return match result {
+                Ok(line) => {
+                    // Keep the leading space so history can honour `ignore_space`
+                    let _ = self.editor.history_mut().add(line.trim_end());
+                    Ok(ReadlineResult::Line(line.trim().to_string()))
+                }
+                Err(ReadlineError::Interrupted) => {
+                    // A keybinding may have interrupted readline to run a nosh action
+                    let pending = self.pending_action.lock().ok().and_then(|mut p| p.take());
+                    match pending {
+                        Some(PendingAction::Slash(command)) => {
+                            Ok(ReadlineResult::Line(command.to_string()))
+                        }
+                        Some(PendingAction::FuzzySearch(query)) => {
+                            // Replace the interrupted prompt with the picker, then
+                            // re-prompt with the chosen (or original) command
+                            erase_lines(&prompt, &query);
+                            let picked = history_picker::pick(self.history_store(), &query)
+                                .unwrap_or_else(|e| {
+                                    eprintln!("History search failed: {}", e);
+                                    None
+                                });
+                            self.initial_input = Some(picked.unwrap_or(query));
+                            continue;
+                        }
+                        None => Ok(ReadlineResult::Interrupted), // Ctrl+C
+                    }
+                }
+                Err(ReadlineError::Eof) => Ok(ReadlineResult::Eof), // Ctrl+D
+                Err(e) => Err(e.into()),
+            };
         }
     }
 
@@ -141,9 +324,139 @@ impl Repl {
         // Reload plugins
         self.plugin_manager = PluginManager::new();
         let _ = self.plugin_manager.load_plugins();
+        self.plugin_manager.set_profiling(self.profile_always);
+        self.plugin_manager
+            .set_max_concurrent(self.max_concurrent_plugins);
+        self.plugin_manager
+            .set_context_cache_size(self.context_cache_size);
 
         // Reload theme
-        self.theme = Theme::load(theme_name).unwrap_or_default();
+        let (mut theme, warning) = Theme::load_or_default(theme_name);
+        if let Some(warning) = warning {
+            eprintln!("{}", warning);
+        }
+        theme.set_last_exit(self.theme.last_exit());
+        theme.set_shell_state(self.theme.shell_state());
+        self.plugin_manager.set_icons(theme.icons.clone());
+        self.plugin_manager
+            .prefetch(|name| theme.is_plugin_enabled(name));
+        self.theme = theme;
+        self.theme.set_background(self.background);
+        self.update_slash_arguments();
+    }
+
+    /// Limit how many plugin commands run at once per prompt.
+    pub fn set_max_concurrent_plugins(&mut self, limit: usize) {
+        self.max_concurrent_plugins = limit;
+        self.plugin_manager.set_max_concurrent(limit);
+    }
+
+    /// Keep the project context of up to `size` directories.
+    pub fn set_context_cache_size(&mut self, size: usize) {
+        self.context_cache_size = size;
+        self.plugin_manager.set_context_cache_size(size);
+    }
+
+    /// Set the terminal background used to pick theme color overrides.
+    pub fn set_background(&mut self, background: Background) {
+        self.background = background;
+        self.theme.set_background(background);
+    }
+
+    /// Open the last command in $EDITOR and pre-fill the next prompt with the result.
+    /// The edited command is not run until the user confirms it with Enter.
+    pub fn edit_last_command(&mut self) -> Result<()> {
+        let last = self
+            .recent_commands(20)
+            .into_iter()
+            .find(|c| !c.starts_with('/'));
+
+        let Some(last) = last else {
+            println!("No previous command to edit.");
+            return Ok(());
+        };
+
+        match edit::edit_command(&last)? {
+            Some(edited) => self.initial_input = Some(edited),
+            None => println!("Edit cancelled."),
+        }
+        Ok(())
+    }
+
+    /// Get the most recent unique commands from history, newest first.
+    pub fn recent_commands(&self, limit: usize) -> Vec<String> {
+        self.editor.history().recent(limit)
+    }
+
+    /// Record the exit code of the last submitted command in history and
+    /// for the prompt (`{prompt:char}`, `{#if error}`).
+    pub fn record_exit(&mut self, code: u8) {
+        let _ = self.history_store().update_last_exit(code as i32);
+        self.theme.set_last_exit(code as i32);
+    }
+
+    /// Update the shell state shown by `{shlvl}` and `{bg_status}`.
+    pub fn set_shell_state(&mut self, state: crate::exec::PromptState) {
+        self.theme.set_shell_state(state);
+    }
+
+    /// The SQLite history store (for stats queries).
+    pub fn history_store(&self) -> &crate::history::History {
+        self.editor.history().store()
+    }
+
+    /// Skip recording commands typed with a leading space.
+    pub fn set_history_ignore_space(&mut self, yes: bool) {
+        self.editor.history_mut().ignore_space(yes);
+    }
+
+    /// Set how repeated commands are skipped and collapsed in history.
+    pub fn set_history_dedup(&mut self, ignore_dups: bool, mode: crate::history::DedupMode) {
+        let history = self.editor.history_mut();
+        let _ = history.ignore_dups(ignore_dups);
+        history.set_dedup_mode(mode);
+    }
+
+    /// Cap the stored history at `max` entries, pruning the oldest (0 = no cap).
+    pub fn set_history_max_entries(&mut self, max: usize) {
+        let _ = self.editor.history_mut().set_max_len(max);
+    }
+
+    /// Turn `?`/`??` AI query styling, hints and completion on or off.
+    pub fn set_ai_enabled(&self, enabled: bool) {
+        if let Some(helper) = self.editor.helper() {
+            helper.set_ai_enabled(enabled);
+        }
+    }
+
+    /// Set the `$PATH` searched when completing and highlighting commands.
+    pub fn set_path_var(&self, path_var: std::ffi::OsString) {
+        self.completion_manager.set_path_var(path_var);
+    }
+
+    /// Set the wrapper commands (`sudo`, `env`, ...) skipped when completing.
+    pub fn set_completion_wrappers(&self, wrappers: Vec<String>) {
+        self.completion_manager.set_wrappers(wrappers);
+    }
+
+    /// Dynamic completers whose output was cut off, for `/debug`.
+    pub fn truncated_completers(&self) -> Vec<(String, usize)> {
+        self.completion_manager.truncated_completers()
+    }
+
+    /// Nearest known subcommand of `command` to a mistyped `word`.
+    pub fn suggest_subcommand(&self, command: &str, word: &str) -> Option<String> {
+        self.completion_manager.suggest_subcommand(command, word)
+    }
+
+    /// Plugin names provided by more than one package.
+    pub fn plugin_collisions(&self) -> Vec<(String, Vec<String>)> {
+        self.plugin_manager.collisions()
+    }
+
+    /// Loaded plugins whose name without the package prefix is `base`.
+    pub fn plugins_named(&self, base: &str) -> Vec<String> {
+        self.plugin_manager.plugins_named(base)
     }
 
     /// List all loaded plugins.
@@ -159,8 +472,45 @@ impl Repl {
         self.plugin_manager.debug_plugin(plugin_name).await
     }
 
+    /// Lint the current theme's prompt format against the loaded plugins.
+    pub fn theme_warnings(&self) -> Vec<crate::plugins::theme::LintWarning> {
+        self.theme.lint(&self.plugin_manager)
+    }
+
+    /// Whether the current theme enables a plugin.
+    pub fn plugin_enabled(&self, name: &str) -> bool {
+        self.theme.is_plugin_enabled(name)
+    }
+
+    /// The current theme's prompt format string.
+    pub fn theme_format(&self) -> String {
+        self.theme.prompt.format.joined()
+    }
+
     /// Get variables used by current theme.
     pub fn theme_variables(&self) -> Vec<String> {
         self.theme.get_plugin_variables()
     }
 }
+
+/// CSI escape sequences, which take up no columns on screen.
+static ANSI_ESCAPE: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").unwrap());
+
+/// Erase the terminal rows used by a prompt and its input, which rustyline
+/// leaves on screen when interrupted.
+fn erase_lines(prompt: &str, line: &str) {
+    let text = ANSI_ESCAPE
+        .replace_all(&format!("{}{}", prompt, line), "")
+        .to_string();
+    let width = crossterm::terminal::size()
+        .map(|(w, _)| w.max(1) as usize)
+        .unwrap_or(80);
+    let rows: usize = text
+        .split('\n')
+        .map(|l| l.chars().count().div_ceil(width).max(1))
+        .sum();
+
+    print!("\x1b[{}A\r\x1b[J", rows);
+    let _ = std::io::Write::flush(&mut std::io::stdout());
+}
@@ -0,0 +1,85 @@
+//! Scripted REPL input.
+//!
+//! `nosh --script FILE` feeds the lines of a file to the REPL as if they
+//! were typed, so whole sessions can run without a terminal. Used for
+//! end-to-end tests of the REPL loop.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// Where the REPL reads its next line from when not reading a terminal.
+pub trait LineSource {
+    /// The next line, or `None` at end of input.
+    fn next_line(&mut self) -> Option<String>;
+}
+
+/// Lines read from a script file. Blank lines and `#` comments are skipped.
+pub struct ScriptInput {
+    lines: VecDeque<String>,
+}
+
+impl ScriptInput {
+    /// Read the script at `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Ok(Self::from_text(&content))
+    }
+
+    /// Script from text, one input line per line.
+    pub fn from_text(text: &str) -> Self {
+        let lines = text
+            .lines()
+            .filter(|line| {
+                let line = line.trim();
+                !line.is_empty() && !line.starts_with('#')
+            })
+            .map(str::to_string)
+            .collect();
+        Self { lines }
+    }
+}
+
+impl LineSource for ScriptInput {
+    fn next_line(&mut self) -> Option<String> {
+        self.lines.pop_front()
+    }
+}
+
+/// Script path from `--script FILE`.
+///
+/// Only the flag is honored: an environment variable would be inherited by
+/// every command the session runs, so a nested `nosh` would replay it too.
+pub fn script_path(args: &[String]) -> Option<String> {
+    args.iter()
+        .position(|a| a == "--script")
+        .and_then(|i| args.get(i + 1).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_skips_blanks_and_comments() {
+        let mut input = ScriptInput::from_text("# setup\necho hi\n\n  ls -la\nexit\n");
+        assert_eq!(input.next_line().as_deref(), Some("echo hi"));
+        assert_eq!(input.next_line().as_deref(), Some("  ls -la"));
+        assert_eq!(input.next_line().as_deref(), Some("exit"));
+        assert_eq!(input.next_line(), None);
+    }
+
+    #[test]
+    fn test_script_path_from_flag_only() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            script_path(&args(&["nosh", "--script", "run.txt"])).as_deref(),
+            Some("run.txt")
+        );
+        assert_eq!(script_path(&args(&["nosh", "--script"])), None);
+        assert_eq!(script_path(&args(&["nosh"])), None);
+    }
+}
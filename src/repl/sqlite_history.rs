@@ -9,7 +9,7 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::rc::Rc;
 
-use crate::history::History as SqliteHistory;
+use crate::history::{DedupMode, History as SqliteHistory};
 
 /// Batch size for loading history entries.
 const BATCH_SIZE: usize = 100;
@@ -30,22 +30,55 @@ pub struct SqliteRustylineHistory {
     loaded_count: RefCell<usize>,
     /// Commands added during this session (newest at end)
     session_entries: RefCell<Vec<String>>,
+    /// Skip lines starting with a space (like bash's `ignorespace`)
+    ignore_space: bool,
+    /// Skip a command identical to the one just before it
+    ignore_dups: bool,
+    /// How repeats are collapsed when navigating
+    dedup_mode: DedupMode,
 }
 
 impl SqliteRustylineHistory {
     /// Create a new SQLite-backed history.
     pub fn open(path: &Path) -> Result<Self, String> {
         let db = SqliteHistory::open(path).map_err(|e| e.to_string())?;
+        Ok(Self::from_store(db))
+    }
 
-        let total = db.count().unwrap_or(0) as usize;
+    fn from_store(db: SqliteHistory) -> Self {
+        let total = db.readline_len(DedupMode::default()).unwrap_or(0) as usize;
 
-        Ok(Self {
+        Self {
             db: Rc::new(db),
             total_count: RefCell::new(total),
             cache: RefCell::new(HashMap::new()),
             loaded_count: RefCell::new(0),
             session_entries: RefCell::new(Vec::new()),
-        })
+            ignore_space: true,
+            ignore_dups: true,
+            dedup_mode: DedupMode::default(),
+        }
+    }
+
+    /// Change how repeated commands are collapsed, reloading lazily.
+    pub fn set_dedup_mode(&mut self, mode: DedupMode) {
+        if mode == self.dedup_mode {
+            return;
+        }
+        self.dedup_mode = mode;
+        *self.total_count.borrow_mut() = self.db.readline_len(mode).unwrap_or(0) as usize;
+        self.cache.borrow_mut().clear();
+        *self.loaded_count.borrow_mut() = 0;
+    }
+
+    /// Get the most recent unique commands, newest first.
+    pub fn recent(&self, limit: usize) -> Vec<String> {
+        self.db.recent(limit).unwrap_or_default()
+    }
+
+    /// The underlying SQLite history store.
+    pub fn store(&self) -> &SqliteHistory {
+        &self.db
     }
 
     /// Ensure we have entries loaded up to the given index.
@@ -72,7 +105,7 @@ impl SqliteRustylineHistory {
             let load_count = loaded + (batch_count * BATCH_SIZE);
             let load_count = load_count.min(total_db);
 
-            if let Ok(entries) = self.db.for_readline(load_count) {
+            if let Ok(entries) = self.db.for_readline(load_count, self.dedup_mode) {
                 let mut cache = self.cache.borrow_mut();
                 for (i, entry) in entries.into_iter().enumerate() {
                     cache.insert(i, entry);
@@ -112,7 +145,21 @@ impl History for SqliteRustylineHistory {
     }
 
     fn add(&mut self, line: &str) -> Result<bool, rustyline::error::ReadlineError> {
+        if self.ignore_space && line.starts_with(' ') {
+            self.db.forget_last();
+            return Ok(false);
+        }
+        let line = line.trim();
         if line.is_empty() {
+            self.db.forget_last();
+            return Ok(false);
+        }
+        if self.ignore_dups
+            && let Ok(Some(last)) = self.db.last_entry()
+            && last.command == line
+        {
+            // The duplicate isn't the row last added by this session
+            self.db.forget_last();
             return Ok(false);
         }
 
@@ -139,18 +186,24 @@ impl History for SqliteRustylineHistory {
         self.len() == 0
     }
 
-    fn set_max_len(&mut self, _len: usize) -> Result<(), rustyline::error::ReadlineError> {
-        // SQLite doesn't need a max length - it can handle millions of entries
+    fn set_max_len(&mut self, len: usize) -> Result<(), rustyline::error::ReadlineError> {
+        // 0 keeps everything; otherwise drop the oldest rows beyond the cap
+        if self.db.prune_to(len).unwrap_or(0) > 0 {
+            *self.total_count.borrow_mut() =
+                self.db.readline_len(self.dedup_mode).unwrap_or(0) as usize;
+            self.cache.borrow_mut().clear();
+            *self.loaded_count.borrow_mut() = 0;
+        }
         Ok(())
     }
 
-    fn ignore_dups(&mut self, _yes: bool) -> Result<(), rustyline::error::ReadlineError> {
-        // Deduplication is handled at query time
+    fn ignore_dups(&mut self, yes: bool) -> Result<(), rustyline::error::ReadlineError> {
+        self.ignore_dups = yes;
         Ok(())
     }
 
-    fn ignore_space(&mut self, _yes: bool) {
-        // Not implemented - we store all commands
+    fn ignore_space(&mut self, yes: bool) {
+        self.ignore_space = yes;
     }
 
     fn save(&mut self, _path: &Path) -> Result<(), rustyline::error::ReadlineError> {
@@ -238,3 +291,66 @@ impl History for SqliteRustylineHistory {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignore_space() {
+        let mut history =
+            SqliteRustylineHistory::from_store(SqliteHistory::open_in_memory().unwrap());
+
+        assert!(!history.add(" export TOKEN=secret").unwrap());
+        assert!(history.add("ls -la").unwrap());
+        assert_eq!(history.recent(10), vec!["ls -la"]);
+        assert_eq!(history.len(), 1);
+
+        history.ignore_space(false);
+        assert!(history.add(" pwd").unwrap());
+        assert_eq!(history.recent(10), vec!["pwd", "ls -la"]);
+    }
+
+    #[test]
+    fn test_skipped_line_keeps_previous_exit() {
+        let mut history =
+            SqliteRustylineHistory::from_store(SqliteHistory::open_in_memory().unwrap());
+
+        history.add("make").unwrap();
+        history.store().update_last_exit(0).unwrap();
+
+        // An unsaved line's failure mustn't land on the previous command
+        assert!(!history.add(" cmd").unwrap());
+        history.store().update_last_exit(1).unwrap();
+        assert_eq!(
+            history.store().last_entry().unwrap().unwrap().exit_code,
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_ignore_dups() {
+        let mut history =
+            SqliteRustylineHistory::from_store(SqliteHistory::open_in_memory().unwrap());
+
+        assert!(history.add("make").unwrap());
+        assert!(!history.add("make").unwrap());
+        assert!(history.add("make test").unwrap());
+        assert!(history.add("make").unwrap());
+        assert_eq!(history.store().count_since(0).unwrap(), 3);
+
+        history.ignore_dups(false).unwrap();
+        assert!(history.add("make").unwrap());
+        assert_eq!(history.store().count_since(0).unwrap(), 4);
+
+        // A skipped duplicate doesn't take over the previous exit code
+        history.ignore_dups(true).unwrap();
+        history.store().update_last_exit(0).unwrap();
+        assert!(!history.add("make").unwrap());
+        history.store().update_last_exit(2).unwrap();
+        assert_eq!(
+            history.store().last_entry().unwrap().unwrap().exit_code,
+            Some(0)
+        );
+    }
+}
@@ -1,7 +1,12 @@
+mod mode;
 mod parser;
 mod permissions;
 pub mod prompt;
 
-pub use parser::{ParsedCommand, RiskLevel, parse_command};
+pub use mode::{
+    Gate, apply_mode, auto_approve_below, gate, is_auto_approved, is_granted, is_paranoid,
+    set_auto_approve_below, set_paranoid,
+};
+pub use parser::{ParsedCommand, RiskLevel, parse_chain, parse_command, riskiest};
 pub use permissions::PermissionStore;
 pub use prompt::{PermissionChoice, prompt_for_permission};
@@ -0,0 +1,215 @@
+//! Paranoid safety mode.
+//!
+//! When enabled, every command above `Safe` needs explicit approval, stored
+//! and session permissions are ignored, and network commands are treated
+//! as `High` risk.
+//!
+//! `[safety] auto_approve_below` lets commands under a risk level run
+//! without asking.
+
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use super::{ParsedCommand, PermissionStore, RiskLevel};
+
+static PARANOID: AtomicBool = AtomicBool::new(false);
+static AUTO_APPROVE_BELOW: AtomicU8 = AtomicU8::new(RiskLevel::Low as u8);
+
+/// Enable or disable paranoid mode for this session.
+pub fn set_paranoid(enabled: bool) {
+    PARANOID.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether paranoid mode is enabled.
+pub fn is_paranoid() -> bool {
+    PARANOID.load(Ordering::Relaxed)
+}
+
+/// Set the risk level commands must be below to run without approval.
+pub fn set_auto_approve_below(threshold: RiskLevel) {
+    AUTO_APPROVE_BELOW.store(threshold as u8, Ordering::Relaxed);
+}
+
+/// The configured auto-approval threshold.
+pub fn auto_approve_below() -> RiskLevel {
+    match AUTO_APPROVE_BELOW.load(Ordering::Relaxed) {
+        0 => RiskLevel::Safe,
+        1 => RiskLevel::Low,
+        2 => RiskLevel::Medium,
+        3 => RiskLevel::High,
+        4 => RiskLevel::Critical,
+        _ => RiskLevel::Blocked,
+    }
+}
+
+/// Whether a command at `level` runs without asking. Critical and blocked
+/// commands never do, and paranoid mode only lets `Safe` through.
+pub fn is_auto_approved(level: RiskLevel, threshold: RiskLevel, paranoid: bool) -> bool {
+    let threshold = if paranoid {
+        threshold.min(RiskLevel::Low)
+    } else {
+        threshold.min(RiskLevel::Critical)
+    };
+    level < threshold
+}
+
+/// Apply the safety mode to an assessed command.
+pub fn apply_mode(mut parsed: ParsedCommand, paranoid: bool) -> ParsedCommand {
+    if paranoid
+        && parsed.info.is_network
+        && matches!(
+            parsed.risk_level,
+            RiskLevel::Safe | RiskLevel::Low | RiskLevel::Medium
+        )
+    {
+        parsed.risk_level = RiskLevel::High;
+        parsed.risk_reason = format!("{} [paranoid]", parsed.risk_reason);
+    }
+    parsed
+}
+
+/// Whether a previously granted permission covers this command.
+/// Always false in paranoid mode.
+pub fn is_granted(
+    parsed: &ParsedCommand,
+    permissions: &PermissionStore,
+    cwd: &str,
+    paranoid: bool,
+) -> bool {
+    if paranoid {
+        return false;
+    }
+
+    // Check permissions in order: global command, command+directory (checking actual paths), all-directory, command+host
+    permissions.is_command_allowed(&parsed.info.command, &parsed.info.command_pattern)
+        || permissions.are_affected_paths_allowed(
+            &parsed.info.command,
+            &parsed.info.command_pattern,
+            &parsed.info.affected_paths,
+            cwd,
+        )
+        || permissions.is_directory_allowed(cwd)
+        || permissions.are_hosts_allowed(&parsed.info.command, &parsed.info.hosts)
+}
+
+/// What the safety layer makes of a command line before asking anyone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gate {
+    /// Never runs
+    Blocked,
+    /// Runs only after the critical warning is confirmed
+    Critical,
+    /// Runs without asking
+    Approved,
+    /// Needs a permission choice
+    Ask,
+}
+
+/// Gate a command line split into `segments`, with `parsed` its riskiest
+/// part (see [`super::riskiest`]). The riskiest part decides the level,
+/// and a grant must cover every part.
+pub fn gate(
+    parsed: &ParsedCommand,
+    segments: &[ParsedCommand],
+    permissions: &PermissionStore,
+    cwd: &str,
+) -> Gate {
+    let paranoid = is_paranoid();
+    match parsed.risk_level {
+        RiskLevel::Blocked => Gate::Blocked,
+        RiskLevel::Critical => Gate::Critical,
+        level if is_auto_approved(level, auto_approve_below(), paranoid) => Gate::Approved,
+        _ if !segments.is_empty()
+            && segments
+                .iter()
+                .all(|s| is_granted(s, permissions, cwd, paranoid)) =>
+        {
+            Gate::Approved
+        }
+        _ => Gate::Ask,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safety::{parse_chain, parse_command, riskiest};
+
+    #[test]
+    fn test_paranoid_ignores_granted_permissions() {
+        let mut permissions = PermissionStore::default();
+        permissions.allow_command("rm", true);
+
+        let parsed = parse_command("rm notes.txt");
+        assert_eq!(parsed.risk_level, RiskLevel::Low);
+        assert!(is_granted(&parsed, &permissions, "/tmp", false));
+        assert!(!is_granted(&parsed, &permissions, "/tmp", true));
+    }
+
+    #[test]
+    fn test_gate_needs_every_segment() {
+        let mut permissions = PermissionStore::default();
+        permissions.allow_command("git", true);
+
+        let line = "git status && rm -rf build";
+        let segments = parse_chain(line);
+        let parsed = riskiest(line, &segments);
+        assert_eq!(parsed.info.command, "rm");
+        assert_eq!(gate(&parsed, &segments, &permissions, "/tmp"), Gate::Ask);
+
+        let line = "ls && rm -rf build";
+        let segments = parse_chain(line);
+        let parsed = riskiest(line, &segments);
+        assert_eq!(gate(&parsed, &segments, &permissions, "/tmp"), Gate::Ask);
+
+        permissions.allow_command("rm", true);
+        let line = "git status && rm -rf build";
+        let segments = parse_chain(line);
+        let parsed = riskiest(line, &segments);
+        assert_eq!(
+            gate(&parsed, &segments, &permissions, "/tmp"),
+            Gate::Approved
+        );
+    }
+
+    #[test]
+    fn test_paranoid_escalates_network_commands() {
+        let parsed = apply_mode(parse_command("curl https://example.com"), false);
+        assert_eq!(parsed.risk_level, RiskLevel::Medium);
+
+        let parsed = apply_mode(parse_command("curl https://example.com"), true);
+        assert_eq!(parsed.risk_level, RiskLevel::High);
+
+        // Non-network commands keep their level
+        let parsed = apply_mode(parse_command("ls"), true);
+        assert_eq!(parsed.risk_level, RiskLevel::Safe);
+    }
+
+    #[test]
+    fn test_auto_approve_threshold() {
+        let parsed = parse_command("rm -r build");
+        assert_eq!(parsed.risk_level, RiskLevel::Medium);
+
+        // Default: only safe commands skip the prompt
+        assert!(!is_auto_approved(parsed.risk_level, RiskLevel::Low, false));
+        assert!(is_auto_approved(RiskLevel::Safe, RiskLevel::Low, false));
+
+        assert!(is_auto_approved(parsed.risk_level, RiskLevel::High, false));
+        assert!(!is_auto_approved(RiskLevel::High, RiskLevel::High, false));
+
+        // Critical and blocked always need the dedicated checks
+        assert!(!is_auto_approved(
+            RiskLevel::Critical,
+            RiskLevel::Blocked,
+            false
+        ));
+        assert!(!is_auto_approved(
+            RiskLevel::Blocked,
+            RiskLevel::Blocked,
+            false
+        ));
+
+        // Paranoid mode ignores the threshold
+        assert!(!is_auto_approved(parsed.risk_level, RiskLevel::High, true));
+        assert!(is_auto_approved(RiskLevel::Safe, RiskLevel::High, true));
+    }
+}
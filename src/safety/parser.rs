@@ -1,7 +1,9 @@
+use serde::{Deserialize, Serialize};
 use std::env;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum RiskLevel {
     Safe,     // echo, pwd, ls (no writes)
     Low,      // single file write, git operations
@@ -11,6 +13,20 @@ pub enum RiskLevel {
     Blocked,  // absolutely never allow
 }
 
+impl RiskLevel {
+    /// Short label shown to the user ("low", "CRITICAL", ...).
+    pub fn label(&self) -> &'static str {
+        match self {
+            RiskLevel::Safe => "safe",
+            RiskLevel::Low => "low",
+            RiskLevel::Medium => "medium",
+            RiskLevel::High => "high",
+            RiskLevel::Critical => "CRITICAL",
+            RiskLevel::Blocked => "BLOCKED",
+        }
+    }
+}
+
 /// Commands that have subcommands (e.g., "git log", "docker run")
 const COMMANDS_WITH_SUBCOMMANDS: &[&str] = &[
     "git",
@@ -60,6 +76,8 @@ pub struct CommandInfo {
     pub is_privileged: bool,
     #[allow(dead_code)]
     pub affected_paths: Vec<String>,
+    /// Hosts a network command talks to (e.g., "api.example.com" for a curl URL)
+    pub hosts: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -72,6 +90,11 @@ pub struct ParsedCommand {
 
 const DESTRUCTIVE_COMMANDS: &[&str] = &["rm", "rmdir", "mv", "unlink"];
 const NETWORK_COMMANDS: &[&str] = &["curl", "wget", "ssh", "scp", "rsync", "nc", "netcat"];
+/// ssh options that take a value (`ssh -p 22 host`).
+const SSH_VALUE_OPTIONS: &str = "bcDEeFIiJLlmOopQRSWw";
+/// curl and wget options whose value is never a destination (`curl -o out.txt`).
+const CURL_VALUE_OPTIONS: &str = "AbcdeHmoruwXz";
+const WGET_VALUE_OPTIONS: &str = "aOoPtTUw";
 const PRIVILEGED_COMMANDS: &[&str] = &["sudo", "su", "doas"];
 const SAFE_COMMANDS: &[&str] = &[
     "echo", "pwd", "ls", "cat", "head", "tail", "grep", "find", "which", "whereis", "whoami",
@@ -175,6 +198,15 @@ pub fn parse_command(raw: &str) -> ParsedCommand {
         .map(|a| resolve_path(a))
         .collect();
 
+    // Chained commands and substitutions can reach anything, so they never
+    // get hosts a grant could match
+    let hosts =
+        if is_network && split_chain(raw).len() == 1 && !raw.contains('`') && !raw.contains("$(") {
+            extract_hosts(&command, &args).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+
     let info = CommandInfo {
         command: command.clone(),
         subcommand,
@@ -184,6 +216,7 @@ pub fn parse_command(raw: &str) -> ParsedCommand {
         is_network,
         is_privileged,
         affected_paths,
+        hosts,
     };
 
     let (risk_level, risk_reason) = assess_risk(&command, &args, &info);
@@ -196,6 +229,76 @@ pub fn parse_command(raw: &str) -> ParsedCommand {
     }
 }
 
+/// Split a command line into the commands chained by `&&`, `||`, `;`, `|`,
+/// `&` or newlines. Operators inside quotes are left alone.
+pub fn split_chain(raw: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut chars = raw.chars().peekable();
+    let mut in_single = false;
+    let mut in_double = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if !in_single => {
+                current.push(c);
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            '\'' if !in_double => {
+                in_single = !in_single;
+                current.push(c);
+            }
+            '"' if !in_single => {
+                in_double = !in_double;
+                current.push(c);
+            }
+            '&' | '|' | ';' | '\n' if !in_single && !in_double => {
+                // `&&` and `||` are one operator; `>&` and `|&` redirections aren't splits
+                if (c == '&' || c == '|') && chars.peek() == Some(&c) {
+                    chars.next();
+                } else if c == '&' && current.ends_with('>') {
+                    current.push(c);
+                    continue;
+                }
+                segments.push(std::mem::take(&mut current));
+            }
+            _ => current.push(c),
+        }
+    }
+    segments.push(current);
+
+    segments
+        .into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Parse and assess each command in a chain separately.
+pub fn parse_chain(raw: &str) -> Vec<ParsedCommand> {
+    split_chain(raw).iter().map(|s| parse_command(s)).collect()
+}
+
+/// Assess a command line by its riskiest part.
+///
+/// The whole line counts as a part too, for patterns that span a pipe
+/// (`curl ... | sh`). The result keeps the full line as `raw` so prompts
+/// show everything that will run.
+pub fn riskiest(raw: &str, segments: &[ParsedCommand]) -> ParsedCommand {
+    let whole = parse_command(raw);
+    segments
+        .iter()
+        .filter(|s| s.risk_level > whole.risk_level)
+        .max_by_key(|s| s.risk_level)
+        .map(|s| ParsedCommand {
+            raw: raw.to_string(),
+            ..s.clone()
+        })
+        .unwrap_or(whole)
+}
+
 /// Extract subcommand from commands that support subcommands.
 /// Returns (subcommand, command_pattern) where:
 /// - subcommand is Some("log") for "git log -5"
@@ -222,6 +325,103 @@ fn extract_subcommand(command: &str, args: &[String]) -> (Option<String>, String
     (None, command.to_string())
 }
 
+/// Extract the hosts a network command connects to.
+///
+/// URLs (`https://user@host:8080/path`) count for every network command;
+/// `[user@]host` for ssh and nc, and `[user@]host:path` for scp and rsync.
+/// Any other operand of curl and wget is taken as a host too, since they
+/// treat it as a URL. Returns `None` for options that could send traffic
+/// somewhere we can't see (`ssh -o ProxyCommand=...`, config files).
+fn extract_hosts(command: &str, args: &[String]) -> Option<Vec<String>> {
+    let value_options = match command {
+        "ssh" => SSH_VALUE_OPTIONS,
+        "curl" => CURL_VALUE_OPTIONS,
+        "wget" => WGET_VALUE_OPTIONS,
+        _ => "",
+    };
+    let mut hosts = Vec::new();
+    let mut add = |host: Option<String>| {
+        if let Some(host) = host
+            && !hosts.contains(&host)
+        {
+            hosts.push(host);
+        }
+    };
+    let mut args = args.iter();
+    let mut first_operand = true;
+
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            // `--opt=value` or a long flag; a separate value is taken as an operand
+            if ["--config", "--input-file"].contains(&arg.split('=').next().unwrap_or("")) {
+                return None;
+            }
+            continue;
+        }
+        if let Some(flags) = arg.strip_prefix('-').filter(|f| !f.is_empty()) {
+            // Short flags may be grouped (`-vp 22`) or carry their value (`-p22`)
+            for (i, flag) in flags.char_indices() {
+                if (command == "ssh" && "oF".contains(flag))
+                    || (command == "curl" && flag == 'K')
+                    || (command == "wget" && flag == 'i')
+                {
+                    return None;
+                }
+                if !value_options.contains(flag) {
+                    continue;
+                }
+                let attached = &flags[i + flag.len_utf8()..];
+                let value = if attached.is_empty() {
+                    args.next().map(String::as_str)
+                } else {
+                    Some(attached)
+                };
+                if command == "ssh" && flag == 'J' {
+                    // Jump hosts are connected to as well
+                    for jump in value.unwrap_or("").split(',') {
+                        add(host_of(jump));
+                    }
+                }
+                break;
+            }
+            continue;
+        }
+
+        if let Some((_, rest)) = arg.split_once("://") {
+            add(host_of(rest.split(['/', '?', '#']).next().unwrap_or("")));
+        } else {
+            match command {
+                "ssh" if first_operand => add(host_of(arg)),
+                // The rest is the remote command
+                "ssh" => break,
+                "nc" | "netcat" if first_operand => add(host_of(arg)),
+                // Ports after the host
+                "nc" | "netcat" if arg.chars().all(|c| c.is_ascii_digit() || c == '-') => {}
+                // Paths without `host:` are local
+                "scp" | "rsync" => add(arg
+                    .split_once(':')
+                    .filter(|(host, _)| !host.is_empty() && !host.contains('/'))
+                    .and_then(|(host, _)| host_of(host))),
+                _ => add(host_of(arg).or_else(|| Some(arg.to_lowercase()))),
+            }
+        }
+        first_operand = false;
+    }
+    Some(hosts)
+}
+
+/// The host in `[user@]host[:port]`, lowercased.
+fn host_of(authority: &str) -> Option<String> {
+    let host = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let host = if let Some(bracketed) = host.strip_prefix('[') {
+        // IPv6 literal: [::1]:8080
+        bracketed.split(']').next().unwrap_or("")
+    } else {
+        host.split(':').next().unwrap_or("")
+    };
+    (!host.is_empty()).then(|| host.to_lowercase())
+}
+
 fn assess_risk(command: &str, args: &[String], info: &CommandInfo) -> (RiskLevel, String) {
     // Check for blocked patterns
     if is_blocked(command, args) {
@@ -316,6 +516,73 @@ mod tests {
         assert_eq!(parsed.risk_level, RiskLevel::Safe);
     }
 
+    #[test]
+    fn test_network_hosts() {
+        let hosts = |cmd: &str| parse_command(cmd).info.hosts;
+        assert_eq!(
+            hosts("curl -H 'X: y' https://API.internal.example.com:8443/v1?q=1"),
+            vec!["api.internal.example.com"]
+        );
+        assert_eq!(hosts("ssh -p 2222 deploy@build01 uptime"), vec!["build01"]);
+        assert_eq!(
+            hosts("scp ./notes.txt me@files.example.com:/tmp/ other:dir/"),
+            vec!["files.example.com", "other"]
+        );
+        assert_eq!(hosts("wget http://[::1]:8080/x"), vec!["::1"]);
+        assert!(hosts("curl -o out.txt").is_empty());
+        assert!(hosts("ls https://example.com").is_empty());
+
+        // Bare operands are destinations too
+        assert_eq!(
+            hosts("curl https://api.example.com evil.example.com"),
+            vec!["api.example.com", "evil.example.com"]
+        );
+        assert_eq!(hosts("curl -so/dev/null Example.com"), vec!["example.com"]);
+        assert_eq!(hosts("nc -v db01 5432"), vec!["db01"]);
+        assert_eq!(
+            hosts("ssh -J jump1,jump2 -vp22 app"),
+            vec!["jump1", "jump2", "app"]
+        );
+
+        // Anything that hides where traffic goes has no hosts at all
+        assert!(hosts("ssh -oProxyCommand='nc evil 22' app").is_empty());
+        assert!(hosts("ssh -o ProxyCommand=x app").is_empty());
+        assert!(hosts("ssh -F ./cfg app").is_empty());
+        assert!(hosts("curl -K urls.txt").is_empty());
+        assert!(hosts("curl https://api.example.com && curl https://evil.com").is_empty());
+        assert!(hosts("curl https://api.example.com | sh").is_empty());
+        assert!(hosts("curl https://$(cat host)").is_empty());
+    }
+
+    #[test]
+    fn test_split_chain() {
+        assert_eq!(
+            split_chain("cd build && make -j4 || echo 'a && b'; ls | wc -l"),
+            vec!["cd build", "make -j4", "echo 'a && b'", "ls", "wc -l"]
+        );
+        assert_eq!(split_chain("cmd 2>&1 &"), vec!["cmd 2>&1"]);
+        assert_eq!(split_chain("ls"), vec!["ls"]);
+    }
+
+    #[test]
+    fn test_riskiest_segment_wins() {
+        let raw = "ls && rm -rf /";
+        let parsed = riskiest(raw, &parse_chain(raw));
+        assert_eq!(parsed.risk_level, RiskLevel::Blocked);
+        assert_eq!(parsed.info.command, "rm");
+        assert_eq!(parsed.raw, raw);
+
+        let raw = "echo hi; rm -rf ./target";
+        assert_eq!(
+            riskiest(raw, &parse_chain(raw)).risk_level,
+            RiskLevel::Medium
+        );
+        assert_eq!(
+            riskiest("ls -la", &parse_chain("ls -la")).info.command,
+            "ls"
+        );
+    }
+
     #[test]
     fn test_rm_single_file() {
         let parsed = parse_command("rm temp.txt");
@@ -4,6 +4,7 @@ use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 
+use crate::fs_util::{self, FileLock};
 use crate::paths;
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -25,6 +26,12 @@ pub struct PermissionStore {
     #[serde(default)]
     pub allowed_command_directories: HashMap<String, HashSet<String>>,
 
+    /// Network commands allowed to reach specific hosts.
+    /// Key: base command (e.g., "curl")
+    /// Value: set of hosts it may talk to (e.g., "api.internal.example.com")
+    #[serde(default)]
+    pub allowed_command_hosts: HashMap<String, HashSet<String>>,
+
     /// Session-only allowed commands/patterns (not persisted)
     #[serde(skip)]
     session_commands: HashSet<String>,
@@ -37,6 +44,18 @@ pub struct PermissionStore {
     #[serde(skip)]
     session_command_directories: HashMap<String, HashSet<String>>,
 
+    /// Session-only command+host permissions (not persisted)
+    #[serde(skip)]
+    session_command_hosts: HashMap<String, HashSet<String>>,
+
+    /// While set, persisted grants wait for `finish_batch` to be saved
+    #[serde(skip)]
+    batching: bool,
+
+    /// Persisted grants made during a batch that aren't on disk yet
+    #[serde(skip)]
+    dirty: bool,
+
     #[serde(skip)]
     path: PathBuf,
 }
@@ -58,15 +77,50 @@ impl PermissionStore {
         }
     }
 
+    /// Write the store back to the permissions file.
+    ///
+    /// A store that wasn't loaded from disk (`Default`) has no path and
+    /// stays in memory.
     pub fn save(&self) -> Result<()> {
+        if self.path.as_os_str().is_empty() {
+            return Ok(());
+        }
         if let Some(parent) = self.path.parent() {
             fs::create_dir_all(parent)?;
         }
+        let _lock = FileLock::acquire(&self.path)?;
         let content = toml::to_string_pretty(self)?;
-        fs::write(&self.path, content)?;
+        fs_util::write_atomic(&self.path, content)?;
         Ok(())
     }
 
+    /// Hold back saving persisted grants until `finish_batch`.
+    ///
+    /// Used by the agentic loop so every grant made during a run is
+    /// written in one atomic save, whether the run finishes or is cancelled.
+    pub fn begin_batch(&mut self) {
+        self.batching = true;
+    }
+
+    /// End a batch, saving the grants made since `begin_batch` (if any).
+    pub fn finish_batch(&mut self) -> Result<()> {
+        self.batching = false;
+        if self.dirty {
+            self.save()?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Save a persisted grant now, or mark it pending while batching.
+    fn persist(&mut self) {
+        if self.batching {
+            self.dirty = true;
+        } else {
+            let _ = self.save();
+        }
+    }
+
     /// Check if a command pattern is allowed.
     ///
     /// For commands with subcommands (e.g., "git log"):
@@ -90,7 +144,12 @@ impl PermissionStore {
             return true;
         }
 
-        false
+        // Check glob patterns (e.g., "npm run *", "docker-*")
+        self.allowed_commands
+            .iter()
+            .chain(self.session_commands.iter())
+            .filter(|p| p.contains('*'))
+            .any(|p| pattern_matches(p, command_pattern) || pattern_matches(p, command))
     }
 
     /// Legacy method for backward compatibility - checks only base command.
@@ -202,6 +261,34 @@ impl PermissionStore {
             .all(|path| self.is_path_allowed_for_command(command, command_pattern, path))
     }
 
+    /// Check if a network command may reach every one of `hosts`.
+    /// Returns false when no host is known.
+    pub fn are_hosts_allowed(&self, command: &str, hosts: &[String]) -> bool {
+        !hosts.is_empty()
+            && hosts.iter().all(|host| {
+                [&self.allowed_command_hosts, &self.session_command_hosts]
+                    .iter()
+                    .any(|store| store.get(command).is_some_and(|h| h.contains(host)))
+            })
+    }
+
+    /// Allow a network command to reach the given hosts.
+    /// E.g., allow "curl" to "api.internal.example.com"
+    pub fn allow_command_for_hosts(&mut self, command: &str, hosts: &[String], persist: bool) {
+        let store = if persist {
+            &mut self.allowed_command_hosts
+        } else {
+            &mut self.session_command_hosts
+        };
+        store
+            .entry(command.to_string())
+            .or_default()
+            .extend(hosts.iter().cloned());
+        if persist {
+            self.persist();
+        }
+    }
+
     /// Allow a command or command pattern.
     ///
     /// The pattern can be:
@@ -210,7 +297,7 @@ impl PermissionStore {
     pub fn allow_command(&mut self, pattern: &str, persist: bool) {
         if persist {
             self.allowed_commands.insert(pattern.to_string());
-            let _ = self.save();
+            self.persist();
         } else {
             self.session_commands.insert(pattern.to_string());
         }
@@ -219,7 +306,7 @@ impl PermissionStore {
     pub fn allow_directory(&mut self, directory: &str, persist: bool) {
         if persist {
             self.allowed_directories.insert(directory.to_string());
-            let _ = self.save();
+            self.persist();
         } else {
             self.session_directories.insert(directory.to_string());
         }
@@ -233,7 +320,7 @@ impl PermissionStore {
                 .entry(pattern.to_string())
                 .or_default()
                 .insert(directory.to_string());
-            let _ = self.save();
+            self.persist();
         } else {
             self.session_command_directories
                 .entry(pattern.to_string())
@@ -243,6 +330,38 @@ impl PermissionStore {
     }
 }
 
+/// Match a permission pattern like `npm run *` against a command pattern.
+/// A trailing ` *` also matches the bare command (`git *` matches `git`).
+fn pattern_matches(pattern: &str, text: &str) -> bool {
+    glob_match(pattern, text)
+        || pattern
+            .strip_suffix(" *")
+            .is_some_and(|p| glob_match(p, text))
+}
+
+/// Simple glob match where `*` matches any sequence of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No '*' in pattern
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -252,9 +371,13 @@ mod tests {
             allowed_commands: HashSet::new(),
             allowed_directories: HashSet::new(),
             allowed_command_directories: HashMap::new(),
+            allowed_command_hosts: HashMap::new(),
             session_commands: HashSet::new(),
             session_directories: HashSet::new(),
             session_command_directories: HashMap::new(),
+            session_command_hosts: HashMap::new(),
+            batching: false,
+            dirty: false,
             path: PathBuf::from("/tmp/test_permissions.toml"),
         }
     }
@@ -437,4 +560,99 @@ mod tests {
         assert!(store.are_affected_paths_allowed("rm", "rm", &empty, "/home/user/project"));
         assert!(!store.are_affected_paths_allowed("rm", "rm", &empty, "/home/user/other"));
     }
+
+    #[test]
+    fn test_glob_patterns() {
+        let mut store = create_test_store();
+        store.allow_command("git *", false);
+        store.allow_command("npm run *", false);
+        store.allow_command("docker-*", false);
+
+        assert!(store.is_command_allowed("git", "git log"));
+        assert!(store.is_command_allowed("git", "git"));
+        assert!(store.is_command_allowed("npm", "npm run build"));
+        assert!(store.is_command_allowed("npm", "npm run"));
+        assert!(!store.is_command_allowed("npm", "npm install"));
+        assert!(store.is_command_allowed("docker-compose", "docker-compose up"));
+        assert!(!store.is_command_allowed("docker", "docker run"));
+        assert!(!store.is_command_allowed("gitk", "gitk"));
+    }
+
+    #[test]
+    fn test_command_hosts() {
+        let mut store = create_test_store();
+        store.allow_command_for_hosts("curl", &["api.internal.example.com".to_string()], false);
+
+        let allowed = crate::safety::parse_command("curl https://api.internal.example.com/health");
+        let other = crate::safety::parse_command("curl https://evil.example.com/health");
+        let wget = crate::safety::parse_command("wget https://api.internal.example.com/health");
+        assert!(store.are_hosts_allowed("curl", &allowed.info.hosts));
+        assert!(!store.are_hosts_allowed("curl", &other.info.hosts));
+        assert!(!store.are_hosts_allowed("wget", &wget.info.hosts));
+        assert!(!store.are_hosts_allowed("curl", &[]));
+
+        // Every host has to be allowed
+        let both = crate::safety::parse_command(
+            "curl https://api.internal.example.com/a https://evil.example.com/b",
+        );
+        assert!(!store.are_hosts_allowed("curl", &both.info.hosts));
+        let bare = crate::safety::parse_command(
+            "curl https://api.internal.example.com/a evil.example.com",
+        );
+        assert!(!store.are_hosts_allowed("curl", &bare.info.hosts));
+        let piped =
+            crate::safety::parse_command("curl https://api.internal.example.com/a; curl evil.com");
+        assert!(!store.are_hosts_allowed("curl", &piped.info.hosts));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("a*c", "abbc"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "axbyc"));
+        assert!(!glob_match("a*b*c", "axcyb"));
+        assert!(!glob_match("abc", "abcd"));
+        assert!(!glob_match("ab*ab", "ab"));
+    }
+
+    #[test]
+    fn test_pathless_store_stays_in_memory() {
+        let mut store = PermissionStore::default();
+        store.allow_command("cargo", true);
+        assert!(store.save().is_ok());
+        assert!(store.allowed_commands.contains("cargo"));
+    }
+
+    #[test]
+    fn test_batched_grants_saved_once_on_cancel() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_perm_batch_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut store = create_test_store();
+        store.path = dir.join("permissions.toml");
+
+        store.begin_batch();
+        store.allow_command("cargo", true);
+        store.allow_directory("/home/user/project", true);
+        store.allow_command("ls", false);
+        assert!(!store.path.exists(), "nothing is written mid-run");
+
+        // A cancelled run still ends the batch
+        store.finish_batch().unwrap();
+        let saved: PermissionStore =
+            toml::from_str(&fs::read_to_string(&store.path).unwrap()).unwrap();
+        assert!(saved.allowed_commands.contains("cargo"));
+        assert!(saved.allowed_directories.contains("/home/user/project"));
+        assert!(!saved.allowed_commands.contains("ls"));
+
+        // Nothing pending, so a second flush doesn't rewrite the file
+        fs::remove_file(&store.path).unwrap();
+        store.finish_batch().unwrap();
+        assert!(!store.path.exists());
+
+        // Outside a batch grants are saved right away again
+        store.allow_command("make", true);
+        assert!(store.path.exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -1,8 +1,54 @@
-use crate::safety::{ParsedCommand, RiskLevel};
+use crate::safety::ParsedCommand;
 use crossterm::ExecutableCommand;
 use crossterm::style::{Color, ResetColor, SetForegroundColor};
 use dialoguer::{Confirm, Select, theme::ColorfulTheme};
 use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static EXPLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Always show the detailed explanation with safety prompts.
+pub fn set_explain(enabled: bool) {
+    EXPLAIN.store(enabled, Ordering::Relaxed);
+}
+
+/// Why a command was flagged: the matched rule, the paths it resolved and
+/// the pattern saved permissions are matched against.
+pub fn explain(parsed: &ParsedCommand) -> String {
+    let info = &parsed.info;
+    let mut lines = vec![format!(
+        "  Rule:     {} ({})",
+        parsed.risk_reason,
+        parsed.risk_level.label()
+    )];
+    lines.push(format!("  Pattern:  {}", info.command_pattern));
+
+    let flags: Vec<&str> = [
+        (info.is_destructive, "destructive"),
+        (info.is_network, "network"),
+        (info.is_privileged, "privileged"),
+    ]
+    .into_iter()
+    .filter_map(|(set, name)| set.then_some(name))
+    .collect();
+    if !flags.is_empty() {
+        lines.push(format!("  Flags:    {}", flags.join(", ")));
+    }
+    if !info.affected_paths.is_empty() {
+        lines.push(format!("  Paths:    {}", info.affected_paths.join(", ")));
+    }
+    if !info.hosts.is_empty() {
+        lines.push(format!("  Hosts:    {}", info.hosts.join(", ")));
+    }
+    lines.join("\n")
+}
+
+fn print_explanation(stdout: &mut io::Stdout, parsed: &ParsedCommand) -> io::Result<()> {
+    stdout.execute(SetForegroundColor(Color::DarkGrey))?;
+    writeln!(stdout, "{}", explain(parsed))?;
+    stdout.execute(ResetColor)?;
+    Ok(())
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum PermissionChoice {
@@ -11,6 +57,7 @@ pub enum PermissionChoice {
     AllowCommand,     // Allow base command (e.g., "git" - allows all subcommands)
     AllowCommandHere, // Allow this command/pattern in this directory only
     AllowHere,        // Allow all commands in this directory
+    AllowCommandHost, // Allow this network command to reach these hosts (e.g., "curl" to one API)
     Deny,
 }
 
@@ -25,16 +72,13 @@ pub fn prompt_for_permission(parsed: &ParsedCommand) -> io::Result<PermissionCho
     writeln!(
         stdout,
         "Risk: {} - {}",
-        match parsed.risk_level {
-            RiskLevel::Safe => "safe",
-            RiskLevel::Low => "low",
-            RiskLevel::Medium => "medium",
-            RiskLevel::High => "high",
-            RiskLevel::Critical => "CRITICAL",
-            RiskLevel::Blocked => "BLOCKED",
-        },
+        parsed.risk_level.label(),
         parsed.risk_reason
     )?;
+    let mut explained = EXPLAIN.load(Ordering::Relaxed);
+    if explained {
+        print_explanation(&mut stdout, parsed)?;
+    }
     writeln!(stdout)?;
 
     // Build options based on whether command has a subcommand
@@ -42,57 +86,78 @@ pub fn prompt_for_permission(parsed: &ParsedCommand) -> io::Result<PermissionCho
     let command = &parsed.info.command;
     let command_pattern = &parsed.info.command_pattern;
 
-    let mut options: Vec<String> = vec!["Allow once".to_string()];
+    let mut options: Vec<(String, PermissionChoice)> =
+        vec![("Allow once".to_string(), PermissionChoice::AllowOnce)];
+
+    // Network commands with known targets can be allowed per host
+    if !parsed.info.hosts.is_empty() {
+        options.push((
+            format!(
+                "Always allow \"{}\" to {}",
+                command,
+                parsed.info.hosts.join(", ")
+            ),
+            PermissionChoice::AllowCommandHost,
+        ));
+    }
 
     if has_subcommand {
         // Show option for specific subcommand (e.g., "git log")
-        options.push(format!(
-            "Always allow \"{}\" commands here",
-            command_pattern
+        options.push((
+            format!("Always allow \"{}\" commands here", command_pattern),
+            PermissionChoice::AllowCommandHere,
         ));
-        options.push(format!(
-            "Always allow \"{}\" commands everywhere",
-            command_pattern
+        options.push((
+            format!("Always allow \"{}\" commands everywhere", command_pattern),
+            PermissionChoice::AllowSubcommand,
         ));
         // Show option for all subcommands (e.g., all "git" commands)
-        options.push(format!("Always allow all \"{}\" commands", command));
+        options.push((
+            format!("Always allow all \"{} *\" commands", command),
+            PermissionChoice::AllowCommand,
+        ));
     } else {
         // No subcommand - show directory-scoped option first, then global
-        options.push(format!("Always allow \"{}\" here", command));
-        options.push(format!("Always allow \"{}\" everywhere", command));
+        options.push((
+            format!("Always allow \"{}\" here", command),
+            PermissionChoice::AllowCommandHere,
+        ));
+        options.push((
+            format!("Always allow \"{}\" everywhere", command),
+            PermissionChoice::AllowCommand,
+        ));
     }
 
-    options.push("Always allow all commands here".to_string());
-    options.push("Don't run".to_string());
+    options.push((
+        "Always allow all commands here".to_string(),
+        PermissionChoice::AllowHere,
+    ));
+    options.push(("Don't run".to_string(), PermissionChoice::Deny));
 
-    let option_refs: Vec<&str> = options.iter().map(|s| s.as_str()).collect();
+    loop {
+        let mut option_refs: Vec<&str> = options.iter().map(|(label, _)| label.as_str()).collect();
+        if !explained {
+            option_refs.push("? Why am I being asked?");
+        }
 
-    let selection = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt("What would you like to do?")
-        .items(&option_refs)
-        .default(0)
-        .interact()
-        .map_err(io::Error::other)?;
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("What would you like to do?")
+            .items(&option_refs)
+            .default(0)
+            .interact()
+            .map_err(io::Error::other)?;
 
-    if has_subcommand {
-        // With subcommand: 0=once, 1=subcommand here, 2=subcommand everywhere, 3=all command, 4=all here, 5=deny
-        Ok(match selection {
-            0 => PermissionChoice::AllowOnce,
-            1 => PermissionChoice::AllowCommandHere, // "git log" here only
-            2 => PermissionChoice::AllowSubcommand,  // "git log" everywhere
-            3 => PermissionChoice::AllowCommand,     // all "git" commands
-            4 => PermissionChoice::AllowHere,        // all commands here
-            _ => PermissionChoice::Deny,
-        })
-    } else {
-        // Without subcommand: 0=once, 1=command here, 2=command everywhere, 3=all here, 4=deny
-        Ok(match selection {
-            0 => PermissionChoice::AllowOnce,
-            1 => PermissionChoice::AllowCommandHere, // "rm" here only
-            2 => PermissionChoice::AllowCommand,     // "rm" everywhere
-            3 => PermissionChoice::AllowHere,        // all commands here
-            _ => PermissionChoice::Deny,
-        })
+        if selection == options.len() {
+            print_explanation(&mut stdout, parsed)?;
+            writeln!(stdout)?;
+            explained = true;
+            continue;
+        }
+
+        return Ok(options
+            .into_iter()
+            .nth(selection)
+            .map_or(PermissionChoice::Deny, |(_, choice)| choice));
     }
 }
 
@@ -102,6 +167,9 @@ pub fn print_blocked(parsed: &ParsedCommand) -> io::Result<()> {
     writeln!(stdout, "\n✗ Command blocked: {}", parsed.raw)?;
     stdout.execute(ResetColor)?;
     writeln!(stdout, "Reason: {}", parsed.risk_reason)?;
+    if EXPLAIN.load(Ordering::Relaxed) {
+        print_explanation(&mut stdout, parsed)?;
+    }
     Ok(())
 }
 
@@ -111,6 +179,9 @@ pub fn print_critical_warning(parsed: &ParsedCommand) -> io::Result<bool> {
     writeln!(stdout, "\n⚠ CRITICAL: {}", parsed.raw)?;
     stdout.execute(ResetColor)?;
     writeln!(stdout, "Reason: {}", parsed.risk_reason)?;
+    if EXPLAIN.load(Ordering::Relaxed) {
+        print_explanation(&mut stdout, parsed)?;
+    }
     writeln!(stdout)?;
 
     Confirm::with_theme(&ColorfulTheme::default())
@@ -119,3 +190,25 @@ pub fn print_critical_warning(parsed: &ParsedCommand) -> io::Result<bool> {
         .interact()
         .map_err(io::Error::other)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safety::parse_command;
+
+    #[test]
+    fn test_explain_shows_affected_paths() {
+        let dir = std::env::temp_dir().join(format!("nosh_test_explain_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let parsed = parse_command(&format!("rm -rf {}", dir.display()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = &parsed.info.affected_paths[0];
+        assert!(path.ends_with(&format!("nosh_test_explain_{}", std::process::id())));
+
+        let text = explain(&parsed);
+        assert!(text.contains(&format!("Paths:    {}", path)));
+        assert!(text.contains("Pattern:  rm"));
+        assert!(text.contains("destructive"));
+        assert!(text.contains(&parsed.risk_reason));
+    }
+}
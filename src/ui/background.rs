@@ -0,0 +1,261 @@
+//! Terminal background detection.
+//!
+//! Asks the terminal for its background color with an OSC 11 query so
+//! themes can pick `[colors.light]` or `[colors.dark]` overrides. A DA1
+//! query follows it: every terminal answers that one, so its reply marks the
+//! end of the conversation without waiting out the timeout.
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long to wait for the terminal to answer the queries.
+pub const QUERY_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Keys typed while waiting for the terminal's replies.
+static TYPEAHEAD: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+/// Terminal background brightness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Background {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// The `[ui] background` setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BackgroundSetting {
+    /// Ask the terminal, falling back to dark
+    #[default]
+    Auto,
+    Dark,
+    Light,
+}
+
+/// Resolve the background for a setting, querying the terminal for `auto`.
+pub fn resolve(setting: BackgroundSetting) -> Background {
+    match setting {
+        BackgroundSetting::Dark => Background::Dark,
+        BackgroundSetting::Light => Background::Light,
+        BackgroundSetting::Auto => detect(QUERY_TIMEOUT).unwrap_or_default(),
+    }
+}
+
+/// Query the terminal background with OSC 11.
+///
+/// Returns `None` when stdin/stdout aren't a terminal or the terminal
+/// doesn't answer within `timeout`. Anything typed meanwhile is kept for
+/// [`take_typeahead`].
+pub fn detect(timeout: Duration) -> Option<Background> {
+    use crossterm::terminal;
+    use std::io::IsTerminal;
+
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return None;
+    }
+    if std::env::var("TERM").is_ok_and(|t| t == "dumb") {
+        return None;
+    }
+
+    let was_raw = terminal::is_raw_mode_enabled().unwrap_or(false);
+    if !was_raw {
+        terminal::enable_raw_mode().ok()?;
+    }
+    let replies = query(timeout);
+    if !was_raw {
+        let _ = terminal::disable_raw_mode();
+    }
+
+    let replies = replies?;
+    if let Ok(mut typeahead) = TYPEAHEAD.lock() {
+        typeahead.extend_from_slice(&replies.typeahead);
+    }
+    parse_osc11_response(&replies.osc11?)
+}
+
+/// Take the text typed while the terminal was being queried.
+///
+/// Control keys are dropped; only printable text is handed back.
+pub fn take_typeahead() -> String {
+    let bytes = TYPEAHEAD
+        .lock()
+        .map(|mut t| std::mem::take(&mut *t))
+        .unwrap_or_default();
+    String::from_utf8_lossy(&bytes)
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect()
+}
+
+/// Send OSC 11 then DA1, and read from the raw stdin fd until the DA1
+/// reply arrives or `timeout` passes.
+///
+/// Reads bypass std's buffered stdin so no bytes are held back from the
+/// line editor.
+fn query(timeout: Duration) -> Option<Replies> {
+    let mut stdout = std::io::stdout();
+    stdout.write_all(b"\x1b]11;?\x07\x1b[c").ok()?;
+    stdout.flush().ok()?;
+
+    let fd = libc::STDIN_FILENO;
+    let deadline = Instant::now() + timeout;
+    let mut response = Vec::new();
+    let mut buf = [0u8; 64];
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: pollfd points to a single valid pollfd for the duration of the call.
+        let ready = unsafe { libc::poll(&mut pollfd, 1, remaining.as_millis() as libc::c_int) };
+        if ready <= 0 {
+            break;
+        }
+        // SAFETY: buf is valid for writes of buf.len() bytes.
+        let n = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) };
+        if n <= 0 {
+            break;
+        }
+        response.extend_from_slice(&buf[..n as usize]);
+        if split_replies(&response).done {
+            break;
+        }
+    }
+
+    Some(split_replies(&response))
+}
+
+/// The terminal's replies, separated from input typed meanwhile.
+#[derive(Debug, Default, PartialEq)]
+struct Replies {
+    /// The OSC 11 reply, if the terminal sent one
+    osc11: Option<Vec<u8>>,
+    /// Whether the DA1 reply (always the last one) has arrived
+    done: bool,
+    /// Bytes that are part of neither reply
+    typeahead: Vec<u8>,
+}
+
+/// Split what was read from the terminal into replies and typeahead.
+fn split_replies(buf: &[u8]) -> Replies {
+    let mut replies = Replies::default();
+    let mut i = 0;
+
+    while i < buf.len() {
+        let rest = &buf[i..];
+        if rest.starts_with(b"\x1b]11;") {
+            // An unterminated reply is still arriving (or was cut off)
+            let Some(len) = osc_len(rest) else { break };
+            replies.osc11 = Some(rest[..len].to_vec());
+            i += len;
+        } else if let Some(len) = da1_len(rest) {
+            replies.done = true;
+            i += len;
+        } else {
+            replies.typeahead.push(buf[i]);
+            i += 1;
+        }
+    }
+    replies
+}
+
+/// Length of an OSC sequence up to its BEL or ST terminator.
+fn osc_len(seq: &[u8]) -> Option<usize> {
+    seq.windows(2)
+        .position(|w| w[0] == b'\x07' || w == b"\x1b\\")
+        .map(|p| if seq[p] == b'\x07' { p + 1 } else { p + 2 })
+        .or_else(|| (seq.last() == Some(&b'\x07')).then_some(seq.len()))
+}
+
+/// Length of a DA1 reply like `ESC [ ? 62 ; 22 c`.
+fn da1_len(seq: &[u8]) -> Option<usize> {
+    let params = seq.strip_prefix(b"\x1b[?")?;
+    let digits = params
+        .iter()
+        .take_while(|b| b.is_ascii_digit() || **b == b';')
+        .count();
+    (params.get(digits) == Some(&b'c')).then_some(3 + digits + 1)
+}
+
+/// Parse a reply like `ESC ]11;rgb:RRRR/GGGG/BBBB BEL`.
+fn parse_osc11_response(response: &[u8]) -> Option<Background> {
+    let text = std::str::from_utf8(response).ok()?;
+    let start = text.find("rgb:")? + "rgb:".len();
+    let body = text[start..].trim_end_matches(['\x07', '\\', '\x1b']);
+
+    let mut channels = body.split('/').map(parse_channel);
+    let (r, g, b) = (channels.next()??, channels.next()??, channels.next()??);
+
+    // Perceived brightness (ITU-R BT.601 weights)
+    let luma = 0.299 * r + 0.587 * g + 0.114 * b;
+    Some(if luma > 0.5 {
+        Background::Light
+    } else {
+        Background::Dark
+    })
+}
+
+/// Parse a 1-4 digit hex color channel into 0.0..=1.0.
+fn parse_channel(hex: &str) -> Option<f64> {
+    if hex.is_empty() || hex.len() > 4 {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let max = (1u32 << (4 * hex.len())) - 1;
+    Some(value as f64 / max as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_osc11_response() {
+        assert_eq!(
+            parse_osc11_response(b"\x1b]11;rgb:0000/0000/0000\x07"),
+            Some(Background::Dark)
+        );
+        assert_eq!(
+            parse_osc11_response(b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\"),
+            Some(Background::Light)
+        );
+        assert_eq!(
+            parse_osc11_response(b"\x1b]11;rgb:fd/f6/e3\x07"),
+            Some(Background::Light)
+        );
+        assert_eq!(parse_osc11_response(b"\x1b]11;?\x07"), None);
+    }
+
+    #[test]
+    fn test_split_replies() {
+        // Both replies, with keys typed before and between them
+        let replies = split_replies(b"ls\x1b]11;rgb:ffff/ffff/ffff\x1b\\ -l\x1b[?62;22c");
+        assert_eq!(
+            replies.osc11.as_deref(),
+            Some(&b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\"[..])
+        );
+        assert!(replies.done);
+        assert_eq!(replies.typeahead, b"ls -l");
+
+        // Terminals without OSC 11 still answer DA1
+        let replies = split_replies(b"\x1b[?1;2cgit");
+        assert_eq!(replies.osc11, None);
+        assert!(replies.done);
+        assert_eq!(replies.typeahead, b"git");
+
+        // Still waiting on a partial reply
+        let replies = split_replies(b"\x1b]11;rgb:00");
+        assert!(!replies.done);
+        assert!(replies.typeahead.is_empty());
+        assert!(!split_replies(b"\x1b]11;rgb:0/0/0\x07\x1b[?6").done);
+    }
+}
@@ -1,16 +1,39 @@
 //! Terminal UI components for nosh.
 
+pub mod background;
 mod output_box;
 pub mod spinner;
 pub mod theme;
 
-pub use output_box::OutputBox;
+pub use output_box::{DEFAULT_MAX_LINES, OutputBox};
 
+use serde::{Deserialize, Serialize};
 use termimad::MadSkin;
 use theme::colors;
 
+use crate::exec::timing::{CommandTiming, format_timing};
+use crate::safety::{ParsedCommand, RiskLevel};
+
+/// How much of each agentic step to show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AgenticVerbosity {
+    /// Only the commands being run
+    Quiet,
+    /// Commands, reasoning and collapsed output
+    #[default]
+    Normal,
+    /// Everything, including full output, exit codes and timings
+    Verbose,
+}
+
 /// Format a step header with iteration number and command
-pub fn format_step(iteration: usize, command: &str, reasoning: Option<&str>) -> String {
+pub fn format_step(
+    iteration: usize,
+    command: &str,
+    reasoning: Option<&str>,
+    verbosity: AgenticVerbosity,
+) -> String {
     let mut result = format!(
         "\n  {}{}─{} {}",
         colors::CYAN,
@@ -19,16 +42,47 @@ pub fn format_step(iteration: usize, command: &str, reasoning: Option<&str>) ->
         command
     );
 
-    if let Some(reason) = reasoning {
+    if let Some(reason) = reasoning
+        && verbosity != AgenticVerbosity::Quiet
+    {
         result.push_str(&format!("\n    {}{}{}", colors::DIM, reason, colors::RESET));
     }
 
     result
 }
 
-/// Format command output in a dimmed box
-pub fn format_output(output: &str) -> String {
-    OutputBox::default().render(output)
+/// Format command output in a dimmed box, collapsing past `max_lines` (0 = no limit)
+pub fn format_output(output: &str, max_lines: usize) -> String {
+    OutputBox::new(max_lines).render(output)
+}
+
+/// Format the result of an agentic step (empty when nothing should be shown)
+pub fn format_step_output(
+    output: &str,
+    exit_code: i32,
+    timing: &CommandTiming,
+    max_lines: usize,
+    verbosity: AgenticVerbosity,
+) -> String {
+    match verbosity {
+        AgenticVerbosity::Quiet => String::new(),
+        AgenticVerbosity::Normal => format_output(output, max_lines),
+        AgenticVerbosity::Verbose => {
+            let status = format!(
+                "    {}exit {} · {}{}",
+                colors::DIM,
+                exit_code,
+                format_timing(timing),
+                colors::RESET
+            );
+            let boxed = format_output(output, 0);
+            if boxed.is_empty() {
+                status
+            } else {
+                format!("{}\n{}", boxed, status)
+            }
+        }
+    }
 }
 
 /// Format a translated command for simple query mode
@@ -36,6 +90,56 @@ pub fn format_translated_command(command: &str) -> String {
     format!("{}⚡{} {}", colors::CYAN, colors::RESET, command)
 }
 
+/// Format an AI-translated command served from the local cache.
+pub fn format_cached_command(command: &str) -> String {
+    format!(
+        "{} {}(cached){}",
+        format_translated_command(command),
+        colors::DIM,
+        colors::RESET
+    )
+}
+
+/// Format a chained command as one line per segment with its risk level,
+/// marking the riskiest segment (empty for a single command).
+pub fn format_chain_preview(segments: &[ParsedCommand]) -> String {
+    if segments.len() < 2 {
+        return String::new();
+    }
+    let riskiest = segments
+        .iter()
+        .map(|s| &s.risk_level)
+        .max()
+        .cloned()
+        .unwrap_or(RiskLevel::Safe);
+    let width = segments.iter().map(|s| s.raw.len()).max().unwrap_or(0);
+
+    let lines: Vec<String> = segments
+        .iter()
+        .enumerate()
+        .map(|(i, segment)| {
+            let is_riskiest = segment.risk_level == riskiest && riskiest > RiskLevel::Safe;
+            let color = match segment.risk_level {
+                RiskLevel::Safe | RiskLevel::Low => colors::DIM,
+                RiskLevel::Medium => colors::YELLOW,
+                _ => colors::RED,
+            };
+            format!(
+                "  {} {}. {:<width$}  {}{:<8}{} {}",
+                if is_riskiest { "▶" } else { " " },
+                i + 1,
+                segment.raw,
+                color,
+                segment.risk_level.label(),
+                colors::RESET,
+                segment.risk_reason,
+                width = width
+            )
+        })
+        .collect();
+    lines.join("\n")
+}
+
 /// Format a simple header with separator
 pub fn format_header(title: &str, subtitle: &str) -> String {
     format!(
@@ -51,7 +155,20 @@ pub fn format_header(title: &str, subtitle: &str) -> String {
 
 /// Format a result message with markdown rendering
 pub fn format_result(message: &str) -> String {
+    format_result_with_width(message, terminal_width())
+}
+
+/// Render markdown for a given terminal width.
+fn format_result_with_width(message: &str, width: usize) -> String {
+    let skin = result_skin();
+    let rendered = skin.text(message, Some(width));
+    format!("\n{}", rendered)
+}
+
+/// Build the markdown skin used for AI results.
+fn result_skin() -> MadSkin {
     use termimad::crossterm::style::{Attribute, Color};
+    use termimad::{Alignment, ROUNDED_TABLE_BORDER_CHARS, StyledChar};
 
     let mut skin = MadSkin::default();
 
@@ -64,11 +181,102 @@ pub fn format_result(message: &str) -> String {
     skin.headers[0].set_fg(Color::Green);
     skin.headers[0].add_attr(Attribute::Bold);
 
-    let rendered = skin.term_text(message);
-    format!("\n{}", rendered)
+    // Lists: colored bullets, wrapped lines stay indented under the item
+    skin.bullet = StyledChar::from_fg_char(Color::Cyan, '•');
+
+    // Tables: dim rounded borders, left-aligned so tables wider than the
+    // terminal wrap cell content instead of misaligning columns
+    skin.table_border_chars = ROUNDED_TABLE_BORDER_CHARS;
+    skin.table.compound_style.set_fg(Color::DarkGrey);
+    skin.table.align = Alignment::Left;
+
+    skin
+}
+
+/// Current terminal width, falling back to 80 columns when not a terminal.
+fn terminal_width() -> usize {
+    termimad::crossterm::terminal::size()
+        .map(|(width, _)| width as usize)
+        .unwrap_or(80)
+        .max(20)
+}
+
+/// Format a dimmed hint line
+pub fn format_hint(message: &str) -> String {
+    format!("{}{}{}", colors::DIM, message, colors::RESET)
 }
 
 /// Format an error message
 pub fn format_error(message: &str) -> String {
     format!("{}error:{} {}", colors::RED, colors::RESET, message)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agentic_verbosity_formatting() {
+        use std::time::Duration;
+
+        let timing = CommandTiming {
+            wall: Duration::from_millis(1500),
+            user: None,
+            sys: None,
+        };
+        let output = (1..=30)
+            .map(|i| format!("line {}\n", i))
+            .collect::<String>();
+
+        // Quiet: command only, no reasoning or output
+        let step = format_step(1, "ls", Some("look around"), AgenticVerbosity::Quiet);
+        assert!(step.contains("ls"));
+        assert!(!step.contains("look around"));
+        assert!(format_step_output(&output, 0, &timing, 10, AgenticVerbosity::Quiet).is_empty());
+
+        // Normal: reasoning and collapsed output, no status
+        let step = format_step(1, "ls", Some("look around"), AgenticVerbosity::Normal);
+        assert!(step.contains("look around"));
+        let normal = format_step_output(&output, 0, &timing, 10, AgenticVerbosity::Normal);
+        assert!(normal.contains("line 1"));
+        assert!(!normal.contains("line 15\x1b"));
+        assert!(!normal.contains("exit 0"));
+
+        // Verbose: full output plus exit code and timing
+        let step = format_step(1, "ls", Some("look around"), AgenticVerbosity::Verbose);
+        assert!(step.contains("look around"));
+        let verbose = format_step_output(&output, 2, &timing, 10, AgenticVerbosity::Verbose);
+        assert!(verbose.contains("line 15\x1b"));
+        assert!(verbose.contains("exit 2"));
+        assert!(verbose.contains("1.500s"));
+        assert!(
+            format_step_output("", 0, &timing, 10, AgenticVerbosity::Verbose).contains("exit 0")
+        );
+    }
+
+    #[test]
+    fn test_chain_preview() {
+        let preview = format_chain_preview(&crate::safety::parse_chain("a && rm -rf b"));
+        let lines: Vec<&str> = preview.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("    1. a "));
+        assert!(lines[0].contains("low"));
+        assert!(lines[1].starts_with("  ▶ 2. rm -rf b"));
+        assert!(lines[1].contains(&format!("{}medium", colors::YELLOW)));
+        assert!(lines[1].ends_with("Recursive forced delete"));
+
+        assert!(format_chain_preview(&crate::safety::parse_chain("ls -la")).is_empty());
+    }
+
+    #[test]
+    fn test_format_result_table() {
+        let markdown = "| Command | Purpose |\n|---|---|\n| `ls` | list files |\n| `du -sh` | disk usage of a directory |\n\n- first\n- second\n";
+
+        for width in [20, 80] {
+            let rendered = format_result_with_width(markdown, width);
+            assert!(!rendered.trim().is_empty());
+            assert!(rendered.contains("\x1b["));
+            assert!(rendered.contains("list"));
+        }
+    }
+}
@@ -2,20 +2,32 @@
 
 use super::theme::colors;
 
+/// Default number of output lines shown before collapsing.
+pub const DEFAULT_MAX_LINES: usize = 6;
+
 /// Format command output with truncation for long output.
 pub struct OutputBox {
+    /// Maximum lines to show (0 = no limit)
     max_lines: usize,
 }
 
 impl Default for OutputBox {
     fn default() -> Self {
-        Self { max_lines: 6 }
+        Self {
+            max_lines: DEFAULT_MAX_LINES,
+        }
     }
 }
 
 impl OutputBox {
+    /// Create an output box showing at most `max_lines` lines (0 = no limit).
+    pub fn new(max_lines: usize) -> Self {
+        Self { max_lines }
+    }
+
     /// Render output with indentation and truncation.
-    /// Shows last N lines if output exceeds max_lines.
+    /// If output exceeds max_lines, shows the first and last lines
+    /// with a hidden-lines marker in between.
     pub fn render(&self, output: &str) -> String {
         if output.trim().is_empty() {
             return String::new();
@@ -23,33 +35,70 @@ impl OutputBox {
 
         let lines: Vec<&str> = output.lines().collect();
 
-        // Take last N lines if too many
-        let (display_lines, hidden_count) = if lines.len() > self.max_lines {
-            let skip = lines.len() - self.max_lines;
-            (lines.into_iter().skip(skip).collect::<Vec<_>>(), skip)
-        } else {
-            (lines, 0)
-        };
-
-        let mut result = Vec::new();
-
-        // Show hidden lines indicator
-        if hidden_count > 0 {
-            result.push(format!(
-                "\n    {}... {} lines hidden{}",
-                colors::DIM,
-                hidden_count,
-                colors::RESET
-            ));
-        } else {
-            result.push(String::new()); // Empty line before output
+        let mut result = vec![String::new()]; // Empty line before output
+
+        if self.max_lines == 0 || lines.len() <= self.max_lines {
+            for line in &lines {
+                result.push(Self::format_line(line));
+            }
+            return result.join("\n");
         }
 
-        // Content lines with indentation
-        for line in display_lines {
-            result.push(format!("    {}{}{}", colors::DIM, line, colors::RESET));
+        // Collapse the middle, keeping head and tail
+        let head = self.max_lines / 2;
+        let tail = self.max_lines - head;
+        let hidden = lines.len() - head - tail;
+
+        for line in &lines[..head] {
+            result.push(Self::format_line(line));
+        }
+        result.push(format!(
+            "    {}… {} lines hidden …{}",
+            colors::DIM,
+            hidden,
+            colors::RESET
+        ));
+        for line in &lines[lines.len() - tail..] {
+            result.push(Self::format_line(line));
         }
 
         result.join("\n")
     }
+
+    /// Format a content line with indentation.
+    fn format_line(line: &str) -> String {
+        format!("    {}{}{}", colors::DIM, line, colors::RESET)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collapse_long_output() {
+        let output: Vec<String> = (1..=100).map(|i| format!("line {}", i)).collect();
+        let rendered = OutputBox::new(10).render(&output.join("\n"));
+
+        let lines: Vec<&str> = rendered.lines().collect();
+        // Leading blank line, 5 head, marker, 5 tail
+        assert_eq!(lines.len(), 12);
+        assert!(lines[1].contains("line 1\x1b"));
+        assert!(lines[5].contains("line 5\x1b"));
+        assert!(lines[6].contains("… 90 lines hidden …"));
+        assert!(lines[7].contains("line 96"));
+        assert!(lines[11].contains("line 100"));
+    }
+
+    #[test]
+    fn test_short_output_not_collapsed() {
+        let rendered = OutputBox::new(10).render("a\nb\nc");
+        assert!(!rendered.contains("hidden"));
+        assert_eq!(rendered.lines().count(), 4);
+
+        // 0 disables the cap
+        let output: Vec<String> = (1..=50).map(|i| i.to_string()).collect();
+        let rendered = OutputBox::new(0).render(&output.join("\n"));
+        assert!(!rendered.contains("hidden"));
+    }
 }
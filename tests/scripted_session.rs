@@ -0,0 +1,30 @@
+//! End-to-end run of the REPL loop with scripted input.
+
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_scripted_session_runs_commands() {
+    let dir = std::env::temp_dir().join(format!("nosh_test_script_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("session.txt");
+    fs::write(&script, "echo hi\n?list files\nexit\necho unreachable\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_nosh"))
+        .arg("--script")
+        .arg(&script)
+        .env("NOSH_CONFIG_DIR", dir.join("config"))
+        .env("HOME", &dir)
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {}", stderr);
+    assert!(stdout.lines().any(|l| l == "hi"), "stdout: {}", stdout);
+    assert!(!stdout.contains("unreachable"));
+    assert!(stderr.contains("AI is not available in scripted sessions"));
+
+    let _ = fs::remove_dir_all(&dir);
+}